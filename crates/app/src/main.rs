@@ -1,15 +1,29 @@
 use clap::Parser;
-use fluxion_core::Editor;
+use fluxion_core::{Editor, RecentFiles};
 use fluxion_tui::Tui;
 use std::error::Error;
-use tracing::{Level, info};
+use std::path::PathBuf;
+use tracing::{Level, error, info};
 
 /// Fluxion: A Rust-based text editor
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    /// Files to open, one buffer each. `path:line` or `path:line:col` jumps
+    /// to that position
+    files: Vec<String>,
+
+    /// File to open, same as giving it as an argument
     #[arg(short, long)]
     file: Option<String>,
+
+    /// Open the files readonly, for viewing
+    #[arg(short = 'R', long)]
+    view: bool,
+
+    /// Restore buffers and cursor positions from a session file
+    #[arg(long)]
+    session: Option<PathBuf>,
 }
 
 #[tokio::main]
@@ -20,9 +34,55 @@ async fn main() -> Result<(), Box<dyn Error>> {
     info!("Starting Fluxion with args: {:?}", args);
 
     let mut editor = Editor::new("");
+    if let Some(dir) = fluxion_core::config_dir() {
+        editor.buffer_manager.recent_files = RecentFiles::load(dir.join("recent_files"));
+        editor.load_command_history(dir.join("command_history"));
+    }
+    if let Some(session) = &args.session
+        && let Err(e) = editor.load_session(session)
+    {
+        error!("Failed to load session {}: {}", session.display(), e);
+    }
+
+    // Open every file, then show the first one that could be read.
+    let mut first = None;
+    for path in args.file.iter().chain(&args.files) {
+        if !editor.open_location(path) {
+            continue;
+        }
+        if args.view {
+            editor.buffer_manager.current_buffer_mut().readonly = true;
+        }
+        if first.is_none() {
+            first = Some(editor.buffer_manager.current_buffer_id());
+        }
+    }
+    if let Some(id) = first {
+        editor.switch_buffer(id);
+    }
 
     let mut tui = Tui::new()?;
     tui.run(&mut editor)?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_any_number_of_files() {
+        let args = Args::try_parse_from(["fluxion", "a.txt", "src/lib.rs:42"]).expect("valid args");
+        assert_eq!(args.files, ["a.txt", "src/lib.rs:42"]);
+        assert_eq!(args.file, None);
+    }
+
+    #[test]
+    fn file_flag_and_view_mode() {
+        let args = Args::try_parse_from(["fluxion", "-R", "--file", "a.txt"]).expect("valid args");
+        assert_eq!(args.file.as_deref(), Some("a.txt"));
+        assert!(args.view);
+        assert!(args.files.is_empty());
+    }
+}
@@ -1,5 +1,32 @@
+use crate::cursor::Cursor;
+use crate::diagnostic::Diagnostic;
+use crate::encoding::{self, Encoding};
+use crate::filetype::detect_filetype;
+use crate::git::LineChange;
+use crate::highlight::{Highlighter, Language};
+use crate::history::{History, Snapshot};
+use crate::line_ending::LineEnding;
+use crate::recent::RecentFiles;
+use crate::swap;
 use ropey::Rope;
-use std::path::PathBuf;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// What a buffer holds, which decides how it can be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BufferKind {
+    /// Text to edit, usually read from a file.
+    #[default]
+    Text,
+    /// Output of a shell command run with `:make` or `:!`. It is readonly, and
+    /// Enter on a `file:line:col` line opens that spot.
+    Output,
+}
 
 #[derive(Debug, Clone)]
 pub struct Buffer {
@@ -8,7 +35,244 @@ pub struct Buffer {
     pub path: Option<PathBuf>,
     pub title: String,
     pub dirty: bool,
+    /// Refuses edits, and writes without `!`.
+    pub readonly: bool,
     pub is_transient: bool,
+    pub kind: BufferKind,
+    pub history: History,
+    pub line_ending: LineEnding,
+    pub encoding: Encoding,
+    /// Set while the contents are still being read in the background.
+    pub loading: bool,
+    pub highlighter: Highlighter,
+    /// Language name such as `rust`, detected on open or set with
+    /// `:set filetype`.
+    pub filetype: Option<String>,
+    /// Modification time of the file when it was last read or written.
+    pub modified_time: Option<SystemTime>,
+    /// Problems a language server last reported for the file.
+    pub diagnostics: Vec<Diagnostic>,
+    /// How lines differ from the file in git's `HEAD`, by row.
+    pub line_changes: BTreeMap<usize, LineChange>,
+    /// Cursor and scroll position to return to when switching back here.
+    pub cursor: Cursor,
+    pub scroll_offset: usize,
+}
+
+impl Buffer {
+    fn new(id: usize, title: String) -> Self {
+        Self {
+            id,
+            text: Rope::new(),
+            path: None,
+            title,
+            dirty: false,
+            readonly: false,
+            is_transient: false,
+            kind: BufferKind::Text,
+            history: History::new(),
+            line_ending: LineEnding::default(),
+            encoding: Encoding::default(),
+            loading: false,
+            highlighter: Highlighter::default(),
+            filetype: None,
+            modified_time: None,
+            diagnostics: Vec::new(),
+            line_changes: BTreeMap::new(),
+            cursor: Cursor::new(0, 0),
+            scroll_offset: 0,
+        }
+    }
+
+    /// The text of each line in `rows`, newline included, for drawing. A line
+    /// stored in one rope chunk is borrowed rather than copied.
+    pub fn visible_lines(&self, rows: Range<usize>) -> impl Iterator<Item = Cow<'_, str>> {
+        let end = rows.end.min(self.text.len_lines());
+        (rows.start.min(end)..end).map(|row| {
+            let line = self.text.line(row);
+            match line.as_str() {
+                Some(line) => Cow::Borrowed(line),
+                None => Cow::Owned(line.to_string()),
+            }
+        })
+    }
+
+    /// Sets the filetype, switching the highlighter to match.
+    pub fn set_filetype(&mut self, filetype: Option<String>) {
+        let language = Language::from_filetype(filetype.as_deref());
+        if language != self.highlighter.language() {
+            self.highlighter = Highlighter::new(language);
+        }
+        self.filetype = filetype;
+    }
+
+    /// Replaces the whole text, e.g. when restoring an undo snapshot.
+    pub fn set_text(&mut self, text: Rope) {
+        self.text = text;
+        self.highlighter.invalidate_from(0);
+    }
+
+    /// Adds `text` to the end without recording an undo step, as command
+    /// output does when it comes in.
+    pub fn append(&mut self, text: &str) {
+        let end = self.text.len_chars();
+        self.highlighter
+            .invalidate_from(self.text.char_to_line(end));
+        self.text.insert(end, text);
+    }
+
+    /// Strips spaces and tabs from the end of every line as one undo step,
+    /// with `cursor` as where undo puts the cursor back. Returns how many
+    /// lines changed.
+    pub fn trim_trailing_whitespace(&mut self, cursor: Cursor) -> usize {
+        let mut trailing = Vec::new();
+        for (row, line) in self.text.lines().enumerate() {
+            let chars: Vec<char> = line.chars().collect();
+            let len = chars.len() - usize::from(chars.last() == Some(&'\n'));
+            let end = chars[..len]
+                .iter()
+                .rposition(|c| *c != ' ' && *c != '\t')
+                .map_or(0, |i| i + 1);
+            if end < len {
+                let line_start = self.text.line_to_char(row);
+                trailing.push((row, line_start + end..line_start + len));
+            }
+        }
+        let Some((first_row, _)) = trailing.first() else {
+            return 0;
+        };
+
+        self.history.record(Snapshot {
+            text: self.text.clone(),
+            cursor,
+        });
+        self.highlighter.invalidate_from(*first_row);
+        for (_, range) in trailing.iter().rev() {
+            self.text.remove(range.clone());
+        }
+        self.dirty = true;
+        self.is_transient = false;
+        trailing.len()
+    }
+
+    /// Writes the buffer to `path`, or to its own path when `None`. With
+    /// `fix_eol`, a final line without a newline gets one in the file.
+    pub fn save(
+        &mut self,
+        path: Option<PathBuf>,
+        fix_eol: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let save_path = path.unwrap_or_else(|| {
+            self.path
+                .as_ref()
+                .map(|p| {
+                    if let Some(parent) = p.parent() {
+                        if let Some(filename) = p.file_name() {
+                            parent.join(filename)
+                        } else {
+                            p.clone()
+                        }
+                    } else {
+                        PathBuf::from(format!("untitled_{}.txt", self.id))
+                    }
+                })
+                .unwrap_or_else(|| PathBuf::from(format!("untitled_{}.txt", self.id)))
+        });
+
+        self.write_replacing(&save_path, fix_eol)?;
+
+        // What autosave kept is on disk now.
+        if let Some(path) = &self.path {
+            swap::remove_swap_file(path);
+        }
+        self.dirty = false;
+        self.modified_time = modified_time(&save_path);
+        if self.path.as_ref() != Some(&save_path) {
+            let first_line = self.text.line(0).to_string();
+            self.set_filetype(detect_filetype(&save_path, &first_line));
+        }
+        self.path = Some(save_path.clone());
+        self.title = save_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("[Untitled]")
+            .to_string();
+
+        Ok(())
+    }
+
+    /// Whether the file on disk has been modified since this buffer last read
+    /// or wrote it.
+    pub fn changed_on_disk(&self) -> bool {
+        match (&self.path, self.modified_time) {
+            (Some(path), Some(known)) => modified_time(path).is_some_and(|now| now != known),
+            _ => false,
+        }
+    }
+
+    /// Streams the text into a temp file next to `path`, then renames it over
+    /// `path` so a failed write leaves the original untouched. The original's
+    /// permissions carry over to the new file.
+    fn write_replacing(&self, path: &Path, fix_eol: bool) -> io::Result<()> {
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let tmp_path = path.with_file_name(format!(".{}.tmp", file_name));
+
+        let result = File::create(&tmp_path)
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                self.write_text(&mut writer, fix_eol)?;
+                writer.into_inner()?.sync_all()
+            })
+            .and_then(|()| match std::fs::metadata(path) {
+                Ok(metadata) => std::fs::set_permissions(&tmp_path, metadata.permissions()),
+                Err(_) => Ok(()),
+            })
+            .and_then(|()| {
+                // Renaming can fail where a copy still works, e.g. across
+                // filesystems. The copy is not atomic but beats losing the save.
+                std::fs::rename(&tmp_path, path)
+                    .or_else(|_| std::fs::copy(&tmp_path, path).map(|_| ()))
+            });
+        let _ = std::fs::remove_file(&tmp_path);
+        result
+    }
+
+    /// Writes the text chunk by chunk, restoring the file's line endings and
+    /// encoding. With `fix_eol`, ends a non-empty last line with a newline.
+    fn write_text(&self, writer: &mut impl Write, fix_eol: bool) -> io::Result<()> {
+        for chunk in self.text.chunks() {
+            match self.line_ending {
+                LineEnding::Lf => writer.write_all(&self.encoding.encode(chunk)?)?,
+                LineEnding::Crlf => {
+                    let mut lines = chunk.split('\n');
+                    if let Some(first) = lines.next() {
+                        writer.write_all(&self.encoding.encode(first)?)?;
+                    }
+                    for line in lines {
+                        writer.write_all(b"\r\n")?;
+                        writer.write_all(&self.encoding.encode(line)?)?;
+                    }
+                }
+            }
+        }
+        let len = self.text.len_chars();
+        if fix_eol && len > 0 && self.text.char(len - 1) != '\n' {
+            match self.line_ending {
+                LineEnding::Lf => writer.write_all(&self.encoding.encode("\n")?)?,
+                LineEnding::Crlf => writer.write_all(b"\r\n")?,
+            }
+        }
+        writer.flush()
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Whether the file's permissions forbid writing it.
+fn is_read_only(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|m| m.permissions().readonly())
 }
 
 #[derive(Debug)]
@@ -16,22 +280,17 @@ pub struct BufferManager {
     buffers: Vec<Buffer>,
     current_buffer_id: usize,
     next_id: usize,
+    /// Files opened, most recent first, recorded as each one is read.
+    pub recent_files: RecentFiles,
 }
 
 impl BufferManager {
     pub fn new() -> Self {
-        let initial_buffer = Buffer {
-            id: 0,
-            text: Rope::from_str(""),
-            path: None,
-            title: "[No Name]".to_string(),
-            dirty: false,
-            is_transient: false,
-        };
         Self {
-            buffers: vec![initial_buffer],
+            buffers: vec![Buffer::new(0, "[No Name]".to_string())],
             current_buffer_id: 0,
             next_id: 1,
+            recent_files: RecentFiles::default(),
         }
     }
 
@@ -39,21 +298,55 @@ impl BufferManager {
         let id = self.next_id;
         self.next_id += 1;
 
-        let buffer = Buffer {
-            id,
-            text: Rope::from_str(""),
-            path: None,
-            title: format!("[Buffer {}]", id),
-            dirty: false,
-            is_transient: false,
-        };
+        self.buffers
+            .push(Buffer::new(id, format!("[Buffer {}]", id)));
+        id
+    }
 
-        self.buffers.push(buffer);
+    /// The buffer command output goes to, emptied for a new command. It is
+    /// created on first use.
+    pub fn reset_output_buffer(&mut self) -> usize {
+        if let Some(buffer) = self
+            .buffers
+            .iter_mut()
+            .find(|b| b.kind == BufferKind::Output)
+        {
+            buffer.set_text(Rope::new());
+            buffer.cursor = Cursor::new(0, 0);
+            buffer.scroll_offset = 0;
+            return buffer.id;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.buffers.push(Buffer {
+            readonly: true,
+            kind: BufferKind::Output,
+            ..Buffer::new(id, "[Output]".to_string())
+        });
         id
     }
 
+    /// The buffer already showing `path`, comparing canonical paths so
+    /// different spellings of the same file match.
+    pub fn find_by_path(&self, path: &Path) -> Option<usize> {
+        let wanted = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.buffers.iter().find_map(|b| {
+            let open = b.path.as_ref()?;
+            let open = std::fs::canonicalize(open).unwrap_or_else(|_| open.clone());
+            (open == wanted).then_some(b.id)
+        })
+    }
+
+    /// Opens `path` in a new buffer, or returns the buffer that already has it,
+    /// and records it as the most recent file.
     pub fn open_file(&mut self, path: PathBuf) -> Result<usize, Box<dyn std::error::Error>> {
-        let contents = std::fs::read_to_string(&path)?;
+        if let Some(id) = self.find_by_path(&path) {
+            self.recent_files.record(&path);
+            return Ok(id);
+        }
+        let (contents, encoding) = encoding::read_file(&path)?;
+        let line_ending = LineEnding::detect(&contents);
+        let contents = contents.replace("\r\n", "\n");
         let title = path
             .file_name()
             .and_then(|n| n.to_str())
@@ -62,19 +355,95 @@ impl BufferManager {
         let id = self.next_id;
         self.next_id += 1;
 
-        let buffer = Buffer {
-            id,
+        let mut buffer = Buffer {
             text: Rope::from_str(&contents),
             path: Some(path.clone()),
-            title: title.to_string(),
-            dirty: false,
-            is_transient: false,
+            line_ending,
+            encoding,
+            modified_time: modified_time(&path),
+            readonly: is_read_only(&path),
+            ..Buffer::new(id, title.to_string())
         };
+        buffer.set_filetype(detect_filetype(&path, &contents));
 
         self.buffers.push(buffer);
+        self.recent_files.record(&path);
         Ok(id)
     }
 
+    /// Opens `path` as a transient preview buffer, which [`BufferManager::switch_to`]
+    /// deletes once another buffer is switched to. A file that is already open
+    /// keeps its existing buffer.
+    pub fn open_file_transient(
+        &mut self,
+        path: PathBuf,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        if let Some(id) = self.find_by_path(&path) {
+            return Ok(id);
+        }
+        let id = self.open_file(path)?;
+        if let Some(buffer) = self.get_mut(id) {
+            buffer.is_transient = true;
+        }
+        Ok(id)
+    }
+
+    /// Adds an empty placeholder buffer for `path` whose contents arrive later
+    /// through [`BufferManager::finish_loading`].
+    pub fn open_file_deferred(&mut self, path: PathBuf) -> usize {
+        let title = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("[Untitled]")
+            .to_string();
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let mut buffer = Buffer {
+            path: Some(path.clone()),
+            loading: true,
+            ..Buffer::new(id, title)
+        };
+        // Just the extension for now; a shebang is checked once the text is in.
+        buffer.set_filetype(detect_filetype(&path, ""));
+        self.buffers.push(buffer);
+        id
+    }
+
+    /// Swaps the contents read in the background into placeholder buffer `id`.
+    /// A failed read removes the placeholder again.
+    pub fn finish_loading(
+        &mut self,
+        id: usize,
+        contents: std::io::Result<Vec<u8>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(buffer) = self.buffers.iter_mut().find(|b| b.id == id && b.loading) else {
+            return Ok(());
+        };
+        match contents.and_then(Encoding::decode) {
+            Ok((contents, encoding)) => {
+                buffer.encoding = encoding;
+                buffer.line_ending = LineEnding::detect(&contents);
+                buffer.set_text(Rope::from_str(&contents.replace("\r\n", "\n")));
+                if let Some(path) = buffer.path.clone() {
+                    buffer.set_filetype(detect_filetype(&path, &contents));
+                }
+                buffer.modified_time = buffer.path.as_deref().and_then(modified_time);
+                buffer.readonly = buffer.path.as_deref().is_some_and(is_read_only);
+                buffer.loading = false;
+                if let Some(path) = &buffer.path {
+                    self.recent_files.record(path);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                self.delete_buffer(id);
+                Err(e.into())
+            }
+        }
+    }
+
     pub fn current_buffer(&self) -> &Buffer {
         self.buffers
             .iter()
@@ -89,10 +458,13 @@ impl BufferManager {
             .expect("Current buffer should exist")
     }
 
+    /// Makes buffer `id` current, deleting the buffer left behind if it was
+    /// transient.
     pub fn switch_to(&mut self, id: usize) -> bool {
         if self.buffers.iter().any(|b| b.id == id) {
             if let Some(current) = self.buffers.iter().find(|b| b.id == self.current_buffer_id)
                 && current.is_transient
+                && current.id != id
             {
                 self.delete_buffer(self.current_buffer_id);
             }
@@ -103,6 +475,16 @@ impl BufferManager {
         }
     }
 
+    /// Makes buffer `id` current, keeping the buffer left behind even if it
+    /// is transient.
+    pub fn set_current(&mut self, id: usize) -> bool {
+        let exists = self.buffers.iter().any(|b| b.id == id);
+        if exists {
+            self.current_buffer_id = id;
+        }
+        exists
+    }
+
     pub fn next_buffer(&mut self) -> Option<usize> {
         let current_idx = self
             .buffers
@@ -132,16 +514,7 @@ impl BufferManager {
             self.buffers.remove(pos);
 
             if self.buffers.is_empty() {
-                self.buffers.push(Buffer {
-                    id: self.next_id,
-                    text: Rope::from_str(""),
-                    path: None,
-                    title: "[No Name]".to_string(),
-                    dirty: false,
-                    is_transient: false,
-                });
-                self.next_id += 1;
-                self.current_buffer_id = 0;
+                self.current_buffer_id = self.push_empty_buffer();
             } else if self.current_buffer_id == id {
                 self.current_buffer_id = self.buffers[0].id;
             }
@@ -151,41 +524,79 @@ impl BufferManager {
         }
     }
 
+    /// Adds an empty `[No Name]` buffer so there is always one to show.
+    fn push_empty_buffer(&mut self) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.buffers.push(Buffer::new(id, "[No Name]".to_string()));
+        id
+    }
+
     pub fn save_current(
         &mut self,
         path: Option<PathBuf>,
+        fix_eol: bool,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        self.current_buffer_mut().save(path, fix_eol)
+    }
+
+    /// Re-reads the current buffer from its path, discarding unsaved changes
+    /// and undo history. The readonly flag and filetype are worked out again
+    /// as when the file was opened.
+    pub fn reload_current(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         let buffer = self.current_buffer_mut();
-        let data = buffer.text.to_string();
-        let save_path = path.unwrap_or_else(|| {
-            buffer
-                .path
-                .as_ref()
-                .map(|p| {
-                    if let Some(parent) = p.parent() {
-                        if let Some(filename) = p.file_name() {
-                            parent.join(filename)
-                        } else {
-                            p.clone()
-                        }
-                    } else {
-                        PathBuf::from(format!("untitled_{}.txt", buffer.id))
-                    }
-                })
-                .unwrap_or_else(|| PathBuf::from(format!("untitled_{}.txt", buffer.id)))
-        });
+        let path = buffer.path.clone().ok_or("No file name")?;
+        let (contents, encoding) = encoding::read_file(&path)?;
+        buffer.encoding = encoding;
+        buffer.line_ending = LineEnding::detect(&contents);
+        buffer.set_text(Rope::from_str(&contents.replace("\r\n", "\n")));
+        buffer.set_filetype(detect_filetype(&path, &contents));
+        buffer.readonly = is_read_only(&path);
+        buffer.modified_time = modified_time(&path);
+        buffer.dirty = false;
+        buffer.history.clear();
+        Ok(())
+    }
 
-        std::fs::write(&save_path, data.as_bytes())?;
+    /// Whether the current buffer's file was changed by another program since
+    /// it was opened or last saved.
+    pub fn external_change_detected(&self) -> bool {
+        self.current_buffer().changed_on_disk()
+    }
 
-        buffer.dirty = false;
-        buffer.path = Some(save_path.clone());
-        buffer.title = save_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("[Untitled]")
-            .to_string();
+    /// Saves every dirty buffer that has a path. Returns the ids of dirty
+    /// buffers left unsaved, either for lack of a path, because they are
+    /// readonly, because the file changed on disk, or because the write
+    /// failed.
+    pub fn save_all(&mut self, fix_eol: bool) -> Vec<usize> {
+        let mut unsaved = Vec::new();
+        for buffer in self.buffers.iter_mut().filter(|b| b.dirty) {
+            if buffer.path.is_none() || buffer.readonly || buffer.changed_on_disk() {
+                unsaved.push(buffer.id);
+            } else if let Err(e) = buffer.save(None, fix_eol) {
+                tracing::error!("Failed to save {}: {}", buffer.title, e);
+                unsaved.push(buffer.id);
+            }
+        }
+        unsaved
+    }
 
-        Ok(())
+    /// Paths and contents of the modified buffers autosave should copy to
+    /// swap files. Ropes clone cheaply, so the writes can happen elsewhere.
+    pub fn swap_snapshots(&self) -> Vec<(PathBuf, Rope)> {
+        self.buffers
+            .iter()
+            .filter(|b| b.dirty && !b.loading)
+            .filter_map(|b| Some((b.path.clone()?, b.text.clone())))
+            .collect()
+    }
+
+    pub fn get_mut(&mut self, id: usize) -> Option<&mut Buffer> {
+        self.buffers.iter_mut().find(|b| b.id == id)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Buffer> {
+        self.buffers.iter()
     }
 
     pub fn delete_current(&mut self) -> Option<usize> {
@@ -195,17 +606,8 @@ impl BufferManager {
         self.buffers.remove(current_idx);
 
         if self.buffers.is_empty() {
-            self.buffers.push(Buffer {
-                id: self.next_id,
-                text: Rope::from_str(""),
-                path: None,
-                title: "[No Name]".to_string(),
-                dirty: false,
-                is_transient: false,
-            });
-            self.next_id += 1;
-            self.current_buffer_id = 0;
-            Some(0)
+            self.current_buffer_id = self.push_empty_buffer();
+            Some(self.current_buffer_id)
         } else {
             let next_idx = current_idx.min(self.buffers.len() - 1);
             self.current_buffer_id = self.buffers[next_idx].id;
@@ -229,6 +631,31 @@ impl BufferManager {
         self.buffers.iter().filter(|b| !b.is_transient).collect()
     }
 
+    /// One line per listed buffer for `:ls`: id, `%` for the current buffer,
+    /// `+` when modified, then the title and path.
+    pub fn format_listing(&self) -> String {
+        self.list_buffers()
+            .iter()
+            .map(|buffer| {
+                let current = if buffer.id == self.current_buffer_id {
+                    '%'
+                } else {
+                    ' '
+                };
+                let dirty = if buffer.dirty { '+' } else { ' ' };
+                let path = buffer
+                    .path
+                    .as_ref()
+                    .map_or(String::new(), |p| format!(" {}", p.display()));
+                format!(
+                    "{:>3} {}{} \"{}\"{}",
+                    buffer.id, current, dirty, buffer.title, path
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
     pub fn current_buffer_id(&self) -> usize {
         self.current_buffer_id
     }
@@ -239,3 +666,56 @@ impl Default for BufferManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listing_marks_the_current_and_modified_buffers() {
+        let mut manager = BufferManager::new();
+        let id = manager.new_buffer();
+        if let Some(buffer) = manager.get_mut(id) {
+            buffer.path = Some(PathBuf::from("src/main.rs"));
+            buffer.title = "main.rs".to_string();
+            buffer.dirty = true;
+        }
+
+        assert_eq!(
+            manager.format_listing(),
+            "  0 %  \"[No Name]\"\n  1  + \"main.rs\" src/main.rs"
+        );
+    }
+
+    #[test]
+    fn listing_leaves_out_preview_buffers() {
+        let mut manager = BufferManager::new();
+        let id = manager.new_buffer();
+        if let Some(buffer) = manager.get_mut(id) {
+            buffer.is_transient = true;
+        }
+
+        assert_eq!(manager.format_listing(), "  0 %  \"[No Name]\"");
+    }
+
+    #[test]
+    fn visible_lines_borrow_lines_held_in_one_chunk() {
+        let mut buffer = Buffer::new(0, "[No Name]".to_string());
+        buffer.set_text(Rope::from_str("one\ntwo\nthree\n"));
+
+        let lines: Vec<_> = buffer.visible_lines(1..10).collect();
+        assert_eq!(lines, ["two\n", "three\n", ""]);
+        assert!(lines.iter().all(|line| matches!(line, Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn a_line_split_across_chunks_is_copied() {
+        let long = "x".repeat(10_000);
+        let mut buffer = Buffer::new(0, "[No Name]".to_string());
+        buffer.set_text(Rope::from_str(&format!("{}\n", long)));
+
+        let lines: Vec<_> = buffer.visible_lines(0..1).collect();
+        assert_eq!(lines[0].trim_end(), long);
+        assert!(matches!(lines[0], Cow::Owned(_)));
+    }
+}
@@ -0,0 +1,38 @@
+use std::error::Error;
+
+/// Source and sink for the `+` register, normally the OS clipboard.
+///
+/// Kept behind a trait so the editor can run headless (or in tests) without
+/// touching a real clipboard.
+pub trait ClipboardProvider {
+    fn get_text(&mut self) -> Result<String, Box<dyn Error>>;
+    fn set_text(&mut self, text: &str) -> Result<(), Box<dyn Error>>;
+}
+
+/// The OS clipboard, via `arboard`.
+#[cfg(feature = "clipboard")]
+pub struct SystemClipboard {
+    clipboard: arboard::Clipboard,
+}
+
+#[cfg(feature = "clipboard")]
+impl SystemClipboard {
+    /// Connects to the OS clipboard. Fails on headless systems with no
+    /// clipboard to talk to.
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            clipboard: arboard::Clipboard::new()?,
+        })
+    }
+}
+
+#[cfg(feature = "clipboard")]
+impl ClipboardProvider for SystemClipboard {
+    fn get_text(&mut self) -> Result<String, Box<dyn Error>> {
+        Ok(self.clipboard.get_text()?)
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), Box<dyn Error>> {
+        Ok(self.clipboard.set_text(text)?)
+    }
+}
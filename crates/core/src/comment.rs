@@ -0,0 +1,34 @@
+/// Prefix that starts a line comment in a buffer of `filetype`. Falls back
+/// to `#`, which most config and script formats use.
+pub fn line_comment_prefix(filetype: Option<&str>) -> &'static str {
+    match filetype {
+        Some(
+            "rust" | "c" | "cpp" | "cs" | "go" | "java" | "kotlin" | "swift" | "javascript"
+            | "typescript" | "zig",
+        ) => "//",
+        Some("lua" | "sql" | "haskell") => "--",
+        Some("lisp" | "clojure" | "dosini") => ";",
+        Some("tex") => "%",
+        Some("vim") => "\"",
+        _ => "#",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_follow_the_filetype() {
+        assert_eq!(line_comment_prefix(Some("rust")), "//");
+        assert_eq!(line_comment_prefix(Some("python")), "#");
+        assert_eq!(line_comment_prefix(Some("lua")), "--");
+        assert_eq!(line_comment_prefix(Some("vim")), "\"");
+    }
+
+    #[test]
+    fn unknown_filetypes_use_a_hash() {
+        assert_eq!(line_comment_prefix(Some("cobol")), "#");
+        assert_eq!(line_comment_prefix(None), "#");
+    }
+}
@@ -0,0 +1,301 @@
+use crate::range::parse_range;
+use std::path::Path;
+
+/// The ex commands Tab completes, by their full names.
+const COMMAND_NAMES: &[&str] = &[
+    "TrimWhitespace",
+    "ballbutexcept",
+    "bclose",
+    "bdelete",
+    "bnext",
+    "bprev",
+    "browse",
+    "bufdo",
+    "buffer",
+    "buffers",
+    "cclose",
+    "cnext",
+    "copen",
+    "cprevious",
+    "e",
+    "enew",
+    "files",
+    "grep",
+    "ls",
+    "make",
+    "mksession",
+    "q",
+    "qall",
+    "quit",
+    "recover",
+    "set",
+    "sort",
+    "source",
+    "stats",
+    "w",
+    "wall",
+    "wq",
+    "wqall",
+    "xall",
+];
+
+/// Commands whose argument is a file, completed as a path.
+const PATH_COMMANDS: &[&str] = &[
+    "e",
+    "w",
+    "w!",
+    "wq",
+    "wq!",
+    "mksession",
+    "mks",
+    "source",
+    "so",
+];
+
+/// Completes the last path component of `input` against the files in its
+/// directory, extending it to the longest prefix shared by every match. A
+/// lone directory match gets a trailing `/`. Returns `None` when nothing
+/// matches or there is nothing more to add.
+pub fn complete_path(input: &str) -> Option<String> {
+    let (dir, matches) = matching_entries(input)?;
+    let prefix = &input[dir.len()..];
+    let completed = match matches.as_slice() {
+        [] => return None,
+        [(name, true)] => format!("{}/", name),
+        [(name, false)] => name.clone(),
+        [(first, _), rest @ ..] => rest.iter().fold(first.clone(), |common, (name, _)| {
+            common
+                .chars()
+                .zip(name.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect()
+        }),
+    };
+
+    (completed.len() > prefix.len()).then(|| format!("{}{}", dir, completed))
+}
+
+/// Splits `input` into its directory, up to the last `/`, and the start of a
+/// name, and returns the directory with its entries whose names start with
+/// that, sorted, and whether each is a directory. Dotfiles only match a name
+/// that starts with `.`.
+fn matching_entries(input: &str) -> Option<(&str, Vec<(String, bool)>)> {
+    let (dir, prefix) = match input.rfind('/') {
+        Some(idx) => (&input[..=idx], &input[idx + 1..]),
+        None => ("", input),
+    };
+    let search_dir = if dir.is_empty() {
+        Path::new(".")
+    } else {
+        Path::new(dir)
+    };
+
+    let mut matches: Vec<(String, bool)> = std::fs::read_dir(search_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let is_dir = entry.path().is_dir();
+            name.starts_with(prefix).then_some((name, is_dir))
+        })
+        .filter(|(name, _)| !name.starts_with('.') || prefix.starts_with('.'))
+        .collect();
+    matches.sort();
+    Some((dir, matches))
+}
+
+/// What Tab can complete at the end of the command line `input`: the
+/// command name while the first word is typed, after any range, or a path
+/// after a command that takes a file. Returns where the word being completed
+/// starts in `input` and the words that can replace it, sorted.
+pub fn command_line_candidates(input: &str) -> (usize, Vec<String>) {
+    let Ok((_, command)) = parse_range(input, 0, 0, |_| Some(0)) else {
+        return (input.len(), Vec::new());
+    };
+    let command = command.trim_start();
+    let start = input.len() - command.len();
+    let Some((name, _)) = command.split_once(char::is_whitespace) else {
+        let names = COMMAND_NAMES
+            .iter()
+            .filter(|name| name.starts_with(command))
+            .map(|name| name.to_string())
+            .collect();
+        return (start, names);
+    };
+    if !PATH_COMMANDS.contains(&name) {
+        return (input.len(), Vec::new());
+    }
+
+    let start = input
+        .rfind(char::is_whitespace)
+        .map_or(input.len(), |idx| idx + 1);
+    let paths = matching_entries(&input[start..])
+        .map(|(dir, matches)| {
+            matches
+                .into_iter()
+                .map(|(name, is_dir)| {
+                    let slash = if is_dir { "/" } else { "" };
+                    format!("{}{}{}", dir, name, slash)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    (start, paths)
+}
+
+/// Tab completion in progress on the command line: the candidates Tab cycles
+/// through and which one is shown.
+#[derive(Debug, Clone)]
+pub(crate) struct Completion {
+    /// The command line as typed before the first Tab.
+    typed: String,
+    /// Where in `typed` the word the candidates replace starts.
+    start: usize,
+    candidates: Vec<String>,
+    /// The candidate shown, or `None` while `typed` is.
+    selected: Option<usize>,
+}
+
+impl Completion {
+    /// The completions of `typed`, or `None` if there aren't any.
+    pub(crate) fn new(typed: &str) -> Option<Self> {
+        let (start, candidates) = command_line_candidates(typed);
+        (!candidates.is_empty()).then(|| Self {
+            typed: typed.to_string(),
+            start,
+            candidates,
+            selected: None,
+        })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Moves to the next candidate, or the previous one, passing through the
+    /// typed text between the last and the first as vim does. Returns the
+    /// command line to show.
+    pub(crate) fn step(&mut self, forward: bool) -> String {
+        let last = self.candidates.len() - 1;
+        self.selected = match (self.selected, forward) {
+            (None, true) => Some(0),
+            (None, false) => Some(last),
+            (Some(idx), true) if idx < last => Some(idx + 1),
+            (Some(idx), false) if idx > 0 => Some(idx - 1),
+            (Some(_), _) => None,
+        };
+        match self.selected {
+            Some(idx) => format!("{}{}", &self.typed[..self.start], self.candidates[idx]),
+            None => self.typed.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A temp dir holding `alpha.rs`, `alpine/`, `beta.rs` and `.hidden`,
+    /// and its path with a trailing `/`.
+    fn project() -> (tempfile::TempDir, String) {
+        let dir = tempfile::tempdir().expect("temp dir");
+        for name in ["alpha.rs", "beta.rs", ".hidden"] {
+            std::fs::write(dir.path().join(name), "").expect("file written");
+        }
+        std::fs::create_dir(dir.path().join("alpine")).expect("dir made");
+        let path = format!("{}/", dir.path().display());
+        (dir, path)
+    }
+
+    #[test]
+    fn the_first_word_completes_to_command_names() {
+        assert_eq!(
+            command_line_candidates("b"),
+            (
+                0,
+                [
+                    "ballbutexcept",
+                    "bclose",
+                    "bdelete",
+                    "bnext",
+                    "bprev",
+                    "browse",
+                    "bufdo",
+                    "buffer",
+                    "buffers"
+                ]
+                .map(String::from)
+                .to_vec()
+            )
+        );
+        assert_eq!(
+            command_line_candidates("wq"),
+            (0, vec!["wq".to_string(), "wqall".to_string()])
+        );
+        assert_eq!(command_line_candidates("zz"), (0, Vec::new()));
+    }
+
+    #[test]
+    fn a_range_is_skipped_before_the_command_name() {
+        assert_eq!(
+            command_line_candidates("%so"),
+            (1, vec!["sort".to_string(), "source".to_string()])
+        );
+    }
+
+    #[test]
+    fn other_commands_take_no_path() {
+        assert_eq!(command_line_candidates("set /"), (5, Vec::new()));
+    }
+
+    #[test]
+    fn file_commands_complete_paths_with_directories_marked() {
+        let (_dir, root) = project();
+        let (start, candidates) = command_line_candidates(&format!("e {}al", root));
+        assert_eq!(start, 2);
+        assert_eq!(
+            candidates,
+            [format!("{}alpha.rs", root), format!("{}alpine/", root)]
+        );
+    }
+
+    #[test]
+    fn dotfiles_need_a_leading_dot() {
+        let (_dir, root) = project();
+        let (_, all) = command_line_candidates(&format!("w {}", root));
+        assert_eq!(all.len(), 3);
+        let (_, hidden) = command_line_candidates(&format!("w {}.", root));
+        assert_eq!(hidden, [format!("{}.hidden", root)]);
+    }
+
+    #[test]
+    fn a_path_completes_to_the_shared_prefix() {
+        let (_dir, root) = project();
+        assert_eq!(
+            complete_path(&format!("{}a", root)),
+            Some(format!("{}alp", root))
+        );
+        assert_eq!(
+            complete_path(&format!("{}alpi", root)),
+            Some(format!("{}alpine/", root))
+        );
+        assert_eq!(complete_path(&format!("{}alp", root)), None);
+        assert_eq!(complete_path(&format!("{}z", root)), None);
+    }
+
+    #[test]
+    fn stepping_cycles_through_the_candidates_and_back_to_the_input() {
+        let mut completion = Completion::new("%so").expect("candidates");
+        assert_eq!(completion.len(), 2);
+        let forward: Vec<String> = (0..4).map(|_| completion.step(true)).collect();
+        assert_eq!(forward, ["%sort", "%source", "%so", "%sort"]);
+        assert_eq!(completion.step(false), "%so");
+        assert_eq!(completion.step(false), "%source");
+    }
+
+    #[test]
+    fn nothing_to_complete_is_none() {
+        assert!(Completion::new("xyz").is_none());
+    }
+}
@@ -0,0 +1,10 @@
+use std::path::PathBuf;
+
+/// `$XDG_CONFIG_HOME/fluxion`, or `~/.config/fluxion` when unset, where the
+/// settings and the lists kept between sessions live.
+pub fn config_dir() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("fluxion"))
+}
@@ -0,0 +1,41 @@
+use crate::Cursor;
+
+/// How serious a [`Diagnostic`] is, most serious first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+/// A problem a language server reported in a buffer, from `start` up to
+/// but not including `end`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub start: Cursor,
+    pub end: Cursor,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Char columns of `row` this diagnostic covers, if any. An empty range
+    /// covers the one char it starts on, so it can still be seen.
+    pub fn cols_on(&self, row: usize) -> Option<std::ops::Range<usize>> {
+        if row < self.start.row || row > self.end.row {
+            return None;
+        }
+        let from = if row == self.start.row {
+            self.start.col
+        } else {
+            0
+        };
+        let to = if row == self.end.row {
+            self.end.col
+        } else {
+            usize::MAX
+        };
+        Some(from..to.max(from + 1))
+    }
+}
@@ -0,0 +1,96 @@
+use std::borrow::Cow;
+use std::io;
+
+/// How much of a file is checked for NUL bytes when deciding it is binary.
+const BINARY_SNIFF_LEN: usize = 8192;
+
+/// Character encoding a buffer was read with and is written back in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// Single-byte Western European text, read as windows-1252 so every byte
+    /// maps to a char and back.
+    Latin1,
+}
+
+impl Encoding {
+    /// Vim's `fileencoding` name for this encoding.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Latin1 => "latin1",
+        }
+    }
+
+    /// Decodes a file's bytes as UTF-8 when they are valid, else as Latin-1.
+    /// Files that look binary are refused rather than shown garbled.
+    pub fn decode(bytes: Vec<u8>) -> io::Result<(String, Self)> {
+        if bytes.iter().take(BINARY_SNIFF_LEN).any(|&b| b == 0) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "binary file"));
+        }
+        match String::from_utf8(bytes) {
+            Ok(text) => Ok((text, Encoding::Utf8)),
+            Err(e) => {
+                let (text, _) = encoding_rs::WINDOWS_1252.decode_without_bom_handling(e.as_bytes());
+                Ok((text.into_owned(), Encoding::Latin1))
+            }
+        }
+    }
+
+    /// Encodes `text` for writing, failing if a char has no representation
+    /// in this encoding.
+    pub fn encode<'a>(&self, text: &'a str) -> io::Result<Cow<'a, [u8]>> {
+        match self {
+            Encoding::Utf8 => Ok(Cow::Borrowed(text.as_bytes())),
+            Encoding::Latin1 => {
+                let (bytes, _, unmappable) = encoding_rs::WINDOWS_1252.encode(text);
+                if unmappable {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("conversion to {} failed", self.name()),
+                    ));
+                }
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+/// Reads and decodes the file at `path`.
+pub(crate) fn read_file(path: &std::path::Path) -> io::Result<(String, Encoding)> {
+    Encoding::decode(std::fs::read(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_utf8_stays_utf8() {
+        let (text, encoding) = Encoding::decode("café\n".into()).expect("decodes");
+        assert_eq!((text.as_str(), encoding), ("café\n", Encoding::Utf8));
+    }
+
+    #[test]
+    fn invalid_utf8_falls_back_to_latin1() {
+        let (text, encoding) = Encoding::decode(b"caf\xe9 \x80\n".to_vec()).expect("decodes");
+        assert_eq!((text.as_str(), encoding), ("café €\n", Encoding::Latin1));
+        assert_eq!(
+            Encoding::Latin1.encode(&text).expect("encodes").as_ref(),
+            b"caf\xe9 \x80\n"
+        );
+    }
+
+    #[test]
+    fn a_nul_byte_means_binary() {
+        let error = Encoding::decode(b"ELF\0\x01".to_vec()).expect_err("binary");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn chars_latin1_lacks_fail_to_encode() {
+        assert!(Encoding::Latin1.encode("日本").is_err());
+        assert!(Encoding::Utf8.encode("日本").is_ok());
+    }
+}
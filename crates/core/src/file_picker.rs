@@ -12,6 +12,10 @@ pub struct FilePicker {
     pub current_dir: PathBuf,
     pub files: Vec<FileInfo>,
     pub selected_idx: usize,
+    /// Whether dotfiles are listed.
+    pub show_hidden: bool,
+    /// Whether `files` holds the recent files instead of `current_dir`.
+    pub listing_recent: bool,
 }
 
 impl FilePicker {
@@ -20,10 +24,15 @@ impl FilePicker {
             current_dir: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
             files: Vec::new(),
             selected_idx: 0,
+            show_hidden: false,
+            listing_recent: false,
         }
     }
 
     pub fn refresh(&mut self) {
+        if self.listing_recent {
+            return;
+        }
         self.files = std::fs::read_dir(&self.current_dir)
             .map(|entries| {
                 entries
@@ -38,6 +47,7 @@ impl FilePicker {
                         let is_dir = path.is_dir();
                         FileInfo { name, is_dir, path }
                     })
+                    .filter(|file| self.show_hidden || !file.name.starts_with('.'))
                     .collect::<Vec<_>>()
             })
             .unwrap_or_default();
@@ -71,15 +81,52 @@ impl FilePicker {
         }
     }
 
+    pub fn toggle_hidden(&mut self) {
+        self.show_hidden = !self.show_hidden;
+        self.refresh();
+    }
+
+    /// Lists `paths`, most recent first, with the first one selected. Files
+    /// under `current_dir` are named relative to it.
+    pub fn list_recent(&mut self, paths: &[PathBuf]) {
+        self.listing_recent = true;
+        self.selected_idx = 0;
+        self.files = paths
+            .iter()
+            .map(|path| FileInfo {
+                name: path
+                    .strip_prefix(&self.current_dir)
+                    .unwrap_or(path)
+                    .display()
+                    .to_string(),
+                is_dir: false,
+                path: path.clone(),
+            })
+            .collect();
+    }
+
+    /// Lists `dir` with the first entry selected.
+    pub fn navigate_to(&mut self, dir: PathBuf) {
+        self.listing_recent = false;
+        self.current_dir = dir;
+        self.selected_idx = 0;
+        self.refresh();
+    }
+
+    /// Moves up one directory, selecting the directory just left.
     pub fn navigate_to_parent(&mut self) -> bool {
-        if let Some(parent) = self.current_dir.parent() {
-            self.current_dir = parent.to_path_buf();
-            self.refresh();
-            self.selected_idx = 0;
-            true
-        } else {
-            false
+        if self.listing_recent {
+            return false;
         }
+        let Some(parent) = self.current_dir.parent().map(|p| p.to_path_buf()) else {
+            return false;
+        };
+        let previous = self.current_dir.clone();
+        self.navigate_to(parent);
+        if let Some(idx) = self.files.iter().position(|f| f.path == previous) {
+            self.selected_idx = idx;
+        }
+        true
     }
 }
 
@@ -88,3 +135,84 @@ impl Default for FilePicker {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(picker: &FilePicker) -> Vec<&str> {
+        picker.files.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    fn project() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().expect("temp dir");
+        std::fs::create_dir(dir.path().join("src")).expect("dir created");
+        std::fs::write(dir.path().join("src/main.rs"), "").expect("file written");
+        std::fs::write(dir.path().join("README"), "").expect("file written");
+        dir
+    }
+
+    #[test]
+    fn directories_are_listed_first() {
+        let dir = project();
+        let mut picker = FilePicker::new();
+        picker.navigate_to(dir.path().to_path_buf());
+        assert_eq!(names(&picker), ["src", "README"]);
+    }
+
+    #[test]
+    fn descending_and_ascending_again() {
+        let dir = project();
+        let mut picker = FilePicker::new();
+        picker.navigate_to(dir.path().to_path_buf());
+        picker.move_down();
+
+        picker.navigate_to(dir.path().join("src"));
+        assert_eq!(names(&picker), ["main.rs"]);
+        assert_eq!(picker.selected_idx, 0);
+
+        assert!(picker.navigate_to_parent());
+        assert_eq!(picker.current_dir, dir.path());
+        assert_eq!(picker.selected_file().map(|f| f.name.as_str()), Some("src"));
+    }
+
+    #[test]
+    fn dotfiles_are_hidden_until_toggled() {
+        let dir = project();
+        std::fs::write(dir.path().join(".gitignore"), "").expect("file written");
+        let mut picker = FilePicker::new();
+        picker.navigate_to(dir.path().to_path_buf());
+        assert_eq!(names(&picker), ["src", "README"]);
+
+        picker.toggle_hidden();
+        assert_eq!(names(&picker), ["src", ".gitignore", "README"]);
+        picker.toggle_hidden();
+        assert_eq!(names(&picker), ["src", "README"]);
+    }
+
+    #[test]
+    fn hiding_files_keeps_the_selection_in_range() {
+        let dir = project();
+        std::fs::write(dir.path().join(".z"), "").expect("file written");
+        let mut picker = FilePicker::new();
+        picker.show_hidden = true;
+        picker.navigate_to(dir.path().to_path_buf());
+        picker.move_down();
+        picker.move_down();
+        assert_eq!(
+            picker.selected_file().map(|f| f.name.as_str()),
+            Some("README")
+        );
+
+        picker.toggle_hidden();
+        assert_eq!(picker.selected_idx, 1);
+    }
+
+    #[test]
+    fn the_root_has_no_parent() {
+        let mut picker = FilePicker::new();
+        picker.navigate_to(PathBuf::from("/"));
+        assert!(!picker.navigate_to_parent());
+        assert_eq!(picker.current_dir, PathBuf::from("/"));
+    }
+}
@@ -0,0 +1,113 @@
+use std::path::Path;
+
+/// Names the language of the file at `path` from its name or extension or, for
+/// files without one, from a `#!` line at the top of `contents`. Names follow vim's
+/// filetypes, e.g. `rust`, `python` or `sh`.
+pub fn detect_filetype(path: &Path, contents: &str) -> Option<String> {
+    let filetype = match (path.file_name()?.to_str()?, path.extension()) {
+        ("Makefile" | "makefile" | "GNUmakefile", _) => Some("make"),
+        ("Dockerfile", _) => Some("dockerfile"),
+        (_, Some(ext)) => from_extension(ext.to_str()?),
+        (_, None) => contents.lines().next().and_then(from_shebang),
+    };
+    filetype.map(str::to_string)
+}
+
+fn from_extension(extension: &str) -> Option<&'static str> {
+    Some(match extension {
+        "rs" => "rust",
+        "py" | "pyw" => "python",
+        "js" | "mjs" | "cjs" | "jsx" => "javascript",
+        "ts" | "tsx" => "typescript",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" => "cpp",
+        "cs" => "cs",
+        "go" => "go",
+        "java" => "java",
+        "kt" => "kotlin",
+        "swift" => "swift",
+        "zig" => "zig",
+        "rb" => "ruby",
+        "pl" => "perl",
+        "lua" => "lua",
+        "sql" => "sql",
+        "hs" => "haskell",
+        "sh" | "bash" => "sh",
+        "zsh" => "zsh",
+        "fish" => "fish",
+        "vim" => "vim",
+        "tex" => "tex",
+        "lisp" | "el" => "lisp",
+        "clj" => "clojure",
+        "json" => "json",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        "ini" => "dosini",
+        "md" | "markdown" => "markdown",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "xml" => "xml",
+        _ => return None,
+    })
+}
+
+/// The filetype a `#!/usr/bin/env python3` style line runs the file as.
+fn from_shebang(line: &str) -> Option<&'static str> {
+    let mut words = line.strip_prefix("#!")?.split_whitespace();
+    let mut program = Path::new(words.next()?).file_name()?.to_str()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    Some(match program {
+        "sh" | "bash" | "dash" | "ksh" => "sh",
+        "zsh" => "zsh",
+        "fish" => "fish",
+        "python" => "python",
+        "node" | "deno" => "javascript",
+        "ruby" => "ruby",
+        "perl" => "perl",
+        "lua" => "lua",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(name: &str, contents: &str) -> Option<String> {
+        detect_filetype(Path::new(name), contents)
+    }
+
+    #[test]
+    fn extensions_name_the_language() {
+        assert_eq!(detect("src/main.rs", "").as_deref(), Some("rust"));
+        assert_eq!(detect("setup.py", "").as_deref(), Some("python"));
+        assert_eq!(detect("Makefile", "").as_deref(), Some("make"));
+    }
+
+    #[test]
+    fn a_shebang_names_an_extensionless_script() {
+        assert_eq!(
+            detect("build", "#!/bin/bash\necho hi\n").as_deref(),
+            Some("sh")
+        );
+        assert_eq!(
+            detect("tool", "#!/usr/bin/env -S python3.12\n").as_deref(),
+            Some("python")
+        );
+    }
+
+    #[test]
+    fn a_shebang_does_not_override_the_extension() {
+        assert_eq!(detect("notes.txt", "#!/bin/bash\n"), None);
+    }
+
+    #[test]
+    fn unknown_files_have_no_filetype() {
+        assert_eq!(detect("data.xyz", ""), None);
+        assert_eq!(detect("README", "hello\n"), None);
+        assert_eq!(detect("run", "#!/usr/bin/awk\n"), None);
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tempfile::NamedTempFile;
+
+/// How a line differs from the last commit, as marked in the gutter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineChange {
+    Added,
+    Modified,
+    /// Lines were removed just below this one, or above it on the first line.
+    Deleted,
+}
+
+impl LineChange {
+    /// The gutter marker: `+`, `~` or `_`.
+    pub fn marker(self) -> char {
+        match self {
+            Self::Added => '+',
+            Self::Modified => '~',
+            Self::Deleted => '_',
+        }
+    }
+}
+
+/// How each line of `text`, the contents of the file at `path`, differs
+/// from the file in `HEAD`, by 0-based row. `None` when the file isn't
+/// committed in a git repository, or git can't be run.
+///
+/// `text` needn't be saved: like vim-gitgutter, both versions are written
+/// to temp files and compared with `git diff --no-index`. The temp files are
+/// created fresh with unguessable names, so nothing planted in the shared
+/// temp dir gets written through, and are removed when done.
+pub fn line_changes(path: &Path, text: &str) -> Option<BTreeMap<usize, LineChange>> {
+    let head = head_text(path)?;
+    let temp_file = |contents: &str| -> std::io::Result<NamedTempFile> {
+        let mut file = tempfile::Builder::new().prefix("fluxion-git-").tempfile()?;
+        file.write_all(contents.as_bytes())?;
+        Ok(file)
+    };
+    let output = temp_file(&head)
+        .and_then(|from| Ok((from, temp_file(text)?)))
+        .and_then(|(from, to)| {
+            Command::new("git")
+                .args([
+                    "diff",
+                    "--no-index",
+                    "--no-color",
+                    "--no-ext-diff",
+                    "-U0",
+                    "--",
+                ])
+                .args([from.path(), to.path()])
+                .stdin(Stdio::null())
+                .stderr(Stdio::null())
+                .output()
+        });
+    // `--no-index` exits with 1 when the files differ.
+    let output = output
+        .ok()
+        .filter(|o| matches!(o.status.code(), Some(0 | 1)))?;
+    Some(parse_diff(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The committed contents of `path`, with `\n` line endings, or `None` if it
+/// isn't tracked in a git repository.
+fn head_text(path: &Path) -> Option<String> {
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+    let name = path.file_name()?.to_str()?;
+    let output = Command::new("git")
+        .current_dir(dir.unwrap_or(Path::new(".")))
+        .args(["show", &format!("HEAD:./{}", name)])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.replace("\r\n", "\n"))
+}
+
+/// Reads the hunk headers of a `-U0` unified diff, such as
+/// `@@ -12,2 +12,3 @@`, into the change of each line of the new version.
+/// Where a hunk replaces lines, the first ones count as modified and any
+/// extra new ones as added.
+pub fn parse_diff(diff: &str) -> BTreeMap<usize, LineChange> {
+    let mut changes = BTreeMap::new();
+    for line in diff.lines() {
+        let Some(((_, old_len), (new_start, new_len))) = parse_hunk_header(line) else {
+            continue;
+        };
+        if new_len == 0 {
+            // The old lines went after new line `new_start`, 1-based.
+            changes.insert(new_start.saturating_sub(1), LineChange::Deleted);
+            continue;
+        }
+        let first = new_start.saturating_sub(1);
+        for row in first..first + new_len {
+            let change = if row - first < old_len {
+                LineChange::Modified
+            } else {
+                LineChange::Added
+            };
+            changes.insert(row, change);
+        }
+    }
+    changes
+}
+
+/// The `(start, length)` of the old and new ranges in a hunk header. A range
+/// written without a length is one line long.
+fn parse_hunk_header(line: &str) -> Option<((usize, usize), (usize, usize))> {
+    let ranges = line.strip_prefix("@@ -")?.split(" @@").next()?;
+    let (old, new) = ranges.split_once(" +")?;
+    let parse = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    Some((parse(old)?, parse(new)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use LineChange::{Added, Deleted, Modified};
+
+    fn changes(diff: &str) -> Vec<(usize, LineChange)> {
+        parse_diff(diff).into_iter().collect()
+    }
+
+    #[test]
+    fn added_lines_are_marked_added() {
+        assert_eq!(
+            changes("@@ -3,0 +4,2 @@\n+one\n+two\n"),
+            [(3, Added), (4, Added)]
+        );
+    }
+
+    #[test]
+    fn removed_lines_mark_the_line_above() {
+        assert_eq!(changes("@@ -5,2 +4,0 @@\n-one\n-two\n"), [(3, Deleted)]);
+        // Lines removed from the very top mark the first line.
+        assert_eq!(changes("@@ -1 +0,0 @@\n-gone\n"), [(0, Deleted)]);
+    }
+
+    #[test]
+    fn changed_lines_are_modified_then_added() {
+        assert_eq!(changes("@@ -2 +2 @@\n-a\n+b\n"), [(1, Modified)]);
+        assert_eq!(
+            changes("@@ -7,1 +7,3 @@\n-a\n+b\n+c\n+d\n"),
+            [(6, Modified), (7, Added), (8, Added)]
+        );
+    }
+
+    #[test]
+    fn every_hunk_of_a_diff_is_read() {
+        let diff = "\
+diff --git a/x b/x
+--- a/x
++++ b/x
+@@ -1,0 +2 @@ fn main() {
++    new();
+@@ -10,2 +11 @@
+-old
+-older
++newer
+@@ -20 +20,0 @@
+-gone
+";
+        assert_eq!(changes(diff), [(1, Added), (10, Modified), (19, Deleted)]);
+    }
+
+    #[test]
+    fn lines_that_only_look_like_hunks_are_skipped() {
+        assert_eq!(changes("@@ nonsense @@\n+@@ -1 +1 @@\n"), []);
+    }
+
+    #[test]
+    fn a_file_outside_a_repository_has_no_changes() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("loose.txt");
+        std::fs::write(&path, "text\n").expect("file written");
+        assert_eq!(line_changes(&path, "text\nmore\n"), None);
+    }
+
+    #[test]
+    fn an_edited_committed_file_is_compared_with_head() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .current_dir(dir.path())
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .expect("git runs");
+            assert!(status.success(), "git {:?}", args);
+        };
+        let path = dir.path().join("file.txt");
+        std::fs::write(&path, "one\ntwo\nthree\n").expect("file written");
+        git(&["init", "-q"]);
+        git(&["add", "file.txt"]);
+        git(&["commit", "-q", "-m", "init"]);
+
+        let changes = line_changes(&path, "one\nTWO\nthree\nfour\n").expect("tracked");
+        assert_eq!(
+            changes.into_iter().collect::<Vec<_>>(),
+            [(1, Modified), (3, Added)]
+        );
+    }
+}
@@ -0,0 +1,98 @@
+/// A parsed `:g/pattern/d` command, which deletes the lines matching a
+/// pattern, or with `:g!` or `:v` the lines that don't.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobalDelete {
+    pub pattern: String,
+    /// Delete the lines that don't match instead.
+    pub invert: bool,
+}
+
+impl GlobalDelete {
+    /// Parses `g/pattern/d`, `g!/pattern/d` or `v/pattern/d`, optionally with a
+    /// leading `%`. A `\/` in the pattern stands for a literal slash. Returns
+    /// `None` if `command` is not a global command at all.
+    pub fn parse(command: &str) -> Option<Result<Self, String>> {
+        let command = command.strip_prefix('%').unwrap_or(command);
+        let (invert, body) = if let Some(body) = command.strip_prefix("g/") {
+            (false, body)
+        } else if let Some(body) = command.strip_prefix("g!/") {
+            (true, body)
+        } else {
+            (true, command.strip_prefix("v/")?)
+        };
+
+        let mut pattern = String::new();
+        let mut chars = body.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('/') => pattern.push('/'),
+                    Some(other) => {
+                        pattern.push('\\');
+                        pattern.push(other);
+                    }
+                    None => pattern.push('\\'),
+                },
+                '/' => break,
+                _ => pattern.push(c),
+            }
+        }
+
+        if pattern.is_empty() {
+            return Some(Err("E35: No previous regular expression".to_string()));
+        }
+        match chars.as_str().trim() {
+            "d" | "delete" => Some(Ok(Self { pattern, invert })),
+            "" => Some(Err("E471: Argument required".to_string())),
+            other => Some(Err(format!("E492: Not an editor command: {}", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn global(pattern: &str, invert: bool) -> Option<Result<GlobalDelete, String>> {
+        Some(Ok(GlobalDelete {
+            pattern: pattern.to_string(),
+            invert,
+        }))
+    }
+
+    #[test]
+    fn parses_each_form() {
+        assert_eq!(GlobalDelete::parse("g/foo/d"), global("foo", false));
+        assert_eq!(GlobalDelete::parse("g!/foo/d"), global("foo", true));
+        assert_eq!(GlobalDelete::parse("v/foo/delete"), global("foo", true));
+        assert_eq!(GlobalDelete::parse("%g/foo/d"), global("foo", false));
+    }
+
+    #[test]
+    fn an_escaped_slash_is_part_of_the_pattern() {
+        assert_eq!(GlobalDelete::parse(r"g/a\/b/d"), global("a/b", false));
+        assert_eq!(GlobalDelete::parse(r"g/a\d/d"), global(r"a\d", false));
+    }
+
+    #[test]
+    fn other_commands_are_not_global() {
+        assert_eq!(GlobalDelete::parse("grep foo"), None);
+        assert_eq!(GlobalDelete::parse("s/a/b/"), None);
+    }
+
+    #[test]
+    fn reports_a_missing_pattern_or_command() {
+        assert_eq!(
+            GlobalDelete::parse("g//d"),
+            Some(Err("E35: No previous regular expression".to_string()))
+        );
+        assert_eq!(
+            GlobalDelete::parse("g/foo/"),
+            Some(Err("E471: Argument required".to_string()))
+        );
+        assert_eq!(
+            GlobalDelete::parse("g/foo/p"),
+            Some(Err("E492: Not an editor command: p".to_string()))
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use crate::quickfix::QuickfixEntry;
+use crate::search::Search;
+use ignore::WalkBuilder;
+use regex::Regex;
+use std::path::Path;
+
+/// How much of a file is checked for a NUL byte to tell binary files from
+/// text, as grep does.
+const BINARY_CHECK_LEN: usize = 8192;
+
+/// Every match of `regex` in `contents`, the text of the file at `path`, with
+/// its 1-based line and column and the line's text as the message.
+pub fn find_matches(path: &Path, contents: &str, regex: &Regex) -> Vec<QuickfixEntry> {
+    contents
+        .lines()
+        .enumerate()
+        .flat_map(|(row, line)| {
+            Search::find_in_line(regex, line)
+                .into_iter()
+                .map(move |cols| QuickfixEntry {
+                    path: path.to_path_buf(),
+                    line: row + 1,
+                    col: Some(cols.start + 1),
+                    message: line.trim().to_string(),
+                })
+        })
+        .collect()
+}
+
+/// Searches the files under `root` for `regex`, passing each match to
+/// `on_match` with its path relative to `root`. Files `.gitignore` leaves
+/// out, hidden files and binary files are skipped. Stops early once
+/// `on_match` returns `false`.
+pub fn grep_files(root: &Path, regex: &Regex, mut on_match: impl FnMut(QuickfixEntry) -> bool) {
+    for entry in WalkBuilder::new(root).build().flatten() {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let Ok(bytes) = std::fs::read(entry.path()) else {
+            continue;
+        };
+        if bytes[..bytes.len().min(BINARY_CHECK_LEN)].contains(&0) {
+            continue;
+        }
+        let path = entry.path().strip_prefix(root).unwrap_or(entry.path());
+        let contents = String::from_utf8_lossy(&bytes);
+        for found in find_matches(path, &contents, regex) {
+            if !on_match(found) {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn found(path: &str, line: usize, col: usize, message: &str) -> QuickfixEntry {
+        QuickfixEntry {
+            path: PathBuf::from(path),
+            line,
+            col: Some(col),
+            message: message.to_string(),
+        }
+    }
+
+    fn regex(pattern: &str) -> Regex {
+        Regex::new(pattern).expect("a valid pattern")
+    }
+
+    #[test]
+    fn every_match_in_a_file_is_found() {
+        let contents = "fn main() {\n    let x = foo(foo);\n}\n  foo\n";
+        assert_eq!(
+            find_matches(Path::new("src/main.rs"), contents, &regex("foo")),
+            [
+                found("src/main.rs", 2, 13, "let x = foo(foo);"),
+                found("src/main.rs", 2, 17, "let x = foo(foo);"),
+                found("src/main.rs", 4, 3, "foo"),
+            ]
+        );
+    }
+
+    #[test]
+    fn columns_count_chars_not_bytes() {
+        assert_eq!(
+            find_matches(Path::new("a.txt"), "café bar\n", &regex("bar")),
+            [found("a.txt", 1, 6, "café bar")]
+        );
+    }
+
+    #[test]
+    fn no_match_finds_nothing() {
+        assert!(find_matches(Path::new("a.txt"), "abc\n", &regex("z")).is_empty());
+    }
+
+    fn grep(root: &Path, pattern: &str) -> Vec<QuickfixEntry> {
+        let mut matches = Vec::new();
+        grep_files(root, &regex(pattern), |entry| {
+            matches.push(entry);
+            true
+        });
+        matches.sort_by(|a, b| a.path.cmp(&b.path));
+        matches
+    }
+
+    #[test]
+    fn the_walk_skips_ignored_and_binary_files() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        std::fs::create_dir(dir.path().join(".git")).expect("dir made");
+        std::fs::create_dir(dir.path().join("src")).expect("dir made");
+        std::fs::write(dir.path().join(".gitignore"), "target/\n").expect("file written");
+        std::fs::write(dir.path().join("src/lib.rs"), "needle\n").expect("file written");
+        std::fs::create_dir(dir.path().join("target")).expect("dir made");
+        std::fs::write(dir.path().join("target/out.rs"), "needle\n").expect("file written");
+        std::fs::write(dir.path().join("blob.bin"), b"needle\0\n").expect("file written");
+
+        assert_eq!(
+            grep(dir.path(), "needle"),
+            [found("src/lib.rs", 1, 1, "needle")]
+        );
+    }
+
+    #[test]
+    fn the_walk_stops_when_told_to() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        std::fs::write(dir.path().join("a.txt"), "x\nx\nx\n").expect("file written");
+        let mut count = 0;
+        grep_files(dir.path(), &regex("x"), |_| {
+            count += 1;
+            count < 2
+        });
+        assert_eq!(count, 2);
+    }
+}
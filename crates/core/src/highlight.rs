@@ -0,0 +1,441 @@
+use ropey::Rope;
+use std::ops::Range;
+
+/// Languages the highlighter knows how to tokenize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    PlainText,
+    Rust,
+    Json,
+}
+
+impl Language {
+    /// The language for a buffer's filetype, falling back to plain text.
+    pub fn from_filetype(filetype: Option<&str>) -> Self {
+        match filetype {
+            Some("rust") => Self::Rust,
+            Some("json") => Self::Json,
+            _ => Self::PlainText,
+        }
+    }
+}
+
+/// What a highlighted span of text is. Frontends decide how each kind looks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Keyword,
+    Type,
+    Constant,
+    String,
+    Number,
+    Comment,
+    Macro,
+    Attribute,
+    Property,
+}
+
+/// A char column range within a line and its kind.
+pub type Token = (Range<usize>, TokenKind);
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "type", "unsafe", "use",
+    "where", "while",
+];
+
+const RUST_PRIMITIVES: &[&str] = &[
+    "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64",
+    "i128", "isize", "f32", "f64",
+];
+
+/// Where a line leaves off, for constructs that continue onto the next line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum LineState {
+    #[default]
+    Normal,
+    /// Inside a (possibly nested) block comment of the given depth.
+    BlockComment(usize),
+    /// Inside a string literal.
+    Str,
+}
+
+#[derive(Debug, Clone)]
+struct HighlightedLine {
+    tokens: Vec<Token>,
+    end_state: LineState,
+}
+
+/// Tokenizes a buffer line by line, caching each line until an edit at or
+/// above it invalidates the cache.
+#[derive(Debug, Clone, Default)]
+pub struct Highlighter {
+    language: Language,
+    lines: Vec<HighlightedLine>,
+}
+
+impl Highlighter {
+    pub fn new(language: Language) -> Self {
+        Self {
+            language,
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Tokens for line `row` of `text`, tokenizing any uncached lines above it
+    /// first since they decide where the line starts.
+    pub fn line(&mut self, text: &Rope, row: usize) -> &[Token] {
+        if self.language == Language::PlainText || row >= text.len_lines() {
+            return &[];
+        }
+        while self.lines.len() <= row {
+            let idx = self.lines.len();
+            let state = self.lines.last().map(|l| l.end_state).unwrap_or_default();
+            let chars: Vec<char> = text.line(idx).chars().collect();
+            let line = match self.language {
+                Language::Rust => tokenize_rust(&chars, state),
+                Language::Json => tokenize_json(&chars),
+                Language::PlainText => HighlightedLine {
+                    tokens: Vec::new(),
+                    end_state: LineState::Normal,
+                },
+            };
+            self.lines.push(line);
+        }
+        &self.lines[row].tokens
+    }
+
+    /// Drops cached tokens for `row` and every line below it.
+    pub fn invalidate_from(&mut self, row: usize) {
+        self.lines.truncate(row);
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Scans a string body from `i` to its closing quote. Returns the index after
+/// the quote, or `None` if the line ends first.
+fn scan_string(chars: &[char], mut i: usize, quote: char) -> Option<usize> {
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            c if c == quote => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Scans a block comment body from `i` at nesting `depth`. Returns where the
+/// scan stopped and the depth left open (zero once the comment closes).
+fn scan_block_comment(chars: &[char], mut i: usize, mut depth: usize) -> (usize, usize) {
+    while i < chars.len() {
+        match (chars[i], chars.get(i + 1)) {
+            ('*', Some('/')) => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    break;
+                }
+            }
+            ('/', Some('*')) => {
+                depth += 1;
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+    (i, depth)
+}
+
+fn scan_number(chars: &[char], mut i: usize) -> usize {
+    while i < chars.len() {
+        let c = chars[i];
+        let next_is_digit = chars.get(i + 1).is_some_and(|n| n.is_ascii_digit());
+        if is_ident_char(c) || (c == '.' && next_is_digit) {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    i
+}
+
+fn tokenize_rust(chars: &[char], state: LineState) -> HighlightedLine {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    match state {
+        LineState::BlockComment(depth) => {
+            let (end, depth) = scan_block_comment(chars, 0, depth);
+            tokens.push((0..end, TokenKind::Comment));
+            if depth > 0 {
+                return HighlightedLine {
+                    tokens,
+                    end_state: LineState::BlockComment(depth),
+                };
+            }
+            i = end;
+        }
+        LineState::Str => match scan_string(chars, 0, '"') {
+            Some(end) => {
+                tokens.push((0..end, TokenKind::String));
+                i = end;
+            }
+            None => {
+                tokens.push((0..chars.len(), TokenKind::String));
+                return HighlightedLine {
+                    tokens,
+                    end_state: LineState::Str,
+                };
+            }
+        },
+        LineState::Normal => {}
+    }
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let start = i;
+
+        if c == '/' && next == Some('/') {
+            tokens.push((start..chars.len(), TokenKind::Comment));
+            break;
+        } else if c == '/' && next == Some('*') {
+            let (end, depth) = scan_block_comment(chars, i + 2, 1);
+            tokens.push((start..end, TokenKind::Comment));
+            if depth > 0 {
+                return HighlightedLine {
+                    tokens,
+                    end_state: LineState::BlockComment(depth),
+                };
+            }
+            i = end;
+        } else if c == '"' || (c == 'b' && next == Some('"')) {
+            let body = if c == 'b' { i + 2 } else { i + 1 };
+            match scan_string(chars, body, '"') {
+                Some(end) => {
+                    tokens.push((start..end, TokenKind::String));
+                    i = end;
+                }
+                None => {
+                    tokens.push((start..chars.len(), TokenKind::String));
+                    return HighlightedLine {
+                        tokens,
+                        end_state: LineState::Str,
+                    };
+                }
+            }
+        } else if c == '\'' && (next == Some('\\') || chars.get(i + 2) == Some(&'\'')) {
+            // A char literal; a quote followed by anything else is a lifetime.
+            let end = scan_string(chars, i + 1, '\'').unwrap_or(chars.len());
+            tokens.push((start..end, TokenKind::String));
+            i = end;
+        } else if c == '#' && matches!(next, Some('[') | Some('!')) {
+            let end = chars[i..]
+                .iter()
+                .position(|&c| c == ']')
+                .map_or(chars.len(), |p| i + p + 1);
+            tokens.push((start..end, TokenKind::Attribute));
+            i = end;
+        } else if c.is_ascii_digit() {
+            i = scan_number(chars, i);
+            tokens.push((start..i, TokenKind::Number));
+        } else if is_ident_start(c) {
+            while i < chars.len() && is_ident_char(chars[i]) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let kind = if chars.get(i) == Some(&'!') && chars.get(i + 1) != Some(&'=') {
+                i += 1;
+                Some(TokenKind::Macro)
+            } else if RUST_KEYWORDS.contains(&word.as_str()) {
+                Some(TokenKind::Keyword)
+            } else if word == "true" || word == "false" {
+                Some(TokenKind::Constant)
+            } else if RUST_PRIMITIVES.contains(&word.as_str())
+                || word.starts_with(|c: char| c.is_uppercase())
+            {
+                Some(TokenKind::Type)
+            } else {
+                None
+            };
+            if let Some(kind) = kind {
+                tokens.push((start..i, kind));
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    HighlightedLine {
+        tokens,
+        end_state: LineState::Normal,
+    }
+}
+
+fn tokenize_json(chars: &[char]) -> HighlightedLine {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+
+        if c == '"' {
+            i = scan_string(chars, i + 1, '"').unwrap_or(chars.len());
+            let is_key = chars[i..]
+                .iter()
+                .find(|c| !c.is_whitespace())
+                .is_some_and(|&c| c == ':');
+            let kind = if is_key {
+                TokenKind::Property
+            } else {
+                TokenKind::String
+            };
+            tokens.push((start..i, kind));
+        } else if c == '-' || c.is_ascii_digit() {
+            i += 1;
+            while i < chars.len()
+                && (chars[i].is_ascii_digit() || matches!(chars[i], '.' | 'e' | 'E' | '+' | '-'))
+            {
+                i += 1;
+            }
+            tokens.push((start..i, TokenKind::Number));
+        } else if c.is_ascii_alphabetic() {
+            while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if matches!(word.as_str(), "true" | "false" | "null") {
+                tokens.push((start..i, TokenKind::Constant));
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    HighlightedLine {
+        tokens,
+        end_state: LineState::Normal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text and kind of every token on each line of `text`.
+    fn tokens(language: Language, text: &str) -> Vec<Vec<(String, TokenKind)>> {
+        let rope = Rope::from_str(text);
+        let mut highlighter = Highlighter::new(language);
+        (0..rope.len_lines())
+            .map(|row| {
+                let line: Vec<char> = rope.line(row).chars().collect();
+                highlighter
+                    .line(&rope, row)
+                    .iter()
+                    .map(|(range, kind)| (line[range.clone()].iter().collect(), *kind))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn token(text: &str, kind: TokenKind) -> (String, TokenKind) {
+        (text.to_string(), kind)
+    }
+
+    #[test]
+    fn rust_keywords_types_and_literals_get_their_own_kinds() {
+        let lines = tokens(
+            Language::Rust,
+            "pub fn f(x: u8) -> &str { \"hi\" } // done\n",
+        );
+        assert_eq!(
+            lines[0],
+            [
+                token("pub", TokenKind::Keyword),
+                token("fn", TokenKind::Keyword),
+                token("u8", TokenKind::Type),
+                token("str", TokenKind::Type),
+                token("\"hi\"", TokenKind::String),
+                token("// done\n", TokenKind::Comment),
+            ]
+        );
+    }
+
+    #[test]
+    fn identifiers_are_not_keywords() {
+        let lines = tokens(Language::Rust, "let format = fnord;\n");
+        assert_eq!(lines[0], [token("let", TokenKind::Keyword)]);
+    }
+
+    #[test]
+    fn rust_block_comments_and_strings_continue_across_lines() {
+        let lines = tokens(Language::Rust, "/* a\nb */ fn\n\"x\ny\" let\n");
+        assert_eq!(lines[0], [token("/* a\n", TokenKind::Comment)]);
+        assert_eq!(
+            lines[1],
+            [
+                token("b */", TokenKind::Comment),
+                token("fn", TokenKind::Keyword)
+            ]
+        );
+        assert_eq!(lines[2], [token("\"x\n", TokenKind::String)]);
+        assert_eq!(
+            lines[3],
+            [
+                token("y\"", TokenKind::String),
+                token("let", TokenKind::Keyword)
+            ]
+        );
+    }
+
+    #[test]
+    fn json_keys_values_and_constants() {
+        let lines = tokens(Language::Json, "{\"a\": \"b\", \"n\": -1.5, \"t\": true}\n");
+        assert_eq!(
+            lines[0],
+            [
+                token("\"a\"", TokenKind::Property),
+                token("\"b\"", TokenKind::String),
+                token("\"n\"", TokenKind::Property),
+                token("-1.5", TokenKind::Number),
+                token("\"t\"", TokenKind::Property),
+                token("true", TokenKind::Constant),
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_text_has_no_tokens() {
+        assert!(
+            tokens(Language::PlainText, "fn main() {}\n")
+                .iter()
+                .all(|line| line.is_empty())
+        );
+    }
+
+    #[test]
+    fn invalidating_retokenizes_the_edited_lines() {
+        let mut rope = Rope::from_str("fn\nlet\n");
+        let mut highlighter = Highlighter::new(Language::Rust);
+        assert_eq!(highlighter.line(&rope, 1).len(), 1);
+
+        rope.remove(3..6);
+        rope.insert(3, "/* x */");
+        highlighter.invalidate_from(1);
+        assert_eq!(highlighter.line(&rope, 1), [(0..7, TokenKind::Comment)]);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::Cursor;
+use ropey::Rope;
+
+/// A point-in-time copy of a buffer's text and the cursor that goes with it.
+///
+/// Ropes share structure between clones, so taking a snapshot is cheap even for
+/// large buffers.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub text: Rope,
+    pub cursor: Cursor,
+}
+
+/// Per-buffer undo/redo stacks.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    undo_stack: Vec<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the state before a change. Any redo history is invalidated.
+    pub fn record(&mut self, snapshot: Snapshot) {
+        self.undo_stack.push(snapshot);
+        self.redo_stack.clear();
+    }
+
+    /// Steps back one change, stashing `current` for redo.
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let previous = self.undo_stack.pop()?;
+        self.redo_stack.push(current);
+        Some(previous)
+    }
+
+    /// Re-applies the most recently undone change, stashing `current` for undo.
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let next = self.redo_stack.pop()?;
+        self.undo_stack.push(current);
+        Some(next)
+    }
+
+    /// Drops the most recent change without making it redoable, for a change
+    /// that is being taken back rather than undone.
+    pub fn discard(&mut self) -> Option<Snapshot> {
+        self.undo_stack.pop()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(text: &str, col: usize) -> Snapshot {
+        Snapshot {
+            text: Rope::from_str(text),
+            cursor: Cursor::new(0, col),
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_round_trip() {
+        let mut history = History::new();
+        history.record(snapshot("a", 0));
+
+        let previous = history.undo(snapshot("ab", 1)).expect("one change to undo");
+        assert_eq!(previous.text.to_string(), "a");
+        assert_eq!(previous.cursor, Cursor::new(0, 0));
+
+        let next = history.redo(previous).expect("one change to redo");
+        assert_eq!(next.text.to_string(), "ab");
+        assert_eq!(next.cursor, Cursor::new(0, 1));
+        assert!(history.redo(next).is_none());
+    }
+
+    #[test]
+    fn recording_a_change_invalidates_redo() {
+        let mut history = History::new();
+        history.record(snapshot("a", 0));
+        let previous = history.undo(snapshot("ab", 1)).expect("one change to undo");
+
+        history.record(previous);
+        assert!(history.redo(snapshot("ac", 1)).is_none());
+    }
+
+    #[test]
+    fn undo_with_nothing_recorded_is_none() {
+        let mut history = History::new();
+        assert!(history.undo(snapshot("", 0)).is_none());
+
+        history.record(snapshot("a", 0));
+        history.clear();
+        assert!(history.undo(snapshot("a", 0)).is_none());
+    }
+
+    #[test]
+    fn a_discarded_change_cannot_be_redone() {
+        let mut history = History::new();
+        history.record(snapshot("a", 0));
+        history.record(snapshot("ab", 1));
+
+        let discarded = history.discard().expect("a change to discard");
+        assert_eq!(discarded.text.to_string(), "ab");
+        assert!(history.redo(snapshot("abc", 2)).is_none());
+        let previous = history.undo(discarded).expect("the earlier change");
+        assert_eq!(previous.text.to_string(), "a");
+    }
+}
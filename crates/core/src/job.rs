@@ -0,0 +1,13 @@
+use regex::Regex;
+
+/// Work the editor hands the frontend to run in the background. See
+/// [`Editor::take_pending_jobs`](crate::Editor::take_pending_jobs).
+#[derive(Debug, Clone)]
+pub enum Job {
+    /// A shell command from `:make` or `:!`, whose output goes to the output
+    /// buffer.
+    Shell(String),
+    /// `:grep`: every match of `regex` in the files under the working
+    /// directory, each added to the quickfix list.
+    Grep { pattern: String, regex: Regex },
+}
@@ -1,47 +1,367 @@
+use completion::Completion;
 use ropey::Rope;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::ops::{Range, RangeInclusive};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 mod buffer;
+mod clipboard;
+mod comment;
+mod completion;
+mod config;
 mod cursor;
+mod diagnostic;
+mod encoding;
 mod file_picker;
+mod filetype;
+mod git;
+mod global;
+mod grep;
+mod highlight;
+mod history;
+mod job;
+mod line_ending;
+mod location;
+mod message;
 mod mode;
+mod observer;
+mod operator;
+mod options;
+mod quickfix;
+mod range;
+mod recent;
+mod register;
+mod search;
+mod session;
+mod shell;
+mod snapshot;
+mod stats;
+mod substitute;
+mod swap;
 
-pub use buffer::{Buffer, BufferManager};
+pub use buffer::{Buffer, BufferKind, BufferManager};
+pub use clipboard::ClipboardProvider;
+#[cfg(feature = "clipboard")]
+pub use clipboard::SystemClipboard;
+pub use comment::line_comment_prefix;
+pub use completion::{command_line_candidates, complete_path};
+pub use config::config_dir;
 pub use cursor::Cursor;
+pub use diagnostic::{Diagnostic, Severity};
+pub use encoding::Encoding;
 pub use file_picker::{FileInfo, FilePicker};
+pub use filetype::detect_filetype;
+pub use git::{LineChange, line_changes, parse_diff};
+pub use global::GlobalDelete;
+pub use grep::{find_matches, grep_files};
+pub use highlight::{Highlighter, Language, Token, TokenKind};
+pub use history::{History, Snapshot};
+pub use job::Job;
+pub use line_ending::LineEnding;
+pub use location::parse_location;
+pub use message::MessageKind;
 pub use mode::Mode;
+pub use observer::{EditEvent, EditObserver};
+use operator::MotionKind;
+pub use operator::Operator;
+pub use options::{EditorOptions, LineNumberMode, OptionValue, SetArg};
+pub use quickfix::{QuickfixEntry, QuickfixList, parse_error_line};
+pub use range::parse_range;
+pub use recent::{MAX_RECENT_FILES, RecentFiles};
+pub use register::{CLIPBOARD_REGISTER, DEFAULT_REGISTER, Register, RegisterKind};
+pub use search::Search;
+pub use session::{Session, SessionBuffer};
+pub use shell::{check_status, make_command, run_shell, shell_command};
+pub use snapshot::{EditorSnapshot, PREVIEW_CHARS};
+pub use stats::TextStats;
+pub use substitute::Substitution;
+pub use swap::{has_swap_file, remove_swap_file, swap_path, write_swap_file};
 
+/// Minimum number of lines kept visible above and below the cursor when scrolling.
+const SCROLL_OFF: usize = 3;
+
+/// Most positions the jump list keeps before dropping the oldest.
+const JUMP_LIST_LEN: usize = 100;
+
+/// Most command lines the command history keeps, as vim's `history` option
+/// does.
+const MAX_COMMAND_HISTORY: usize = 200;
+
+/// Largest count a command takes; longer counts stop growing here so that
+/// counted motions stay quick and row arithmetic can't overflow.
+const MAX_COUNT: usize = 99_999;
+
+/// File `:mksession` writes to when no name is given.
+const DEFAULT_SESSION_FILE: &str = "session.json";
+
+/// How many `untitled` names the save dialog tries before leaving the name
+/// for the user to type.
+const MAX_UNTITLED_NAMES: usize = 999;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Action {
     Quit,
     Insert(char),
+    InsertTab,
     Delete,
+    /// A char typed into the `:`, `/` or save prompt.
+    InsertIntoCommand(char),
     DeleteFromCommand,
     NoOp,
     MoveUp,
     MoveDown,
     MoveLeft,
     MoveRight,
+    /// `w`: to the start of the next word.
+    MoveWordForward,
+    MoveLineStart,
+    MoveLineEnd,
+    MoveFirstNonBlank,
+    FindChar(char),
+    FindCharBackward(char),
+    TillChar(char),
+    TillCharBackward(char),
+    RepeatFind,
+    MatchBracket,
+    RepeatFindReverse,
+    GotoFirstLine,
+    GotoLastLine,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    /// Report line, word, char and byte counts, for the selection too in visual mode.
+    ShowStats,
+    Undo,
+    Redo,
+    /// `m<letter>`: remember the cursor position under a letter.
+    SetMark(char),
+    /// `` `<letter> ``: go back to a position set with `m`.
+    JumpToMark(char),
+    /// `Ctrl-o`: go back to where the cursor was before the last jump.
+    JumpBack,
+    /// `Ctrl-i`: go forward again after `Ctrl-o`.
+    JumpForward,
+    /// `Ctrl-n`: add a cursor at the next occurrence of the word under the cursor.
+    AddCursorAtNextMatch,
+    /// Replay the last change made from normal mode, as `.` does.
+    RepeatLastChange,
+    SelectRegister(char),
+    /// Start recording typed keys into a register, as `q<reg>` does.
+    RecordMacro(char),
+    /// Stop recording and store the keys in the register.
+    StopMacroRecording,
+    /// Replay the keys stored in a register. The frontend owns key mapping, so
+    /// it does the replaying; the editor ignores this action.
+    PlayMacro(char),
+    Yank,
+    PasteAfter,
+    PasteBefore,
+    DeleteSelection,
+    DeleteCharUnderCursor,
+    /// Toggle the case of `count` chars from the cursor and move past them.
+    ToggleCaseUnderCursor,
+    /// Wait for a motion and apply the operator to the text it moves over.
+    Operator(Operator),
+    /// `C`: replace from the cursor to the end of the line.
+    ChangeToLineEnd,
+    JoinLines,
+    IndentLine,
+    DedentLine,
+    /// `gcc` and `gc`: comment out lines, or uncomment them if they all are.
+    ToggleComment,
     CancelKeySequence,
     EnterInsertMode,
+    /// `R`: overwrite text instead of inserting it.
+    EnterReplaceMode,
+    AppendAfterCursor,
+    AppendLineEnd,
+    InsertLineStart,
+    OpenLineBelow,
+    OpenLineAbove,
     EnterNormalMode,
     EnterVisualMode,
+    EnterVisualLineMode,
+    EnterVisualBlockMode,
     EnterCommandMode,
     ExecuteCommand,
+    EnterSearchMode,
+    ExecuteSearch,
+    SearchNext,
+    SearchPrev,
+    /// A digit typed before a motion or operator, e.g. the `3` in `3j`.
+    CountDigit(usize),
     SwitchBuffer(usize),
+    NewBuffer,
     NextBuffer,
     PrevBuffer,
     CloseBuffer,
     CloseAllBuffersExcept,
     SaveBuffer,
     SaveBufferAs(Option<PathBuf>),
+    CompletePath,
+    /// Up on the command line: the previous command in the history.
+    CommandHistoryPrev,
+    /// Down on the command line: the next command in the history.
+    CommandHistoryNext,
+    /// Tab on the command line: the next completion of the command name or
+    /// path being typed.
+    CompleteCommand,
+    /// Shift-Tab on the command line: the previous completion.
+    CompleteCommandBack,
     EnterFilePicker,
     SelectFile(String),
     FilePickerUp,
     FilePickerDown,
     FilePickerEnter,
+    FilePickerParent,
+    FilePickerToggleHidden,
     FilePickerEsc,
     OpenFile(String),
+    EnterBufferList,
+    BufferListUp,
+    BufferListDown,
+    BufferListSelect,
+    ReloadBuffer,
     CancelDialog,
+    /// Enter in the output buffer: open the `file:line:col` on the cursor line.
+    OpenErrorLocation,
+    /// `:cnext`: open the next quickfix entry, wrapping around at the end.
+    QuickfixNext,
+    /// `:cprev`: open the previous quickfix entry, wrapping around at the start.
+    QuickfixPrev,
+}
+
+impl Action {
+    /// Whether the action changes the buffer text or starts insert mode to do
+    /// so. These are refused on a readonly buffer.
+    fn edits_text(&self) -> bool {
+        matches!(
+            self,
+            Action::Insert(_)
+                | Action::InsertTab
+                | Action::Delete
+                | Action::Undo
+                | Action::Redo
+                | Action::PasteAfter
+                | Action::PasteBefore
+                | Action::DeleteSelection
+                | Action::DeleteCharUnderCursor
+                | Action::ToggleCaseUnderCursor
+                | Action::Operator(
+                    Operator::Delete
+                        | Operator::Change
+                        | Operator::Lowercase
+                        | Operator::Uppercase
+                        | Operator::ToggleCase
+                )
+                | Action::ChangeToLineEnd
+                | Action::EnterReplaceMode
+                | Action::JoinLines
+                | Action::IndentLine
+                | Action::DedentLine
+                | Action::ToggleComment
+                | Action::EnterInsertMode
+                | Action::AppendAfterCursor
+                | Action::AppendLineEnd
+                | Action::InsertLineStart
+                | Action::OpenLineBelow
+                | Action::OpenLineAbove
+        )
+    }
+}
+
+/// A change `.` can replay: the normal mode action that started it with its
+/// count, then the motion that completed an operator and the keys typed if
+/// it entered insert mode.
+#[derive(Debug, Clone)]
+struct Change {
+    action: Action,
+    count: Option<usize>,
+    then: Vec<Action>,
+}
+
+/// Where a `/` search started, restored if it's cancelled. While the query
+/// is typed the cursor previews its first match.
+#[derive(Debug, Clone)]
+struct SearchOrigin {
+    cursor: Cursor,
+    scroll_offset: usize,
+    query: Option<String>,
+}
+
+/// Kinds of char a word is made of. A word is a run of one kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Blank,
+    Word,
+    Punctuation,
+}
+
+fn char_class(c: char) -> CharClass {
+    if c.is_whitespace() {
+        CharClass::Blank
+    } else if c.is_alphanumeric() || c == '_' {
+        CharClass::Word
+    } else {
+        CharClass::Punctuation
+    }
+}
+
+/// Brackets and quotes `autopairs` closes, with their closing char.
+const AUTO_PAIRS: [(char, char); 5] =
+    [('(', ')'), ('[', ']'), ('{', '}'), ('"', '"'), ('\'', '\'')];
+
+/// `arg` without one pair of quotes around it, so `:grep "a b"` searches for
+/// `a b`.
+fn unquote(arg: &str) -> &str {
+    let arg = arg.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = arg
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    arg
+}
+
+/// The first integer in `line`, with its sign, which `:sort n` orders lines by.
+fn first_number(line: &str) -> Option<i64> {
+    let start = line.find(|c: char| c.is_ascii_digit())?;
+    let digits = &line[start..];
+    let end = digits
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(digits.len());
+    let number: i64 = digits[..end].parse().unwrap_or(i64::MAX);
+    if line[..start].ends_with('-') {
+        Some(-number)
+    } else {
+        Some(number)
+    }
+}
+
+/// Each grapheme of `line`, such as an emoji or a letter with combining
+/// accents, with the char column it starts at.
+fn grapheme_cols(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.graphemes(true).scan(0, |col, grapheme| {
+        let start = *col;
+        *col += grapheme.chars().count();
+        Some((start, grapheme))
+    })
+}
+
+/// A single-line character search made with `f`, `F`, `t` or `T`.
+#[derive(Debug, Clone, Copy)]
+struct CharFind {
+    target: char,
+    forward: bool,
+    /// Stop one char short of the target, as `t` and `T` do.
+    till: bool,
 }
 
 /// The core editor state.
@@ -49,10 +369,98 @@ pub struct Editor {
     pub buffer_manager: BufferManager,
     pub cursor: Cursor,
     pub scroll_offset: usize,
+    /// Number of text rows the frontend last reported as visible.
+    pub viewport_height: usize,
+    /// Number of text columns the frontend last reported as visible.
+    pub viewport_width: usize,
+    /// First screen column shown when `wrap` is off and long lines scroll
+    /// sideways.
+    pub left_col: usize,
     pub should_quit: bool,
     pub mode: Mode,
     pub command_input: String,
+    /// Command lines run from `:`, oldest first, recalled with Up and Down.
+    pub command_history: Vec<String>,
+    /// The `command_history` entry being shown while recalling, and the
+    /// input typed before recalling began, which Down brings back at the end.
+    command_recall: Option<(usize, String)>,
+    /// Where `command_history` is kept between sessions, one command per
+    /// line. See [`Editor::load_command_history`].
+    command_history_file: Option<PathBuf>,
+    /// Candidates Tab is cycling through on the command line.
+    command_completion: Option<Completion>,
     pub file_picker: FilePicker,
+    /// Highlighted row of the buffer list, indexing [`Editor::get_buffers`].
+    pub buffer_list_idx: usize,
+    pub registers: HashMap<char, Register>,
+    /// Register chosen with `"<reg>` for the next yank or paste.
+    pub selected_register: Option<char>,
+    /// Backs the `+` register. Without one, `+` acts like any other register.
+    clipboard: Option<Box<dyn ClipboardProvider>>,
+    /// Told about every change to buffer text, in the order they were added.
+    observers: Vec<Box<dyn EditObserver>>,
+    /// Where visual mode was entered; the selection spans from here to the cursor.
+    pub selection_anchor: Option<Cursor>,
+    pub search: Search,
+    pub options: EditorOptions,
+    /// The last `f`/`F`/`t`/`T`, repeated by `;` and `,`.
+    last_find: Option<CharFind>,
+    /// Feedback from the last command, shown until the next keypress.
+    pub status_message: Option<(String, MessageKind)>,
+    /// Count typed so far for the next motion or operator.
+    pub pending_count: Option<usize>,
+    /// Whether the current change already has an undo snapshot. Stays set for a
+    /// whole insert session so it undoes as one step.
+    change_open: bool,
+    /// Positions set with `m`, as `(buffer id, cursor)`. They don't move
+    /// when text is edited above them.
+    pub marks: HashMap<char, (usize, Cursor)>,
+    /// Positions jumped away from by searches, `gg`, `G`, `:N`, `%` and
+    /// buffer switches, as `(buffer id, cursor)`, oldest first.
+    jump_list: Vec<(usize, Cursor)>,
+    /// Where `Ctrl-o` and `Ctrl-i` are in `jump_list`. Equal to its length
+    /// unless walking back through it.
+    jump_idx: usize,
+    /// Chars overwritten so far in replace mode, latest last, for Backspace to
+    /// put back. `None` where a typed char went past the end of the line.
+    replaced: Vec<Option<char>>,
+    /// Rows of the last visual selection, which a command line starting with
+    /// `'<,'>` applies to.
+    visual_rows: Option<RangeInclusive<usize>>,
+    /// Set while a search query is being typed.
+    search_origin: Option<SearchOrigin>,
+    /// Extra cursors that insert mode edits apply to alongside `cursor`,
+    /// added with `Ctrl-n`. Esc in normal mode drops them.
+    pub secondary_cursors: Vec<Cursor>,
+    /// Operator typed in normal mode, with its count, waiting for a motion.
+    pending_operator: Option<(Operator, Option<usize>)>,
+    /// The change being recorded while its operator or insert session is
+    /// still open.
+    recording: Option<Change>,
+    /// The last finished change, replayed by `.`.
+    last_change: Option<Change>,
+    /// Register a macro is being recorded into and the keys recorded so far.
+    macro_recording: Option<(char, String)>,
+    /// When set, opened files are read by the frontend in the background
+    /// instead of on the spot. See [`Editor::take_pending_loads`].
+    pub defer_file_loads: bool,
+    /// Placeholder buffers waiting for the frontend to read their file.
+    pending_loads: Vec<(usize, PathBuf)>,
+    /// When set, `:make`, `:!` and `:grep` are run by the frontend in the
+    /// background. See [`Editor::take_pending_jobs`].
+    pub defer_jobs: bool,
+    /// Jobs waiting for the frontend to start them, by job id.
+    pending_jobs: Vec<(usize, Job)>,
+    /// The job whose results are being collected, by id. Results from any
+    /// other job are dropped.
+    job: Option<(usize, Job)>,
+    last_job_id: usize,
+    /// Locations read from the output of the last job, or found by `:grep`.
+    pub quickfix: QuickfixList,
+    /// Rows of the output buffer already read into `quickfix`.
+    quickfix_rows: usize,
+    /// Whether the frontend shows the quickfix list, as `:copen` asks.
+    pub show_quickfix: bool,
 }
 
 impl Editor {
@@ -61,17 +469,111 @@ impl Editor {
             buffer_manager: BufferManager::new(),
             cursor: Cursor::new(0, 0),
             scroll_offset: 0,
+            viewport_height: 0,
+            viewport_width: 0,
+            left_col: 0,
             should_quit: false,
             mode: Mode::Normal,
             command_input: String::new(),
+            command_history: Vec::new(),
+            command_recall: None,
+            command_history_file: None,
+            command_completion: None,
             file_picker: FilePicker::new(),
+            buffer_list_idx: 0,
+            registers: HashMap::new(),
+            selected_register: None,
+            clipboard: Self::system_clipboard(),
+            observers: Vec::new(),
+            selection_anchor: None,
+            search: Search::new(),
+            options: EditorOptions::default(),
+            last_find: None,
+            status_message: None,
+            pending_count: None,
+            defer_file_loads: false,
+            pending_loads: Vec::new(),
+            defer_jobs: false,
+            pending_jobs: Vec::new(),
+            job: None,
+            last_job_id: 0,
+            quickfix: QuickfixList::default(),
+            quickfix_rows: 0,
+            show_quickfix: false,
+            change_open: false,
+            marks: HashMap::new(),
+            jump_list: Vec::new(),
+            jump_idx: 0,
+            replaced: Vec::new(),
+            visual_rows: None,
+            search_origin: None,
+            secondary_cursors: Vec::new(),
+            pending_operator: None,
+            recording: None,
+            last_change: None,
+            macro_recording: None,
+        }
+    }
+
+    #[cfg(feature = "clipboard")]
+    fn system_clipboard() -> Option<Box<dyn ClipboardProvider>> {
+        match SystemClipboard::new() {
+            Ok(clipboard) => Some(Box::new(clipboard)),
+            Err(e) => {
+                tracing::warn!("System clipboard unavailable: {}", e);
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    fn system_clipboard() -> Option<Box<dyn ClipboardProvider>> {
+        None
+    }
+
+    /// Adds an observer to be told about each edit from now on.
+    pub fn add_observer(&mut self, observer: Box<dyn EditObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// Passes the event `make` builds to every observer. It isn't built when
+    /// nobody is listening.
+    fn notify(&mut self, make: impl FnOnce(&Self) -> EditEvent) {
+        if self.observers.is_empty() {
+            return;
         }
+        let event = make(self);
+        for observer in &mut self.observers {
+            observer.on_edit(&event);
+        }
+    }
+
+    /// Tells the observers the whole text of buffer `buffer_id` changed.
+    fn notify_reset(&mut self, buffer_id: usize) {
+        self.notify(|_| EditEvent::Reset { buffer_id });
+    }
+
+    /// Replaces the provider behind the `+` register, e.g. with a test double.
+    pub fn set_clipboard_provider(&mut self, provider: Option<Box<dyn ClipboardProvider>>) {
+        self.clipboard = provider;
     }
 
     pub fn get_current_text(&self) -> &Rope {
         &self.buffer_manager.current_buffer().text
     }
 
+    /// Register a macro is being recorded into, if any.
+    pub fn recording_macro(&self) -> Option<char> {
+        self.macro_recording.as_ref().map(|(name, _)| *name)
+    }
+
+    /// Adds keys, in the frontend's notation, to the macro being recorded.
+    pub fn record_macro_keys(&mut self, keys: &str) {
+        if let Some((_, recorded)) = &mut self.macro_recording {
+            recorded.push_str(keys);
+        }
+    }
+
     pub fn get_current_title(&self) -> &str {
         self.buffer_manager.current_buffer().title.as_str()
     }
@@ -84,297 +586,3286 @@ impl Editor {
         self.buffer_manager.current_buffer().path.as_ref()
     }
 
+    /// Number of lines in the current buffer, not counting the empty line
+    /// after a trailing newline.
+    pub fn line_count(&self) -> usize {
+        self.last_line() + 1
+    }
+
     pub fn get_buffers(&self) -> Vec<&Buffer> {
         self.buffer_manager.list_buffers()
     }
 
-    fn cursor_to_byte(&self) -> usize {
+    /// The cursor as a char index into the rope, which is what its inserts and
+    /// removals take. Not a byte offset.
+    fn cursor_to_char_idx(&self) -> usize {
         let buffer = self.buffer_manager.current_buffer();
         buffer.text.line_to_char(self.cursor.row) + self.cursor.col
     }
 
-    fn clamp_col_to_line(&mut self) {
+    fn cursor_from_char_idx(&self, char_idx: usize) -> Cursor {
+        let text = self.get_current_text();
+        let row = text.char_to_line(char_idx);
+        Cursor::new(row, char_idx - text.line_to_char(row))
+    }
+
+    /// Snapshots the current buffer for undo unless the change in progress
+    /// already has one.
+    fn begin_change(&mut self) {
+        if self.change_open {
+            return;
+        }
+        self.change_open = true;
+        let cursor = self.cursor;
+        let buffer = self.buffer_manager.current_buffer_mut();
+        let snapshot = Snapshot {
+            text: buffer.text.clone(),
+            cursor,
+        };
+        buffer.history.record(snapshot);
+    }
+
+    /// Inserts `text` at `char_idx` in the current buffer. All buffer edits go
+    /// through here or `remove_text` so they are recorded for undo.
+    fn insert_text(&mut self, char_idx: usize, text: &str) {
+        let buffer = self.buffer_manager.current_buffer();
+        if buffer.loading || buffer.readonly {
+            return;
+        }
+        self.begin_change();
+        self.notify(|editor| EditEvent::Insert {
+            buffer_id: editor.buffer_manager.current_buffer_id(),
+            char_idx,
+            position: editor.cursor_from_char_idx(char_idx),
+            text: text.to_string(),
+        });
+        let buffer = self.buffer_manager.current_buffer_mut();
+        let row = buffer.text.char_to_line(char_idx);
+        buffer.highlighter.invalidate_from(row);
+        buffer.text.insert(char_idx, text);
+        buffer.dirty = true;
+        buffer.is_transient = false;
+    }
+
+    /// Removes the chars in `range` from the current buffer.
+    fn remove_text(&mut self, range: Range<usize>) {
         let buffer = self.buffer_manager.current_buffer();
-        let line_len = buffer.text.line(self.cursor.row).len_chars();
+        if range.is_empty() || buffer.loading || buffer.readonly {
+            return;
+        }
+        self.begin_change();
+        self.notify(|editor| EditEvent::Delete {
+            buffer_id: editor.buffer_manager.current_buffer_id(),
+            range: range.clone(),
+            position: editor.cursor_from_char_idx(range.start),
+            text: editor.get_current_text().slice(range.clone()).to_string(),
+        });
+        let buffer = self.buffer_manager.current_buffer_mut();
+        let row = buffer.text.char_to_line(range.start);
+        buffer.highlighter.invalidate_from(row);
+        buffer.text.remove(range);
+        buffer.dirty = true;
+        buffer.is_transient = false;
+    }
+
+    fn undo(&mut self) {
+        let current = Snapshot {
+            text: self.get_current_text().clone(),
+            cursor: self.cursor,
+        };
+        let buffer = self.buffer_manager.current_buffer_mut();
+        if let Some(snapshot) = buffer.history.undo(current) {
+            buffer.set_text(snapshot.text);
+            buffer.dirty = true;
+            self.cursor = snapshot.cursor;
+            self.clamp_cursor();
+            self.notify_reset(self.buffer_manager.current_buffer_id());
+        }
+    }
+
+    fn redo(&mut self) {
+        let current = Snapshot {
+            text: self.get_current_text().clone(),
+            cursor: self.cursor,
+        };
+        let buffer = self.buffer_manager.current_buffer_mut();
+        if let Some(snapshot) = buffer.history.redo(current) {
+            buffer.set_text(snapshot.text);
+            buffer.dirty = true;
+            self.cursor = snapshot.cursor;
+            self.clamp_cursor();
+            self.notify_reset(self.buffer_manager.current_buffer_id());
+        }
+    }
+
+    /// Number of chars on `row`, excluding the line terminator.
+    fn line_len(&self, row: usize) -> usize {
+        let line = self.get_current_text().line(row);
+        let mut len = line.len_chars();
+        if len > 0 && line.char(len - 1) == '\n' {
+            len -= 1;
+            if len > 0 && line.char(len - 1) == '\r' {
+                len -= 1;
+            }
+        }
+        len
+    }
+
+    /// Column of the first non-whitespace char on `row`, or the last column if the
+    /// line is blank.
+    fn first_non_blank_col(&self, row: usize) -> usize {
+        let line_len = self.line_len(row);
+        self.get_current_text()
+            .line(row)
+            .chars()
+            .take(line_len)
+            .position(|c| !c.is_whitespace())
+            .unwrap_or(line_len.saturating_sub(1))
+    }
+
+    /// Rows a line command works on: those of the visual selection, or `count`
+    /// lines from the cursor down.
+    fn target_rows(&self, count: usize) -> RangeInclusive<usize> {
+        match self.selection_range() {
+            Some((start, end)) if self.mode.is_visual() => start.row..=end.row,
+            _ => {
+                self.cursor.row
+                    ..=self
+                        .cursor
+                        .row
+                        .saturating_add(count - 1)
+                        .min(self.last_line())
+            }
+        }
+    }
+
+    /// Comments out `rows` with the buffer's line comment prefix, lined up at
+    /// their smallest indent, or takes the prefix off again when every
+    /// non-blank row already starts with it. Blank rows are left alone.
+    fn toggle_comment(&mut self, rows: RangeInclusive<usize>) {
+        let filetype = self.buffer_manager.current_buffer().filetype.as_deref();
+        let prefix = line_comment_prefix(filetype);
+        let lines: Vec<(usize, String, usize)> = rows
+            .filter_map(|row| {
+                let line = self.line_text(row);
+                let indent = line.chars().position(|c| !c.is_whitespace())?;
+                Some((row, line, indent))
+            })
+            .collect();
+        let uncomment = !lines.is_empty()
+            && lines.iter().all(|(_, line, indent)| {
+                line.chars()
+                    .skip(*indent)
+                    .collect::<String>()
+                    .starts_with(prefix)
+            });
+        let min_indent = lines
+            .iter()
+            .map(|(_, _, indent)| *indent)
+            .min()
+            .unwrap_or(0);
+
+        for (row, line, indent) in lines.into_iter().rev() {
+            let line_start = self.get_current_text().line_to_char(row);
+            if uncomment {
+                let start = line_start + indent;
+                let mut end = start + prefix.chars().count();
+                if line.chars().nth(end - line_start) == Some(' ') {
+                    end += 1;
+                }
+                self.remove_text(start..end);
+            } else {
+                self.insert_text(line_start + min_indent, &format!("{} ", prefix));
+            }
+        }
+        self.clamp_col_to_line();
+    }
+
+    /// Joins the cursor's line with the next one, replacing the line break and
+    /// the next line's indentation with a single space. Does nothing on the
+    /// last line.
+    fn join_line(&mut self) {
+        let row = self.cursor.row;
+        if row >= self.last_line() {
+            return;
+        }
+        let text = self.get_current_text();
+        let line_len = self.line_len(row);
+        let join_idx = text.line_to_char(row) + line_len;
+        let next_start = text.line_to_char(row + 1);
+        let next_line = text.line(row + 1);
+        let indent_len = next_line
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .count();
+        let next_first = next_line.chars().nth(indent_len);
+        let ends_blank = line_len == 0 || text.char(join_idx - 1).is_whitespace();
+
+        let separator = match next_first {
+            None | Some('\n') | Some('\r') | Some(')') => "",
+            _ if ends_blank => "",
+            _ => " ",
+        };
+        self.remove_text(join_idx..next_start + indent_len);
+        self.insert_text(join_idx, separator);
+        self.cursor = Cursor::new(row, line_len);
+    }
+
+    /// Widens (or narrows) the leading whitespace of `row` by one shift width,
+    /// rebuilding it from tabs or spaces per `expand_tabs`. Blank lines are
+    /// left alone.
+    fn shift_line(&mut self, row: usize, indent: bool) {
+        let line_len = self.line_len(row);
+        let indent_len = self
+            .get_current_text()
+            .line(row)
+            .chars()
+            .take(line_len)
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .count();
+        if indent_len == line_len {
+            return;
+        }
+
+        let width = self.display_col(row, indent_len);
+        let new_width = if indent {
+            width + self.options.shift_width
+        } else {
+            width.saturating_sub(self.options.shift_width)
+        };
+        let new_indent = if self.options.expand_tabs {
+            " ".repeat(new_width)
+        } else {
+            let tab_width = self.options.tab_width.max(1);
+            "\t".repeat(new_width / tab_width) + &" ".repeat(new_width % tab_width)
+        };
+
+        let line_start = self.get_current_text().line_to_char(row);
+        let current: String = self
+            .get_current_text()
+            .slice(line_start..line_start + indent_len)
+            .into();
+        if current != new_indent {
+            self.remove_text(line_start..line_start + indent_len);
+            self.insert_text(line_start, &new_indent);
+        }
+    }
+
+    /// Leading whitespace of `row`, up to char column `max_col`, for a line
+    /// opened from it. Empty when auto-indent is off.
+    fn auto_indent(&self, row: usize, max_col: usize) -> String {
+        if !self.options.auto_indent {
+            return String::new();
+        }
+        self.get_current_text()
+            .line(row)
+            .chars()
+            .take(max_col)
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect()
+    }
+
+    /// Index of the last line, ignoring the empty line ropey reports after a
+    /// trailing newline.
+    fn last_line(&self) -> usize {
+        let text = self.get_current_text();
+        let last = text.len_lines().saturating_sub(1);
+        if last > 0 && text.line(last).len_chars() == 0 {
+            last - 1
+        } else {
+            last
+        }
+    }
+
+    /// Pulls the cursor back inside the buffer after the text changed under it.
+    fn clamp_cursor(&mut self) {
+        let last_row = self.last_line();
+        self.cursor.row = self.cursor.row.min(last_row);
+        self.clamp_col_to_line();
+    }
+
+    fn clamp_col_to_line(&mut self) {
+        let line_len = self.line_len(self.cursor.row);
         if self.cursor.col > line_len {
             self.cursor.col = line_len;
         }
     }
 
+    /// Screen column of char column `col` on `row`, with tabs expanded to the
+    /// next tab stop and wide chars such as CJK or emoji taking two cells.
+    pub fn display_col(&self, row: usize, col: usize) -> usize {
+        let line = self.line_text(row);
+        grapheme_cols(&line)
+            .take_while(|(start, _)| *start < col)
+            .fold(0, |display, (_, grapheme)| {
+                display + self.grapheme_width(grapheme, display)
+            })
+    }
+
+    /// Char column of the grapheme on `row` that covers screen column
+    /// `display`, clamped to the end of the line.
+    fn col_at_display(&self, row: usize, display: usize) -> usize {
+        let line = self.line_text(row);
+        let mut width = 0;
+        for (col, grapheme) in grapheme_cols(&line) {
+            width += self.grapheme_width(grapheme, width);
+            if width > display {
+                return col;
+            }
+        }
+        self.line_len(row)
+    }
+
+    /// Screen cells taken by `grapheme` when drawn at screen column `display`.
+    fn grapheme_width(&self, grapheme: &str, display: usize) -> usize {
+        let tab_width = self.options.tab_width.max(1);
+        match grapheme {
+            "\t" => tab_width - display % tab_width,
+            _ => grapheme.width(),
+        }
+    }
+
+    /// The text of `row` without its line terminator.
+    fn line_text(&self, row: usize) -> String {
+        let len = self.line_len(row);
+        self.get_current_text()
+            .line(row)
+            .chars()
+            .take(len)
+            .collect()
+    }
+
+    /// Char column of the grapheme after the one at `col` on `row`, or the end
+    /// of the line.
+    fn next_grapheme_col(&self, row: usize, col: usize) -> usize {
+        let line = self.line_text(row);
+        grapheme_cols(&line)
+            .map(|(start, _)| start)
+            .find(|&start| start > col)
+            .unwrap_or_else(|| line.chars().count())
+    }
+
+    /// Char column of the grapheme before `col` on `row`, or the start of the
+    /// line.
+    fn prev_grapheme_col(&self, row: usize, col: usize) -> usize {
+        let line = self.line_text(row);
+        grapheme_cols(&line)
+            .map(|(start, _)| start)
+            .take_while(|&start| start < col)
+            .last()
+            .unwrap_or(0)
+    }
+
+    /// Jumps to the bracket matching the one under the cursor, or the first one
+    /// after it on the line. Stays put when the bracket is unmatched.
+    fn match_bracket(&mut self) {
+        const PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+        let text = self.get_current_text();
+        let row = self.cursor.row;
+        let line_start = text.line_to_char(row);
+        let Some((start, (open, close))) = (self.cursor.col..self.line_len(row))
+            .map(|col| line_start + col)
+            .find_map(|idx| {
+                let c = text.char(idx);
+                let pair = PAIRS.into_iter().find(|&(o, cl)| c == o || c == cl)?;
+                Some((idx, pair))
+            })
+        else {
+            return;
+        };
+        let forward = text.char(start) == open;
+
+        let mut depth = 0usize;
+        let mut idx = start;
+        let found = loop {
+            let c = text.char(idx);
+            if c == open {
+                depth = if forward { depth + 1 } else { depth - 1 };
+            } else if c == close {
+                depth = if forward { depth - 1 } else { depth + 1 };
+            }
+            if depth == 0 {
+                break Some(idx);
+            }
+            if forward && idx + 1 < text.len_chars() {
+                idx += 1;
+            } else if !forward && idx > 0 {
+                idx -= 1;
+            } else {
+                break None;
+            }
+        };
+
+        if let Some(idx) = found {
+            self.cursor = self.cursor_from_char_idx(idx);
+        }
+    }
+
+    /// Moves to the `count`th occurrence of the target on the cursor's line,
+    /// leaving the cursor alone if there aren't that many. A repeated `t`/`T`
+    /// skips the char right next to the cursor so it doesn't get stuck.
+    fn find_char(&mut self, find: CharFind, count: usize, repeat: bool) {
+        let row = self.cursor.row;
+        let col = self.cursor.col;
+        let line: Vec<char> = self
+            .get_current_text()
+            .line(row)
+            .chars()
+            .take(self.line_len(row))
+            .collect();
+        let skip = usize::from(find.till && repeat);
+
+        let found = if find.forward {
+            (col + 1 + skip..line.len())
+                .filter(|&i| line[i] == find.target)
+                .nth(count - 1)
+                .map(|i| if find.till { i - 1 } else { i })
+        } else {
+            (0..col.saturating_sub(skip))
+                .rev()
+                .filter(|&i| line[i] == find.target)
+                .nth(count - 1)
+                .map(|i| if find.till { i + 1 } else { i })
+        };
+        if let Some(col) = found {
+            self.cursor.col = col;
+        }
+    }
+
+    /// Moves to `row`, keeping the cursor's screen column across tabs.
+    fn move_to_row(&mut self, row: usize) {
+        let display = self.display_col(self.cursor.row, self.cursor.col);
+        self.cursor.row = row;
+        self.cursor.col = self.col_at_display(row, display);
+    }
+
     fn move_up(&mut self) {
         if self.cursor.row > 0 {
-            self.cursor.row -= 1;
-            self.clamp_col_to_line();
+            self.move_to_row(self.cursor.row - 1);
         }
     }
 
     fn move_down(&mut self) {
         let buffer = self.buffer_manager.current_buffer();
         if self.cursor.row < buffer.text.len_lines().saturating_sub(1) {
-            self.cursor.row += 1;
-            self.clamp_col_to_line();
+            self.move_to_row(self.cursor.row + 1);
         }
     }
 
     fn move_left(&mut self) {
         if self.cursor.col > 0 {
-            self.cursor.col -= 1;
+            self.cursor.col = self.prev_grapheme_col(self.cursor.row, self.cursor.col);
         } else if self.cursor.row > 0 {
             self.cursor.row -= 1;
-            let buffer = self.buffer_manager.current_buffer();
-            self.cursor.col = buffer.text.line(self.cursor.row).len_chars();
+            self.cursor.col = self.line_len(self.cursor.row);
         }
     }
 
     fn move_right(&mut self) {
-        let buffer = self.buffer_manager.current_buffer();
-        let line_len = buffer.text.line(self.cursor.row).len_chars();
+        let line_len = self.line_len(self.cursor.row);
         if self.cursor.col < line_len {
-            self.cursor.col += 1;
-        } else if self.cursor.row < buffer.text.len_lines().saturating_sub(1) {
+            self.cursor.col = self.next_grapheme_col(self.cursor.row, self.cursor.col);
+        } else if self.cursor.row < self.get_current_text().len_lines().saturating_sub(1) {
             self.cursor.row += 1;
             self.cursor.col = 0;
         }
     }
 
+    /// Scrolls the view `lines` rows down (or up) and moves the cursor the
+    /// same distance, so it stays put on screen until a buffer edge stops it.
+    fn scroll_lines(&mut self, lines: usize, down: bool) {
+        let last_line = self.last_line();
+        let max_scroll = self.line_count().saturating_sub(self.viewport_height);
+        let row = if down {
+            self.scroll_offset = (self.scroll_offset + lines).min(max_scroll);
+            self.cursor.row + lines
+        } else {
+            self.scroll_offset = self.scroll_offset.saturating_sub(lines);
+            self.cursor.row.saturating_sub(lines)
+        };
+
+        // Keep the cursor inside the `SCROLL_OFF` margins of the new view so
+        // the view doesn't scroll back to reach it.
+        let margin = SCROLL_OFF.min(self.viewport_height.saturating_sub(1) / 2);
+        let top = if self.scroll_offset > 0 {
+            self.scroll_offset + margin
+        } else {
+            0
+        };
+        let bottom = if self.scroll_offset < max_scroll {
+            (self.scroll_offset + self.viewport_height).saturating_sub(margin + 1)
+        } else {
+            last_line
+        };
+        self.move_to_row(row.clamp(top, bottom.max(top)).min(last_line));
+    }
+
+    fn goto_line(&mut self, row: usize) {
+        self.cursor.row = row.min(self.last_line());
+        self.clamp_col_to_line();
+    }
+
+    fn move_line_start(&mut self) {
+        self.cursor.col = 0;
+    }
+
+    /// Places the cursor on the last char of the line (column 0 for an empty line).
+    fn move_line_end(&mut self) {
+        self.cursor.col = self.line_len(self.cursor.row).saturating_sub(1);
+    }
+
+    fn move_first_non_blank(&mut self) {
+        self.cursor.col = self.first_non_blank_col(self.cursor.row);
+    }
+
+    /// Records the visible text size and re-scrolls so the cursor stays in view.
+    pub fn set_viewport_size(&mut self, width: usize, height: usize) {
+        self.viewport_width = width;
+        self.viewport_height = height;
+        self.scroll_to_cursor(height);
+    }
+
+    /// Adjusts `scroll_offset` so the cursor row lies within
+    /// `[scroll_offset, scroll_offset + viewport_height)`, keeping a `SCROLL_OFF`
+    /// margin where the buffer is long enough. Wrapped lines count for every
+    /// screen row they take. Without `wrap`, also scrolls sideways to the
+    /// cursor's column.
+    pub fn scroll_to_cursor(&mut self, viewport_height: usize) {
+        if viewport_height == 0 {
+            return;
+        }
+
+        let margin = SCROLL_OFF.min(viewport_height.saturating_sub(1) / 2);
+        let row = self.cursor.row;
+
+        if row < self.scroll_offset + margin {
+            self.scroll_offset = row.saturating_sub(margin);
+        } else if row + margin >= self.scroll_offset + viewport_height {
+            self.scroll_offset = row + margin + 1 - viewport_height;
+        }
+
+        let len_lines = self.get_current_text().len_lines();
+        self.scroll_offset = self
+            .scroll_offset
+            .min(len_lines.saturating_sub(viewport_height));
+
+        if self.options.wrap {
+            self.left_col = 0;
+            // Lines above the cursor may wrap onto more rows than the view has.
+            let below = (row + margin).min(self.last_line());
+            let mut used: usize = (self.scroll_offset..=below)
+                .map(|r| self.screen_rows(r))
+                .sum();
+            while used > viewport_height && self.scroll_offset < row {
+                used -= self.screen_rows(self.scroll_offset);
+                self.scroll_offset += 1;
+            }
+        } else if self.viewport_width > 0 {
+            let col = self.display_col(row, self.cursor.col);
+            if col < self.left_col {
+                self.left_col = col;
+            } else if col >= self.left_col + self.viewport_width {
+                self.left_col = col + 1 - self.viewport_width;
+            }
+        }
+    }
+
+    /// Screen columns of `row` at which each of the screen rows it takes
+    /// starts, the first always 0. A grapheme that doesn't fit at the end of a
+    /// row moves whole to the next one. Just `[0]` when `wrap` is off.
+    pub fn wrap_starts(&self, row: usize) -> Vec<usize> {
+        let width = self.viewport_width;
+        let mut starts = vec![0];
+        if !self.options.wrap || width == 0 {
+            return starts;
+        }
+        let line = self.line_text(row);
+        let mut display = 0;
+        for (_, grapheme) in grapheme_cols(&line) {
+            let grapheme_width = self.grapheme_width(grapheme, display);
+            let row_start = starts[starts.len() - 1];
+            if display > row_start && display + grapheme_width > row_start + width {
+                starts.push(display);
+            }
+            display += grapheme_width;
+        }
+        starts
+    }
+
+    /// Number of screen rows `row` takes, more than one where it wraps.
+    pub fn screen_rows(&self, row: usize) -> usize {
+        self.wrap_starts(row).len()
+    }
+
+    /// Where `cursor` is drawn as `(screen row, screen column)` relative to
+    /// the top left of the text area, or `None` when it is scrolled out of view.
+    pub fn screen_position(&self, cursor: Cursor) -> Option<(usize, usize)> {
+        if cursor.row < self.scroll_offset {
+            return None;
+        }
+        let display = self.display_col(cursor.row, cursor.col);
+        let starts = self.wrap_starts(cursor.row);
+        let wrapped = starts.iter().rposition(|&start| start <= display)?;
+        let mut screen_row = (self.scroll_offset..cursor.row)
+            .map(|r| self.screen_rows(r))
+            .sum::<usize>()
+            + wrapped;
+        let mut screen_col = display - starts[wrapped];
+        if !self.options.wrap {
+            screen_col = screen_col.checked_sub(self.left_col)?;
+        } else if self.viewport_width > 0 && screen_col >= self.viewport_width {
+            // Just past the end of a line that fills its last row exactly.
+            screen_row += 1;
+            screen_col -= self.viewport_width;
+        }
+        (screen_row < self.viewport_height && screen_col < self.viewport_width)
+            .then_some((screen_row, screen_col))
+    }
+
     pub fn handle_action(&mut self, action: Action) {
+        if !self.mode.is_typing() {
+            self.change_open = false;
+        }
+        if !matches!(action, Action::NoOp) {
+            self.status_message = None;
+        }
+        let explicit_count = match action {
+            Action::NoOp | Action::CountDigit(_) | Action::SelectRegister(_) => None,
+            _ => self.pending_count.take(),
+        };
+        let pending_operator = match action {
+            Action::NoOp | Action::CountDigit(_) => None,
+            _ => self.pending_operator.take(),
+        };
+        // The counts before the operator and the motion multiply, as in `2d3w`.
+        let explicit_count = match (pending_operator.and_then(|(_, n)| n), explicit_count) {
+            (Some(a), Some(b)) => Some(a.saturating_mul(b).min(MAX_COUNT)),
+            (a, b) => a.or(b),
+        };
+        let count = explicit_count.unwrap_or(1);
+        let motion = MotionKind::of(&action);
+        if pending_operator.is_some() && motion.is_none() && !matches!(action, Action::Operator(_))
+        {
+            // Anything but a motion cancels the operator.
+            self.recording = None;
+            return;
+        }
+
+        if action.edits_text() && self.buffer_manager.current_buffer().readonly {
+            self.show_error("E21: Cannot make changes, 'readonly' is set");
+            return;
+        }
+        // Only typing in insert mode knows how to edit at every cursor.
+        if action.edits_text()
+            && !matches!(
+                action,
+                Action::Insert(_) | Action::InsertTab | Action::Delete | Action::EnterInsertMode
+            )
+        {
+            self.secondary_cursors.clear();
+        }
+        let mode_before = self.mode;
+        let recorded = action.clone();
+        let from = self.cursor;
+
         match action {
             Action::Quit => self.should_quit = true,
-            Action::Insert(c) => {
-                let byte_pos = self.cursor_to_byte();
-                let buffer = self.buffer_manager.current_buffer_mut();
-                buffer.text.insert_char(byte_pos, c);
-                buffer.dirty = true;
-                self.update_cursor_after_insert(c);
-            }
-            Action::Delete => {
-                let byte_pos = self.cursor_to_byte();
-                if byte_pos > 0 {
-                    let buffer = self.buffer_manager.current_buffer_mut();
-                    buffer.text.remove(byte_pos - 1..byte_pos);
-                    buffer.dirty = true;
-                    self.update_cursor_after_delete();
-                }
+            Action::Insert('\n') if self.mode == Mode::Replace => {
+                // Like vim, Enter in replace mode starts a new line without
+                // eating a char, and Backspace won't restore across it.
+                self.replaced.clear();
+                self.edit_at_cursors(&action);
             }
+            Action::Insert(c) if self.mode == Mode::Replace => self.overtype(c),
+            Action::InsertTab if self.mode == Mode::Replace => self.overtype('\t'),
+            Action::Delete if self.mode == Mode::Replace => self.restore_overtyped(),
+            Action::Insert(_) | Action::InsertTab | Action::Delete => self.edit_at_cursors(&action),
+            Action::InsertIntoCommand(c) => self.insert_into_command(c),
             Action::DeleteFromCommand => {
+                self.command_recall = None;
+                self.command_completion = None;
                 self.command_input.pop();
+                self.preview_search();
             }
-            Action::MoveUp => self.move_up(),
-            Action::MoveDown => self.move_down(),
-            Action::MoveLeft => self.move_left(),
-            Action::MoveRight => self.move_right(),
-            Action::EnterInsertMode => self.mode = Mode::Insert,
-            Action::EnterNormalMode => self.mode = Mode::Normal,
-            Action::EnterVisualMode => self.mode = Mode::Visual,
-            Action::EnterCommandMode => {
-                self.mode = Mode::Command;
-                self.command_input.clear();
+            Action::CountDigit(digit) => {
+                let count = self.pending_count.unwrap_or(0);
+                self.pending_count = Some(
+                    count
+                        .saturating_mul(10)
+                        .saturating_add(digit)
+                        .min(MAX_COUNT),
+                );
             }
-            Action::ExecuteCommand => self.execute_command(),
-            Action::SwitchBuffer(id) => {
-                if self.buffer_manager.switch_to(id) {
-                    self.cursor = Cursor::new(0, 0);
+            Action::FindChar(target)
+            | Action::FindCharBackward(target)
+            | Action::TillChar(target)
+            | Action::TillCharBackward(target) => {
+                let find = CharFind {
+                    target,
+                    forward: matches!(action, Action::FindChar(_) | Action::TillChar(_)),
+                    till: matches!(action, Action::TillChar(_) | Action::TillCharBackward(_)),
+                };
+                self.last_find = Some(find);
+                self.find_char(find, count, false);
+            }
+            Action::RepeatFind | Action::RepeatFindReverse => {
+                if let Some(mut find) = self.last_find {
+                    if action == Action::RepeatFindReverse {
+                        find.forward = !find.forward;
+                    }
+                    self.find_char(find, count, true);
                 }
             }
-            Action::NextBuffer => {
-                if let Some(id) = self.buffer_manager.next_buffer()
-                    && self.buffer_manager.switch_to(id)
-                {
-                    self.cursor = Cursor::new(0, 0);
+            Action::MatchBracket => {
+                self.push_jump();
+                self.match_bracket();
+            }
+            Action::MoveUp => (0..count).for_each(|_| self.move_up()),
+            Action::MoveDown => (0..count).for_each(|_| self.move_down()),
+            // A counted `h`/`l` stays on the current line instead of wrapping.
+            Action::MoveLeft => match explicit_count {
+                Some(n) => {
+                    for _ in 0..n {
+                        self.cursor.col = self.prev_grapheme_col(self.cursor.row, self.cursor.col);
+                    }
+                }
+                None => self.move_left(),
+            },
+            Action::MoveRight => match explicit_count {
+                Some(n) => {
+                    for _ in 0..n {
+                        self.cursor.col = self.next_grapheme_col(self.cursor.row, self.cursor.col);
+                    }
+                }
+                None => self.move_right(),
+            },
+            Action::MoveWordForward => {
+                let mut idx = self.cursor_to_char_idx();
+                for _ in 0..count {
+                    idx = self.next_word_start(idx);
+                }
+                self.cursor = self.cursor_from_char_idx(idx);
+                if pending_operator.is_none() {
+                    self.cursor.col = self
+                        .cursor
+                        .col
+                        .min(self.line_len(self.cursor.row).saturating_sub(1));
                 }
             }
-            Action::PrevBuffer => {
-                if let Some(id) = self.buffer_manager.prev_buffer()
-                    && self.buffer_manager.switch_to(id)
-                {
-                    self.cursor = Cursor::new(0, 0);
+            Action::MoveLineStart => self.move_line_start(),
+            Action::MoveLineEnd => self.move_line_end(),
+            Action::MoveFirstNonBlank => self.move_first_non_blank(),
+            Action::GotoFirstLine => {
+                self.push_jump();
+                self.goto_line(explicit_count.map_or(0, |n| n - 1));
+            }
+            Action::GotoLastLine => {
+                self.push_jump();
+                let row = explicit_count.map_or(self.last_line(), |n| n.saturating_sub(1));
+                self.goto_line(row);
+            }
+            Action::HalfPageDown | Action::HalfPageUp => {
+                let lines = (self.viewport_height / 2).max(1) * count;
+                self.scroll_lines(lines, action == Action::HalfPageDown);
+            }
+            // A page keeps two lines of the previous one in view, like vim.
+            Action::PageDown | Action::PageUp => {
+                let lines = self.viewport_height.saturating_sub(2).max(1) * count;
+                self.scroll_lines(lines, action == Action::PageDown);
+            }
+            Action::ShowStats => self.show_stats(),
+            Action::RepeatLastChange => {
+                if let Some(change) = self.last_change.clone() {
+                    self.pending_count = explicit_count.or(change.count);
+                    self.handle_action(change.action);
+                    for action in change.then {
+                        self.handle_action(action);
+                    }
+                    if self.mode.is_typing() {
+                        self.handle_action(Action::EnterNormalMode);
+                    }
                 }
             }
-            Action::CloseBuffer => {
-                if let Some(_new_id) = self.buffer_manager.delete_current() {
-                    self.cursor = Cursor::new(0, 0);
+            Action::Undo => (0..count).for_each(|_| self.undo()),
+            Action::Redo => (0..count).for_each(|_| self.redo()),
+            Action::SelectRegister(name) => self.selected_register = Some(name),
+            Action::RecordMacro(name) => {
+                if name.is_ascii_alphanumeric() || name == DEFAULT_REGISTER {
+                    self.macro_recording = Some((name, String::new()));
                 }
             }
-            Action::CloseAllBuffersExcept => {
-                let current_id = self.buffer_manager.current_buffer_id();
-                self.buffer_manager.delete_all_except(current_id);
+            Action::StopMacroRecording => {
+                if let Some((name, keys)) = self.macro_recording.take() {
+                    self.registers.insert(name, Register::charwise(keys));
+                }
             }
-            Action::SaveBuffer => {
-                let buffer = self.buffer_manager.current_buffer();
-                if buffer.path.is_none() {
-                    self.mode = Mode::SaveDialog;
-                    self.command_input.clear();
-                } else if let Err(e) = self.buffer_manager.save_current(None) {
-                    eprintln!("Failed to save buffer: {}", e);
+            Action::PlayMacro(_) => {}
+            Action::Yank => {
+                if self.mode.is_visual() {
+                    self.yank_selection();
+                    self.mode = Mode::Normal;
+                    self.selection_anchor = None;
+                } else {
+                    self.yank_lines(count);
                 }
             }
-            Action::SaveBufferAs(path) => {
-                if let Err(e) = self.buffer_manager.save_current(path) {
-                    eprintln!("Failed to save buffer: {}", e);
+            Action::DeleteSelection => {
+                self.delete_selection();
+                self.mode = Mode::Normal;
+                self.selection_anchor = None;
+            }
+            // Like vim, `J` and `2J` both join two lines.
+            Action::JoinLines => (0..count.max(2) - 1).for_each(|_| self.join_line()),
+            Action::IndentLine | Action::DedentLine => {
+                let indent = action == Action::IndentLine;
+                let rows = self.target_rows(count);
+                let first_row = *rows.start();
+                for row in rows {
+                    self.shift_line(row, indent);
+                }
+                self.cursor.row = first_row;
+                self.cursor.col = self.first_non_blank_col(first_row);
+                if self.mode.is_visual() {
+                    self.mode = Mode::Normal;
+                    self.selection_anchor = None;
                 }
             }
-            Action::OpenFile(filename) => {
-                let path = PathBuf::from(filename);
-                if let Ok(id) = self.buffer_manager.open_file(path)
-                    && self.buffer_manager.switch_to(id)
-                {
-                    self.cursor = Cursor::new(0, 0);
+            Action::ToggleComment => {
+                self.toggle_comment(self.target_rows(count));
+                if self.mode.is_visual() {
+                    self.mode = Mode::Normal;
+                    self.selection_anchor = None;
                 }
             }
-            Action::CancelDialog => {
+            Action::DeleteCharUnderCursor => self.delete_chars_under_cursor(count),
+            Action::ToggleCaseUnderCursor => self.toggle_case_under_cursor(count),
+            // `u`, `U` and `~` on a visual selection.
+            Action::Operator(operator) if self.mode.is_visual() && operator.changes_case() => {
+                self.change_selection_case(operator);
                 self.mode = Mode::Normal;
-                self.command_input.clear();
+                self.selection_anchor = None;
             }
-            Action::CancelKeySequence => {}
-            Action::EnterFilePicker => {
-                self.mode = Mode::FilePicker;
-                self.init_file_picker();
+            // `!` on a visual selection starts a `:'<,'>!` filter.
+            Action::Operator(Operator::Filter) if self.mode.is_visual() => {
+                self.enter_command_mode();
+                self.command_input.push('!');
             }
-            Action::SelectFile(path) => {
-                let path_buf = PathBuf::from(path);
-                if let Ok(id) = self.buffer_manager.open_file(path_buf)
-                    && self.buffer_manager.switch_to(id)
-                {
-                    self.cursor = Cursor::new(0, 0);
+            Action::Operator(operator) => match pending_operator {
+                // `dd`, `cc` and `yy` act on `count` whole lines.
+                Some((pending, _)) if pending == operator => {
+                    let last = self
+                        .cursor
+                        .row
+                        .saturating_add(count - 1)
+                        .min(self.last_line());
+                    let to = Cursor::new(last, 0);
+                    self.apply_operator(operator, self.cursor, to, MotionKind::Linewise);
                 }
-                self.mode = Mode::Normal;
+                Some(_) => {}
+                None => self.pending_operator = Some((operator, explicit_count)),
+            },
+            Action::ChangeToLineEnd => {
+                let last = self
+                    .cursor
+                    .row
+                    .saturating_add(count - 1)
+                    .min(self.last_line());
+                let end = self.get_current_text().line_to_char(last) + self.line_len(last);
+                self.change_range(self.cursor_to_char_idx().min(end)..end);
             }
-            Action::FilePickerUp => {
-                self.file_picker_up();
+            Action::PasteAfter => self.paste(true, count),
+            Action::PasteBefore => self.paste(false, count),
+            Action::EnterInsertMode => self.mode = Mode::Insert,
+            Action::EnterReplaceMode => {
+                self.mode = Mode::Replace;
+                self.replaced.clear();
             }
-            Action::FilePickerDown => {
-                self.file_picker_down();
+            Action::AppendAfterCursor => {
+                self.cursor.col = (self.cursor.col + 1).min(self.line_len(self.cursor.row));
+                self.mode = Mode::Insert;
             }
-            Action::FilePickerEnter => {
-                if let Some(file) = self.file_picker.selected_file() {
-                    if file.is_dir {
-                        self.file_picker.current_dir = file.path.clone();
-                        self.file_picker.refresh();
-                        self.file_picker.selected_idx = 0;
-                    } else if let Ok(id) = self.buffer_manager.open_file(file.path.clone())
-                        // INFO: .clone() here can be replaced with something else as we are giving up on perf here.
-                        && self.buffer_manager.switch_to(id)
-                    {
-                        self.cursor = Cursor::new(0, 0);
-                        self.mode = Mode::Normal;
-                    }
-                }
+            Action::AppendLineEnd | Action::InsertLineStart if self.mode == Mode::VisualBlock => {
+                self.insert_in_block(action == Action::AppendLineEnd);
             }
-            Action::FilePickerEsc => {
+            Action::AppendLineEnd => {
+                self.cursor.col = self.line_len(self.cursor.row);
+                self.mode = Mode::Insert;
+                self.selection_anchor = None;
+            }
+            Action::InsertLineStart => {
+                let row = self.cursor.row;
+                let line_len = self.line_len(row);
+                self.cursor.col = self
+                    .get_current_text()
+                    .line(row)
+                    .chars()
+                    .take(line_len)
+                    .position(|c| !c.is_whitespace())
+                    .unwrap_or(line_len);
+                self.mode = Mode::Insert;
+                self.selection_anchor = None;
+            }
+            Action::OpenLineBelow => {
+                let row = self.cursor.row;
+                let line_len = self.line_len(row);
+                let char_idx = self.get_current_text().line_to_char(row) + line_len;
+                let indent = self.auto_indent(row, line_len);
+                self.insert_text(char_idx, &format!("\n{}", indent));
+                self.cursor = Cursor::new(row + 1, indent.chars().count());
+                self.mode = Mode::Insert;
+            }
+            Action::OpenLineAbove => {
+                let row = self.cursor.row;
+                let char_idx = self.get_current_text().line_to_char(row);
+                let indent = self.auto_indent(row, self.line_len(row));
+                self.insert_text(char_idx, &format!("{}\n", indent));
+                self.cursor = Cursor::new(row, indent.chars().count());
+                self.mode = Mode::Insert;
+            }
+            Action::EnterNormalMode => {
+                if self.mode == Mode::Normal {
+                    self.secondary_cursors.clear();
+                }
                 self.mode = Mode::Normal;
+                self.selection_anchor = None;
             }
-            Action::NoOp => {}
-        }
+            Action::AddCursorAtNextMatch => self.add_cursor_at_next_match(),
+            Action::EnterVisualMode
+            | Action::EnterVisualLineMode
+            | Action::EnterVisualBlockMode => {
+                let mode = match action {
+                    Action::EnterVisualMode => Mode::Visual,
+                    Action::EnterVisualLineMode => Mode::VisualLine,
+                    _ => Mode::VisualBlock,
+                };
+                // Like vim, `v`, `V` and `Ctrl-v` switch between the kinds of
+                // selection, or leave visual mode when it is already that kind.
+                if self.mode == mode {
+                    self.mode = Mode::Normal;
+                    self.selection_anchor = None;
+                } else {
+                    if !self.mode.is_visual() {
+                        self.selection_anchor = Some(self.cursor);
+                    }
+                    self.mode = mode;
+                }
+            }
+            Action::EnterCommandMode => self.enter_command_mode(),
+            Action::ExecuteCommand => self.execute_command(),
+            Action::EnterSearchMode => {
+                self.mode = Mode::Search;
+                self.selection_anchor = None;
+                self.command_input.clear();
+                self.search_origin = Some(SearchOrigin {
+                    cursor: self.cursor,
+                    scroll_offset: self.scroll_offset,
+                    query: self.search.query.clone(),
+                });
+            }
+            Action::ExecuteSearch => {
+                let origin = self.search_origin.take();
+                if let Some(origin) = &origin {
+                    self.cursor = origin.cursor;
+                }
+                if self.command_input.is_empty() {
+                    self.search.query = origin.and_then(|origin| origin.query);
+                } else {
+                    self.search.query = Some(std::mem::take(&mut self.command_input));
+                    self.push_jump();
+                    self.search_next(true);
+                }
+                self.mode = Mode::Normal;
+                self.command_input.clear();
+            }
+            Action::SearchNext => {
+                self.push_jump();
+                (0..count).for_each(|_| self.search_next(true));
+            }
+            Action::SearchPrev => {
+                self.push_jump();
+                (0..count).for_each(|_| self.search_next(false));
+            }
+            Action::SetMark(name) if name.is_ascii_alphabetic() => {
+                let id = self.buffer_manager.current_buffer_id();
+                self.marks.insert(name, (id, self.cursor));
+            }
+            Action::SetMark(_) => {
+                self.show_error("E191: Argument must be a letter or forward/backward quote")
+            }
+            Action::JumpToMark(name) => self.jump_to_mark(name),
+            Action::JumpBack => (0..count).for_each(|_| self.jump_back()),
+            Action::JumpForward => (0..count).for_each(|_| self.jump_forward()),
+            Action::SwitchBuffer(id) => {
+                self.switch_buffer(id);
+            }
+            Action::NextBuffer => {
+                if let Some(id) = self.buffer_manager.next_buffer() {
+                    self.switch_buffer(id);
+                }
+            }
+            Action::PrevBuffer => {
+                if let Some(id) = self.buffer_manager.prev_buffer() {
+                    self.switch_buffer(id);
+                }
+            }
+            Action::NewBuffer => self.new_buffer(),
+            Action::CloseBuffer => self.close_buffer(false),
+            Action::CloseAllBuffersExcept => self.close_other_buffers(false),
+            Action::SaveBuffer => {
+                let buffer = self.buffer_manager.current_buffer();
+                if buffer.path.is_none() {
+                    self.open_save_dialog();
+                } else {
+                    self.save_current(None, false);
+                }
+            }
+            Action::SaveBufferAs(path) => {
+                // Confirming the save dialog saves to whatever name was typed.
+                let path = if self.mode == Mode::SaveDialog {
+                    let input = std::mem::take(&mut self.command_input);
+                    self.mode = Mode::Normal;
+                    path.or_else(|| (!input.is_empty()).then(|| PathBuf::from(input)))
+                } else {
+                    path
+                };
+                if path.is_some() || self.get_current_path().is_some() {
+                    self.save_current(path, false);
+                }
+            }
+            Action::CompletePath => {
+                if let Some(completed) = complete_path(&self.command_input) {
+                    self.command_input = completed;
+                }
+            }
+            Action::CommandHistoryPrev => self.recall_command(true),
+            Action::CommandHistoryNext => self.recall_command(false),
+            Action::CompleteCommand => self.complete_command(true),
+            Action::CompleteCommandBack => self.complete_command(false),
+            Action::OpenFile(filename) => {
+                self.open_path(PathBuf::from(filename));
+            }
+            Action::ReloadBuffer => self.reload_buffer(),
+            Action::OpenErrorLocation => self.open_error_location(),
+            Action::QuickfixNext => self.quickfix_step(true),
+            Action::QuickfixPrev => self.quickfix_step(false),
+            Action::EnterBufferList => self.enter_buffer_list(),
+            Action::BufferListUp => {
+                self.buffer_list_idx = self.buffer_list_idx.saturating_sub(1);
+            }
+            Action::BufferListDown => {
+                let last = self.get_buffers().len().saturating_sub(1);
+                self.buffer_list_idx = (self.buffer_list_idx + 1).min(last);
+            }
+            Action::BufferListSelect => {
+                if let Some(id) = self.get_buffers().get(self.buffer_list_idx).map(|b| b.id) {
+                    self.switch_buffer(id);
+                }
+                self.mode = Mode::Normal;
+            }
+            Action::CancelDialog => {
+                if let Some(origin) = self.search_origin.take() {
+                    self.cursor = origin.cursor;
+                    self.scroll_offset = origin.scroll_offset;
+                    self.search.query = origin.query;
+                }
+                self.mode = Mode::Normal;
+                self.command_input.clear();
+            }
+            Action::CancelKeySequence => {}
+            Action::EnterFilePicker => {
+                self.mode = Mode::FilePicker;
+                self.init_file_picker();
+            }
+            Action::SelectFile(path) => {
+                self.open_path(PathBuf::from(path));
+                self.mode = Mode::Normal;
+            }
+            Action::FilePickerUp => {
+                self.file_picker_up();
+            }
+            Action::FilePickerDown => {
+                self.file_picker_down();
+            }
+            Action::FilePickerEnter => {
+                if let Some(file) = self.file_picker.selected_file() {
+                    let path = file.path.clone();
+                    if file.is_dir {
+                        self.file_picker.navigate_to(path);
+                    } else if self.open_path_at(path, None, true) {
+                        self.mode = Mode::Normal;
+                    }
+                }
+            }
+            Action::FilePickerParent => {
+                self.file_picker.navigate_to_parent();
+            }
+            Action::FilePickerToggleHidden => {
+                self.file_picker.toggle_hidden();
+            }
+            Action::FilePickerEsc => {
+                self.mode = Mode::Normal;
+            }
+            Action::NoOp => {}
+        }
+        if let Some((operator, _)) = pending_operator
+            && let Some(kind) = motion
+        {
+            self.finish_operator(operator, &recorded, from, kind, count);
+        }
+        let operator_pending = pending_operator.is_some();
+        self.record_change(recorded, explicit_count, mode_before, operator_pending);
+        self.scroll_to_cursor(self.viewport_height);
+    }
+
+    /// Tracks the change `.` repeats. A text-changing action from normal mode
+    /// starts one; if it entered insert mode, the keys typed there are added
+    /// until the session ends.
+    fn record_change(
+        &mut self,
+        action: Action,
+        count: Option<usize>,
+        mode_before: Mode,
+        operator_pending: bool,
+    ) {
+        match mode_before {
+            Mode::Normal if operator_pending => {
+                if let Some(change) = &mut self.recording {
+                    change.count = count;
+                    change.then.push(action);
+                }
+            }
+            Mode::Normal
+                if action.edits_text() && !matches!(action, Action::Undo | Action::Redo) =>
+            {
+                self.recording = Some(Change {
+                    action,
+                    count,
+                    then: Vec::new(),
+                });
+            }
+            Mode::Insert | Mode::Replace
+                if matches!(
+                    action,
+                    Action::Insert(_) | Action::InsertTab | Action::Delete
+                ) =>
+            {
+                if let Some(change) = &mut self.recording {
+                    change.then.push(action);
+                }
+            }
+            _ => {}
+        }
+        if !self.mode.is_typing() && self.pending_operator.is_none() && self.recording.is_some() {
+            self.last_change = self.recording.take();
+        }
+    }
+
+    /// Moves to the next (or previous) match of the last search, wrapping
+    /// around the buffer. Leaves the cursor alone when nothing matches.
+    fn search_next(&mut self, forward: bool) {
+        let text = self.get_current_text().clone();
+        if let Err(message) = self.search.update(&text, &self.options) {
+            self.show_error(message);
+            return;
+        }
+
+        let char_idx = self.cursor_to_char_idx();
+        let target = if forward {
+            self.search.next_match(char_idx)
+        } else {
+            self.search.prev_match(char_idx)
+        };
+        match target {
+            Some(target) => self.cursor = self.cursor_from_char_idx(target),
+            None => {
+                if let Some(query) = self.search.query.clone().filter(|q| !q.is_empty()) {
+                    self.show_error(format!("E486: Pattern not found: {}", query));
+                }
+            }
+        }
+    }
+
+    /// Stores `register` in the selected register (if any) and the default one.
+    /// The `+` register goes to the clipboard, falling back to internal storage
+    /// if the clipboard is unavailable.
+    fn store_register(&mut self, register: Register) {
+        if let Some(name) = self.selected_register.take()
+            && name != DEFAULT_REGISTER
+        {
+            let copied = name == CLIPBOARD_REGISTER
+                && self.clipboard.as_mut().is_some_and(|clipboard| {
+                    clipboard
+                        .set_text(&register.text)
+                        .inspect_err(|e| tracing::warn!("Failed to set clipboard: {}", e))
+                        .is_ok()
+                });
+            if !copied {
+                self.registers.insert(name, register.clone());
+            }
+        }
+        self.registers.insert(DEFAULT_REGISTER, register);
+    }
+
+    /// Reads register `name`, consulting the clipboard for `+`.
+    fn read_register(&mut self, name: char) -> Option<Register> {
+        if name == CLIPBOARD_REGISTER
+            && let Some(clipboard) = self.clipboard.as_mut()
+        {
+            match clipboard.get_text() {
+                Ok(text) if text.ends_with('\n') => return Some(Register::linewise(text)),
+                Ok(text) => return Some(Register::charwise(text)),
+                Err(e) => tracing::warn!("Failed to read clipboard: {}", e),
+            }
+        }
+        self.registers.get(&name).cloned()
+    }
+
+    /// The visual selection as `(start, end)` in document order, regardless of
+    /// whether the anchor is before or after the cursor. Both ends are inclusive;
+    /// in visual line mode they stretch to the line start and terminator.
+    pub fn selection_range(&self) -> Option<(Cursor, Cursor)> {
+        let anchor = self.selection_anchor?;
+        let (start, end) = (anchor.min(self.cursor), anchor.max(self.cursor));
+        if self.mode == Mode::VisualLine {
+            let end_col = self.line_len(end.row);
+            return Some((Cursor::new(start.row, 0), Cursor::new(end.row, end_col)));
+        }
+        Some((start, end))
+    }
+
+    /// The top-left and bottom-right corners of the selection in visual block
+    /// mode, both inclusive.
+    pub fn block_selection(&self) -> Option<(Cursor, Cursor)> {
+        if self.mode != Mode::VisualBlock {
+            return None;
+        }
+        let anchor = self.selection_anchor?;
+        Some((
+            Cursor::new(
+                anchor.row.min(self.cursor.row),
+                anchor.col.min(self.cursor.col),
+            ),
+            Cursor::new(
+                anchor.row.max(self.cursor.row),
+                anchor.col.max(self.cursor.col),
+            ),
+        ))
+    }
+
+    /// Char range of the block selection on each of its rows, cut short where
+    /// a line ends inside or before the block.
+    fn block_char_ranges(&self) -> Vec<Range<usize>> {
+        let Some((top_left, bottom_right)) = self.block_selection() else {
+            return Vec::new();
+        };
+        let text = self.get_current_text();
+        (top_left.row..=bottom_right.row)
+            .map(|row| {
+                let line_start = text.line_to_char(row);
+                let line_len = self.line_len(row);
+                line_start + top_left.col.min(line_len)
+                    ..line_start + (bottom_right.col + 1).min(line_len)
+            })
+            .collect()
+    }
+
+    /// The text of the block selection, one line per row.
+    fn block_text(&self, ranges: &[Range<usize>]) -> String {
+        let text = self.get_current_text();
+        ranges
+            .iter()
+            .map(|range| text.slice(range.clone()).to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Starts insert mode on every row of the block selection at once, at the
+    /// block's left edge for `I` or after its right edge for `A`. Rows that end
+    /// before the block are left alone, as in vim.
+    fn insert_in_block(&mut self, append: bool) {
+        let Some((top_left, bottom_right)) = self.block_selection() else {
+            return;
+        };
+        let col = if append {
+            bottom_right.col + 1
+        } else {
+            top_left.col
+        };
+        let mut cursors: Vec<Cursor> = (top_left.row..=bottom_right.row)
+            .filter(|&row| self.line_len(row) >= top_left.col)
+            .map(|row| Cursor::new(row, col.min(self.line_len(row))))
+            .collect();
+        if !cursors.is_empty() {
+            self.cursor = cursors.remove(0);
+            self.secondary_cursors = cursors;
+        }
+        self.mode = Mode::Insert;
+        self.selection_anchor = None;
+    }
+
+    /// Shows the counts for the buffer, prefixed by those for the visual
+    /// selection when there is one.
+    fn show_stats(&mut self) {
+        let text = self.get_current_text();
+        let total = TextStats::of(text.slice(..));
+        let message = match self.selection_char_range() {
+            Some(range) => {
+                let selected = TextStats::of(text.slice(range));
+                format!(
+                    "Selected {} of {} lines; {} of {} words; {} of {} chars; {} of {} bytes",
+                    selected.lines,
+                    total.lines,
+                    selected.words,
+                    total.words,
+                    selected.chars,
+                    total.chars,
+                    selected.bytes,
+                    total.bytes
+                )
+            }
+            None => total.to_string(),
+        };
+        self.show_info(message);
+    }
+
+    /// Char range of the visual selection, inclusive of the char at its end.
+    fn selection_char_range(&self) -> Option<Range<usize>> {
+        let (start, end) = self.selection_range()?;
+        let text = self.get_current_text();
+        let start_idx = text.line_to_char(start.row) + start.col;
+        let end_idx = (text.line_to_char(end.row) + end.col + 1).min(text.len_chars());
+        Some(start_idx..end_idx)
+    }
+
+    fn yank_selection(&mut self) {
+        if let Some((top_left, _)) = self.block_selection() {
+            let text = self.block_text(&self.block_char_ranges());
+            self.store_register(Register::charwise(text));
+            self.cursor = top_left;
+            self.clamp_col_to_line();
+        } else if self.mode == Mode::VisualLine
+            && let Some((start, end)) = self.selection_range()
+        {
+            self.cursor = Cursor::new(start.row, self.cursor.col);
+            self.yank_lines(end.row - start.row + 1);
+            self.clamp_col_to_line();
+        } else if let Some(range) = self.selection_char_range() {
+            let text = self.get_current_text().slice(range.clone()).to_string();
+            self.store_register(Register::charwise(text));
+            self.cursor = self.cursor_from_char_idx(range.start);
+        }
+    }
+
+    /// Removes the visual selection, keeping the deleted text in the register.
+    fn delete_selection(&mut self) {
+        if let Some((top_left, _)) = self.block_selection() {
+            let ranges = self.block_char_ranges();
+            let text = self.block_text(&ranges);
+            self.store_register(Register::charwise(text));
+            for range in ranges.into_iter().rev() {
+                self.remove_text(range);
+            }
+            self.cursor = top_left;
+            self.clamp_col_to_line();
+        } else if self.mode == Mode::VisualLine
+            && let Some((start, end)) = self.selection_range()
+        {
+            self.cursor.row = start.row;
+            self.delete_lines(end.row - start.row + 1);
+        } else if let Some(range) = self.selection_char_range() {
+            let text = self.get_current_text().slice(range.clone()).to_string();
+            self.store_register(Register::charwise(text));
+            self.remove_text(range.clone());
+            self.cursor = self.cursor_from_char_idx(range.start);
+            self.clamp_col_to_line();
+        }
+    }
+
+    /// Char range covering `count` whole lines starting at the cursor row,
+    /// including their line terminators.
+    fn line_char_range(&self, count: usize) -> Range<usize> {
+        let text = self.get_current_text();
+        let start = text.line_to_char(self.cursor.row);
+        let end_row = self.cursor.row.saturating_add(count);
+        let end = if end_row < text.len_lines() {
+            text.line_to_char(end_row)
+        } else {
+            text.len_chars()
+        };
+        start..end
+    }
+
+    /// Deletes `count` lines from the cursor down into the register and lands
+    /// on the first non-blank of the line that takes their place.
+    fn delete_lines(&mut self, count: usize) {
+        let row = self.cursor.row;
+        let mut range = self.line_char_range(count);
+        let lines = self.get_current_text().slice(range.clone()).to_string();
+        if !lines.ends_with('\n') && row > 0 {
+            // The final line has no newline of its own, so take the previous one.
+            range.start -= 1;
+        }
+
+        self.store_register(Register::linewise(lines));
+        self.remove_text(range);
+        self.cursor.row = row.min(self.last_line());
+        self.move_first_non_blank();
+    }
+
+    /// Deletes `count` graphemes starting under the cursor (unlike
+    /// `Action::Delete`, which removes the char before it), never reaching past
+    /// the end of the line, and pulls the cursor back if it ends up past the line.
+    fn delete_chars_under_cursor(&mut self, count: usize) {
+        let row = self.cursor.row;
+        let line_len = self.line_len(row);
+        if line_len == 0 {
+            return;
+        }
+        self.cursor.col = self.prev_grapheme_col(row, self.cursor.col.min(line_len - 1) + 1);
+
+        let char_idx = self.cursor_to_char_idx();
+        let mut end_col = self.cursor.col;
+        for _ in 0..count {
+            end_col = self.next_grapheme_col(row, end_col);
+        }
+        let end = char_idx + end_col - self.cursor.col;
+        let deleted = self.get_current_text().slice(char_idx..end).to_string();
+        self.store_register(Register::charwise(deleted));
+        self.remove_text(char_idx..end);
+
+        let line_len = self.line_len(row);
+        if self.cursor.col >= line_len {
+            self.cursor.col = self.prev_grapheme_col(row, line_len);
+        }
+    }
+
+    /// Char index of the start of the word after `idx`, crossing lines. An
+    /// empty line counts as a word.
+    fn next_word_start(&self, idx: usize) -> usize {
+        let text = self.get_current_text();
+        let len = text.len_chars();
+        let mut idx = idx;
+        if idx >= len {
+            return len;
+        }
+        let class = char_class(text.char(idx));
+        if class != CharClass::Blank {
+            while idx < len && char_class(text.char(idx)) == class {
+                idx += 1;
+            }
+        }
+        while idx < len && text.char(idx).is_whitespace() {
+            if text.char(idx) == '\n' && idx + 1 < len && text.char(idx + 1) == '\n' {
+                return idx + 1;
+            }
+            idx += 1;
+        }
+        idx
+    }
+
+    /// Column just past the `count`th word from the cursor, as `cw` sees it:
+    /// the rest of the word under the cursor without the space after it. A
+    /// run of punctuation or of blanks counts as a word of its own.
+    fn word_end_col(&self, count: usize) -> usize {
+        let row = self.cursor.row;
+        let len = self.line_len(row);
+        let chars: Vec<char> = self
+            .get_current_text()
+            .line(row)
+            .chars()
+            .take(len)
+            .collect();
+        let mut end = self.cursor.col.min(len);
+        for i in 0..count {
+            if i > 0 {
+                while end < len && chars[end].is_whitespace() {
+                    end += 1;
+                }
+            }
+            let Some(&first) = chars.get(end) else {
+                break;
+            };
+            while end < len && char_class(chars[end]) == char_class(first) {
+                end += 1;
+            }
+        }
+        end
+    }
+
+    /// Deletes the char `range` into the register and starts insert mode
+    /// where it was.
+    fn change_range(&mut self, range: Range<usize>) {
+        let changed = self.get_current_text().slice(range.clone()).to_string();
+        self.store_register(Register::charwise(changed));
+        self.remove_text(range.clone());
+        self.cursor = self.cursor_from_char_idx(range.start);
+        self.mode = Mode::Insert;
+    }
+
+    /// Replaces `count` lines from the cursor down with a single line holding
+    /// only their indent (or nothing without `auto_indent`), yanking them
+    /// linewise, and starts insert mode at its end.
+    fn change_lines(&mut self, count: usize) {
+        let row = self.cursor.row;
+        let last = row.saturating_add(count - 1).min(self.last_line());
+        let text = self.get_current_text();
+        let start = text.line_to_char(row);
+        let end = text.line_to_char(last) + self.line_len(last);
+        let lines = text.slice(start..end).to_string();
+        let indent = self.auto_indent(row, self.line_len(row));
+
+        self.store_register(Register::linewise(lines));
+        self.remove_text(start..end);
+        self.insert_text(start, &indent);
+        self.cursor = Cursor::new(row, indent.chars().count());
+        self.mode = Mode::Insert;
+    }
+
+    /// Applies `operator` once the motion that followed it has moved the
+    /// cursor from `from`. Like vim, `cw` stops at the end of the word and
+    /// `dw` on a line's last word stops at the end of the line.
+    fn finish_operator(
+        &mut self,
+        operator: Operator,
+        motion: &Action,
+        from: Cursor,
+        kind: MotionKind,
+        count: usize,
+    ) {
+        let mut to = self.cursor;
+        if *motion == Action::MoveWordForward {
+            self.cursor = from;
+            if operator == Operator::Change {
+                to = Cursor::new(from.row, self.word_end_col(count));
+            } else if to.row > from.row {
+                to = Cursor::new(to.row - 1, self.line_len(to.row - 1));
+            }
+        }
+        self.apply_operator(operator, from, to, kind);
+    }
+
+    /// Deletes, changes or yanks the text between `from` and `to`, in either
+    /// order, treating it as `kind` says.
+    fn apply_operator(&mut self, operator: Operator, from: Cursor, to: Cursor, kind: MotionKind) {
+        let (start, end) = (from.min(to), from.max(to));
+        if operator == Operator::Filter {
+            // Always whole lines, handed to the command line as `.,.+N!`.
+            self.cursor = start;
+            self.mode = Mode::Command;
+            self.command_input = match end.row - start.row {
+                0 => ".!".to_string(),
+                n => format!(".,.+{}!", n),
+            };
+            return;
+        }
+        if kind == MotionKind::Linewise {
+            self.cursor = start;
+            let count = end.row - start.row + 1;
+            match operator {
+                Operator::Delete => self.delete_lines(count),
+                Operator::Change => self.change_lines(count),
+                Operator::Yank => self.yank_lines(count),
+                _ => self.change_case(self.line_char_range(count), operator),
+            }
+            return;
+        }
+
+        let text = self.get_current_text();
+        let start_idx = text.line_to_char(start.row) + start.col;
+        let mut end_idx = text.line_to_char(end.row) + end.col;
+        if kind == MotionKind::Inclusive {
+            end_idx += 1;
+        }
+        let range = start_idx..end_idx.min(text.len_chars());
+        match operator {
+            Operator::Delete => {
+                let deleted = text.slice(range.clone()).to_string();
+                self.store_register(Register::charwise(deleted));
+                self.remove_text(range.clone());
+                self.cursor = self.cursor_from_char_idx(range.start);
+                let line_len = self.line_len(self.cursor.row);
+                self.cursor.col = self.cursor.col.min(line_len.saturating_sub(1));
+            }
+            Operator::Change => self.change_range(range),
+            Operator::Yank => {
+                let yanked = text.slice(range).to_string();
+                self.store_register(Register::charwise(yanked));
+                self.cursor = start;
+            }
+            _ => {
+                self.change_case(range, operator);
+                self.cursor = start;
+            }
+        }
+    }
+
+    /// Rewrites the chars in `range` with their case changed by `operator`.
+    fn change_case(&mut self, range: Range<usize>, operator: Operator) {
+        let text = self.get_current_text().slice(range.clone()).to_string();
+        let changed = operator.apply_case(&text);
+        if changed != text {
+            self.remove_text(range.clone());
+            self.insert_text(range.start, &changed);
+        }
+    }
+
+    /// `~`: toggles the case of `count` chars from the cursor, stopping at the
+    /// end of the line, and moves past them. On the last char it stays put.
+    fn toggle_case_under_cursor(&mut self, count: usize) {
+        let row = self.cursor.row;
+        let line_len = self.line_len(row);
+        if line_len == 0 {
+            return;
+        }
+        let line_start = self.get_current_text().line_to_char(row);
+        let end = self.cursor.col.saturating_add(count).min(line_len);
+        self.change_case(
+            line_start + self.cursor.col..line_start + end,
+            Operator::ToggleCase,
+        );
+        self.cursor.col = end.min(self.line_len(row) - 1);
+    }
+
+    /// Changes the case of the visual selection and puts the cursor at its
+    /// start.
+    fn change_selection_case(&mut self, operator: Operator) {
+        let Some((start, _)) = self.selection_range() else {
+            return;
+        };
+        let ranges = if self.mode == Mode::VisualBlock {
+            self.block_char_ranges()
+        } else {
+            self.selection_char_range().into_iter().collect()
+        };
+        for range in ranges.into_iter().rev() {
+            self.change_case(range, operator);
+        }
+        self.cursor = self
+            .block_selection()
+            .map_or(start, |(top_left, _)| top_left);
+        self.clamp_col_to_line();
+    }
+
+    fn yank_lines(&mut self, count: usize) {
+        let range = self.line_char_range(count);
+        let text = self.get_current_text().slice(range).to_string();
+        self.store_register(Register::linewise(text));
+    }
+
+    /// Pastes the selected register `count` times after (or before) the cursor.
+    /// Linewise contents go on their own lines; charwise contents go inline.
+    fn paste(&mut self, after: bool, count: usize) {
+        let name = self.selected_register.take().unwrap_or(DEFAULT_REGISTER);
+        let Some(mut register) = self.read_register(name) else {
+            return;
+        };
+        register.text = register.text.repeat(count);
+
+        match register.kind {
+            RegisterKind::Linewise => {
+                let row = if after {
+                    self.cursor.row + 1
+                } else {
+                    self.cursor.row
+                };
+                let text = self.get_current_text();
+                if row < text.len_lines() {
+                    let char_idx = text.line_to_char(row);
+                    self.insert_text(char_idx, &register.text);
+                } else {
+                    // Pasting below a last line that has no newline of its own.
+                    let char_idx = text.len_chars();
+                    let lines = register.text.strip_suffix('\n').unwrap_or(&register.text);
+                    self.insert_text(char_idx, &format!("\n{}", lines));
+                }
+                self.cursor.row = row;
+                self.move_first_non_blank();
+            }
+            RegisterKind::Charwise => {
+                let line_len = self.line_len(self.cursor.row);
+                let col = if after && line_len > 0 {
+                    (self.cursor.col + 1).min(line_len)
+                } else {
+                    self.cursor.col
+                };
+                let char_idx = self.get_current_text().line_to_char(self.cursor.row) + col;
+                self.insert_text(char_idx, &register.text);
+                let end = char_idx + register.text.chars().count();
+                self.cursor = self.cursor_from_char_idx(end.saturating_sub(1).max(char_idx));
+            }
+        }
+    }
+
+    /// Applies an insert mode edit at the primary cursor and every secondary
+    /// one. Edits run from the end of the buffer back so the positions still
+    /// to be edited stay valid; each cursor then shifts by the edits before it.
+    fn edit_at_cursors(&mut self, action: &Action) {
+        let text = self.get_current_text();
+        let char_idx = |cursor: Cursor| text.line_to_char(cursor.row) + cursor.col;
+        let mut cursors: Vec<(usize, bool)> = self
+            .secondary_cursors
+            .iter()
+            .map(|&cursor| (char_idx(cursor), false))
+            .collect();
+        cursors.push((char_idx(self.cursor), true));
+        // Where cursors have met, the primary one is kept.
+        cursors.sort_by_key(|&(idx, primary)| (idx, !primary));
+        cursors.dedup_by_key(|(idx, _)| *idx);
+
+        let mut moves = vec![(0, 0); cursors.len()];
+        for (i, &(idx, _)) in cursors.iter().enumerate().rev() {
+            moves[i] = self.edit_at(action, idx);
+        }
+
+        let mut shift = 0;
+        self.secondary_cursors.clear();
+        for (&(_, primary), &(new_idx, grown)) in cursors.iter().zip(&moves) {
+            let cursor = self.cursor_from_char_idx(new_idx.saturating_add_signed(shift));
+            if primary {
+                self.cursor = cursor;
+            } else {
+                self.secondary_cursors.push(cursor);
+            }
+            shift += grown;
+        }
+        // Backspace can bring cursors together; they go on as one.
+        let primary = self.cursor;
+        self.secondary_cursors.retain(|&cursor| cursor != primary);
+        self.secondary_cursors.dedup();
+    }
+
+    /// Replace mode typing: puts `c` in place of the char under the cursor,
+    /// or appends it at the end of the line.
+    fn overtype(&mut self, c: char) {
+        let idx = self.cursor_to_char_idx();
+        let original = (self.cursor.col < self.line_len(self.cursor.row))
+            .then(|| self.get_current_text().char(idx));
+        if original.is_some() {
+            self.remove_text(idx..idx + 1);
+        }
+        self.insert_text(idx, c.encode_utf8(&mut [0; 4]));
+        self.replaced.push(original);
+        self.cursor.col += 1;
+    }
+
+    /// Replace mode Backspace: steps left, putting back the char that was
+    /// overtyped there. Past the start of the session it only moves.
+    fn restore_overtyped(&mut self) {
+        if self.cursor.col == 0 {
+            return;
+        }
+        self.cursor.col -= 1;
+        if let Some(original) = self.replaced.pop() {
+            let idx = self.cursor_to_char_idx();
+            self.remove_text(idx..idx + 1);
+            if let Some(original) = original {
+                self.insert_text(idx, original.encode_utf8(&mut [0; 4]));
+            }
+        }
+    }
+
+    /// Performs an insert mode edit at char `idx`. Returns where that cursor
+    /// ends up and how many chars the buffer grew by (negative if it shrank).
+    fn edit_at(&mut self, action: &Action, idx: usize) -> (usize, isize) {
+        if self.options.autopairs
+            && let Some(edit) = self.edit_pair_at(action, idx)
+        {
+            return edit;
+        }
+        let cursor = self.cursor_from_char_idx(idx);
+        let inserted = match action {
+            Action::Insert('\n') => format!("\n{}", self.auto_indent(cursor.row, cursor.col)),
+            Action::Insert(c) => c.to_string(),
+            Action::InsertTab if self.options.expand_tabs => {
+                let width = self.options.tab_width.max(1);
+                let col = self.display_col(cursor.row, cursor.col);
+                " ".repeat(width - col % width)
+            }
+            Action::InsertTab => "\t".to_string(),
+            Action::Delete if idx > 0 => {
+                self.remove_text(idx - 1..idx);
+                return (idx - 1, -1);
+            }
+            _ => return (idx, 0),
+        };
+        self.insert_text(idx, &inserted);
+        let len = inserted.chars().count();
+        (idx + len, len as isize)
+    }
+
+    /// The `autopairs` take on an insert mode edit at char `idx`, or `None` to
+    /// edit as usual. An opener gets its closer too, a closer steps over the
+    /// same char, and backspace between an empty pair removes both.
+    fn edit_pair_at(&mut self, action: &Action, idx: usize) -> Option<(usize, isize)> {
+        let text = self.get_current_text();
+        let next = (idx < text.len_chars()).then(|| text.char(idx));
+        let prev = idx.checked_sub(1).map(|i| text.char(i));
+        match *action {
+            Action::Insert(c)
+                if next == Some(c) && AUTO_PAIRS.iter().any(|&(_, close)| close == c) =>
+            {
+                Some((idx + 1, 0))
+            }
+            Action::Insert(c) => {
+                let &(open, close) = AUTO_PAIRS.iter().find(|&&(open, _)| open == c)?;
+                // A quote right after a word is an apostrophe or closes a string.
+                if open == close && prev.is_some_and(|p| char_class(p) == CharClass::Word) {
+                    return None;
+                }
+                self.insert_text(idx, &format!("{}{}", open, close));
+                Some((idx + 1, 2))
+            }
+            Action::Delete => {
+                let open = prev?;
+                AUTO_PAIRS.contains(&(open, next?)).then(|| {
+                    self.remove_text(idx - 1..idx + 1);
+                    (idx - 1, -2)
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Adds a cursor at the next whole-word occurrence of the word under the
+    /// primary cursor, after the most recently added cursor and wrapping
+    /// around the buffer. Each cursor keeps the primary's offset into the word.
+    fn add_cursor_at_next_match(&mut self) {
+        let text = self.get_current_text();
+        let cursor_idx = self.cursor_to_char_idx();
+        let is_word =
+            |idx: usize| idx < text.len_chars() && char_class(text.char(idx)) == CharClass::Word;
+        if !is_word(cursor_idx) {
+            return;
+        }
+        let mut start = cursor_idx;
+        while start > 0 && is_word(start - 1) {
+            start -= 1;
+        }
+        let mut end = cursor_idx;
+        while is_word(end) {
+            end += 1;
+        }
+        let word: Vec<char> = text.slice(start..end).chars().collect();
+        let offset = cursor_idx - start;
+
+        let last = self
+            .secondary_cursors
+            .last()
+            .copied()
+            .unwrap_or(self.cursor);
+        let last_start = (text.line_to_char(last.row) + last.col).saturating_sub(offset);
+        let chars: Vec<char> = text.chars().collect();
+        let len = chars.len();
+        let found = (1..=len).map(|step| (last_start + step) % len).find(|&i| {
+            chars[i..].starts_with(&word)
+                && (i == 0 || char_class(chars[i - 1]) != CharClass::Word)
+                && chars
+                    .get(i + word.len())
+                    .is_none_or(|&c| char_class(c) != CharClass::Word)
+        });
+        let Some(found) = found.filter(|&i| i != start) else {
+            self.show_info("No more matches");
+            return;
+        };
+        let cursor = self.cursor_from_char_idx(found + offset);
+        if !self.secondary_cursors.contains(&cursor) {
+            self.secondary_cursors.push(cursor);
+        }
+        self.show_info(format!("{} cursors", self.secondary_cursors.len() + 1));
+    }
+
+    fn execute_command(&mut self) {
+        let input = std::mem::take(&mut self.command_input);
+        self.mode = Mode::Normal;
+        self.command_recall = None;
+        self.command_completion = None;
+        self.add_command_history(input.trim());
+        self.run_command(input.trim());
+    }
+
+    /// Makes `command` the newest entry of the command history, moving it
+    /// there if it was run before, and writes the history back to its file.
+    fn add_command_history(&mut self, command: &str) {
+        if command.is_empty() {
+            return;
+        }
+        self.command_history.retain(|c| c != command);
+        self.command_history.push(command.to_string());
+        let excess = self
+            .command_history
+            .len()
+            .saturating_sub(MAX_COMMAND_HISTORY);
+        self.command_history.drain(..excess);
+
+        let Some(file) = &self.command_history_file else {
+            return;
+        };
+        let mut contents = self.command_history.join("\n");
+        contents.push('\n');
+        let written = match file.parent() {
+            Some(dir) => std::fs::create_dir_all(dir),
+            None => Ok(()),
+        }
+        .and_then(|()| std::fs::write(file, contents));
+        if let Err(e) = written {
+            tracing::warn!("Cannot write command history to {}: {}", file.display(), e);
+        }
+    }
+
+    /// Reads the command history kept in `file` and keeps it there from now
+    /// on. A missing file is an empty history.
+    pub fn load_command_history(&mut self, file: PathBuf) {
+        let contents = std::fs::read_to_string(&file).unwrap_or_default();
+        self.command_history = contents
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        let excess = self
+            .command_history
+            .len()
+            .saturating_sub(MAX_COMMAND_HISTORY);
+        self.command_history.drain(..excess);
+        self.command_history_file = Some(file);
+    }
+
+    /// Up and Down on the command line: replaces the input with the previous
+    /// or next command in the history that starts with what was typed before
+    /// recalling began, as vim does. Going down past the newest brings back
+    /// the typed input.
+    fn recall_command(&mut self, older: bool) {
+        self.command_completion = None;
+        let (idx, typed) = self
+            .command_recall
+            .take()
+            .unwrap_or_else(|| (self.command_history.len(), self.command_input.clone()));
+        let found = if older {
+            self.command_history[..idx]
+                .iter()
+                .rposition(|c| c.starts_with(&typed))
+        } else {
+            self.command_history
+                .iter()
+                .enumerate()
+                .skip(idx + 1)
+                .find(|(_, c)| c.starts_with(&typed))
+                .map(|(i, _)| i)
+        };
+        match found {
+            Some(found) => {
+                self.command_input = self.command_history[found].clone();
+                self.command_recall = Some((found, typed));
+            }
+            // Nothing older: stay on the oldest match.
+            None if older => self.command_recall = Some((idx, typed)),
+            None => self.command_input = typed,
+        }
+    }
+
+    /// Tab and Shift-Tab on the command line: completes the command name or
+    /// path being typed, cycling through the candidates when pressed again.
+    /// A lone candidate is simply taken, so the next Tab after completing a
+    /// directory lists what's inside it.
+    fn complete_command(&mut self, forward: bool) {
+        let completion = self
+            .command_completion
+            .take()
+            .or_else(|| Completion::new(&self.command_input));
+        let Some(mut completion) = completion else {
+            return;
+        };
+        self.command_recall = None;
+        self.command_input = completion.step(forward);
+        if completion.len() > 1 {
+            self.command_completion = Some(completion);
+        }
+    }
+
+    /// Opens the command line, starting it with `'<,'>` when leaving a visual
+    /// mode so the command applies to the selected lines.
+    fn enter_command_mode(&mut self) {
+        self.command_input.clear();
+        self.command_recall = None;
+        self.command_completion = None;
+        if self.mode.is_visual()
+            && let Some((start, end)) = self.selection_range()
+        {
+            self.visual_rows = Some(start.row..=end.row);
+            self.command_input.push_str("'<,'>");
+        }
+        self.mode = Mode::Command;
+        self.selection_anchor = None;
+    }
+
+    /// Runs an ex command line, typed without the leading `:`.
+    fn run_command(&mut self, command: &str) {
+        let id = self.buffer_manager.current_buffer_id();
+        let mark = |name| match name {
+            '<' => self.visual_rows.as_ref().map(|rows| *rows.start()),
+            '>' => self.visual_rows.as_ref().map(|rows| *rows.end()),
+            _ => match self.marks.get(&name) {
+                Some((buffer_id, cursor)) if *buffer_id == id => Some(cursor.row),
+                _ => None,
+            },
+        };
+        let (rows, command) = match parse_range(command, self.cursor.row, self.last_line(), mark) {
+            Ok(parsed) => parsed,
+            Err(message) => {
+                self.show_error(message);
+                return;
+            }
+        };
+        let parts: Vec<&str> = command.split_whitespace().collect();
+
+        match parts.first().copied() {
+            Some(first) if first.starts_with("s/") => match Substitution::parse(command) {
+                Some(Ok(substitution)) => self.substitute(&substitution, rows),
+                Some(Err(message)) => self.show_error(message),
+                None => {}
+            },
+            Some(first)
+                if ["g/", "g!/", "v/"]
+                    .iter()
+                    .any(|prefix| first.starts_with(prefix)) =>
+            {
+                match GlobalDelete::parse(command) {
+                    Some(Ok(global)) => self.global_delete(&global, rows),
+                    Some(Err(message)) => self.show_error(message),
+                    None => {}
+                }
+            }
+            Some("q") | Some("quit") => self.quit(),
+            Some("q!") | Some("quit!") => self.should_quit = true,
+            Some("qa") | Some("qall") => self.quit(),
+            Some("qa!") | Some("qall!") => self.should_quit = true,
+            Some("wa") | Some("wall") => {
+                self.save_all();
+            }
+            Some("wqa") | Some("wqall") | Some("xa") | Some("xall") => {
+                self.should_quit = self.save_all();
+            }
+            Some(cmd @ ("w" | "w!")) => {
+                let force = cmd == "w!";
+                if let Some(path) = parts.get(1) {
+                    self.save_current(Some(PathBuf::from(*path)), force);
+                } else {
+                    let buffer = self.buffer_manager.current_buffer();
+                    if buffer.path.is_none() {
+                        self.open_save_dialog();
+                        return;
+                    }
+                    self.save_current(None, force);
+                }
+            }
+            Some(cmd @ ("wq" | "wq!")) => {
+                let buffer = self.buffer_manager.current_buffer();
+                if buffer.path.is_none() {
+                    self.open_save_dialog();
+                    return;
+                }
+                if self.save_current(None, cmd == "wq!") {
+                    self.quit();
+                }
+            }
+            Some("!q") => self.should_quit = true,
+            Some("bn") | Some("bnext") => {
+                if let Some(id) = self.buffer_manager.next_buffer() {
+                    self.switch_buffer(id);
+                }
+            }
+            Some("bp") | Some("bprev") => {
+                if let Some(id) = self.buffer_manager.prev_buffer() {
+                    self.switch_buffer(id);
+                }
+            }
+            Some("bd") | Some("bdelete") | Some("bx") | Some("bc") | Some("bclose") => {
+                self.close_buffer(false)
+            }
+            Some("bd!") | Some("bdelete!") | Some("bx!") | Some("bc!") | Some("bclose!") => {
+                self.close_buffer(true)
+            }
+            Some("baex") | Some("ballbutexcept") => self.close_other_buffers(false),
+            Some("baex!") | Some("ballbutexcept!") => self.close_other_buffers(true),
+            Some("b") | Some("buffer") => match parts.get(1) {
+                Some(n) => self.switch_to_buffer_arg(n),
+                None => self.show_error("E471: Argument required"),
+            },
+            Some("set") | Some("se") => {
+                for arg in &parts[1..] {
+                    self.set_option(arg);
+                }
+            }
+            Some("e!") => self.reload_buffer(),
+            Some("recover") | Some("rec") => self.recover_buffer(),
+            Some("enew") | Some("ene") => self.new_buffer(),
+            Some("mksession") | Some("mks") => {
+                let path = PathBuf::from(parts.get(1).copied().unwrap_or(DEFAULT_SESSION_FILE));
+                match self.save_session(&path) {
+                    Ok(()) => self.show_info(format!("Session written to {}", path.display())),
+                    Err(e) => self.show_error(format!("E482: Can't create file: {}", e)),
+                }
+            }
+            Some("source") | Some("so") => match parts.get(1) {
+                Some(path) => {
+                    if let Err(e) = self.load_session(Path::new(path)) {
+                        self.show_error(format!("E484: Can't open file {}: {}", path, e));
+                    }
+                }
+                None => self.show_error("E471: Argument required"),
+            },
+            Some("ls") | Some("buffers") | Some("files") => {
+                let listing = self.buffer_manager.format_listing();
+                self.show_info(listing);
+            }
+            Some("stats") => self.show_stats(),
+            Some(cmd @ ("sort" | "sor" | "sort!" | "sor!")) => {
+                let numeric = match parts.get(1).copied() {
+                    None => false,
+                    Some("n") => true,
+                    Some(arg) => {
+                        self.show_error(format!("E474: Invalid argument: {}", arg));
+                        return;
+                    }
+                };
+                let rows = rows.unwrap_or(0..=self.last_line());
+                self.sort_lines(rows, cmd.ends_with('!'), numeric);
+            }
+            Some("TrimWhitespace") => {
+                if self.buffer_manager.current_buffer().readonly {
+                    self.show_error("E21: Cannot make changes, 'readonly' is set");
+                    return;
+                }
+                match self.trim_current_buffer() {
+                    0 => self.show_info("No trailing whitespace"),
+                    1 => self.show_info("Trimmed trailing whitespace on 1 line"),
+                    n => self.show_info(format!("Trimmed trailing whitespace on {} lines", n)),
+                }
+            }
+            Some("e") => {
+                if let Some(filename) = parts.get(1) {
+                    self.open_location(filename);
+                }
+            }
+            Some("bufdo") | Some("bufd") => {
+                let sub_command = command
+                    .split_once(char::is_whitespace)
+                    .map(|(_, rest)| rest);
+                match sub_command.map(str::trim) {
+                    Some(sub_command) if !sub_command.is_empty() => self.bufdo(sub_command),
+                    _ => self.show_error("E471: Argument required"),
+                }
+            }
+            Some("cnext") | Some("cn") => self.quickfix_step(true),
+            Some("cprevious") | Some("cprev") | Some("cp") | Some("cNext") | Some("cN") => {
+                self.quickfix_step(false)
+            }
+            Some("browse") | Some("bro") => match parts.get(1).copied() {
+                Some("oldfiles") | Some("ol") => self.browse_recent_files(),
+                Some(_) => self.show_error(format!("E492: Not an editor command: {}", command)),
+                None => self.show_error("E471: Argument required"),
+            },
+            Some("copen") | Some("cope") => self.show_quickfix = true,
+            Some("cclose") | Some("ccl") => self.show_quickfix = false,
+            Some("grep") | Some("gr") => {
+                let pattern = command
+                    .split_once(char::is_whitespace)
+                    .map(|(_, rest)| rest);
+                match pattern.map(unquote).filter(|p| !p.is_empty()) {
+                    Some(pattern) => self.start_grep(pattern.to_string()),
+                    None => self.show_error("E471: Argument required"),
+                }
+            }
+            Some("make") | Some("mak") => {
+                let args = command
+                    .split_once(char::is_whitespace)
+                    .map(|(_, rest)| rest);
+                self.start_job(make_command(args.unwrap_or("").trim()));
+            }
+            // `:{range}!cmd` filters the lines through `cmd`; without a range
+            // its output goes to the output buffer.
+            Some(first) if first.starts_with('!') => {
+                let shell_command = command[1..].trim();
+                if shell_command.is_empty() {
+                    self.show_error("E471: Argument required");
+                    return;
+                }
+                match rows {
+                    Some(rows) => self.filter_lines(rows, shell_command),
+                    None => self.start_job(shell_command.to_string()),
+                }
+            }
+            Some(n) => self.show_error(format!("E492: Not an editor command: {}", n)),
+            // A range on its own, as in `:42` or `:$`, jumps to its last line.
+            None => {
+                if let Some(rows) = rows {
+                    self.push_jump();
+                    self.goto_line(*rows.end());
+                    self.move_first_non_blank();
+                }
+            }
+        }
     }
 
-    fn update_cursor_after_insert(&mut self, c: char) {
-        if c == '\n' {
-            self.cursor.row += 1;
-            self.cursor.col = 0;
-        } else {
-            self.cursor.col += 1;
+    /// Runs `command` with each buffer current in turn, then goes back to the
+    /// buffer it started from. The first error any buffer reported is shown
+    /// at the end.
+    fn bufdo(&mut self, command: &str) {
+        let start = self.buffer_manager.current_buffer_id();
+        let ids: Vec<usize> = self.get_buffers().iter().map(|b| b.id).collect();
+        let mut first_error = None;
+        for id in ids {
+            if !self.visit_buffer(id) {
+                continue;
+            }
+            self.run_command(command);
+            self.mode = Mode::Normal;
+            if let Some((message, MessageKind::Error)) = self.status_message.take() {
+                first_error.get_or_insert(message);
+            }
+            if self.should_quit {
+                return;
+            }
+        }
+        self.visit_buffer(start);
+        if let Some(message) = first_error {
+            self.show_error(message);
         }
     }
 
-    fn update_cursor_after_delete(&mut self) {
-        if self.cursor.col > 0 {
-            self.cursor.col -= 1;
-        } else if self.cursor.row > 0 {
-            self.cursor.row -= 1;
-            let buffer = self.buffer_manager.current_buffer_mut();
-            self.cursor.col = buffer.text.line(self.cursor.row).len_chars();
+    /// Applies a substitution to `rows`, or else the cursor line or the whole
+    /// buffer, and reports the number of replacements in the status message.
+    fn substitute(&mut self, substitution: &Substitution, rows: Option<RangeInclusive<usize>>) {
+        if self.buffer_manager.current_buffer().readonly {
+            self.show_error("E21: Cannot make changes, 'readonly' is set");
+            return;
+        }
+        let rows = match rows {
+            Some(rows) => *rows.start()..*rows.end() + 1,
+            None if substitution.whole_buffer => 0..self.get_current_text().len_lines(),
+            None => self.cursor.row..self.cursor.row + 1,
+        };
+        let matcher = match Search::compile(&substitution.pattern, &self.options) {
+            Ok(matcher) => matcher,
+            Err(message) => {
+                self.show_error(message);
+                return;
+            }
+        };
+
+        let mut count = 0;
+        let mut lines = 0;
+        let mut last_row = None;
+        // Bottom-up so earlier line starts stay valid while later lines change.
+        for row in rows.rev() {
+            let text = self.get_current_text();
+            let line_start = text.line_to_char(row);
+            let mut matches = Search::find_in_line(&matcher, &text.line(row).to_string());
+            if matches.is_empty() {
+                continue;
+            }
+            if !substitution.global {
+                matches.truncate(1);
+            }
+
+            for range in matches.iter().rev() {
+                self.remove_text(line_start + range.start..line_start + range.end);
+                self.insert_text(line_start + range.start, &substitution.replacement);
+            }
+            count += matches.len();
+            lines += 1;
+            last_row.get_or_insert(row);
+        }
+
+        match last_row {
+            Some(row) => {
+                self.cursor.row = row;
+                self.move_first_non_blank();
+                self.show_info(format!(
+                    "{} substitution{} on {} line{}",
+                    count,
+                    if count == 1 { "" } else { "s" },
+                    lines,
+                    if lines == 1 { "" } else { "s" },
+                ));
+            }
+            None => {
+                self.show_error(format!("E486: Pattern not found: {}", substitution.pattern));
+            }
         }
     }
 
-    fn execute_command(&mut self) {
-        let command = self.command_input.trim();
-        let parts: Vec<&str> = command.split_whitespace().collect();
+    /// Deletes the lines in `rows`, or the whole buffer, that match the
+    /// pattern of `global` (or don't, when inverted) and reports how many went.
+    fn global_delete(&mut self, global: &GlobalDelete, rows: Option<RangeInclusive<usize>>) {
+        if self.buffer_manager.current_buffer().readonly {
+            self.show_error("E21: Cannot make changes, 'readonly' is set");
+            return;
+        }
+        let matcher = match Search::compile(&global.pattern, &self.options) {
+            Ok(matcher) => matcher,
+            Err(message) => {
+                self.show_error(message);
+                return;
+            }
+        };
+        let rows = rows.unwrap_or(0..=self.last_line());
+        let doomed: Vec<usize> = rows
+            .filter(|&row| matcher.is_match(&self.line_text(row)) != global.invert)
+            .collect();
+        let Some(&first) = doomed.first() else {
+            if global.invert {
+                self.show_error(format!(
+                    "E486: Pattern found in every line: {}",
+                    global.pattern
+                ));
+            } else {
+                self.show_error(format!("E486: Pattern not found: {}", global.pattern));
+            }
+            return;
+        };
 
-        match parts.first().copied() {
-            Some("q") | Some("quit") => self.should_quit = true,
-            Some("w") => {
-                if let Some(path) = parts.get(1) {
-                    let save_path = PathBuf::from(*path);
-                    if let Err(e) = self.buffer_manager.save_current(Some(save_path)) {
-                        eprintln!("Failed to save buffer: {}", e);
-                    }
-                } else {
-                    let buffer = self.buffer_manager.current_buffer();
-                    if buffer.path.is_none() {
-                        self.mode = Mode::SaveDialog;
-                        self.command_input.clear();
-                        return;
-                    }
-                    if let Err(e) = self.buffer_manager.save_current(None) {
-                        eprintln!("Failed to save buffer: {}", e);
-                    }
+        // Bottom-up so the rows still to go keep their indices.
+        for &row in doomed.iter().rev() {
+            let text = self.get_current_text();
+            let mut start = text.line_to_char(row);
+            let end = text.line_to_char(row + 1);
+            if row > 0 && row == self.last_line() && text.char(end - 1) != '\n' {
+                // The final line has no newline of its own, so take the previous one.
+                start -= 1;
+            }
+            self.remove_text(start..end);
+        }
+        self.cursor.row = first.min(self.last_line());
+        self.move_first_non_blank();
+        match doomed.len() {
+            1 => self.show_info("1 fewer line"),
+            n => self.show_info(format!("{} fewer lines", n)),
+        }
+    }
+
+    /// Sorts `rows` by their text, or by the first number in each line when
+    /// `numeric` is set, in which case lines without one come first. Equal
+    /// lines keep their order. The last row keeps whatever line ending it had.
+    fn sort_lines(&mut self, rows: RangeInclusive<usize>, reverse: bool, numeric: bool) {
+        if self.buffer_manager.current_buffer().readonly {
+            self.show_error("E21: Cannot make changes, 'readonly' is set");
+            return;
+        }
+        let (first, last) = (*rows.start(), *rows.end());
+        let lines: Vec<String> = rows.map(|row| self.line_text(row)).collect();
+        let mut sorted = lines.clone();
+        sorted.sort_by(|a, b| {
+            let order = if numeric {
+                first_number(a).cmp(&first_number(b))
+            } else {
+                a.cmp(b)
+            };
+            if reverse { order.reverse() } else { order }
+        });
+        if sorted == lines {
+            return;
+        }
+
+        let text = self.get_current_text();
+        let start = text.line_to_char(first);
+        let end = text.line_to_char(last) + self.line_len(last);
+        self.remove_text(start..end);
+        self.insert_text(start, &sorted.join("\n"));
+        self.cursor = Cursor { row: first, col: 0 };
+        self.move_first_non_blank();
+    }
+
+    /// Replaces `rows` with what `command` prints when given them on stdin. If
+    /// it fails, the buffer is left alone and its stderr is shown instead.
+    fn filter_lines(&mut self, rows: RangeInclusive<usize>, command: &str) {
+        if self.buffer_manager.current_buffer().readonly {
+            self.show_error("E21: Cannot make changes, 'readonly' is set");
+            return;
+        }
+        let (first, last) = (*rows.start(), *rows.end());
+        let text = self.get_current_text();
+        let mut start = text.line_to_char(first);
+        let end = text.line_to_char(last + 1);
+        let input = text.slice(start..end).to_string();
+        let mut output = match run_shell(command, &input) {
+            Ok(output) => output,
+            Err(message) => {
+                self.show_error(message);
+                return;
+            }
+        };
+
+        // The last row keeps whatever line ending it had.
+        let had_newline = input.ends_with('\n');
+        if had_newline && !output.is_empty() && !output.ends_with('\n') {
+            output.push('\n');
+        } else if !had_newline && output.ends_with('\n') {
+            output.pop();
+        } else if !had_newline && output.is_empty() && first > 0 {
+            // Nothing replaces the final line, so take the newline before it.
+            start -= 1;
+        }
+        if output != input {
+            self.remove_text(start..end);
+            self.insert_text(start, &output);
+        }
+        self.cursor = Cursor::new(first.min(self.last_line()), 0);
+        self.move_first_non_blank();
+        match last - first + 1 {
+            1 => self.show_info("1 line filtered"),
+            n => self.show_info(format!("{} lines filtered", n)),
+        }
+    }
+
+    /// Captures the cursor, mode, view and the start of the current buffer's
+    /// text.
+    pub fn snapshot(&self) -> EditorSnapshot {
+        let text = self.get_current_text();
+        EditorSnapshot {
+            cursor: self.cursor,
+            mode: self.mode,
+            buffer_id: self.buffer_manager.current_buffer_id(),
+            scroll_offset: self.scroll_offset,
+            line_count: self.line_count(),
+            text_preview: text.chars().take(PREVIEW_CHARS).collect(),
+        }
+    }
+
+    /// Captures the buffers backed by files and the cursor in the current one.
+    pub fn session(&self) -> Session {
+        let current_id = self.buffer_manager.current_buffer_id();
+        let mut session = Session::default();
+        for buffer in self.buffer_manager.iter().filter(|b| !b.is_transient) {
+            let Some(path) = &buffer.path else {
+                continue;
+            };
+            let cursor = if buffer.id == current_id {
+                self.cursor
+            } else {
+                buffer.cursor
+            };
+            if buffer.id == current_id {
+                session.current = Some(session.buffers.len());
+            }
+            session.buffers.push(SessionBuffer {
+                path: std::path::absolute(path).unwrap_or_else(|_| path.clone()),
+                row: cursor.row,
+                col: cursor.col,
+            });
+        }
+        session
+    }
+
+    pub fn save_session(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, self.session().to_json()?)?;
+        Ok(())
+    }
+
+    /// Opens every file in the session that can still be read and returns to
+    /// the buffer and cursor that were current.
+    pub fn load_session(&mut self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let session = Session::from_json(&std::fs::read_to_string(path)?)?;
+        let mut current = None;
+        for (idx, entry) in session.buffers.iter().enumerate() {
+            if !entry.path.exists() {
+                tracing::warn!("Skipping missing session file {}", entry.path.display());
+                continue;
+            }
+            let id = match self.buffer_manager.open_file(entry.path.clone()) {
+                Ok(id) => id,
+                Err(e) => {
+                    tracing::warn!("Skipping session file {}: {}", entry.path.display(), e);
+                    continue;
                 }
+            };
+            if let Some(buffer) = self.buffer_manager.get_mut(id) {
+                buffer.cursor = entry.cursor();
             }
-            Some("wq") => {
-                let buffer = self.buffer_manager.current_buffer();
-                if buffer.path.is_none() {
-                    self.mode = Mode::SaveDialog;
-                    self.command_input.clear();
-                    return;
+            if session.current == Some(idx) || current.is_none() {
+                current = Some(id);
+            }
+        }
+        if let Some(id) = current {
+            self.switch_buffer(id);
+        }
+        Ok(())
+    }
+
+    /// Switches to buffer `id`, remembering where the cursor was in the buffer
+    /// being left and putting it back where it was in the one switched to.
+    pub fn switch_buffer(&mut self, id: usize) -> bool {
+        if id != self.buffer_manager.current_buffer_id() {
+            self.push_jump();
+        }
+        self.change_buffer(id, true)
+    }
+
+    /// Remembers the cursor position before a jump, dropping any positions
+    /// `Ctrl-o` had walked back past.
+    fn push_jump(&mut self) {
+        let position = (self.buffer_manager.current_buffer_id(), self.cursor);
+        self.jump_list.truncate(self.jump_idx);
+        if self.jump_list.last() != Some(&position) {
+            self.jump_list.push(position);
+        }
+        if self.jump_list.len() > JUMP_LIST_LEN {
+            self.jump_list.remove(0);
+        }
+        self.jump_idx = self.jump_list.len();
+    }
+
+    /// Goes to mark `name`, switching to its buffer and clamping the cursor
+    /// if the text has since shrunk.
+    fn jump_to_mark(&mut self, name: char) {
+        let Some(&(id, cursor)) = self.marks.get(&name) else {
+            self.show_error("E20: Mark not set");
+            return;
+        };
+        if id != self.buffer_manager.current_buffer_id() {
+            if !self.switch_buffer(id) {
+                self.marks.remove(&name);
+                self.show_error("E20: Mark not set");
+                return;
+            }
+        } else {
+            self.push_jump();
+        }
+        self.cursor = cursor;
+        self.clamp_cursor();
+    }
+
+    /// Moves one entry back in the jump list. Leaving the newest end records
+    /// where the cursor is so `Ctrl-i` can return to it.
+    fn jump_back(&mut self) {
+        if self.jump_idx == 0 {
+            return;
+        }
+        if self.jump_idx == self.jump_list.len() {
+            let position = (self.buffer_manager.current_buffer_id(), self.cursor);
+            self.jump_list.push(position);
+        }
+        self.jump_idx -= 1;
+        self.go_to_jump();
+    }
+
+    fn jump_forward(&mut self) {
+        if self.jump_idx + 1 < self.jump_list.len() {
+            self.jump_idx += 1;
+            self.go_to_jump();
+        }
+    }
+
+    /// Puts the cursor at the jump list entry at `jump_idx`, switching buffers
+    /// if needed. Entries for buffers that have since closed are dropped.
+    fn go_to_jump(&mut self) {
+        let (id, cursor) = self.jump_list[self.jump_idx];
+        if id != self.buffer_manager.current_buffer_id() && !self.change_buffer(id, true) {
+            self.jump_list.retain(|(jump_id, _)| *jump_id != id);
+            self.jump_idx = self.jump_idx.min(self.jump_list.len());
+            return;
+        }
+        self.cursor = cursor;
+        self.clamp_cursor();
+    }
+
+    /// Makes buffer `id` current without discarding a transient buffer being
+    /// left, for commands that pass through buffers and come back.
+    fn visit_buffer(&mut self, id: usize) -> bool {
+        self.change_buffer(id, false)
+    }
+
+    fn change_buffer(&mut self, id: usize, discard_transient: bool) -> bool {
+        let buffer = self.buffer_manager.current_buffer_mut();
+        buffer.cursor = self.cursor;
+        buffer.scroll_offset = self.scroll_offset;
+        let switched = if discard_transient {
+            self.buffer_manager.switch_to(id)
+        } else {
+            self.buffer_manager.set_current(id)
+        };
+        if switched {
+            self.restore_buffer_view();
+        }
+        switched
+    }
+
+    /// Switches to the buffer whose id is typed as `arg` in `:b N` or `:N`.
+    fn switch_to_buffer_arg(&mut self, arg: &str) {
+        match arg.parse::<usize>() {
+            Ok(id) if self.switch_buffer(id) => {}
+            Ok(id) => self.show_error(format!("E86: Buffer {} does not exist", id)),
+            Err(_) => self.show_error(format!("E94: No matching buffer for {}", arg)),
+        }
+    }
+
+    /// Loads the cursor and scroll position saved in the current buffer.
+    fn restore_buffer_view(&mut self) {
+        let buffer = self.buffer_manager.current_buffer();
+        self.cursor = buffer.cursor;
+        self.scroll_offset = buffer.scroll_offset;
+        self.secondary_cursors.clear();
+        self.clamp_cursor();
+    }
+
+    /// Creates an empty buffer and switches to it.
+    fn new_buffer(&mut self) {
+        let id = self.buffer_manager.new_buffer();
+        self.switch_buffer(id);
+    }
+
+    /// Opens the buffer list with the current buffer highlighted.
+    fn enter_buffer_list(&mut self) {
+        let current_id = self.buffer_manager.current_buffer_id();
+        self.buffer_list_idx = self
+            .get_buffers()
+            .iter()
+            .position(|b| b.id == current_id)
+            .unwrap_or(0);
+        self.mode = Mode::BufferList;
+    }
+
+    /// Opens a `path`, `path:line` or `path:line:col` argument and puts the
+    /// cursor on the position it names, clamped to the file.
+    pub fn open_location(&mut self, arg: &str) -> bool {
+        let (path, cursor) = parse_location(arg);
+        self.open_path_at(path, cursor, false)
+    }
+
+    pub fn open_path(&mut self, path: PathBuf) -> bool {
+        self.open_path_at(path, None, false)
+    }
+
+    /// Opens `path` and switches to it, moving the cursor to `cursor` if given.
+    /// A file that is already open switches to its existing buffer. With
+    /// `defer_file_loads` a new buffer starts as a placeholder and the read is
+    /// queued for the frontend.
+    ///
+    /// A `transient` buffer is a preview: it is dropped when another buffer is
+    /// switched to, unless it was edited first. Opening a previewed file
+    /// normally keeps it.
+    fn open_path_at(&mut self, path: PathBuf, cursor: Option<Cursor>, transient: bool) -> bool {
+        let existing = self.buffer_manager.find_by_path(&path);
+        let id = if let Some(id) = existing {
+            if !transient && let Some(buffer) = self.buffer_manager.get_mut(id) {
+                buffer.is_transient = false;
+            }
+            self.buffer_manager.recent_files.record(&path);
+            id
+        } else if self.defer_file_loads {
+            let id = self.buffer_manager.open_file_deferred(path.clone());
+            if let Some(buffer) = self.buffer_manager.get_mut(id) {
+                buffer.is_transient = transient;
+            }
+            self.pending_loads.push((id, path));
+            id
+        } else {
+            let opened = if transient {
+                self.buffer_manager.open_file_transient(path)
+            } else {
+                self.buffer_manager.open_file(path)
+            };
+            match opened {
+                Ok(id) => id,
+                Err(e) => {
+                    self.show_error(format!("E484: Can't open file: {}", e));
+                    return false;
                 }
-                if let Err(e) = self.buffer_manager.save_current(None) {
-                    eprintln!("Failed to save buffer: {}", e);
+            }
+        };
+        self.switch_buffer(id);
+        if existing.is_none()
+            && let Some(path) = self.get_current_path()
+            && has_swap_file(path)
+        {
+            self.show_error(format!(
+                "E325: ATTENTION: Found a swap file {}; :recover restores it",
+                swap_path(path).display()
+            ));
+        }
+        if let Some(cursor) = cursor {
+            // Also kept on the buffer, so a placeholder still lands there once
+            // its contents arrive.
+            if let Some(buffer) = self.buffer_manager.get_mut(id) {
+                buffer.cursor = cursor;
+            }
+            self.cursor = cursor;
+            self.clamp_cursor();
+        }
+        true
+    }
+
+    /// Hands the frontend the files it should read in the background, as
+    /// `(buffer id, path)` pairs. Results go back through
+    /// [`Editor::finish_load`].
+    pub fn take_pending_loads(&mut self) -> Vec<(usize, PathBuf)> {
+        std::mem::take(&mut self.pending_loads)
+    }
+
+    /// Fills placeholder buffer `id` with the bytes read from its file.
+    pub fn finish_load(&mut self, id: usize, contents: std::io::Result<Vec<u8>>) {
+        let was_current = self.buffer_manager.current_buffer_id() == id;
+        if let Err(e) = self.buffer_manager.finish_loading(id, contents) {
+            self.show_error(format!("E484: Can't open file: {}", e));
+        }
+        if was_current {
+            // Either the placeholder's start, or the buffer that replaced it.
+            self.restore_buffer_view();
+        }
+    }
+
+    /// Runs `command` with its output going to the output buffer, which is
+    /// emptied and switched to.
+    fn start_job(&mut self, command: String) {
+        let id = self.buffer_manager.reset_output_buffer();
+        self.notify_reset(id);
+        self.quickfix = QuickfixList::default();
+        self.quickfix_rows = 0;
+        if id == self.buffer_manager.current_buffer_id() {
+            self.restore_buffer_view();
+        } else {
+            self.switch_buffer(id);
+        }
+        self.run_job(Job::Shell(command));
+    }
+
+    /// `:grep`: fills the quickfix list with the matches of `pattern` in the
+    /// files under the working directory.
+    fn start_grep(&mut self, pattern: String) {
+        let regex = match Search::compile(&pattern, &self.options) {
+            Ok(regex) => regex,
+            Err(message) => {
+                self.show_error(message);
+                return;
+            }
+        };
+        self.quickfix = QuickfixList::default();
+        self.show_quickfix = true;
+        self.run_job(Job::Grep { pattern, regex });
+    }
+
+    /// Makes `job` the one whose results are collected, superseding any job
+    /// still running. With `defer_jobs` the frontend runs it in the
+    /// background; otherwise it runs to the end on the spot.
+    fn run_job(&mut self, job: Job) {
+        self.last_job_id += 1;
+        let id = self.last_job_id;
+        self.job = Some((id, job.clone()));
+        if self.defer_jobs {
+            self.show_info(match &job {
+                Job::Shell(command) => format!("Running {}", command),
+                Job::Grep { pattern, .. } => format!("Searching for {}", pattern),
+            });
+            self.pending_jobs.push((id, job));
+            return;
+        }
+        let result = match job {
+            Job::Shell(command) => {
+                let output = shell_command(&command).stdin(Stdio::null()).output();
+                if let Ok(output) = &output {
+                    self.append_job_output(id, &String::from_utf8_lossy(&output.stdout));
+                    self.append_job_output(id, &String::from_utf8_lossy(&output.stderr));
                 }
-                self.should_quit = true;
+                check_status(&command, output.map(|output| output.status))
             }
-            Some("!q") => self.should_quit = true,
-            Some("bn") | Some("bnext") => {
-                if let Some(id) = self.buffer_manager.next_buffer() {
-                    let _ = self.buffer_manager.switch_to(id);
-                    self.cursor = Cursor::new(0, 0);
+            Job::Grep { regex, .. } => {
+                grep_files(Path::new("."), &regex, |entry| {
+                    self.add_grep_match(id, entry);
+                    true
+                });
+                Ok(())
+            }
+        };
+        self.finish_job(id, result);
+    }
+
+    /// Hands the frontend the jobs it should run in the background, by job
+    /// id. Shell output goes back through [`Editor::append_job_output`],
+    /// grep matches through [`Editor::add_grep_match`] and how the job ended
+    /// through [`Editor::finish_job`].
+    pub fn take_pending_jobs(&mut self) -> Vec<(usize, Job)> {
+        std::mem::take(&mut self.pending_jobs)
+    }
+
+    /// The job whose results are being collected. Any other job the frontend
+    /// still has running was superseded and can be stopped.
+    pub fn current_job(&self) -> Option<usize> {
+        self.job.as_ref().map(|(id, _)| *id)
+    }
+
+    /// Adds `text` that job `job` printed to the end of the output buffer.
+    /// Output from a superseded job is dropped.
+    pub fn append_job_output(&mut self, job: usize, text: &str) {
+        if text.is_empty() || self.current_job() != Some(job) {
+            return;
+        }
+        let Some(buffer) = self
+            .buffer_manager
+            .iter()
+            .find(|b| b.kind == BufferKind::Output)
+        else {
+            return;
+        };
+        let id = buffer.id;
+        let char_idx = buffer.text.len_chars();
+        let row = buffer.text.len_lines() - 1;
+        let position = Cursor::new(row, char_idx - buffer.text.line_to_char(row));
+        let text = text.replace("\r\n", "\n");
+        self.notify(|_| EditEvent::Insert {
+            buffer_id: id,
+            char_idx,
+            position,
+            text: text.clone(),
+        });
+        if let Some(buffer) = self.buffer_manager.get_mut(id) {
+            buffer.append(&text);
+        }
+        self.read_quickfix_lines(false);
+    }
+
+    /// Adds the output buffer's lines not yet read to the quickfix list. The
+    /// last line is left for later unless the job is `finished`, as more of
+    /// it may still be coming.
+    fn read_quickfix_lines(&mut self, finished: bool) {
+        let Some(buffer) = self
+            .buffer_manager
+            .iter()
+            .find(|b| b.kind == BufferKind::Output)
+        else {
+            return;
+        };
+        let lines = buffer.text.len_lines();
+        let end = if finished { lines } else { lines - 1 };
+        for row in self.quickfix_rows..end {
+            let line = buffer.text.line(row).to_string();
+            self.quickfix.add_line(row, line.trim_end_matches('\n'));
+        }
+        self.quickfix_rows = self.quickfix_rows.max(end);
+    }
+
+    /// Adds a match that grep job `job` found to the quickfix list. Matches
+    /// from a superseded job are dropped.
+    pub fn add_grep_match(&mut self, job: usize, entry: QuickfixEntry) {
+        if self.current_job() == Some(job) {
+            self.quickfix.push(entry, None);
+        }
+    }
+
+    /// Reports in the status message how job `job` ended, with `result`
+    /// carrying the error when it failed.
+    pub fn finish_job(&mut self, job: usize, result: Result<(), String>) {
+        let Some((_, job)) = self.job.take_if(|(id, _)| *id == job) else {
+            return;
+        };
+        match job {
+            Job::Shell(command) => {
+                self.read_quickfix_lines(true);
+                self.show_quickfix = !self.quickfix.entries().is_empty();
+                match result {
+                    Ok(()) => self.show_info(format!("{} finished", command)),
+                    Err(message) => self.show_error(message),
                 }
             }
-            Some("bp") | Some("bprev") => {
-                if let Some(id) = self.buffer_manager.prev_buffer() {
-                    let _ = self.buffer_manager.switch_to(id);
-                    self.cursor = Cursor::new(0, 0);
+            Job::Grep { pattern, .. } => match (result, self.quickfix.entries().len()) {
+                (Err(message), _) => self.show_error(message),
+                (Ok(()), 0) => {
+                    self.show_quickfix = false;
+                    self.show_error(format!("E480: No match: {}", pattern));
                 }
+                (Ok(()), 1) => self.show_info("1 match"),
+                (Ok(()), n) => self.show_info(format!("{} matches", n)),
+            },
+        }
+    }
+
+    /// Opens the quickfix entry read from the cursor line of the output
+    /// buffer, as in `src/main.rs:3:5`.
+    fn open_error_location(&mut self) {
+        if self.buffer_manager.current_buffer().kind != BufferKind::Output {
+            return;
+        }
+        if let Some(idx) = self.quickfix.select_row(self.cursor.row) {
+            self.open_quickfix_entry(idx);
+        }
+    }
+
+    /// Opens the next quickfix entry, or the previous one.
+    fn quickfix_step(&mut self, forward: bool) {
+        match self.quickfix.step(forward) {
+            Some(idx) => self.open_quickfix_entry(idx),
+            None => self.show_error("E42: No Errors"),
+        }
+    }
+
+    /// Opens the file of quickfix entry `idx` at its line and column, and shows
+    /// its message as vim does, e.g. `(2 of 5) error: expected ;`.
+    fn open_quickfix_entry(&mut self, idx: usize) {
+        let entries = self.quickfix.entries();
+        let Some(entry) = entries.get(idx).cloned() else {
+            return;
+        };
+        let count = entries.len();
+        let cursor = Cursor::new(entry.line - 1, entry.col.map_or(0, |col| col - 1));
+        if self.open_path_at(entry.path, Some(cursor), false) {
+            self.show_info(format!("({} of {}) {}", idx + 1, count, entry.message));
+        }
+    }
+
+    /// Replaces the current buffer's contents with what is on disk.
+    fn reload_buffer(&mut self) {
+        if self.get_current_path().is_none() {
+            self.show_error("E32: No file name");
+            return;
+        }
+        match self.buffer_manager.reload_current() {
+            Ok(()) => {
+                self.change_open = false;
+                self.clamp_cursor();
+                self.notify_reset(self.buffer_manager.current_buffer_id());
+            }
+            Err(e) => self.show_error(format!("E484: Can't open file: {}", e)),
+        }
+    }
+
+    /// Replaces the current buffer's contents with what autosave left in its
+    /// swap file, as one undo step. The swap file stays until the next write.
+    /// Swap files are always UTF-8 with `\n` endings, so the buffer keeps the
+    /// encoding and line endings of its file for the next write.
+    fn recover_buffer(&mut self) {
+        let Some(path) = self.get_current_path().cloned() else {
+            self.show_error("E32: No file name");
+            return;
+        };
+        if self.buffer_manager.current_buffer().readonly {
+            self.show_error("E21: Cannot make changes, 'readonly' is set");
+            return;
+        }
+        let swap = swap_path(&path);
+        let contents = match std::fs::read_to_string(&swap) {
+            Ok(contents) => contents,
+            Err(_) => {
+                self.show_error(format!("E305: No swap file found for {}", path.display()));
+                return;
+            }
+        };
+        // A step of its own, apart from any edit before or after.
+        self.change_open = false;
+        self.begin_change();
+        self.change_open = false;
+        let buffer = self.buffer_manager.current_buffer_mut();
+        buffer.set_text(Rope::from_str(&contents.replace("\r\n", "\n")));
+        buffer.dirty = true;
+        buffer.is_transient = false;
+        self.clamp_cursor();
+        self.notify_reset(self.buffer_manager.current_buffer_id());
+        self.show_info(format!("Recovered from {}", swap.display()));
+    }
+
+    pub fn show_info(&mut self, message: impl Into<String>) {
+        self.status_message = Some((message.into(), MessageKind::Info));
+    }
+
+    pub fn show_error(&mut self, message: impl Into<String>) {
+        self.status_message = Some((message.into(), MessageKind::Error));
+    }
+
+    /// Asks for a file name, starting from an `untitled` name that isn't taken
+    /// yet in the working directory. If they all are, it starts out empty.
+    fn open_save_dialog(&mut self) {
+        self.mode = Mode::SaveDialog;
+        self.command_input = (1..=MAX_UNTITLED_NAMES)
+            .map(|n| match n {
+                1 => "untitled.txt".to_string(),
+                n => format!("untitled-{}.txt", n),
+            })
+            .find(|name| !Path::new(name).exists())
+            .unwrap_or_default();
+    }
+
+    /// Saves the current buffer, to `path` if given, and reports the outcome
+    /// on the status line. Returns whether the write succeeded.
+    fn save_current(&mut self, path: Option<PathBuf>, force: bool) -> bool {
+        let buffer = self.buffer_manager.current_buffer();
+        let own_file = path.is_none() || path == buffer.path;
+        if !force && own_file && buffer.readonly {
+            self.show_error("E45: 'readonly' option is set (add ! to override)");
+            return false;
+        }
+        if !force && own_file && self.buffer_manager.external_change_detected() {
+            self.show_error(format!(
+                "W11: \"{}\" has changed since editing started (add ! to override)",
+                self.get_current_title()
+            ));
+            return false;
+        }
+        let was_dirty = buffer.dirty;
+        let trimmed = self.options.trim_trailing && self.trim_current_buffer() > 0;
+        match self.buffer_manager.save_current(path, self.options.fix_eol) {
+            Ok(()) => {
+                let lines = self.line_count();
+                // What landed on disk, after line ending and encoding changes.
+                let bytes = self
+                    .get_current_path()
+                    .and_then(|path| std::fs::metadata(path).ok())
+                    .map_or(0, |metadata| metadata.len());
+                self.show_info(format!(
+                    "\"{}\" {}L, {}B written",
+                    self.get_current_title(),
+                    lines,
+                    bytes
+                ));
+                true
             }
-            Some("bx") | Some("bc") | Some("bclose") => {
-                if let Some(_new_id) = self.buffer_manager.delete_current() {
-                    self.cursor = Cursor::new(0, 0);
+            Err(e) => {
+                if trimmed {
+                    self.undo_trim(self.buffer_manager.current_buffer_id(), was_dirty);
                 }
+                self.show_error(format!("E212: Can't open file for writing: {}", e));
+                false
             }
-            Some("baex") | Some("ballbutexcept") => {
-                let current_id = self.buffer_manager.current_buffer_id();
-                self.buffer_manager.delete_all_except(current_id);
+        }
+    }
+
+    /// Trims trailing whitespace in the current buffer, keeping the cursor
+    /// inside its line. Returns how many lines changed.
+    fn trim_current_buffer(&mut self) -> usize {
+        let cursor = self.cursor;
+        let trimmed = self
+            .buffer_manager
+            .current_buffer_mut()
+            .trim_trailing_whitespace(cursor);
+        self.clamp_cursor();
+        if trimmed > 0 {
+            self.notify_reset(self.buffer_manager.current_buffer_id());
+        }
+        trimmed
+    }
+
+    /// Puts back the whitespace a save trimmed from buffer `id` when the write
+    /// then failed, leaving its modified flag as it was before.
+    fn undo_trim(&mut self, id: usize, dirty: bool) {
+        let Some(buffer) = self.buffer_manager.get_mut(id) else {
+            return;
+        };
+        let Some(snapshot) = buffer.history.discard() else {
+            return;
+        };
+        buffer.set_text(snapshot.text);
+        buffer.dirty = dirty;
+        if id == self.buffer_manager.current_buffer_id() {
+            self.cursor = snapshot.cursor;
+            self.clamp_cursor();
+        }
+        self.notify_reset(id);
+    }
+
+    /// Writes every modified buffer, reporting the first one that could not be
+    /// written. Returns whether everything was saved.
+    fn save_all(&mut self) -> bool {
+        // Only the buffers that will be written get trimmed.
+        let mut trimmed = Vec::new();
+        if self.options.trim_trailing {
+            let current = self.buffer_manager.current_buffer_id();
+            let writable: Vec<usize> = self
+                .get_buffers()
+                .iter()
+                .filter(|b| b.dirty && b.path.is_some() && !b.readonly && !b.changed_on_disk())
+                .map(|b| b.id)
+                .collect();
+            for id in writable {
+                let changed = if id == current {
+                    self.trim_current_buffer()
+                } else if let Some(buffer) = self.buffer_manager.get_mut(id) {
+                    let changed = buffer.trim_trailing_whitespace(buffer.cursor);
+                    if changed > 0 {
+                        self.notify_reset(id);
+                    }
+                    changed
+                } else {
+                    0
+                };
+                if changed > 0 {
+                    trimmed.push(id);
+                }
             }
-            Some("e") => {
-                if let Some(filename) = parts.get(1) {
-                    let path = PathBuf::from(*filename);
-                    if let Ok(id) = self.buffer_manager.open_file(path) {
-                        let _ = self.buffer_manager.switch_to(id);
-                        self.cursor = Cursor::new(0, 0);
+        }
+        let unsaved = self.buffer_manager.save_all(self.options.fix_eol);
+        for &id in unsaved.iter().filter(|id| trimmed.contains(id)) {
+            self.undo_trim(id, true);
+        }
+        match unsaved.first() {
+            Some(&id) => {
+                let buffer = self.buffer_manager.iter().find(|b| b.id == id);
+                self.show_error(match buffer {
+                    Some(b) if b.path.is_none() => format!("E141: No file name for buffer {}", id),
+                    Some(b) if b.readonly => {
+                        format!("E45: 'readonly' option is set for \"{}\"", b.title)
+                    }
+                    Some(b) if b.changed_on_disk() => {
+                        format!("W11: \"{}\" has changed since editing started", b.title)
                     }
+                    _ => format!("E505: Failed to write buffer {}", id),
+                });
+                false
+            }
+            None => true,
+        }
+    }
+
+    /// Closes the current buffer, refusing if it has unsaved changes unless
+    /// `force` is set.
+    fn close_buffer(&mut self, force: bool) {
+        if !force && self.is_current_dirty() {
+            self.show_error(format!(
+                "E89: No write since last change for buffer {} (add ! to override)",
+                self.buffer_manager.current_buffer_id()
+            ));
+            return;
+        }
+        if self.buffer_manager.delete_current().is_some() {
+            self.restore_buffer_view();
+        }
+    }
+
+    /// Closes every buffer but the current one, refusing if any of them has
+    /// unsaved changes unless `force` is set.
+    fn close_other_buffers(&mut self, force: bool) {
+        let current_id = self.buffer_manager.current_buffer_id();
+        if !force
+            && let Some(buffer) = self
+                .buffer_manager
+                .iter()
+                .find(|b| b.dirty && b.id != current_id)
+        {
+            self.show_error(format!(
+                "E89: No write since last change for buffer {} (add ! to override)",
+                buffer.id
+            ));
+            return;
+        }
+        self.buffer_manager.delete_all_except(current_id);
+    }
+
+    /// Quits unless a buffer has unsaved changes, in which case the status line
+    /// explains how to force it.
+    fn quit(&mut self) {
+        if self.is_current_dirty() {
+            self.show_error("E37: No write since last change (add ! to override)");
+        } else if let Some(buffer) = self.get_buffers().into_iter().find(|b| b.dirty) {
+            self.show_error(format!(
+                "E162: No write since last change for buffer \"{}\"",
+                buffer.title
+            ));
+        } else {
+            self.should_quit = true;
+        }
+    }
+
+    /// Applies one argument of `:set`, showing the option's value for `x?`
+    /// and reporting a bad argument on the status line.
+    fn set_option(&mut self, arg: &str) {
+        let result = match SetArg::parse(arg) {
+            Ok(SetArg::Show(name)) => {
+                if let Some(value) = self.option_value(name) {
+                    self.show_info(value.describe(name));
                 }
+                Ok(())
             }
-            Some(n) => {
-                if n.len() == 1
-                    && let Ok(id) = n.parse::<usize>()
-                    && self.buffer_manager.switch_to(id)
-                {
-                    self.cursor = Cursor::new(0, 0);
+            Ok(SetArg::Toggle(name)) => match self.option_value(name) {
+                Some(OptionValue::Bool(on)) => self.assign_option(name, OptionValue::Bool(!on)),
+                _ => Err("E474: Invalid argument"),
+            },
+            Ok(SetArg::Assign(name, value)) => self.assign_option(name, value),
+            Err(message) => {
+                self.show_error(message);
+                return;
+            }
+        };
+        if let Err(message) = result {
+            self.show_error(format!("{}: {}", message, arg));
+        }
+    }
+
+    /// The value of option `name`, by its full name. `fileformat`,
+    /// `filetype` and `readonly` are read from the current buffer.
+    pub fn option_value(&self, name: &str) -> Option<OptionValue> {
+        let buffer = self.buffer_manager.current_buffer();
+        match name {
+            "fileformat" => Some(OptionValue::String(buffer.line_ending.name().to_string())),
+            "filetype" => Some(OptionValue::String(
+                buffer.filetype.clone().unwrap_or_default(),
+            )),
+            "readonly" => Some(OptionValue::Bool(buffer.readonly)),
+            _ => self.options.get(name),
+        }
+    }
+
+    /// Sets option `name`, by its full name, to `value`, on the current
+    /// buffer for the options it keeps.
+    fn assign_option(&mut self, name: &str, value: OptionValue) -> Result<(), &'static str> {
+        let buffer = self.buffer_manager.current_buffer_mut();
+        match (name, value) {
+            ("fileformat", OptionValue::String(value)) => {
+                let line_ending = LineEnding::from_name(&value).ok_or("E474: Invalid argument")?;
+                if buffer.line_ending != line_ending {
+                    buffer.line_ending = line_ending;
+                    buffer.dirty = true;
                 }
             }
-            None => {}
+            ("filetype", OptionValue::String(value)) => {
+                buffer.set_filetype((!value.is_empty()).then_some(value))
+            }
+            ("readonly", OptionValue::Bool(on)) => buffer.readonly = on,
+            (name, value) => self.options.set(name, value)?,
         }
-        self.mode = Mode::Normal;
-        self.command_input.clear();
+        Ok(())
     }
 
     pub fn insert_into_command(&mut self, c: char) {
+        self.command_recall = None;
+        self.command_completion = None;
         self.command_input.push(c);
+        self.preview_search();
+    }
+
+    /// While a search is typed, highlights what the query so far matches and
+    /// moves the cursor to the first match after where the search started.
+    fn preview_search(&mut self) {
+        let Some(origin) = self.search_origin.as_ref().map(|origin| origin.cursor) else {
+            return;
+        };
+        self.search.query = Some(self.command_input.clone());
+        let text = self.get_current_text().clone();
+        // A half-typed pattern may not compile yet; it just previews nothing.
+        let _ = self.search.update(&text, &self.options);
+        let origin_idx = text.line_to_char(origin.row) + origin.col;
+        self.cursor = match self.search.next_match(origin_idx) {
+            Some(target) => self.cursor_from_char_idx(target),
+            None => origin,
+        };
     }
 
     pub fn init_file_picker(&mut self) {
+        self.file_picker.listing_recent = false;
         self.file_picker.refresh();
     }
 
+    /// Files opened in this and earlier sessions, most recent first.
+    pub fn recent_files(&self) -> &[PathBuf] {
+        self.buffer_manager.recent_files.paths()
+    }
+
+    /// `:browse oldfiles`: opens the file picker on the recent files.
+    fn browse_recent_files(&mut self) {
+        if self.recent_files().is_empty() {
+            self.show_info("No recent files");
+            return;
+        }
+        let paths = self.recent_files().to_vec();
+        self.file_picker.list_recent(&paths);
+        self.mode = Mode::FilePicker;
+    }
+
     pub fn file_picker_up(&mut self) {
         self.file_picker.move_up();
     }
@@ -395,9 +3886,7 @@ impl Editor {
         if let Some(file) = self.file_picker.selected_file()
             && file.is_dir
         {
-            self.file_picker.current_dir = file.path.clone();
-            self.file_picker.refresh();
-            self.file_picker.selected_idx = 0;
+            self.file_picker.navigate_to(file.path.clone());
         }
         Ok(())
     }
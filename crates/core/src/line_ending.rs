@@ -0,0 +1,72 @@
+/// Line terminator style of a file. Buffers always hold `\n` internally and
+/// convert back to this style on save.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    /// Picks whichever style most lines in `text` use, preferring `Lf` on a tie.
+    pub fn detect(text: &str) -> Self {
+        let newlines = text.matches('\n').count();
+        let crlf = text.matches("\r\n").count();
+        if crlf > newlines - crlf {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    /// The terminator itself, as written to the file.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+
+    /// Vim's `fileformat` name for this style.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "unix",
+            LineEnding::Crlf => "dos",
+        }
+    }
+
+    /// Parses a `fileformat` name as `:set ff=` takes it, `unix` or `dos`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "unix" => Some(LineEnding::Lf),
+            "dos" => Some(LineEnding::Crlf),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_picks_the_dominant_style() {
+        assert_eq!(LineEnding::detect("a\r\nb\r\nc\n"), LineEnding::Crlf);
+        assert_eq!(LineEnding::detect("a\nb\nc\r\n"), LineEnding::Lf);
+    }
+
+    #[test]
+    fn detect_prefers_lf_on_a_tie_or_without_newlines() {
+        assert_eq!(LineEnding::detect("a\r\nb\n"), LineEnding::Lf);
+        assert_eq!(LineEnding::detect("no newline"), LineEnding::Lf);
+        assert_eq!(LineEnding::detect(""), LineEnding::Lf);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for ending in [LineEnding::Lf, LineEnding::Crlf] {
+            assert_eq!(LineEnding::from_name(ending.name()), Some(ending));
+        }
+        assert_eq!(LineEnding::from_name("mac"), None);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::cursor::Cursor;
+use std::path::PathBuf;
+
+/// Splits a `path:line` or `path:line:col` argument into the path and the
+/// cursor position it names. Line and column are 1-based. Only trailing
+/// numbers are taken off, so a colon elsewhere stays part of the path.
+pub fn parse_location(arg: &str) -> (PathBuf, Option<Cursor>) {
+    let mut path = arg;
+    let mut numbers = Vec::new();
+    while numbers.len() < 2
+        && let Some((rest, tail)) = path.rsplit_once(':')
+        && !rest.is_empty()
+        && let Ok(n) = tail.parse::<usize>()
+    {
+        numbers.push(n);
+        path = rest;
+    }
+
+    let cursor = match numbers[..] {
+        [line] => Some(Cursor::new(line.saturating_sub(1), 0)),
+        [col, line] => Some(Cursor::new(line.saturating_sub(1), col.saturating_sub(1))),
+        _ => None,
+    };
+    (PathBuf::from(path), cursor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_and_line() {
+        assert_eq!(
+            parse_location("src/lib.rs:42"),
+            (PathBuf::from("src/lib.rs"), Some(Cursor::new(41, 0)))
+        );
+    }
+
+    #[test]
+    fn path_line_and_column() {
+        assert_eq!(
+            parse_location("src/lib.rs:42:7"),
+            (PathBuf::from("src/lib.rs"), Some(Cursor::new(41, 6)))
+        );
+    }
+
+    #[test]
+    fn a_colon_in_a_directory_name_stays_in_the_path() {
+        assert_eq!(
+            parse_location("c:dir/file.rs"),
+            (PathBuf::from("c:dir/file.rs"), None)
+        );
+        assert_eq!(
+            parse_location("a:1/file.rs:3"),
+            (PathBuf::from("a:1/file.rs"), Some(Cursor::new(2, 0)))
+        );
+    }
+
+    #[test]
+    fn a_bare_number_is_a_file_name() {
+        assert_eq!(parse_location("42"), (PathBuf::from("42"), None));
+        assert_eq!(parse_location(":42"), (PathBuf::from(":42"), None));
+    }
+
+    #[test]
+    fn line_zero_is_the_first_line() {
+        assert_eq!(
+            parse_location("f:0:0"),
+            (PathBuf::from("f"), Some(Cursor::new(0, 0)))
+        );
+    }
+}
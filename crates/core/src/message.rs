@@ -0,0 +1,6 @@
+/// How a status line message should be presented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    Info,
+    Error,
+}
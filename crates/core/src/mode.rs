@@ -2,8 +2,29 @@
 pub enum Mode {
     Normal,
     Insert,
+    /// Typed chars overwrite the text under the cursor, entered with `R`.
+    Replace,
     Visual,
+    /// Visual mode that selects whole lines, entered with `V`.
+    VisualLine,
+    /// Visual mode that selects a rectangle of columns, entered with `Ctrl-v`.
+    VisualBlock,
     Command,
+    Search,
     SaveDialog,
     FilePicker,
+    BufferList,
+}
+
+impl Mode {
+    /// Insert and replace mode, where typed chars go into the buffer.
+    pub fn is_typing(self) -> bool {
+        matches!(self, Mode::Insert | Mode::Replace)
+    }
+
+    /// Charwise, linewise and block visual mode, where motions extend a
+    /// selection.
+    pub fn is_visual(self) -> bool {
+        matches!(self, Mode::Visual | Mode::VisualLine | Mode::VisualBlock)
+    }
 }
@@ -0,0 +1,32 @@
+use crate::Cursor;
+use std::ops::Range;
+
+/// A change to a buffer's text, as passed to each [`EditObserver`]. Positions
+/// are where the change starts, before it was made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditEvent {
+    /// `text` went in at char index `char_idx`, which is `position`.
+    Insert {
+        buffer_id: usize,
+        char_idx: usize,
+        position: Cursor,
+        text: String,
+    },
+    /// The chars in `range`, starting at `position`, were removed. `text` is
+    /// what they held.
+    Delete {
+        buffer_id: usize,
+        range: Range<usize>,
+        position: Cursor,
+        text: String,
+    },
+    /// The whole text was swapped out at once, e.g. by undo, `:e!` or
+    /// trimming trailing whitespace. Observers should read it afresh.
+    Reset { buffer_id: usize },
+}
+
+/// Gets told about every edit the editor makes, e.g. to keep a language
+/// server or a word count in sync. Added with `Editor::add_observer`.
+pub trait EditObserver {
+    fn on_edit(&mut self, event: &EditEvent);
+}
@@ -0,0 +1,87 @@
+use crate::Action;
+
+/// An operator waiting for a motion, as `d`, `c` and `y` do. Typing the
+/// operator twice applies it to whole lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Delete,
+    Change,
+    Yank,
+    /// `gu`
+    Lowercase,
+    /// `gU`
+    Uppercase,
+    /// `g~`
+    ToggleCase,
+    /// `!`, which opens the command line to filter the lines it covers
+    /// through a shell command.
+    Filter,
+}
+
+impl Operator {
+    /// Whether this is one of the operators that only change case.
+    pub(crate) fn changes_case(self) -> bool {
+        matches!(self, Self::Lowercase | Self::Uppercase | Self::ToggleCase)
+    }
+
+    /// `text` as a case operator leaves it. Chars without case pass through,
+    /// as does everything for the other operators.
+    pub(crate) fn apply_case(self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        for c in text.chars() {
+            match self {
+                Self::Lowercase => out.extend(c.to_lowercase()),
+                Self::Uppercase => out.extend(c.to_uppercase()),
+                Self::ToggleCase if c.is_lowercase() => out.extend(c.to_uppercase()),
+                Self::ToggleCase => out.extend(c.to_lowercase()),
+                _ => out.push(c),
+            }
+        }
+        out
+    }
+}
+
+/// How an operator treats the text between the cursor and where a motion
+/// lands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MotionKind {
+    /// Up to but not including the far end, like `w` or `h`.
+    Exclusive,
+    /// Including the char at the far end, like `$` or `f`.
+    Inclusive,
+    /// Every line the motion touches, like `j` or `gg`.
+    Linewise,
+}
+
+impl MotionKind {
+    /// The kind of motion `action` is, or `None` if it doesn't move the cursor
+    /// and so can't follow an operator.
+    pub(crate) fn of(action: &Action) -> Option<Self> {
+        Some(match action {
+            Action::MoveLeft
+            | Action::MoveRight
+            | Action::MoveLineStart
+            | Action::MoveFirstNonBlank
+            | Action::MoveWordForward
+            | Action::FindCharBackward(_)
+            | Action::TillCharBackward(_)
+            | Action::SearchNext
+            | Action::SearchPrev => Self::Exclusive,
+            Action::MoveLineEnd
+            | Action::FindChar(_)
+            | Action::TillChar(_)
+            | Action::RepeatFind
+            | Action::RepeatFindReverse
+            | Action::MatchBracket => Self::Inclusive,
+            Action::MoveUp
+            | Action::MoveDown
+            | Action::GotoFirstLine
+            | Action::GotoLastLine
+            | Action::HalfPageDown
+            | Action::HalfPageUp
+            | Action::PageDown
+            | Action::PageUp => Self::Linewise,
+            _ => return None,
+        })
+    }
+}
@@ -0,0 +1,390 @@
+/// What the gutter shows next to each line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineNumberMode {
+    /// No gutter.
+    None,
+    /// 1-based line numbers.
+    Absolute,
+    /// Distance from the cursor line.
+    Relative,
+    /// Distance from the cursor, with the cursor line's own number.
+    #[default]
+    Hybrid,
+}
+
+impl LineNumberMode {
+    /// The number shown beside `row` while the cursor is on `cursor_row`.
+    pub fn number_for(self, row: usize, cursor_row: usize) -> Option<usize> {
+        match self {
+            Self::None => None,
+            Self::Absolute => Some(row + 1),
+            Self::Relative => Some(row.abs_diff(cursor_row)),
+            Self::Hybrid if row == cursor_row => Some(row + 1),
+            Self::Hybrid => Some(row.abs_diff(cursor_row)),
+        }
+    }
+
+    /// The mode after turning `number` (absolute) or `relativenumber` on or off,
+    /// combining the two the way vim does.
+    pub fn with(self, absolute: Option<bool>, relative: Option<bool>) -> Self {
+        let has_absolute = matches!(self, Self::Absolute | Self::Hybrid);
+        let has_relative = matches!(self, Self::Relative | Self::Hybrid);
+        match (
+            absolute.unwrap_or(has_absolute),
+            relative.unwrap_or(has_relative),
+        ) {
+            (false, false) => Self::None,
+            (true, false) => Self::Absolute,
+            (false, true) => Self::Relative,
+            (true, true) => Self::Hybrid,
+        }
+    }
+}
+
+/// What an option holds, as `:set` reads and shows it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionValue {
+    Bool(bool),
+    Number(usize),
+    String(String),
+}
+
+impl OptionValue {
+    /// How `:set name?` shows this value of option `name`: `number`,
+    /// `nonumber` or `tabstop=4`.
+    pub fn describe(&self, name: &str) -> String {
+        match self {
+            Self::Bool(true) => name.to_string(),
+            Self::Bool(false) => format!("no{}", name),
+            Self::Number(number) => format!("{}={}", name, number),
+            Self::String(value) => format!("{}={}", name, value),
+        }
+    }
+}
+
+/// What kind of value an option takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OptionKind {
+    Bool,
+    Number,
+    String,
+}
+
+/// Every option `:set` knows, by full name and vim's short name, with the
+/// kind of value it takes. `fileformat`, `filetype` and `readonly` belong to
+/// the current buffer; the rest are [`EditorOptions`].
+const OPTIONS: &[(&str, Option<&str>, OptionKind)] = &[
+    ("autoindent", Some("ai"), OptionKind::Bool),
+    ("autopairs", None, OptionKind::Bool),
+    ("autosave", None, OptionKind::Number),
+    ("colorcolumn", Some("cc"), OptionKind::String),
+    ("cursorline", Some("cul"), OptionKind::Bool),
+    ("expandtab", Some("et"), OptionKind::Bool),
+    ("fileformat", Some("ff"), OptionKind::String),
+    ("filetype", Some("ft"), OptionKind::String),
+    ("fixendofline", Some("fixeol"), OptionKind::Bool),
+    ("ignorecase", Some("ic"), OptionKind::Bool),
+    ("list", None, OptionKind::Bool),
+    ("magic", None, OptionKind::Bool),
+    ("number", Some("nu"), OptionKind::Bool),
+    ("readonly", Some("ro"), OptionKind::Bool),
+    ("relativenumber", Some("rnu"), OptionKind::Bool),
+    ("shiftwidth", Some("sw"), OptionKind::Number),
+    ("smartcase", Some("scs"), OptionKind::Bool),
+    ("tabstop", Some("ts"), OptionKind::Number),
+    ("trimtrailing", None, OptionKind::Bool),
+    ("wrap", None, OptionKind::Bool),
+];
+
+/// The full name and kind of the option called `name`, in full or short.
+fn lookup(name: &str) -> Option<(&'static str, OptionKind)> {
+    OPTIONS
+        .iter()
+        .find(|(full, short, _)| *full == name || *short == Some(name))
+        .map(|&(full, _, kind)| (full, kind))
+}
+
+/// One argument of `:set`, with the option called by its full name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetArg {
+    /// `:set x?`, or `:set x` for an option that isn't a boolean.
+    Show(&'static str),
+    /// `:set x` or `:set nox` for a boolean, or `:set x=value`.
+    Assign(&'static str, OptionValue),
+    /// `:set invx` or `:set x!`, flipping a boolean.
+    Toggle(&'static str),
+}
+
+impl SetArg {
+    /// Reads one argument of `:set`. The error is vim's message for it.
+    pub fn parse(arg: &str) -> Result<Self, String> {
+        let unknown = || format!("E518: Unknown option: {}", arg);
+        let invalid = || format!("E474: Invalid argument: {}", arg);
+
+        if let Some((name, value)) = arg.split_once('=') {
+            let (name, kind) = lookup(name).ok_or_else(unknown)?;
+            let value = match kind {
+                OptionKind::Bool => return Err(invalid()),
+                OptionKind::Number => OptionValue::Number(
+                    value
+                        .parse()
+                        .map_err(|_| format!("E521: Number required after =: {}", arg))?,
+                ),
+                OptionKind::String => OptionValue::String(value.to_string()),
+            };
+            return Ok(Self::Assign(name, value));
+        }
+        if let Some(name) = arg.strip_suffix('?') {
+            let (name, _) = lookup(name).ok_or_else(unknown)?;
+            return Ok(Self::Show(name));
+        }
+        if let Some(name) = arg.strip_suffix('!').or_else(|| arg.strip_prefix("inv")) {
+            match lookup(name) {
+                Some((name, OptionKind::Bool)) => return Ok(Self::Toggle(name)),
+                Some(_) => return Err(invalid()),
+                None => {}
+            }
+        }
+        match lookup(arg) {
+            Some((name, OptionKind::Bool)) => {
+                return Ok(Self::Assign(name, OptionValue::Bool(true)));
+            }
+            Some((name, _)) => return Ok(Self::Show(name)),
+            None => {}
+        }
+        match arg.strip_prefix("no").and_then(lookup) {
+            Some((name, OptionKind::Bool)) => Ok(Self::Assign(name, OptionValue::Bool(false))),
+            Some(_) => Err(invalid()),
+            None => Err(unknown()),
+        }
+    }
+}
+
+/// User-adjustable editor settings, changed with `:set`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditorOptions {
+    /// Display width of a tab stop.
+    pub tab_width: usize,
+    /// Columns added or removed by `>>` and `<<`.
+    pub shift_width: usize,
+    /// Insert spaces instead of a literal tab when Tab is pressed.
+    pub expand_tabs: bool,
+    /// Start new lines with the indentation of the line they were opened from.
+    pub auto_indent: bool,
+    /// Close brackets and quotes as they are typed.
+    pub autopairs: bool,
+    pub line_numbers: LineNumberMode,
+    /// Give the cursor's line a background highlight.
+    pub cursor_line: bool,
+    /// 1-based screen column marked with a ruler, as in `:set colorcolumn=80`.
+    pub color_column: Option<usize>,
+    /// Continue long lines on the rows below instead of scrolling sideways.
+    pub wrap: bool,
+    /// Draw tabs as `→` and trailing spaces as `·`.
+    pub list: bool,
+    /// Strip trailing spaces and tabs from every line when writing.
+    pub trim_trailing: bool,
+    /// End the file with a newline when writing if the last line lacks one.
+    pub fix_eol: bool,
+    /// Seconds between copies of modified buffers to their swap files.
+    pub autosave: Option<usize>,
+    /// Read search and substitution patterns as regexes rather than literal text.
+    pub magic: bool,
+    /// Match searches and substitutions regardless of case.
+    pub ignore_case: bool,
+    /// With `ignore_case`, match case anyway when the pattern has a capital.
+    pub smart_case: bool,
+}
+
+impl Default for EditorOptions {
+    fn default() -> Self {
+        Self {
+            tab_width: 4,
+            shift_width: 4,
+            expand_tabs: false,
+            auto_indent: true,
+            autopairs: false,
+            line_numbers: LineNumberMode::default(),
+            cursor_line: false,
+            color_column: None,
+            wrap: true,
+            list: false,
+            trim_trailing: false,
+            fix_eol: true,
+            autosave: None,
+            magic: true,
+            ignore_case: false,
+            smart_case: false,
+        }
+    }
+}
+
+impl EditorOptions {
+    /// The value of option `name`, by its full name, or `None` if it isn't
+    /// one of these.
+    pub fn get(&self, name: &str) -> Option<OptionValue> {
+        use OptionValue::{Bool, Number, String};
+        let numbers = self.line_numbers;
+        Some(match name {
+            "autoindent" => Bool(self.auto_indent),
+            "autopairs" => Bool(self.autopairs),
+            "autosave" => Number(self.autosave.unwrap_or(0)),
+            "colorcolumn" => String(
+                self.color_column
+                    .map(|column| column.to_string())
+                    .unwrap_or_default(),
+            ),
+            "cursorline" => Bool(self.cursor_line),
+            "expandtab" => Bool(self.expand_tabs),
+            "fixendofline" => Bool(self.fix_eol),
+            "ignorecase" => Bool(self.ignore_case),
+            "list" => Bool(self.list),
+            "magic" => Bool(self.magic),
+            "number" => Bool(matches!(
+                numbers,
+                LineNumberMode::Absolute | LineNumberMode::Hybrid
+            )),
+            "relativenumber" => Bool(matches!(
+                numbers,
+                LineNumberMode::Relative | LineNumberMode::Hybrid
+            )),
+            "shiftwidth" => Number(self.shift_width),
+            "smartcase" => Bool(self.smart_case),
+            "tabstop" => Number(self.tab_width),
+            "trimtrailing" => Bool(self.trim_trailing),
+            "wrap" => Bool(self.wrap),
+            _ => return None,
+        })
+    }
+
+    /// Sets option `name`, by its full name, to `value`. A value out of range
+    /// or of the wrong kind is refused with the start of vim's message.
+    pub fn set(&mut self, name: &str, value: OptionValue) -> Result<(), &'static str> {
+        use OptionValue::{Bool, Number, String};
+        match (name, value) {
+            ("autoindent", Bool(on)) => self.auto_indent = on,
+            ("autopairs", Bool(on)) => self.autopairs = on,
+            ("autosave", Number(seconds)) => self.autosave = (seconds > 0).then_some(seconds),
+            ("colorcolumn", String(value)) => {
+                self.color_column = match value.parse::<usize>() {
+                    Ok(column) => (column > 0).then_some(column),
+                    Err(_) if value.is_empty() => None,
+                    Err(_) => return Err("E474: Invalid argument"),
+                }
+            }
+            ("cursorline", Bool(on)) => self.cursor_line = on,
+            ("expandtab", Bool(on)) => self.expand_tabs = on,
+            ("fixendofline", Bool(on)) => self.fix_eol = on,
+            ("ignorecase", Bool(on)) => self.ignore_case = on,
+            ("list", Bool(on)) => self.list = on,
+            ("magic", Bool(on)) => self.magic = on,
+            ("number", Bool(on)) => self.line_numbers = self.line_numbers.with(Some(on), None),
+            ("relativenumber", Bool(on)) => {
+                self.line_numbers = self.line_numbers.with(None, Some(on))
+            }
+            ("shiftwidth" | "tabstop", Number(0)) => return Err("E487: Argument must be positive"),
+            ("shiftwidth", Number(width)) => self.shift_width = width,
+            ("smartcase", Bool(on)) => self.smart_case = on,
+            ("tabstop", Number(width)) => self.tab_width = width,
+            ("trimtrailing", Bool(on)) => self.trim_trailing = on,
+            ("wrap", Bool(on)) => self.wrap = on,
+            _ => return Err("E474: Invalid argument"),
+        }
+        Ok(())
+    }
+
+    /// Whether searching for `pattern` should ignore case.
+    pub fn ignores_case(&self, pattern: &str) -> bool {
+        self.ignore_case && !(self.smart_case && pattern.chars().any(char::is_uppercase))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The numbers shown for rows 0 to 4 with the cursor on row 2.
+    fn numbers(mode: LineNumberMode) -> Vec<Option<usize>> {
+        (0..5).map(|row| mode.number_for(row, 2)).collect()
+    }
+
+    #[test]
+    fn each_mode_numbers_lines_its_own_way() {
+        assert_eq!(numbers(LineNumberMode::None), [None; 5]);
+        assert_eq!(
+            numbers(LineNumberMode::Absolute),
+            [Some(1), Some(2), Some(3), Some(4), Some(5)]
+        );
+        assert_eq!(
+            numbers(LineNumberMode::Relative),
+            [Some(2), Some(1), Some(0), Some(1), Some(2)]
+        );
+        assert_eq!(
+            numbers(LineNumberMode::Hybrid),
+            [Some(2), Some(1), Some(3), Some(1), Some(2)]
+        );
+    }
+
+    #[test]
+    fn number_and_relativenumber_combine() {
+        let mode = LineNumberMode::None.with(Some(true), None);
+        assert_eq!(mode, LineNumberMode::Absolute);
+        let mode = mode.with(None, Some(true));
+        assert_eq!(mode, LineNumberMode::Hybrid);
+        let mode = mode.with(Some(false), None);
+        assert_eq!(mode, LineNumberMode::Relative);
+        assert_eq!(mode.with(None, Some(false)), LineNumberMode::None);
+    }
+
+    #[test]
+    fn set_args_parse_by_kind() {
+        use OptionValue::{Bool, Number, String};
+        assert_eq!(
+            SetArg::parse("ic"),
+            Ok(SetArg::Assign("ignorecase", Bool(true)))
+        );
+        assert_eq!(
+            SetArg::parse("nowrap"),
+            Ok(SetArg::Assign("wrap", Bool(false)))
+        );
+        assert_eq!(
+            SetArg::parse("ts=8"),
+            Ok(SetArg::Assign("tabstop", Number(8)))
+        );
+        assert_eq!(
+            SetArg::parse("ft=rust"),
+            Ok(SetArg::Assign("filetype", String("rust".into())))
+        );
+        assert_eq!(SetArg::parse("list!"), Ok(SetArg::Toggle("list")));
+        assert_eq!(SetArg::parse("invlist"), Ok(SetArg::Toggle("list")));
+        assert_eq!(SetArg::parse("sw?"), Ok(SetArg::Show("shiftwidth")));
+        assert_eq!(SetArg::parse("tabstop"), Ok(SetArg::Show("tabstop")));
+    }
+
+    #[test]
+    fn set_args_of_the_wrong_kind_are_errors() {
+        let error = |arg: &str| SetArg::parse(arg).expect_err(arg);
+        assert_eq!(error("ts=wide"), "E521: Number required after =: ts=wide");
+        assert_eq!(error("ts=-1"), "E521: Number required after =: ts=-1");
+        assert_eq!(error("wrap=1"), "E474: Invalid argument: wrap=1");
+        assert_eq!(error("ts!"), "E474: Invalid argument: ts!");
+        assert_eq!(error("nots"), "E474: Invalid argument: nots");
+    }
+
+    #[test]
+    fn unknown_options_are_errors() {
+        for arg in ["bogus", "nobogus", "bogus=1", "bogus?", "invbogus"] {
+            assert_eq!(
+                SetArg::parse(arg),
+                Err(format!("E518: Unknown option: {}", arg))
+            );
+        }
+    }
+
+    #[test]
+    fn values_are_described_as_set_shows_them() {
+        assert_eq!(OptionValue::Bool(true).describe("list"), "list");
+        assert_eq!(OptionValue::Bool(false).describe("list"), "nolist");
+        assert_eq!(OptionValue::Number(4).describe("tabstop"), "tabstop=4");
+    }
+}
@@ -0,0 +1,236 @@
+use std::path::PathBuf;
+
+/// A place in a file named by a line of compiler or grep output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuickfixEntry {
+    pub path: PathBuf,
+    /// 1-based, as printed.
+    pub line: usize,
+    /// 1-based, when the line gave one.
+    pub col: Option<usize>,
+    /// What the output said about the place, e.g. `error: expected ;`.
+    pub message: String,
+}
+
+/// Picks the `path:line:col` or `path:line` location out of the start of
+/// `line`, as gcc prints `main.c:3:5: error: ...` and `grep -n` prints
+/// `src/lib.rs:12:...`, with the rest of the line as its message. Leading
+/// spaces and cargo's `-->` arrow are skipped. A path with spaces or made
+/// only of digits, like the `12` of `12:30:45`, doesn't count.
+pub fn parse_error_line(line: &str) -> Option<QuickfixEntry> {
+    let line = line.trim_start();
+    let line = line
+        .strip_prefix("-->")
+        .or_else(|| line.strip_prefix(":::"))
+        .unwrap_or(line)
+        .trim_start();
+    let (path, rest) = line.split_once(':')?;
+    if path.is_empty()
+        || path.contains(char::is_whitespace)
+        || path.chars().all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let (number, rest) = split_field(rest);
+    let line = number.parse().ok().filter(|&n| n > 0)?;
+    let (field, after) = split_field(rest);
+    let (col, message) = match field.parse().ok().filter(|&n: &usize| n > 0) {
+        Some(col) => (Some(col), after),
+        None => (None, rest),
+    };
+    Some(QuickfixEntry {
+        path: PathBuf::from(path),
+        line,
+        col,
+        message: message.trim().to_string(),
+    })
+}
+
+/// Splits `text` at its first `:`, or returns all of it when there is none.
+fn split_field(text: &str) -> (&str, &str) {
+    text.split_once(':').unwrap_or((text, ""))
+}
+
+/// The locations found in a command's output, read a line at a time as it
+/// comes in, and the one last jumped to.
+#[derive(Debug, Clone, Default)]
+pub struct QuickfixList {
+    entries: Vec<QuickfixEntry>,
+    /// Output row each entry was read from, if it came from the output
+    /// buffer.
+    rows: Vec<Option<usize>>,
+    current: Option<usize>,
+    /// The last line that wasn't a location. Cargo prints the message on the
+    /// line above its ` --> file:line:col`.
+    context: String,
+}
+
+impl QuickfixList {
+    /// Reads `line`, found at `row` of the output, adding an entry if it
+    /// names a location.
+    pub fn add_line(&mut self, row: usize, line: &str) {
+        match parse_error_line(line) {
+            Some(mut entry) => {
+                if entry.message.is_empty() {
+                    entry.message = self.context.clone();
+                }
+                self.push(entry, Some(row));
+            }
+            None if !line.trim().is_empty() => self.context = line.trim().to_string(),
+            None => {}
+        }
+    }
+
+    /// Adds `entry`, read from output row `row` if it came from the output
+    /// buffer.
+    pub fn push(&mut self, entry: QuickfixEntry, row: Option<usize>) {
+        self.entries.push(entry);
+        self.rows.push(row);
+    }
+
+    pub fn entries(&self) -> &[QuickfixEntry] {
+        &self.entries
+    }
+
+    /// Index of the entry last jumped to.
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    /// Moves to the next entry, or the previous one, wrapping around at either
+    /// end. Before any jump, forward starts at the first entry and backward at
+    /// the last. Returns the new index, or `None` if the list is empty.
+    pub fn step(&mut self, forward: bool) -> Option<usize> {
+        let len = self.entries.len();
+        if len == 0 {
+            return None;
+        }
+        let next = match (self.current, forward) {
+            (None, true) => 0,
+            (None, false) => len - 1,
+            (Some(idx), true) => (idx + 1) % len,
+            (Some(idx), false) => (idx + len - 1) % len,
+        };
+        self.current = Some(next);
+        self.current
+    }
+
+    /// Makes the entry read from output row `row` the current one.
+    pub fn select_row(&mut self, row: usize) -> Option<usize> {
+        let idx = self.rows.iter().position(|&r| r == Some(row))?;
+        self.current = Some(idx);
+        self.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, line: usize, col: Option<usize>, message: &str) -> QuickfixEntry {
+        QuickfixEntry {
+            path: PathBuf::from(path),
+            line,
+            col,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn reads_path_line_and_column() {
+        assert_eq!(
+            parse_error_line("main.c:3:5: error: expected ';'"),
+            Some(entry("main.c", 3, Some(5), "error: expected ';'"))
+        );
+    }
+
+    #[test]
+    fn the_column_is_optional() {
+        assert_eq!(
+            parse_error_line("src/lib.rs:12:    let x = 1;"),
+            Some(entry("src/lib.rs", 12, None, "let x = 1;"))
+        );
+        assert_eq!(
+            parse_error_line("Makefile:7"),
+            Some(entry("Makefile", 7, None, ""))
+        );
+    }
+
+    #[test]
+    fn cargo_arrows_are_skipped() {
+        assert_eq!(
+            parse_error_line("  --> src/main.rs:4:9"),
+            Some(entry("src/main.rs", 4, Some(9), ""))
+        );
+        assert_eq!(
+            parse_error_line("   ::: src/lib.rs:1:1"),
+            Some(entry("src/lib.rs", 1, Some(1), ""))
+        );
+    }
+
+    #[test]
+    fn lines_that_are_not_locations_are_skipped() {
+        for line in [
+            "error[E0308]: mismatched types",
+            "12:30:45 build finished",
+            "my file.rs:3:1",
+            "src/main.rs:0:1",
+            "src/main.rs:x:1",
+            "",
+        ] {
+            assert_eq!(parse_error_line(line), None, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn a_bare_location_takes_the_message_above_it() {
+        let mut list = QuickfixList::default();
+        list.add_line(0, "error[E0308]: mismatched types");
+        list.add_line(1, " --> src/main.rs:4:9");
+        list.add_line(2, "");
+        list.add_line(3, "src/lib.rs:1:1: warning: unused");
+        assert_eq!(
+            list.entries(),
+            [
+                entry("src/main.rs", 4, Some(9), "error[E0308]: mismatched types"),
+                entry("src/lib.rs", 1, Some(1), "warning: unused"),
+            ]
+        );
+    }
+
+    fn list_of(count: usize) -> QuickfixList {
+        let mut list = QuickfixList::default();
+        for line in 1..=count {
+            list.push(entry("a.rs", line, None, ""), None);
+        }
+        list
+    }
+
+    #[test]
+    fn stepping_wraps_around_either_end() {
+        let mut list = list_of(3);
+        assert_eq!(list.current(), None);
+        let forward: Vec<_> = (0..4).map(|_| list.step(true)).collect();
+        assert_eq!(forward, [Some(0), Some(1), Some(2), Some(0)]);
+        assert_eq!(list.step(false), Some(2));
+    }
+
+    #[test]
+    fn stepping_back_first_starts_at_the_last_entry() {
+        let mut list = list_of(3);
+        assert_eq!(list.step(false), Some(2));
+        assert_eq!(QuickfixList::default().step(true), None);
+    }
+
+    #[test]
+    fn an_output_row_selects_the_entry_read_from_it() {
+        let mut list = QuickfixList::default();
+        list.add_line(0, "building");
+        list.add_line(1, "a.rs:1: x");
+        list.add_line(2, "b.rs:2: y");
+        assert_eq!(list.select_row(2), Some(1));
+        assert_eq!(list.current(), Some(1));
+        assert_eq!(list.select_row(0), None);
+        assert_eq!(list.current(), Some(1));
+    }
+}
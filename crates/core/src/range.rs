@@ -0,0 +1,93 @@
+use std::ops::RangeInclusive;
+
+/// Splits the line range off the front of an ex command, as in `%!sort`,
+/// `'<,'>s/a/b/` or `.,.+2!fmt`, returning the 0-based rows it names and the
+/// rest of the command. An address is `.` for `cursor_row`, `$` for
+/// `last_line`, a 1-based line number or a mark looked up with `mark`, each
+/// optionally followed by `+N` or `-N`. `%` is the whole buffer. Rows past
+/// the end are clamped and a backwards range is turned around.
+pub fn parse_range(
+    command: &str,
+    cursor_row: usize,
+    last_line: usize,
+    mark: impl Fn(char) -> Option<usize>,
+) -> Result<(Option<RangeInclusive<usize>>, &str), String> {
+    if let Some(rest) = command.strip_prefix('%') {
+        return Ok((Some(0..=last_line), rest.trim_start()));
+    }
+    let address = |text| parse_address(text, cursor_row, last_line, &mark);
+    let Some((start, rest)) = address(command)? else {
+        return Ok((None, command));
+    };
+    let (end, rest) = match rest.strip_prefix(',') {
+        Some(rest) => address(rest)?.unwrap_or((cursor_row, rest)),
+        None => (start, rest),
+    };
+    Ok((Some(start.min(end)..=start.max(end)), rest.trim_start()))
+}
+
+/// Reads one address from the front of `text`, or `None` if it doesn't
+/// start with one.
+fn parse_address<'a>(
+    text: &'a str,
+    cursor_row: usize,
+    last_line: usize,
+    mark: &impl Fn(char) -> Option<usize>,
+) -> Result<Option<(usize, &'a str)>, String> {
+    let mut chars = text.chars();
+    let (base, mut rest) = match chars.next() {
+        Some('.') => (Some(cursor_row), chars.as_str()),
+        Some('$') => (Some(last_line), chars.as_str()),
+        Some('\'') => {
+            let name = chars.next().ok_or("E20: Mark not set")?;
+            let row = mark(name).ok_or("E20: Mark not set")?;
+            (Some(row), chars.as_str())
+        }
+        Some(c) if c.is_ascii_digit() => {
+            let (number, rest) = split_number(text);
+            (Some(number.saturating_sub(1)), rest)
+        }
+        _ => (None, text),
+    };
+
+    // `+` and `-` without a number move one line, and without a base count
+    // from the cursor.
+    let mut offset: isize = 0;
+    let mut has_offset = false;
+    while let Some(sign) = rest.chars().next().filter(|c| matches!(c, '+' | '-')) {
+        let (number, after) = split_number(&rest[1..]);
+        let step = if after.len() == rest.len() - 1 {
+            1
+        } else {
+            number
+        };
+        let step = isize::try_from(step).unwrap_or(isize::MAX);
+        offset = if sign == '+' {
+            offset.saturating_add(step)
+        } else {
+            offset.saturating_sub(step)
+        };
+        has_offset = true;
+        rest = after;
+    }
+
+    let base = match base {
+        Some(base) => base,
+        None if has_offset => cursor_row,
+        None => return Ok(None),
+    };
+    let row = base.saturating_add_signed(offset).min(last_line);
+    Ok(Some((row, rest)))
+}
+
+/// The number at the front of `text`, or 0 if there isn't one, and the text
+/// after it. Numbers too big for a `usize` saturate.
+fn split_number(text: &str) -> (usize, &str) {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let number = text[..end]
+        .parse()
+        .unwrap_or(if end == 0 { 0 } else { usize::MAX });
+    (number, &text[end..])
+}
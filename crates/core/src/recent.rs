@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+
+/// How many files the recent-files list keeps.
+pub const MAX_RECENT_FILES: usize = 100;
+
+/// Files opened in this and earlier sessions, most recent first, as
+/// `:browse oldfiles` lists them.
+#[derive(Debug, Clone, Default)]
+pub struct RecentFiles {
+    paths: Vec<PathBuf>,
+    /// Where the list is kept between sessions, one path per line. Without
+    /// one it only lasts for this session.
+    file: Option<PathBuf>,
+}
+
+impl RecentFiles {
+    /// The list kept in `file`, which is written back whenever a file is
+    /// recorded. A missing file is an empty list.
+    pub fn load(file: PathBuf) -> Self {
+        let contents = std::fs::read_to_string(&file).unwrap_or_default();
+        let mut recent = Self {
+            paths: Vec::new(),
+            file: Some(file),
+        };
+        for line in contents.lines().filter(|line| !line.is_empty()) {
+            let path = PathBuf::from(line);
+            if !recent.paths.contains(&path) && recent.paths.len() < MAX_RECENT_FILES {
+                recent.paths.push(path);
+            }
+        }
+        recent
+    }
+
+    /// Moves `path` to the front of the list, dropping the oldest file once
+    /// there are more than [`MAX_RECENT_FILES`].
+    pub fn record(&mut self, path: &Path) {
+        let path = std::fs::canonicalize(path)
+            .or_else(|_| std::path::absolute(path))
+            .unwrap_or_else(|_| path.to_path_buf());
+        self.paths.retain(|p| *p != path);
+        self.paths.insert(0, path);
+        self.paths.truncate(MAX_RECENT_FILES);
+        self.save();
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    fn save(&self) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        let mut contents = String::new();
+        for path in &self.paths {
+            // A name with a newline can't be kept one per line.
+            if let Some(path) = path.to_str().filter(|p| !p.contains('\n')) {
+                contents.push_str(path);
+                contents.push('\n');
+            }
+        }
+        let written = match file.parent() {
+            Some(dir) => std::fs::create_dir_all(dir),
+            None => Ok(()),
+        }
+        .and_then(|()| std::fs::write(file, contents));
+        if let Err(e) = written {
+            tracing::warn!("Cannot write recent files to {}: {}", file.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_latest_file_comes_first_and_only_once() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let [a, b] = ["a.txt", "b.txt"].map(|name| dir.path().join(name));
+        let mut recent = RecentFiles::default();
+
+        recent.record(&a);
+        recent.record(&b);
+        recent.record(&a);
+        // Neither exists, so they are kept as given rather than resolved.
+        assert_eq!(recent.paths(), [a, b]);
+    }
+
+    #[test]
+    fn the_oldest_file_drops_off_past_the_limit() {
+        let mut recent = RecentFiles::default();
+        for n in 0..=MAX_RECENT_FILES {
+            recent.record(Path::new(&format!("/files/{}", n)));
+        }
+        assert_eq!(recent.paths().len(), MAX_RECENT_FILES);
+        assert_eq!(
+            recent.paths().first(),
+            Some(&PathBuf::from(format!("/files/{}", MAX_RECENT_FILES)))
+        );
+        assert!(!recent.paths().contains(&PathBuf::from("/files/0")));
+    }
+
+    #[test]
+    fn the_list_is_kept_between_sessions() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let file = dir.path().join("state/recent_files");
+        let mut recent = RecentFiles::load(file.clone());
+        assert!(recent.paths().is_empty());
+
+        recent.record(Path::new("/files/a"));
+        recent.record(Path::new("/files/b"));
+        let loaded = RecentFiles::load(file);
+        assert_eq!(
+            loaded.paths(),
+            [PathBuf::from("/files/b"), PathBuf::from("/files/a")]
+        );
+    }
+
+    #[test]
+    fn loading_drops_blank_and_repeated_lines() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let file = dir.path().join("recent_files");
+        std::fs::write(&file, "/files/a\n\n/files/b\n/files/a\n").expect("file written");
+
+        assert_eq!(
+            RecentFiles::load(file).paths(),
+            [PathBuf::from("/files/a"), PathBuf::from("/files/b")]
+        );
+    }
+}
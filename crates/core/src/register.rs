@@ -0,0 +1,50 @@
+/// The unnamed register that yanks, deletes and pastes use by default.
+pub const DEFAULT_REGISTER: char = '"';
+
+/// The register backed by the system clipboard.
+pub const CLIPBOARD_REGISTER: char = '+';
+
+/// Whether register contents paste inline or as whole lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterKind {
+    Charwise,
+    Linewise,
+}
+
+/// Text stored in a register. Linewise contents always end with a newline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Register {
+    pub text: String,
+    pub kind: RegisterKind,
+}
+
+impl Register {
+    pub fn charwise(text: String) -> Self {
+        Self {
+            text,
+            kind: RegisterKind::Charwise,
+        }
+    }
+
+    pub fn linewise(mut text: String) -> Self {
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        Self {
+            text,
+            kind: RegisterKind::Linewise,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linewise_text_always_ends_with_a_newline() {
+        assert_eq!(Register::linewise("line".into()).text, "line\n");
+        assert_eq!(Register::linewise("line\n".into()).text, "line\n");
+        assert_eq!(Register::charwise("word".into()).text, "word");
+    }
+}
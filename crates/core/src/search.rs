@@ -0,0 +1,205 @@
+use crate::options::EditorOptions;
+use regex::{Regex, RegexBuilder};
+use ropey::Rope;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// The last search query and the char ranges it matched in the current buffer.
+#[derive(Debug, Default)]
+pub struct Search {
+    pub query: Option<String>,
+    pub matches: Vec<Range<usize>>,
+}
+
+impl Search {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compiles `pattern` as a regex, or as literal text when `magic` is off,
+    /// folding case as `ignorecase` and `smartcase` say.
+    pub fn compile(pattern: &str, options: &EditorOptions) -> Result<Regex, String> {
+        let source = if options.magic {
+            Cow::Borrowed(pattern)
+        } else {
+            Cow::Owned(regex::escape(pattern))
+        };
+        RegexBuilder::new(&source)
+            .case_insensitive(options.ignores_case(pattern))
+            .build()
+            .map_err(|_| format!("E383: Invalid search string: {}", pattern))
+    }
+
+    /// The compiled query, or `None` when there is no query.
+    pub fn matcher(&self, options: &EditorOptions) -> Result<Option<Regex>, String> {
+        match self.query.as_deref().filter(|q| !q.is_empty()) {
+            Some(query) => Self::compile(query, options).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Char column ranges `regex` matches in a single line of text. The line
+    /// ending is left out so `$` matches before it; matches never span lines.
+    pub fn find_in_line(regex: &Regex, line: &str) -> Vec<Range<usize>> {
+        let line = line.trim_end_matches(['\n', '\r']);
+        let mut col = 0;
+        let mut byte = 0;
+        regex
+            .find_iter(line)
+            .map(|m| {
+                col += line[byte..m.start()].chars().count();
+                byte = m.start();
+                col..col + m.as_str().chars().count()
+            })
+            .collect()
+    }
+
+    /// Rescans `text` line by line for the query, replacing `matches`. Lines
+    /// are converted one at a time rather than the whole rope at once.
+    pub fn update(&mut self, text: &Rope, options: &EditorOptions) -> Result<(), String> {
+        self.matches.clear();
+        let Some(regex) = self.matcher(options)? else {
+            return Ok(());
+        };
+        for (row, line) in text.lines().enumerate() {
+            let line_start = text.line_to_char(row);
+            self.matches.extend(
+                Self::find_in_line(&regex, &line.to_string())
+                    .into_iter()
+                    .map(|range| line_start + range.start..line_start + range.end),
+            );
+        }
+        Ok(())
+    }
+
+    /// Start of the first match after `char_idx`, wrapping to the top.
+    pub fn next_match(&self, char_idx: usize) -> Option<usize> {
+        self.matches
+            .iter()
+            .map(|m| m.start)
+            .find(|&start| start > char_idx)
+            .or_else(|| self.matches.first().map(|m| m.start))
+    }
+
+    /// Start of the last match before `char_idx`, wrapping to the bottom.
+    pub fn prev_match(&self, char_idx: usize) -> Option<usize> {
+        self.matches
+            .iter()
+            .rev()
+            .map(|m| m.start)
+            .find(|&start| start < char_idx)
+            .or_else(|| self.matches.last().map(|m| m.start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(query: &str, text: &str) -> Search {
+        let mut search = Search::new();
+        search.query = Some(query.to_string());
+        search
+            .update(&Rope::from_str(text), &EditorOptions::default())
+            .expect("a valid query");
+        search
+    }
+
+    #[test]
+    fn matches_are_char_ranges_across_lines() {
+        let search = search("ab", "ab ab\nxab\n");
+        assert_eq!(search.matches, [0..2, 3..5, 7..9]);
+    }
+
+    #[test]
+    fn next_and_prev_wrap_around_the_buffer() {
+        let search = search("ab", "ab ab\nxab\n");
+        assert_eq!(search.next_match(0), Some(3));
+        assert_eq!(search.next_match(7), Some(0));
+        assert_eq!(search.prev_match(3), Some(0));
+        assert_eq!(search.prev_match(0), Some(7));
+    }
+
+    #[test]
+    fn no_match_finds_nothing() {
+        let search = search("zz", "ab ab\n");
+        assert!(search.matches.is_empty());
+        assert_eq!(search.next_match(0), None);
+        assert_eq!(search.prev_match(0), None);
+    }
+
+    #[test]
+    fn find_in_line_counts_chars_not_bytes() {
+        let regex = Search::compile("b", &EditorOptions::default()).expect("a valid pattern");
+        assert_eq!(Search::find_in_line(&regex, "äb éb\n"), [1..2, 4..5]);
+    }
+
+    #[test]
+    fn ignorecase_matches_any_case() {
+        let options = EditorOptions {
+            ignore_case: true,
+            ..EditorOptions::default()
+        };
+        let regex = Search::compile("foo", &options).expect("a valid pattern");
+        assert_eq!(
+            Search::find_in_line(&regex, "foo Foo FOO\n"),
+            [0..3, 4..7, 8..11]
+        );
+    }
+
+    #[test]
+    fn smartcase_turns_case_back_on_for_an_uppercase_query() {
+        let options = EditorOptions {
+            ignore_case: true,
+            smart_case: true,
+            ..EditorOptions::default()
+        };
+        let regex = Search::compile("foo", &options).expect("a valid pattern");
+        assert_eq!(Search::find_in_line(&regex, "foo Foo\n"), [0..3, 4..7]);
+        let regex = Search::compile("Foo", &options).expect("a valid pattern");
+        assert_eq!(Search::find_in_line(&regex, "foo Foo\n"), vec![4..7]);
+    }
+
+    #[test]
+    fn smartcase_alone_does_not_ignore_case() {
+        let options = EditorOptions {
+            smart_case: true,
+            ..EditorOptions::default()
+        };
+        let regex = Search::compile("foo", &options).expect("a valid pattern");
+        assert_eq!(Search::find_in_line(&regex, "foo Foo\n"), vec![0..3]);
+    }
+
+    #[test]
+    fn anchors_match_at_the_ends_of_each_line() {
+        assert_eq!(search("^ab", "ab ab\nxab\nab\n").matches, [0..2, 10..12]);
+        assert_eq!(search("ab$", "ab ab\nxab\r\n").matches, [3..5, 7..9]);
+    }
+
+    #[test]
+    fn character_classes_match_sets_of_chars() {
+        assert_eq!(search("[0-9]+", "a1 b22\n").matches, [1..2, 4..6]);
+        assert_eq!(search(r"\bx\w", "xa axb xc\n").matches, [0..2, 7..9]);
+    }
+
+    #[test]
+    fn an_invalid_pattern_is_an_error() {
+        let mut search = Search::new();
+        search.query = Some("a(b".to_string());
+        assert_eq!(
+            search.update(&Rope::from_str("a(b\n"), &EditorOptions::default()),
+            Err("E383: Invalid search string: a(b".to_string())
+        );
+        assert!(search.matches.is_empty());
+    }
+
+    #[test]
+    fn nomagic_searches_for_the_literal_text() {
+        let options = EditorOptions {
+            magic: false,
+            ..EditorOptions::default()
+        };
+        let regex = Search::compile("a(b", &options).expect("a literal pattern");
+        assert_eq!(Search::find_in_line(&regex, "xa(b\n"), vec![1..4]);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::cursor::Cursor;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// The open files and where the cursor was in each, as written by
+/// `:mksession` and read back by `:source` or `--session`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Session {
+    pub buffers: Vec<SessionBuffer>,
+    /// Index into `buffers` of the buffer that was current.
+    pub current: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionBuffer {
+    pub path: PathBuf,
+    pub row: usize,
+    pub col: usize,
+}
+
+impl SessionBuffer {
+    pub fn cursor(&self) -> Cursor {
+        Cursor::new(self.row, self.col)
+    }
+}
+
+impl Session {
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_json() {
+        let session = Session {
+            buffers: vec![
+                SessionBuffer {
+                    path: PathBuf::from("/src/main.rs"),
+                    row: 4,
+                    col: 2,
+                },
+                SessionBuffer {
+                    path: PathBuf::from("notes.txt"),
+                    row: 0,
+                    col: 0,
+                },
+            ],
+            current: Some(1),
+        };
+        let json = session.to_json().expect("serializes");
+        assert_eq!(Session::from_json(&json).expect("deserializes"), session);
+    }
+
+    #[test]
+    fn malformed_json_is_an_error() {
+        assert!(Session::from_json("{\"buffers\": 3}").is_err());
+        assert!(Session::from_json("").is_err());
+    }
+}
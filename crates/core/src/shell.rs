@@ -0,0 +1,85 @@
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+
+/// A command that runs `command` through the shell: `sh -c`, or `cmd /C` on
+/// Windows.
+pub fn shell_command(command: &str) -> Command {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut shell = Command::new(shell);
+    shell.args([flag, command]);
+    shell
+}
+
+/// Runs `command` through the shell with `input` on its stdin and returns what
+/// it wrote to stdout. If it can't be started or exits non-zero, the error is
+/// its stderr, or the exit status when it printed nothing there.
+pub fn run_shell(command: &str, input: &str) -> Result<String, String> {
+    let mut child = shell_command(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Cannot run {}: {}", command, e))?;
+
+    // Feed stdin from another thread so a command that writes as it reads
+    // can't fill its stdout pipe while we're still writing.
+    let writer = child.stdin.take().map(|mut stdin| {
+        let input = input.to_string();
+        thread::spawn(move || {
+            // A command that stops reading early, like `head`, closes the pipe.
+            let _ = stdin.write_all(input.as_bytes());
+        })
+    });
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Cannot run {}: {}", command, e))?;
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        return Err(if stderr.is_empty() {
+            exit_message(output.status)
+        } else {
+            stderr.to_string()
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).replace("\r\n", "\n"))
+}
+
+/// The command `:make` runs: `cargo` with `args`, or `cargo build` without
+/// any, in a Cargo project, and `make` with `args` anywhere else.
+pub fn make_command(args: &str) -> String {
+    let (program, default) = if Path::new("Cargo.toml").exists() {
+        ("cargo", "build")
+    } else {
+        ("make", "")
+    };
+    let args = if args.is_empty() { default } else { args };
+    format!("{} {}", program, args).trim_end().to_string()
+}
+
+/// How `command` ended, with a failure described in vim's words.
+pub fn check_status(command: &str, status: io::Result<ExitStatus>) -> Result<(), String> {
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(exit_message(status)),
+        Err(e) => Err(format!("Cannot run {}: {}", command, e)),
+    }
+}
+
+/// How vim reports a shell command that failed.
+fn exit_message(status: ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("shell returned {}", code),
+        None => "shell was killed".to_string(),
+    }
+}
@@ -0,0 +1,18 @@
+use crate::{Cursor, Mode};
+
+/// Most chars of buffer text an [`EditorSnapshot`] keeps.
+pub const PREVIEW_CHARS: usize = 200;
+
+/// The observable state of an editor at one moment, as returned by
+/// `Editor::snapshot`, for tests and scripts to compare against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditorSnapshot {
+    pub cursor: Cursor,
+    pub mode: Mode,
+    /// Id of the current buffer.
+    pub buffer_id: usize,
+    pub scroll_offset: usize,
+    pub line_count: usize,
+    /// The start of the current buffer's text, up to `PREVIEW_CHARS` chars.
+    pub text_preview: String,
+}
@@ -0,0 +1,100 @@
+use ropey::RopeSlice;
+use std::fmt;
+
+/// Line, word, char and byte counts of some text, as shown by `:stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextStats {
+    pub lines: usize,
+    pub words: usize,
+    pub chars: usize,
+    pub bytes: usize,
+}
+
+impl TextStats {
+    /// Counts `text`. Words are runs of non-whitespace; a final line without a
+    /// trailing newline still counts as a line.
+    pub fn of(text: RopeSlice) -> Self {
+        let mut stats = Self {
+            chars: text.len_chars(),
+            bytes: text.len_bytes(),
+            ..Self::default()
+        };
+        let mut in_word = false;
+        let mut last = None;
+        for c in text.chars() {
+            if c == '\n' {
+                stats.lines += 1;
+            }
+            if c.is_whitespace() {
+                in_word = false;
+            } else if !in_word {
+                in_word = true;
+                stats.words += 1;
+            }
+            last = Some(c);
+        }
+        if last.is_some_and(|c| c != '\n') {
+            stats.lines += 1;
+        }
+        stats
+    }
+}
+
+impl fmt::Display for TextStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} lines; {} words; {} chars; {} bytes",
+            self.lines, self.words, self.chars, self.bytes
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ropey::Rope;
+
+    fn stats(text: &str) -> TextStats {
+        TextStats::of(Rope::from_str(text).slice(..))
+    }
+
+    #[test]
+    fn an_empty_buffer_counts_nothing() {
+        assert_eq!(stats(""), TextStats::default());
+    }
+
+    #[test]
+    fn multi_line_text_counts_words_across_lines() {
+        assert_eq!(
+            stats("one two\n  three\tfour\n\nfïve\n"),
+            TextStats {
+                lines: 4,
+                words: 5,
+                chars: 27,
+                bytes: 28,
+            }
+        );
+    }
+
+    #[test]
+    fn a_last_line_without_a_newline_still_counts() {
+        assert_eq!(stats("one\ntwo").lines, 2);
+    }
+
+    #[test]
+    fn a_slice_counts_only_its_own_text() {
+        let rope = Rope::from_str("one two\nthree four\n");
+        let selected = TextStats::of(rope.slice(4..13));
+        assert_eq!(
+            selected,
+            TextStats {
+                lines: 2,
+                words: 2,
+                chars: 9,
+                bytes: 9,
+            }
+        );
+        assert_eq!(selected.to_string(), "2 lines; 2 words; 9 chars; 9 bytes");
+    }
+}
@@ -0,0 +1,106 @@
+/// A parsed `:s/pattern/replacement/[g]` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Substitution {
+    pub pattern: String,
+    pub replacement: String,
+    /// Replace every occurrence on a line rather than just the first.
+    pub global: bool,
+    /// Operate on every line (`:%s`) instead of only the cursor line.
+    pub whole_buffer: bool,
+}
+
+impl Substitution {
+    /// Parses `s/old/new/[g]` or `%s/old/new/[g]`. A `\/` in the pattern or
+    /// replacement stands for a literal slash. Returns `None` if `command` is
+    /// not a substitution at all.
+    pub fn parse(command: &str) -> Option<Result<Self, String>> {
+        let (whole_buffer, rest) = match command.strip_prefix('%') {
+            Some(rest) => (true, rest),
+            None => (false, command),
+        };
+        let body = rest.strip_prefix("s/")?;
+
+        let mut fields = vec![String::new()];
+        let mut chars = body.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('/') => fields.last_mut()?.push('/'),
+                    Some(other) => {
+                        let field = fields.last_mut()?;
+                        field.push('\\');
+                        field.push(other);
+                    }
+                    None => fields.last_mut()?.push('\\'),
+                },
+                '/' if fields.len() < 3 => fields.push(String::new()),
+                _ => fields.last_mut()?.push(c),
+            }
+        }
+
+        let mut fields = fields.into_iter();
+        let pattern = fields.next().unwrap_or_default();
+        let replacement = fields.next().unwrap_or_default();
+        let flags = fields.next().unwrap_or_default();
+
+        if pattern.is_empty() {
+            return Some(Err("E35: No previous regular expression".to_string()));
+        }
+        if let Some(flag) = flags.chars().find(|&f| f != 'g') {
+            return Some(Err(format!("E488: Trailing characters: {}", flag)));
+        }
+
+        Some(Ok(Self {
+            pattern,
+            replacement,
+            global: flags.contains('g'),
+            whole_buffer,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(command: &str) -> Substitution {
+        Substitution::parse(command)
+            .expect("a substitution")
+            .expect("a valid substitution")
+    }
+
+    #[test]
+    fn parses_pattern_replacement_and_flags() {
+        assert_eq!(
+            parse("%s/old/new/g"),
+            Substitution {
+                pattern: "old".into(),
+                replacement: "new".into(),
+                global: true,
+                whole_buffer: true,
+            }
+        );
+        let line = parse("s/old/new");
+        assert!(!line.global && !line.whole_buffer);
+    }
+
+    #[test]
+    fn escaped_slashes_are_literal() {
+        let substitution = parse(r"s/a\/b/c\/d/");
+        assert_eq!(substitution.pattern, "a/b");
+        assert_eq!(substitution.replacement, "c/d");
+        assert_eq!(parse(r"s/\d/x/").pattern, r"\d");
+    }
+
+    #[test]
+    fn other_commands_are_not_substitutions() {
+        assert_eq!(Substitution::parse("set number"), None);
+        assert_eq!(Substitution::parse("%d"), None);
+    }
+
+    #[test]
+    fn an_empty_pattern_or_unknown_flag_is_an_error() {
+        assert!(matches!(Substitution::parse("s//x/"), Some(Err(e)) if e.starts_with("E35")));
+        assert!(matches!(Substitution::parse("s/a/b/x"), Some(Err(e)) if e.starts_with("E488")));
+    }
+}
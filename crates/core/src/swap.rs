@@ -0,0 +1,72 @@
+use ropey::Rope;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Where autosave keeps a copy of the unsaved contents of `path`:
+/// `<path>.fluxion.swap`, next to the file.
+pub fn swap_path(path: &Path) -> PathBuf {
+    let mut swap = path.as_os_str().to_owned();
+    swap.push(".fluxion.swap");
+    PathBuf::from(swap)
+}
+
+/// Whether `path` has a swap file left behind, e.g. by a crash, that
+/// `:recover` can restore.
+pub fn has_swap_file(path: &Path) -> bool {
+    swap_path(path).is_file()
+}
+
+/// Writes `text` to the swap file of `path`, as UTF-8 with `\n` line endings.
+pub fn write_swap_file(path: &Path, text: &Rope) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(swap_path(path))?);
+    text.write_to(&mut writer)?;
+    writer.flush()
+}
+
+/// Deletes the swap file of `path`, if there is one.
+pub fn remove_swap_file(path: &Path) {
+    let _ = std::fs::remove_file(swap_path(path));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_swap_file_sits_next_to_the_file() {
+        assert_eq!(
+            swap_path(Path::new("/tmp/notes.txt")),
+            PathBuf::from("/tmp/notes.txt.fluxion.swap")
+        );
+        assert_eq!(
+            swap_path(Path::new("Makefile")),
+            PathBuf::from("Makefile.fluxion.swap")
+        );
+    }
+
+    #[test]
+    fn a_left_over_swap_file_is_detected_until_removed() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("file.txt");
+        assert!(!has_swap_file(&path));
+
+        write_swap_file(&path, &Rope::from_str("unsaved\n")).expect("swap written");
+        assert!(has_swap_file(&path));
+        assert_eq!(
+            std::fs::read_to_string(swap_path(&path)).expect("swap file"),
+            "unsaved\n"
+        );
+
+        remove_swap_file(&path);
+        assert!(!has_swap_file(&path));
+    }
+
+    #[test]
+    fn a_directory_is_not_a_swap_file() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("file.txt");
+        std::fs::create_dir(swap_path(&path)).expect("dir created");
+        assert!(!has_swap_file(&path));
+    }
+}
@@ -0,0 +1,265 @@
+mod common;
+
+use common::{editor, error, run};
+use fluxion_core::{Action, Editor, Mode};
+
+/// An editor with three buffers, the last one current. Returns their ids.
+fn three_buffers() -> (Editor, Vec<usize>) {
+    let mut editor = editor("first\n");
+    run(&mut editor, "enew");
+    run(&mut editor, "enew");
+    let ids = editor.get_buffers().iter().map(|b| b.id).collect();
+    (editor, ids)
+}
+
+#[test]
+fn the_buffer_list_opens_on_the_current_buffer() {
+    let (mut editor, _) = three_buffers();
+
+    editor.handle_action(Action::EnterBufferList);
+    assert_eq!(editor.mode, Mode::BufferList);
+    assert_eq!(editor.buffer_list_idx, 2);
+}
+
+#[test]
+fn selecting_from_the_buffer_list_switches_buffers() {
+    let (mut editor, ids) = three_buffers();
+
+    editor.handle_action(Action::EnterBufferList);
+    editor.handle_action(Action::BufferListUp);
+    editor.handle_action(Action::BufferListUp);
+    editor.handle_action(Action::BufferListUp);
+    assert_eq!(editor.buffer_list_idx, 0);
+    editor.handle_action(Action::BufferListSelect);
+
+    assert_eq!(editor.mode, Mode::Normal);
+    assert_eq!(editor.buffer_manager.current_buffer_id(), ids[0]);
+}
+
+#[test]
+fn moving_down_stops_at_the_last_buffer() {
+    let (mut editor, ids) = three_buffers();
+    editor.handle_action(Action::EnterBufferList);
+    editor.handle_action(Action::BufferListDown);
+    editor.handle_action(Action::BufferListSelect);
+
+    assert_eq!(editor.buffer_manager.current_buffer_id(), ids[2]);
+}
+
+#[test]
+fn escape_leaves_the_buffer_list_without_switching() {
+    let (mut editor, ids) = three_buffers();
+    editor.handle_action(Action::EnterBufferList);
+    editor.handle_action(Action::BufferListUp);
+
+    editor.handle_action(Action::CancelDialog);
+    assert_eq!(editor.mode, Mode::Normal);
+    assert_eq!(editor.buffer_manager.current_buffer_id(), ids[2]);
+}
+
+/// Changes the current buffer so it has unsaved changes.
+fn change(editor: &mut Editor) {
+    editor
+        .buffer_manager
+        .current_buffer_mut()
+        .set_text("text\n".into());
+    run(editor, "s/text/changed/");
+    assert!(editor.buffer_manager.current_buffer().dirty);
+}
+
+#[test]
+fn bd_closes_a_clean_buffer() {
+    let (mut editor, ids) = three_buffers();
+
+    run(&mut editor, "bd");
+    assert_eq!(editor.get_buffers().len(), 2);
+    assert!(editor.buffer_manager.current_buffer_id() != ids[2]);
+}
+
+#[test]
+fn bd_refuses_to_close_a_changed_buffer() {
+    let (mut editor, ids) = three_buffers();
+    change(&mut editor);
+
+    run(&mut editor, "bd");
+    assert_eq!(editor.get_buffers().len(), 3);
+    assert_eq!(editor.buffer_manager.current_buffer_id(), ids[2]);
+    assert_eq!(
+        error(&editor),
+        Some(
+            format!(
+                "E89: No write since last change for buffer {} (add ! to override)",
+                ids[2]
+            )
+            .as_str()
+        )
+    );
+}
+
+#[test]
+fn bd_bang_closes_a_changed_buffer() {
+    let (mut editor, ids) = three_buffers();
+    change(&mut editor);
+
+    run(&mut editor, "bd!");
+    assert_eq!(editor.get_buffers().len(), 2);
+    assert!(editor.get_buffers().iter().all(|b| b.id != ids[2]));
+}
+
+#[test]
+fn closing_all_other_buffers_checks_them_for_changes() {
+    let (mut editor, ids) = three_buffers();
+    run(&mut editor, &format!("b {}", ids[1]));
+    change(&mut editor);
+    run(&mut editor, &format!("b {}", ids[2]));
+
+    editor.handle_action(Action::CloseAllBuffersExcept);
+    assert_eq!(editor.get_buffers().len(), 3);
+
+    run(&mut editor, "baex!");
+    let left: Vec<usize> = editor.get_buffers().iter().map(|b| b.id).collect();
+    assert_eq!(left, [ids[2]]);
+}
+
+#[test]
+fn enew_adds_an_empty_current_buffer() {
+    let mut editor = editor("first\nsecond\n");
+    run(&mut editor, "2");
+    let before = editor.get_buffers().len();
+    let previous = editor.buffer_manager.current_buffer_id();
+
+    run(&mut editor, "enew");
+    assert_eq!(editor.get_buffers().len(), before + 1);
+    assert!(editor.buffer_manager.current_buffer_id() != previous);
+    assert_eq!(editor.get_current_text().to_string(), "");
+    assert_eq!((editor.cursor.row, editor.cursor.col), (0, 0));
+
+    editor.handle_action(Action::NewBuffer);
+    assert_eq!(editor.get_buffers().len(), before + 2);
+}
+
+#[test]
+fn enew_drops_an_unedited_preview_buffer() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::write(dir.path().join("preview.txt"), "preview\n").expect("file written");
+    let mut editor = editor("");
+    editor.handle_action(Action::EnterFilePicker);
+    editor.file_picker.navigate_to(dir.path().to_path_buf());
+    editor.handle_action(Action::FilePickerEnter);
+    assert!(editor.buffer_manager.current_buffer().is_transient);
+
+    run(&mut editor, "enew");
+    assert!(
+        editor
+            .buffer_manager
+            .iter()
+            .all(|b| b.title != "preview.txt")
+    );
+}
+
+#[test]
+fn switching_back_restores_the_cursor_and_scroll() {
+    let mut editor = editor(&"line\n".repeat(100));
+    editor.set_viewport_size(80, 10);
+    let first = editor.buffer_manager.current_buffer_id();
+    run(&mut editor, "60");
+    editor.handle_action(Action::MoveRight);
+    let (cursor, scroll) = (editor.cursor, editor.scroll_offset);
+    assert!(scroll > 0);
+
+    run(&mut editor, "enew");
+    assert_eq!((editor.cursor.row, editor.scroll_offset), (0, 0));
+
+    run(&mut editor, &format!("b {}", first));
+    assert_eq!(editor.cursor, cursor);
+    assert_eq!(editor.scroll_offset, scroll);
+}
+
+#[test]
+fn next_and_previous_buffer_keep_each_cursor() {
+    let (mut editor, ids) = three_buffers();
+    run(&mut editor, &format!("b {}", ids[0]));
+    editor.handle_action(Action::MoveRight);
+    editor.handle_action(Action::MoveRight);
+
+    run(&mut editor, "bn");
+    assert_eq!(editor.buffer_manager.current_buffer_id(), ids[1]);
+    run(&mut editor, "bp");
+    assert_eq!((editor.cursor.row, editor.cursor.col), (0, 2));
+}
+
+#[test]
+fn b_switches_to_a_two_digit_buffer_id() {
+    let mut editor = editor("first\n");
+    while editor.buffer_manager.current_buffer_id() < 12 {
+        run(&mut editor, "enew");
+    }
+    let first_id = editor.get_buffers()[0].id;
+
+    run(&mut editor, &format!("b {}", first_id));
+    assert_eq!(editor.buffer_manager.current_buffer_id(), first_id);
+    run(&mut editor, "b 12");
+    assert_eq!(error(&editor), None);
+    assert_eq!(editor.buffer_manager.current_buffer_id(), 12);
+}
+
+#[test]
+fn b_with_an_unknown_id_reports_it() {
+    let (mut editor, ids) = three_buffers();
+
+    run(&mut editor, "b 42");
+    assert_eq!(error(&editor), Some("E86: Buffer 42 does not exist"));
+    assert_eq!(editor.buffer_manager.current_buffer_id(), ids[2]);
+}
+
+#[test]
+fn bufdo_runs_the_command_in_every_buffer() {
+    let mut editor = editor("foo one\n");
+    let first_id = editor.buffer_manager.current_buffer_id();
+    run(&mut editor, "enew");
+    editor
+        .buffer_manager
+        .current_buffer_mut()
+        .set_text("foo two\n".into());
+    let second_id = editor.buffer_manager.current_buffer_id();
+
+    run(&mut editor, "bufdo s/foo/bar/");
+    assert_eq!(error(&editor), None);
+    assert_eq!(editor.buffer_manager.current_buffer_id(), second_id);
+    let text_of = |editor: &Editor, id| {
+        editor
+            .buffer_manager
+            .iter()
+            .find(|b| b.id == id)
+            .map(|b| b.text.to_string())
+    };
+    assert_eq!(text_of(&editor, first_id).as_deref(), Some("bar one\n"));
+    assert_eq!(text_of(&editor, second_id).as_deref(), Some("bar two\n"));
+}
+
+#[test]
+fn bufdo_without_a_command_is_an_error() {
+    let (mut editor, _) = three_buffers();
+
+    run(&mut editor, "bufdo");
+    assert_eq!(error(&editor), Some("E471: Argument required"));
+}
+
+#[test]
+fn jumping_back_returns_to_the_previous_buffer() {
+    let mut editor = editor("one\ntwo\nthree\n");
+    let first_id = editor.buffer_manager.current_buffer_id();
+    editor.handle_action(Action::GotoLastLine);
+    run(&mut editor, "enew");
+    let second_id = editor.buffer_manager.current_buffer_id();
+
+    editor.handle_action(Action::JumpBack);
+    assert_eq!(editor.buffer_manager.current_buffer_id(), first_id);
+    assert_eq!(editor.cursor.row, 2);
+    editor.handle_action(Action::JumpBack);
+    assert_eq!(editor.cursor.row, 0);
+
+    editor.handle_action(Action::JumpForward);
+    editor.handle_action(Action::JumpForward);
+    assert_eq!(editor.buffer_manager.current_buffer_id(), second_id);
+}
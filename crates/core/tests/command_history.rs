@@ -0,0 +1,31 @@
+mod common;
+
+use common::{editor, run};
+use fluxion_core::Editor;
+
+#[test]
+fn history_is_kept_in_its_file() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let file = dir.path().join("state/history");
+    let mut editor = editor("");
+    editor.load_command_history(file.clone());
+    assert!(editor.command_history.is_empty());
+
+    run(&mut editor, "set list");
+    run(&mut editor, "set nolist");
+    run(&mut editor, "set list");
+    let mut reloaded = Editor::new("");
+    reloaded.load_command_history(file);
+    assert_eq!(reloaded.command_history, ["set nolist", "set list"]);
+}
+
+#[test]
+fn loading_skips_blank_lines() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let file = dir.path().join("history");
+    std::fs::write(&file, "w\n\nq\n").expect("file written");
+    let mut editor = editor("");
+
+    editor.load_command_history(file);
+    assert_eq!(editor.command_history, ["w", "q"]);
+}
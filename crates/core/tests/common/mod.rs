@@ -0,0 +1,42 @@
+//! Helpers shared by the tests that drive an editor through its actions and
+//! commands.
+#![allow(dead_code)]
+
+use fluxion_core::{Action, Editor, MessageKind};
+
+/// An editor whose current buffer holds `text`, with the cursor at the start.
+pub fn editor(text: &str) -> Editor {
+    let mut editor = Editor::new("");
+    editor
+        .buffer_manager
+        .current_buffer_mut()
+        .set_text(text.into());
+    editor
+}
+
+pub fn text(editor: &Editor) -> String {
+    editor.get_current_text().to_string()
+}
+
+/// Runs `command` as if it was typed after `:` and Enter was pressed.
+pub fn run(editor: &mut Editor, command: &str) {
+    editor.handle_action(Action::EnterCommandMode);
+    editor.command_input = command.to_string();
+    editor.handle_action(Action::ExecuteCommand);
+}
+
+/// The status message, if it is an error.
+pub fn error(editor: &Editor) -> Option<&str> {
+    match &editor.status_message {
+        Some((message, MessageKind::Error)) => Some(message),
+        _ => None,
+    }
+}
+
+/// The status message, if it is not an error.
+pub fn info(editor: &Editor) -> Option<&str> {
+    match &editor.status_message {
+        Some((_, MessageKind::Error)) | None => None,
+        Some((message, _)) => Some(message),
+    }
+}
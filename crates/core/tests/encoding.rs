@@ -0,0 +1,53 @@
+mod common;
+
+use common::{editor, error, run, text};
+use fluxion_core::Encoding;
+use std::path::Path;
+
+#[test]
+fn a_latin1_file_round_trips() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("latin1.txt");
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/latin1.txt");
+    std::fs::copy(fixture, &path).expect("fixture copies");
+    let mut editor = editor("");
+
+    assert!(editor.open_path(path.clone()));
+    assert_eq!(text(&editor), "café\nnaïve\n");
+    assert_eq!(
+        editor.buffer_manager.current_buffer().encoding,
+        Encoding::Latin1
+    );
+
+    run(&mut editor, "s/café/crème/");
+    run(&mut editor, "w");
+    assert_eq!(
+        std::fs::read(&path).expect("saved file"),
+        b"cr\xe8me\nna\xefve\n"
+    );
+}
+
+#[test]
+fn a_char_latin1_cannot_hold_fails_the_save() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("latin1.txt");
+    std::fs::write(&path, b"caf\xe9\n").expect("file written");
+    let mut editor = editor("");
+    assert!(editor.open_path(path.clone()));
+
+    run(&mut editor, "s/café/日本/");
+    run(&mut editor, "w");
+    assert!(error(&editor).is_some_and(|e| e.contains("conversion to latin1 failed")));
+    assert_eq!(std::fs::read(&path).expect("original file"), b"caf\xe9\n");
+}
+
+#[test]
+fn a_binary_file_is_not_opened() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("program");
+    std::fs::write(&path, b"\x7fELF\0\0\x01").expect("file written");
+    let mut editor = editor("");
+
+    assert!(!editor.open_path(path));
+    assert!(error(&editor).is_some_and(|e| e.contains("binary file")));
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::{editor, text};
+use std::path::Path;
+
+use fluxion_core::{Action, Editor, Mode};
+
+#[test]
+fn enter_descends_into_a_directory_and_opens_a_file() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::create_dir(dir.path().join("src")).expect("dir created");
+    std::fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").expect("file written");
+    let mut editor = editor("");
+    editor.handle_action(Action::EnterFilePicker);
+    editor.file_picker.navigate_to(dir.path().to_path_buf());
+
+    editor.handle_action(Action::FilePickerEnter);
+    assert_eq!(editor.file_picker.current_dir, dir.path().join("src"));
+    assert_eq!(editor.mode, Mode::FilePicker);
+
+    editor.handle_action(Action::FilePickerParent);
+    assert_eq!(editor.file_picker.current_dir, dir.path());
+
+    editor.handle_action(Action::FilePickerEnter);
+    editor.handle_action(Action::FilePickerEnter);
+    assert_eq!(editor.mode, Mode::Normal);
+    assert_eq!(text(&editor), "fn main() {}\n");
+}
+
+/// Opens the `down`th entry of `dir` from the file picker.
+fn pick(editor: &mut Editor, dir: &Path, down: usize) {
+    editor.handle_action(Action::EnterFilePicker);
+    editor.file_picker.navigate_to(dir.to_path_buf());
+    for _ in 0..down {
+        editor.handle_action(Action::FilePickerDown);
+    }
+    editor.handle_action(Action::FilePickerEnter);
+}
+
+fn titles(editor: &Editor) -> Vec<&str> {
+    editor
+        .buffer_manager
+        .iter()
+        .map(|b| b.title.as_str())
+        .collect()
+}
+
+#[test]
+fn previewing_another_file_replaces_the_preview() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::write(dir.path().join("a.txt"), "a\n").expect("file written");
+    std::fs::write(dir.path().join("b.txt"), "b\n").expect("file written");
+    let mut editor = editor("");
+
+    pick(&mut editor, dir.path(), 0);
+    assert!(editor.buffer_manager.current_buffer().is_transient);
+    assert!(titles(&editor).contains(&"a.txt"));
+
+    pick(&mut editor, dir.path(), 1);
+    assert_eq!(text(&editor), "b\n");
+    assert!(titles(&editor).contains(&"b.txt"));
+    assert!(!titles(&editor).contains(&"a.txt"));
+}
+
+#[test]
+fn editing_a_preview_keeps_it_open() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::write(dir.path().join("a.txt"), "a\n").expect("file written");
+    std::fs::write(dir.path().join("b.txt"), "b\n").expect("file written");
+    let mut editor = editor("");
+
+    pick(&mut editor, dir.path(), 0);
+    editor.handle_action(Action::EnterInsertMode);
+    editor.handle_action(Action::Insert('x'));
+    editor.handle_action(Action::EnterNormalMode);
+    assert!(!editor.buffer_manager.current_buffer().is_transient);
+
+    pick(&mut editor, dir.path(), 1);
+    assert!(titles(&editor).contains(&"a.txt"));
+    assert!(titles(&editor).contains(&"b.txt"));
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use common::{editor, run};
+
+fn filetype(editor: &fluxion_core::Editor) -> Option<&str> {
+    editor.buffer_manager.current_buffer().filetype.as_deref()
+}
+
+#[test]
+fn opening_a_file_detects_its_filetype() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut editor = editor("");
+    std::fs::write(dir.path().join("main.rs"), "fn main() {}\n").expect("file written");
+    std::fs::write(dir.path().join("deploy"), "#!/bin/bash\n").expect("file written");
+    std::fs::write(dir.path().join("data.xyz"), "").expect("file written");
+
+    assert!(editor.open_path(dir.path().join("main.rs")));
+    assert_eq!(filetype(&editor), Some("rust"));
+    assert!(editor.open_path(dir.path().join("deploy")));
+    assert_eq!(filetype(&editor), Some("sh"));
+    assert!(editor.open_path(dir.path().join("data.xyz")));
+    assert_eq!(filetype(&editor), None);
+}
+
+#[test]
+fn set_filetype_overrides_the_detected_one() {
+    let mut editor = editor("x\n");
+
+    run(&mut editor, "set filetype=rust");
+    assert_eq!(filetype(&editor), Some("rust"));
+    run(&mut editor, "set ft=python");
+    assert_eq!(filetype(&editor), Some("python"));
+    run(&mut editor, "set ft=");
+    assert_eq!(filetype(&editor), None);
+}
@@ -0,0 +1,61 @@
+//! Filters run through `sh`, so these only run on Unix.
+#![cfg(unix)]
+
+mod common;
+
+use common::{editor, error, info, run, text};
+
+#[test]
+fn percent_filters_the_whole_buffer() {
+    let mut editor = editor("pear\napple\nfig\n");
+
+    run(&mut editor, "%!sort");
+    assert_eq!(text(&editor), "apple\nfig\npear\n");
+    assert_eq!(info(&editor), Some("3 lines filtered"));
+}
+
+#[test]
+fn a_range_filters_only_its_lines() {
+    let mut editor = editor("one\ntwo\nthree\nfour\n");
+
+    run(&mut editor, "2,3!tr a-z A-Z");
+    assert_eq!(text(&editor), "one\nTWO\nTHREE\nfour\n");
+    assert_eq!(editor.cursor.row, 1);
+}
+
+#[test]
+fn output_with_more_lines_replaces_the_range() {
+    let mut editor = editor("a\nb\n");
+
+    run(&mut editor, "1!cat; echo extra");
+    assert_eq!(text(&editor), "a\nextra\nb\n");
+    assert_eq!(info(&editor), Some("1 line filtered"));
+}
+
+#[test]
+fn the_last_line_keeps_a_missing_newline_missing() {
+    let mut editor = editor("b\na");
+
+    run(&mut editor, "%!sort");
+    assert_eq!(text(&editor), "a\nb");
+}
+
+#[test]
+fn a_failing_command_shows_its_stderr_and_changes_nothing() {
+    let mut editor = editor("keep\n");
+
+    run(&mut editor, "%!echo oops >&2; exit 3");
+    assert_eq!(error(&editor), Some("oops"));
+    assert_eq!(text(&editor), "keep\n");
+    run(&mut editor, "%!exit 3");
+    assert_eq!(error(&editor), Some("shell returned 3"));
+    assert_eq!(text(&editor), "keep\n");
+}
+
+#[test]
+fn a_filter_needs_a_command() {
+    let mut editor = editor("keep\n");
+
+    run(&mut editor, "%!");
+    assert_eq!(error(&editor), Some("E471: Argument required"));
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::{editor, error, info, run, text};
+
+#[test]
+fn g_deletes_the_matching_lines() {
+    let mut editor = editor("keep\ndrop 1\nkeep\ndrop 2\n");
+
+    run(&mut editor, "g/drop/d");
+    assert_eq!(text(&editor), "keep\nkeep\n");
+    assert_eq!(info(&editor), Some("2 fewer lines"));
+}
+
+#[test]
+fn v_deletes_the_other_lines() {
+    let mut editor = editor("keep\ndrop\nkeep\n");
+
+    run(&mut editor, "v/keep/d");
+    assert_eq!(text(&editor), "keep\nkeep\n");
+    assert_eq!(info(&editor), Some("1 fewer line"));
+}
+
+#[test]
+fn g_bang_is_the_same_as_v() {
+    let mut editor = editor("keep\ndrop\nkeep\n");
+
+    run(&mut editor, "g!/keep/d");
+    assert_eq!(text(&editor), "keep\nkeep\n");
+}
+
+#[test]
+fn g_uses_regex_patterns() {
+    let mut editor = editor("a1\nb\nc22\n");
+
+    run(&mut editor, r"g/\d$/d");
+    assert_eq!(text(&editor), "b\n");
+}
+
+#[test]
+fn deleting_the_last_line_takes_the_newline_before_it() {
+    let mut editor = editor("keep\ndrop");
+
+    run(&mut editor, "g/drop/d");
+    assert_eq!(text(&editor), "keep");
+}
+
+#[test]
+fn a_range_limits_the_lines_looked_at() {
+    let mut editor = editor("x\nx\nx\nx\n");
+
+    run(&mut editor, "2,3g/x/d");
+    assert_eq!(text(&editor), "x\nx\n");
+}
+
+#[test]
+fn no_match_is_an_error_and_changes_nothing() {
+    let mut editor = editor("a\nb\n");
+
+    run(&mut editor, "g/z/d");
+    assert_eq!(error(&editor), Some("E486: Pattern not found: z"));
+    run(&mut editor, "v/./d");
+    assert_eq!(error(&editor), Some("E486: Pattern found in every line: ."));
+    assert_eq!(text(&editor), "a\nb\n");
+}
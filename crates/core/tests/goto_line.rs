@@ -0,0 +1,39 @@
+mod common;
+
+use common::{editor, run};
+
+fn lines(count: usize) -> String {
+    (1..=count).map(|n| format!("line {}\n", n)).collect()
+}
+
+#[test]
+fn a_line_number_moves_to_that_line() {
+    let mut editor = editor(&lines(50));
+    editor.set_viewport_size(80, 10);
+
+    run(&mut editor, "30");
+    assert_eq!(editor.cursor.row, 29);
+    assert!((editor.scroll_offset..editor.scroll_offset + 10).contains(&29));
+    run(&mut editor, "1");
+    assert_eq!(editor.cursor.row, 0);
+    assert_eq!(editor.scroll_offset, 0);
+}
+
+#[test]
+fn a_line_past_the_end_clamps_to_the_last_line() {
+    let mut editor = editor(&lines(50));
+    editor.set_viewport_size(80, 10);
+
+    run(&mut editor, "9999");
+    assert_eq!(editor.cursor.row, 49);
+    assert!((editor.scroll_offset..editor.scroll_offset + 10).contains(&49));
+}
+
+#[test]
+fn line_zero_moves_to_the_first_line() {
+    let mut editor = editor(&lines(5));
+    run(&mut editor, "4");
+
+    run(&mut editor, "0");
+    assert_eq!(editor.cursor.row, 0);
+}
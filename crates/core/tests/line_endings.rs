@@ -0,0 +1,70 @@
+mod common;
+
+use common::{editor, run, text};
+use fluxion_core::LineEnding;
+use std::path::{Path, PathBuf};
+
+/// Copies a fixture into `dir` so saving it leaves the original alone.
+fn copy_fixture(name: &str, dir: &Path) -> PathBuf {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    let path = dir.join(name);
+    std::fs::copy(fixture, &path).expect("fixture copies");
+    path
+}
+
+#[test]
+fn a_crlf_file_keeps_crlf_after_an_edit() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = copy_fixture("crlf.txt", dir.path());
+    let mut editor = editor("");
+
+    assert!(editor.open_path(path.clone()));
+    assert_eq!(text(&editor), "alpha\nbeta\n");
+    assert_eq!(
+        editor.buffer_manager.current_buffer().line_ending,
+        LineEnding::Crlf
+    );
+
+    run(&mut editor, "s/alpha/gamma/");
+    run(&mut editor, "w");
+    assert_eq!(
+        std::fs::read(&path).expect("saved file"),
+        b"gamma\r\nbeta\r\n"
+    );
+}
+
+#[test]
+fn mixed_endings_save_in_the_dominant_style() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = copy_fixture("mixed_endings.txt", dir.path());
+    let mut editor = editor("");
+
+    assert!(editor.open_path(path.clone()));
+    assert_eq!(text(&editor), "one\ntwo\nthree\n");
+    run(&mut editor, "w");
+    assert_eq!(
+        std::fs::read(&path).expect("saved file"),
+        b"one\r\ntwo\r\nthree\r\n"
+    );
+}
+
+#[test]
+fn set_ff_converts_on_save() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = copy_fixture("crlf.txt", dir.path());
+    let mut editor = editor("");
+
+    assert!(editor.open_path(path.clone()));
+    run(&mut editor, "set ff=unix");
+    run(&mut editor, "w");
+    assert_eq!(std::fs::read(&path).expect("saved file"), b"alpha\nbeta\n");
+
+    run(&mut editor, "set ff=dos");
+    run(&mut editor, "w");
+    assert_eq!(
+        std::fs::read(&path).expect("saved file"),
+        b"alpha\r\nbeta\r\n"
+    );
+}
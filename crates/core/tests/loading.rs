@@ -0,0 +1,44 @@
+mod common;
+
+use common::{editor, error, text};
+use std::io;
+
+#[test]
+fn a_placeholder_is_replaced_once_loading_completes() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("big.txt");
+    std::fs::write(&path, "loaded\r\ntext\r\n").expect("file written");
+    let mut editor = editor("");
+    editor.defer_file_loads = true;
+
+    assert!(editor.open_path(path.clone()));
+    assert!(editor.buffer_manager.current_buffer().loading);
+    assert_eq!(text(&editor), "");
+
+    let loads = editor.take_pending_loads();
+    assert_eq!(loads.len(), 1);
+    let (id, load_path) = &loads[0];
+    assert_eq!(*load_path, path);
+    assert!(editor.take_pending_loads().is_empty());
+
+    editor.finish_load(*id, std::fs::read(load_path));
+    let buffer = editor.buffer_manager.current_buffer();
+    assert!(!buffer.loading);
+    assert!(!buffer.dirty);
+    assert_eq!(text(&editor), "loaded\ntext\n");
+}
+
+#[test]
+fn a_failed_load_drops_the_placeholder() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut editor = editor("before\n");
+    editor.defer_file_loads = true;
+
+    assert!(editor.open_path(dir.path().join("gone.txt")));
+    let loads = editor.take_pending_loads();
+    editor.finish_load(loads[0].0, Err(io::Error::from(io::ErrorKind::NotFound)));
+
+    assert!(error(&editor).is_some_and(|e| e.starts_with("E484")));
+    assert!(editor.get_buffers().iter().all(|b| !b.loading));
+    assert_eq!(text(&editor), "before\n");
+}
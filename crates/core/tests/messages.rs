@@ -0,0 +1,63 @@
+mod common;
+
+use common::{editor, error, info, run};
+use fluxion_core::Action;
+
+#[test]
+fn a_failed_save_shows_an_error() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("missing").join("file.txt");
+    let mut editor = editor("text\n");
+
+    run(&mut editor, &format!("w {}", path.display()));
+    assert!(
+        error(&editor).is_some_and(|e| e.starts_with("E212: Can't open file for writing")),
+        "{:?}",
+        editor.status_message
+    );
+}
+
+#[test]
+fn a_successful_save_reports_what_was_written() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("file.txt");
+    let mut editor = editor("one\ntwo\n");
+
+    run(&mut editor, &format!("w {}", path.display()));
+    assert_eq!(info(&editor), Some("\"file.txt\" 2L, 8B written"));
+}
+
+#[test]
+fn an_unknown_command_shows_an_error() {
+    let mut editor = editor("");
+
+    run(&mut editor, "frobnicate");
+    assert!(error(&editor).is_some_and(|e| e.contains("frobnicate")));
+}
+
+#[test]
+fn the_next_action_clears_the_message() {
+    let mut editor = editor("");
+    run(&mut editor, "frobnicate");
+
+    editor.handle_action(Action::NoOp);
+    assert!(editor.status_message.is_some());
+    editor.handle_action(Action::MoveDown);
+    assert_eq!(editor.status_message, None);
+}
+
+#[test]
+fn stats_count_the_buffer_and_the_selection() {
+    let mut editor = editor("one two\nthree\n");
+    editor.handle_action(Action::ShowStats);
+    assert_eq!(info(&editor), Some("2 lines; 3 words; 14 chars; 14 bytes"));
+
+    editor.handle_action(Action::EnterVisualMode);
+    editor.handle_action(Action::MoveRight);
+    editor.handle_action(Action::MoveRight);
+    editor.handle_action(Action::ShowStats);
+    assert_eq!(
+        info(&editor),
+        Some("Selected 1 of 2 lines; 1 of 3 words; 3 of 14 chars; 3 of 14 bytes")
+    );
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use common::{editor, error, run, text};
+
+#[test]
+fn opening_a_file_shows_its_contents() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("file.txt");
+    std::fs::write(&path, "hello\nworld\n").expect("file written");
+    let mut editor = editor("");
+
+    assert!(editor.open_path(path.clone()));
+    assert_eq!(text(&editor), "hello\nworld\n");
+    assert_eq!(editor.get_current_path(), Some(&path));
+    assert_eq!(editor.get_current_title(), "file.txt");
+}
+
+#[test]
+fn each_file_gets_its_own_buffer() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut editor = editor("");
+    for name in ["a.txt", "b.txt"] {
+        std::fs::write(dir.path().join(name), name).expect("file written");
+        assert!(editor.open_path(dir.path().join(name)));
+    }
+
+    let titles: Vec<_> = editor
+        .get_buffers()
+        .iter()
+        .map(|b| b.title.clone())
+        .collect();
+    assert!(titles.contains(&"a.txt".to_string()) && titles.contains(&"b.txt".to_string()));
+    assert_eq!(text(&editor), "b.txt");
+}
+
+#[test]
+fn a_missing_file_is_reported() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut editor = editor("");
+
+    assert!(!editor.open_path(dir.path().join("missing.txt")));
+    assert!(error(&editor).is_some_and(|e| e.starts_with("E484")));
+}
+
+#[test]
+fn a_location_puts_the_cursor_on_its_line_and_column_clamped() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("file.txt");
+    std::fs::write(&path, "one\ntwo\nthree\n").expect("file written");
+    let mut editor = editor("");
+
+    assert!(editor.open_location(&format!("{}:2:3", path.display())));
+    assert_eq!((editor.cursor.row, editor.cursor.col), (1, 2));
+
+    run(&mut editor, &format!("e {}:99:99", path.display()));
+    assert_eq!(editor.get_current_path(), Some(&path));
+    assert_eq!(editor.cursor.row, 2);
+    assert_eq!(editor.cursor.col, 5);
+}
+
+#[test]
+fn opening_the_same_file_twice_reuses_its_buffer() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::create_dir(dir.path().join("sub")).expect("dir created");
+    let path = dir.path().join("file.txt");
+    std::fs::write(&path, "text\n").expect("file written");
+    let mut editor = editor("");
+
+    let first = editor
+        .buffer_manager
+        .open_file(path.clone())
+        .expect("opens");
+    let count = editor.buffer_manager.iter().count();
+    let again = editor
+        .buffer_manager
+        .open_file(dir.path().join("sub/../file.txt"))
+        .expect("opens");
+    assert_eq!(again, first);
+    assert_eq!(editor.buffer_manager.iter().count(), count);
+
+    assert!(editor.open_path(path));
+    assert_eq!(editor.buffer_manager.current_buffer_id(), first);
+    assert_eq!(editor.buffer_manager.iter().count(), count);
+}
+
+#[test]
+fn opened_files_are_listed_most_recent_first() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut editor = editor("");
+    for name in ["a.txt", "b.txt", "a.txt"] {
+        std::fs::write(dir.path().join(name), name).expect("file written");
+        assert!(editor.open_path(dir.path().join(name)));
+    }
+
+    let expected: Vec<_> = ["a.txt", "b.txt"]
+        .iter()
+        .map(|name| std::fs::canonicalize(dir.path().join(name)).expect("canonical path"))
+        .collect();
+    assert_eq!(editor.recent_files(), expected);
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::{editor, error, info, run};
+use fluxion_core::LineNumberMode;
+
+#[test]
+fn set_number_and_relativenumber_pick_the_line_number_mode() {
+    let mut editor = editor("");
+    run(&mut editor, "set nonumber norelativenumber");
+    assert_eq!(editor.options.line_numbers, LineNumberMode::None);
+
+    run(&mut editor, "set number");
+    assert_eq!(editor.options.line_numbers, LineNumberMode::Absolute);
+    run(&mut editor, "set relativenumber");
+    assert_eq!(editor.options.line_numbers, LineNumberMode::Hybrid);
+    run(&mut editor, "set nonumber");
+    assert_eq!(editor.options.line_numbers, LineNumberMode::Relative);
+}
+
+#[test]
+fn set_colorcolumn_turns_the_ruler_on_and_off() {
+    let mut editor = editor("");
+    run(&mut editor, "set colorcolumn=80");
+    assert_eq!(editor.options.color_column, Some(80));
+
+    run(&mut editor, "set cc=0");
+    assert_eq!(editor.options.color_column, None);
+    run(&mut editor, "set cc=wide");
+    assert_eq!(error(&editor), Some("E474: Invalid argument: cc=wide"));
+}
+
+#[test]
+fn list_is_off_until_set() {
+    let mut editor = editor("");
+    assert!(!editor.options.list);
+
+    run(&mut editor, "set list");
+    assert!(editor.options.list);
+    run(&mut editor, "set nolist");
+    assert!(!editor.options.list);
+}
+
+#[test]
+fn set_assigns_toggles_and_shows_values() {
+    let mut editor = editor("");
+    run(&mut editor, "set ts=8 et");
+    assert_eq!(editor.options.tab_width, 8);
+    assert!(editor.options.expand_tabs);
+
+    run(&mut editor, "set et! ts?");
+    assert!(!editor.options.expand_tabs);
+    assert_eq!(info(&editor), Some("tabstop=8"));
+    run(&mut editor, "set expandtab?");
+    assert_eq!(info(&editor), Some("noexpandtab"));
+}
+
+#[test]
+fn set_reports_bad_values_and_unknown_options() {
+    let mut editor = editor("");
+    run(&mut editor, "set ts=wide");
+    assert_eq!(
+        error(&editor),
+        Some("E521: Number required after =: ts=wide")
+    );
+    run(&mut editor, "set ts=0");
+    assert_eq!(
+        error(&editor),
+        Some("E487: Argument must be positive: ts=0")
+    );
+    run(&mut editor, "set ff=mac");
+    assert_eq!(error(&editor), Some("E474: Invalid argument: ff=mac"));
+    run(&mut editor, "set bogus");
+    assert_eq!(error(&editor), Some("E518: Unknown option: bogus"));
+    assert_eq!(editor.options.tab_width, 4);
+}
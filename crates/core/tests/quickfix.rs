@@ -0,0 +1,67 @@
+mod common;
+
+use common::{editor, error, info, run, text};
+use fluxion_core::{Cursor, QuickfixEntry};
+use std::path::Path;
+
+fn entry(path: &Path, line: usize, col: Option<usize>, message: &str) -> QuickfixEntry {
+    QuickfixEntry {
+        path: path.to_path_buf(),
+        line,
+        col,
+        message: message.to_string(),
+    }
+}
+
+#[test]
+fn cnext_opens_each_entry_at_its_position() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let a = dir.path().join("a.rs");
+    let b = dir.path().join("b.rs");
+    std::fs::write(&a, "fn a() {}\nfn aa() {}\n").expect("file written");
+    std::fs::write(&b, "fn b() {}\n").expect("file written");
+    let mut editor = editor("");
+    editor
+        .quickfix
+        .push(entry(&a, 2, Some(4), "error: first"), None);
+    editor
+        .quickfix
+        .push(entry(&b, 1, None, "warning: second"), None);
+
+    run(&mut editor, "cnext");
+    assert_eq!(editor.get_current_path(), Some(&a));
+    assert_eq!(editor.cursor, Cursor::new(1, 3));
+    assert_eq!(info(&editor), Some("(1 of 2) error: first"));
+
+    run(&mut editor, "cn");
+    assert_eq!(text(&editor), "fn b() {}\n");
+    assert_eq!(editor.cursor, Cursor::new(0, 0));
+    assert_eq!(info(&editor), Some("(2 of 2) warning: second"));
+}
+
+#[test]
+fn cnext_and_cprev_wrap_around() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let a = dir.path().join("a.rs");
+    std::fs::write(&a, "one\ntwo\nthree\n").expect("file written");
+    let mut editor = editor("");
+    for line in 1..=3 {
+        editor.quickfix.push(entry(&a, line, None, ""), None);
+    }
+
+    run(&mut editor, "cprev");
+    assert_eq!(editor.cursor.row, 2);
+    run(&mut editor, "cnext");
+    assert_eq!(editor.cursor.row, 0);
+    run(&mut editor, "cp");
+    assert_eq!(editor.cursor.row, 2);
+    assert_eq!(editor.quickfix.current(), Some(2));
+}
+
+#[test]
+fn an_empty_list_is_an_error() {
+    let mut editor = editor("");
+
+    run(&mut editor, "cnext");
+    assert_eq!(error(&editor), Some("E42: No Errors"));
+}
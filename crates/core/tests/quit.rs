@@ -0,0 +1,119 @@
+mod common;
+
+use common::{editor, error, run};
+
+#[test]
+fn q_quits_a_clean_buffer() {
+    let mut editor = editor("text\n");
+
+    run(&mut editor, "q");
+    assert!(editor.should_quit);
+}
+
+#[test]
+fn q_refuses_to_discard_changes() {
+    let mut editor = editor("text\n");
+    run(&mut editor, "s/text/changed/");
+
+    run(&mut editor, "q");
+    assert!(!editor.should_quit);
+    assert_eq!(
+        error(&editor),
+        Some("E37: No write since last change (add ! to override)")
+    );
+}
+
+#[test]
+fn q_bang_quits_anyway() {
+    let mut editor = editor("text\n");
+    run(&mut editor, "s/text/changed/");
+
+    run(&mut editor, "q!");
+    assert!(editor.should_quit);
+}
+
+#[test]
+fn wq_saves_then_quits() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("file.txt");
+    std::fs::write(&path, "text\n").expect("file written");
+    let mut editor = editor("");
+    assert!(editor.open_path(path.clone()));
+    run(&mut editor, "s/text/changed/");
+
+    run(&mut editor, "wq");
+    assert!(editor.should_quit);
+    assert_eq!(
+        std::fs::read_to_string(&path).expect("saved file"),
+        "changed\n"
+    );
+}
+
+/// Opens a temp file holding `text\n` in a new buffer and changes it.
+fn open_changed(editor: &mut fluxion_core::Editor, path: &std::path::Path) {
+    std::fs::write(path, "text\n").expect("file written");
+    assert!(editor.open_path(path.to_path_buf()));
+    run(editor, "s/text/changed/");
+}
+
+#[test]
+fn wa_writes_every_changed_buffer() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let (first, second) = (dir.path().join("a.txt"), dir.path().join("b.txt"));
+    let mut editor = editor("");
+    open_changed(&mut editor, &first);
+    open_changed(&mut editor, &second);
+
+    run(&mut editor, "wa");
+    assert!(editor.get_buffers().iter().all(|b| !b.dirty));
+    assert!(!editor.should_quit);
+    for path in [first, second] {
+        assert_eq!(std::fs::read_to_string(path).expect("saved"), "changed\n");
+    }
+}
+
+#[test]
+fn wa_reports_a_buffer_without_a_name() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut editor = editor("text\n");
+    run(&mut editor, "s/text/changed/");
+    let unnamed = editor.buffer_manager.current_buffer_id();
+    open_changed(&mut editor, &dir.path().join("a.txt"));
+
+    run(&mut editor, "wqa");
+    assert!(!editor.should_quit);
+    assert_eq!(
+        error(&editor),
+        Some(format!("E141: No file name for buffer {}", unnamed).as_str())
+    );
+}
+
+#[test]
+fn qa_blocks_while_another_buffer_is_changed() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut editor = editor("");
+    open_changed(&mut editor, &dir.path().join("a.txt"));
+    std::fs::write(dir.path().join("b.txt"), "clean\n").expect("file written");
+    assert!(editor.open_path(dir.path().join("b.txt")));
+
+    run(&mut editor, "qa");
+    assert!(!editor.should_quit);
+    assert_eq!(
+        error(&editor),
+        Some("E162: No write since last change for buffer \"a.txt\"")
+    );
+
+    run(&mut editor, "qa!");
+    assert!(editor.should_quit);
+}
+
+#[test]
+fn wqa_saves_then_quits() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut editor = editor("");
+    open_changed(&mut editor, &dir.path().join("a.txt"));
+    open_changed(&mut editor, &dir.path().join("b.txt"));
+
+    run(&mut editor, "wqa");
+    assert!(editor.should_quit);
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::{editor, error, run, text};
+use fluxion_core::Action;
+
+fn readonly_editor(contents: &str) -> fluxion_core::Editor {
+    let mut editor = editor(contents);
+    run(&mut editor, "set readonly");
+    assert!(editor.buffer_manager.current_buffer().readonly);
+    editor
+}
+
+#[test]
+fn typing_in_a_readonly_buffer_changes_nothing() {
+    let mut editor = readonly_editor("text\n");
+
+    editor.handle_action(Action::EnterInsertMode);
+    editor.handle_action(Action::Insert('x'));
+    editor.handle_action(Action::Delete);
+    editor.handle_action(Action::EnterNormalMode);
+    editor.handle_action(Action::DeleteCharUnderCursor);
+    assert_eq!(text(&editor), "text\n");
+    assert!(!editor.buffer_manager.current_buffer().dirty);
+}
+
+#[test]
+fn commands_that_edit_a_readonly_buffer_are_refused() {
+    let mut editor = readonly_editor("text\n");
+
+    run(&mut editor, "s/text/changed/");
+    assert_eq!(text(&editor), "text\n");
+    assert_eq!(
+        error(&editor),
+        Some("E21: Cannot make changes, 'readonly' is set")
+    );
+}
+
+#[test]
+fn noreadonly_allows_edits_again() {
+    let mut editor = readonly_editor("text\n");
+
+    run(&mut editor, "set noreadonly");
+    run(&mut editor, "s/text/changed/");
+    assert_eq!(text(&editor), "changed\n");
+}
+
+#[test]
+fn a_file_without_write_permission_opens_readonly() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("locked.txt");
+    std::fs::write(&path, "text\n").expect("file written");
+    let mut permissions = std::fs::metadata(&path).expect("metadata").permissions();
+    permissions.set_readonly(true);
+    std::fs::set_permissions(&path, permissions).expect("permissions set");
+    let mut editor = editor("");
+
+    assert!(editor.open_path(path));
+    assert!(editor.buffer_manager.current_buffer().readonly);
+    run(&mut editor, "w");
+    assert_eq!(
+        error(&editor),
+        Some("E45: 'readonly' option is set (add ! to override)")
+    );
+}
+
+#[test]
+fn write_all_skips_readonly_buffers() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let [locked, open] = ["locked.txt", "open.txt"].map(|name| dir.path().join(name));
+    let mut editor = editor("");
+    for path in [&locked, &open] {
+        std::fs::write(path, "old\n").expect("file written");
+        assert!(editor.open_path(path.clone()));
+        run(&mut editor, "s/old/new/");
+    }
+    run(&mut editor, "b 1");
+    run(&mut editor, "set readonly");
+
+    run(&mut editor, "wa");
+    assert_eq!(
+        error(&editor),
+        Some("E45: 'readonly' option is set for \"locked.txt\"")
+    );
+    assert_eq!(std::fs::read_to_string(&locked).expect("read"), "old\n");
+    assert_eq!(std::fs::read_to_string(&open).expect("read"), "new\n");
+
+    run(&mut editor, "wqa");
+    assert!(!editor.should_quit);
+    assert_eq!(std::fs::read_to_string(&locked).expect("read"), "old\n");
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::{editor, error, info, run, text};
+use fluxion_core::{Editor, Encoding, LineEnding, swap_path};
+use std::path::Path;
+
+/// An editor on `name` in `dir`, holding `contents`, with `swap` left in its
+/// swap file.
+fn open_with_swap(dir: &Path, name: &str, contents: &[u8], swap: &str) -> Editor {
+    let path = dir.join(name);
+    std::fs::write(&path, contents).expect("file written");
+    std::fs::write(swap_path(&path), swap).expect("swap written");
+    let mut editor = editor("");
+    assert!(editor.open_path(path));
+    editor
+}
+
+#[test]
+fn recover_restores_the_swap_contents_as_one_undo_step() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut editor = open_with_swap(dir.path(), "file.txt", b"saved\n", "unsaved work\n");
+
+    run(&mut editor, "recover");
+    assert!(info(&editor).is_some_and(|m| m.starts_with("Recovered from")));
+    assert_eq!(text(&editor), "unsaved work\n");
+    assert!(editor.buffer_manager.current_buffer().dirty);
+
+    editor.handle_action(fluxion_core::Action::Undo);
+    assert_eq!(text(&editor), "saved\n");
+}
+
+#[test]
+fn recover_keeps_the_files_encoding_and_line_endings() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("latin1.txt");
+    let mut editor = open_with_swap(dir.path(), "latin1.txt", b"caf\xe9\r\n", "café au lait\n");
+    assert_eq!(
+        editor.buffer_manager.current_buffer().encoding,
+        Encoding::Latin1
+    );
+
+    run(&mut editor, "recover");
+    assert_eq!(text(&editor), "café au lait\n");
+    let buffer = editor.buffer_manager.current_buffer();
+    assert_eq!(buffer.encoding, Encoding::Latin1);
+    assert_eq!(buffer.line_ending, LineEnding::Crlf);
+
+    run(&mut editor, "w");
+    assert_eq!(error(&editor), None);
+    assert_eq!(
+        std::fs::read(&path).expect("saved file"),
+        b"caf\xe9 au lait\r\n"
+    );
+}
+
+#[test]
+fn recover_refuses_to_change_a_readonly_buffer() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut editor = open_with_swap(dir.path(), "file.txt", b"saved\n", "unsaved work\n");
+    run(&mut editor, "set readonly");
+
+    run(&mut editor, "recover");
+    assert_eq!(
+        error(&editor),
+        Some("E21: Cannot make changes, 'readonly' is set")
+    );
+    assert_eq!(text(&editor), "saved\n");
+    assert!(!editor.buffer_manager.current_buffer().dirty);
+}
+
+#[test]
+fn recover_without_a_swap_file_is_an_error() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("file.txt");
+    std::fs::write(&path, "saved\n").expect("file written");
+    let mut editor = editor("");
+    assert!(editor.open_path(path.clone()));
+
+    run(&mut editor, "recover");
+    assert_eq!(
+        error(&editor).map(str::to_string),
+        Some(format!("E305: No swap file found for {}", path.display()))
+    );
+    assert_eq!(text(&editor), "saved\n");
+}
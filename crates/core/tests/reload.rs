@@ -0,0 +1,80 @@
+mod common;
+
+use common::{editor, error, run, text};
+use fluxion_core::Action;
+
+#[test]
+fn e_bang_rereads_the_file_from_disk() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("file.txt");
+    std::fs::write(&path, "one\ntwo\nthree\n").expect("file written");
+    let mut editor = editor("");
+    assert!(editor.open_path(path.clone()));
+    run(&mut editor, "3");
+    run(&mut editor, "s/three/changed/");
+    assert_eq!(editor.cursor.row, 2);
+
+    std::fs::write(&path, "new\n").expect("file rewritten");
+    run(&mut editor, "e!");
+    assert_eq!(text(&editor), "new\n");
+    assert!(!editor.buffer_manager.current_buffer().dirty);
+    assert_eq!(editor.cursor.row, 0);
+}
+
+#[test]
+fn reloading_detects_the_filetype_and_readonly_again() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("script");
+    std::fs::write(&path, "#!/bin/sh\n").expect("file written");
+    let mut editor = editor("");
+    assert!(editor.open_path(path.clone()));
+    let buffer = editor.buffer_manager.current_buffer();
+    assert_eq!(buffer.filetype.as_deref(), Some("sh"));
+    assert!(!buffer.readonly);
+
+    std::fs::write(&path, "#!/usr/bin/env python3\n").expect("file rewritten");
+    let mut permissions = std::fs::metadata(&path).expect("metadata").permissions();
+    permissions.set_readonly(true);
+    std::fs::set_permissions(&path, permissions).expect("permissions set");
+    run(&mut editor, "e!");
+    let buffer = editor.buffer_manager.current_buffer();
+    assert_eq!(buffer.filetype.as_deref(), Some("python"));
+    assert!(buffer.readonly);
+}
+
+#[test]
+fn reloading_drops_the_undo_history() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("file.txt");
+    std::fs::write(&path, "one\n").expect("file written");
+    let mut editor = editor("");
+    assert!(editor.open_path(path));
+    run(&mut editor, "s/one/two/");
+
+    editor.handle_action(Action::ReloadBuffer);
+    editor.handle_action(Action::Undo);
+    assert_eq!(text(&editor), "one\n");
+}
+
+#[test]
+fn reloading_a_buffer_without_a_file_is_an_error() {
+    let mut editor = editor("text\n");
+
+    run(&mut editor, "e!");
+    assert_eq!(error(&editor), Some("E32: No file name"));
+    assert_eq!(text(&editor), "text\n");
+}
+
+#[test]
+fn reloading_a_deleted_file_is_an_error() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("file.txt");
+    std::fs::write(&path, "one\n").expect("file written");
+    let mut editor = editor("");
+    assert!(editor.open_path(path.clone()));
+    std::fs::remove_file(&path).expect("file removed");
+
+    run(&mut editor, "e!");
+    assert!(error(&editor).is_some_and(|e| e.starts_with("E484")));
+    assert_eq!(text(&editor), "one\n");
+}
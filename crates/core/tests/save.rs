@@ -0,0 +1,239 @@
+mod common;
+
+use common::{editor, error, run, text};
+
+#[test]
+fn saving_a_large_buffer_writes_every_byte() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("large.txt");
+    let contents: String = (0..200_000)
+        .map(|n| format!("line {} with some ünïcode text\n", n))
+        .collect();
+    assert!(contents.len() > 4 * 1024 * 1024);
+    let mut editor = editor(&contents);
+
+    run(&mut editor, &format!("w {}", path.display()));
+    assert_eq!(error(&editor), None);
+    // Compared without `assert_eq!` so a failure doesn't print megabytes.
+    assert!(std::fs::read(&path).expect("saved file") == text(&editor).into_bytes());
+    assert!(!dir.path().join(".large.txt.tmp").exists());
+}
+
+#[test]
+fn a_failed_write_leaves_the_original_file_intact() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("file.txt");
+    std::fs::write(&path, "original\n").expect("file written");
+    let mut editor = editor("");
+    assert!(editor.open_path(path.clone()));
+    run(&mut editor, "s/original/changed/");
+    // A directory where the temp file goes makes the write fail even for
+    // root, which read-only permissions would not stop.
+    std::fs::create_dir(dir.path().join(".file.txt.tmp")).expect("dir created");
+
+    run(&mut editor, "w");
+    assert!(error(&editor).is_some_and(|e| e.starts_with("E212")));
+    assert_eq!(
+        std::fs::read_to_string(&path).expect("original file"),
+        "original\n"
+    );
+    assert!(editor.buffer_manager.current_buffer().dirty);
+}
+
+#[test]
+fn a_failed_rename_and_copy_cleans_up_the_temp_file() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let target = dir.path().join("taken");
+    std::fs::create_dir(&target).expect("dir created");
+    std::fs::write(target.join("keep"), "kept\n").expect("file written");
+    let mut editor = editor("text\n");
+
+    run(&mut editor, &format!("w {}", target.display()));
+    assert!(error(&editor).is_some_and(|e| e.starts_with("E212")));
+    assert!(target.join("keep").exists());
+    assert!(!dir.path().join(".taken.tmp").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn saving_keeps_the_file_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("script.sh");
+    std::fs::write(&path, "echo hi\n").expect("file written");
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o750))
+        .expect("permissions set");
+    let mut editor = editor("");
+    assert!(editor.open_path(path.clone()));
+    run(&mut editor, "s/hi/there/");
+
+    run(&mut editor, "w");
+    let mode = std::fs::metadata(&path)
+        .expect("saved file")
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o750);
+    assert_eq!(
+        std::fs::read_to_string(&path).expect("saved file"),
+        "echo there\n"
+    );
+}
+
+/// Opens `path` and then changes it behind the editor's back, with a
+/// modification time the editor can't have seen.
+fn open_then_change_on_disk(path: &std::path::Path) -> fluxion_core::Editor {
+    std::fs::write(path, "original\n").expect("file written");
+    let mut editor = editor("");
+    assert!(editor.open_path(path.to_path_buf()));
+    std::fs::write(path, "external\n").expect("file rewritten");
+    let file = std::fs::File::options()
+        .write(true)
+        .open(path)
+        .expect("file opens");
+    file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(60))
+        .expect("mtime set");
+    editor
+}
+
+#[test]
+fn a_file_changed_on_disk_is_detected() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("file.txt");
+    let editor = open_then_change_on_disk(&path);
+
+    assert!(editor.buffer_manager.external_change_detected());
+}
+
+#[test]
+fn w_refuses_to_overwrite_an_external_change() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("file.txt");
+    let mut editor = open_then_change_on_disk(&path);
+
+    run(&mut editor, "w");
+    assert_eq!(
+        error(&editor),
+        Some("W11: \"file.txt\" has changed since editing started (add ! to override)")
+    );
+    assert_eq!(std::fs::read_to_string(&path).expect("file"), "external\n");
+
+    run(&mut editor, "w!");
+    assert_eq!(std::fs::read_to_string(&path).expect("file"), "original\n");
+    assert!(!editor.buffer_manager.external_change_detected());
+}
+
+/// An editor on `name` in `dir` whose text now ends its line with spaces.
+fn open_with_trailing_spaces(dir: &std::path::Path, name: &str) -> fluxion_core::Editor {
+    let path = dir.join(name);
+    std::fs::write(&path, "text\n").expect("file written");
+    let mut editor = editor("");
+    assert!(editor.open_path(path));
+    run(&mut editor, "s/text/text  /");
+    run(&mut editor, "set trimtrailing");
+    editor
+}
+
+#[test]
+fn trimtrailing_trims_what_gets_written() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut editor = open_with_trailing_spaces(dir.path(), "file.txt");
+
+    run(&mut editor, "w");
+    assert_eq!(error(&editor), None);
+    assert_eq!(text(&editor), "text\n");
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("file.txt")).expect("saved file"),
+        "text\n"
+    );
+}
+
+#[test]
+fn a_failed_write_keeps_the_trailing_whitespace() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut editor = open_with_trailing_spaces(dir.path(), "file.txt");
+    std::fs::create_dir(dir.path().join(".file.txt.tmp")).expect("dir created");
+
+    run(&mut editor, "w");
+    assert!(error(&editor).is_some_and(|e| e.starts_with("E212")));
+    assert_eq!(text(&editor), "text  \n");
+    assert!(editor.buffer_manager.current_buffer().dirty);
+
+    // The trim left no undo step behind; undo goes back to before the edit.
+    editor.handle_action(fluxion_core::Action::Undo);
+    assert_eq!(text(&editor), "text\n");
+}
+
+#[test]
+fn write_all_trims_only_the_buffers_it_writes() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut editor = open_with_trailing_spaces(dir.path(), "failing.txt");
+    std::fs::create_dir(dir.path().join(".failing.txt.tmp")).expect("dir created");
+    let failing_id = editor.buffer_manager.current_buffer_id();
+    let saved = dir.path().join("saved.txt");
+    std::fs::write(&saved, "text\n").expect("file written");
+    assert!(editor.open_path(saved.clone()));
+    run(&mut editor, "s/text/text  /");
+    run(&mut editor, "enew");
+    run(&mut editor, "s/^/unnamed  /");
+
+    run(&mut editor, "wa");
+    assert!(error(&editor).is_some());
+    assert_eq!(
+        std::fs::read_to_string(&saved).expect("saved file"),
+        "text\n"
+    );
+    assert_eq!(text(&editor), "unnamed  ");
+    let failing = editor
+        .buffer_manager
+        .iter()
+        .find(|b| b.id == failing_id)
+        .expect("failing buffer");
+    assert_eq!(failing.text.to_string(), "text  \n");
+    assert!(failing.dirty);
+}
+
+/// Writes `contents` from a fresh buffer to `name` in `dir`, twice, with
+/// `options` set first, and returns what ended up in the file.
+fn save_twice(dir: &std::path::Path, name: &str, contents: &str, options: &str) -> String {
+    let path = dir.join(name);
+    let mut editor = editor(contents);
+    run(&mut editor, &format!("set {}", options));
+    run(&mut editor, &format!("w {}", path.display()));
+    run(&mut editor, "w");
+    assert_eq!(error(&editor), None);
+    assert_eq!(text(&editor), contents);
+    std::fs::read_to_string(path).expect("saved file")
+}
+
+#[test]
+fn fixeol_ends_the_file_with_one_newline() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    assert_eq!(
+        save_twice(dir.path(), "a.txt", "one\ntwo", "fixeol"),
+        "one\ntwo\n"
+    );
+    assert_eq!(
+        save_twice(dir.path(), "b.txt", "one\ntwo\n", "fixeol"),
+        "one\ntwo\n"
+    );
+    assert_eq!(save_twice(dir.path(), "c.txt", "", "fixeol"), "");
+}
+
+#[test]
+fn nofixeol_writes_the_text_as_it_is() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    assert_eq!(
+        save_twice(dir.path(), "a.txt", "one\ntwo", "nofixeol"),
+        "one\ntwo"
+    );
+}
+
+#[test]
+fn fixeol_uses_the_buffers_line_ending() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    assert_eq!(
+        save_twice(dir.path(), "a.txt", "one\ntwo", "fixeol fileformat=dos"),
+        "one\r\ntwo\r\n"
+    );
+}
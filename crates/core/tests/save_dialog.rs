@@ -0,0 +1,66 @@
+mod common;
+
+use common::{editor, run};
+use fluxion_core::{Action, Mode};
+use std::path::Path;
+
+#[test]
+fn w_on_a_new_buffer_prefills_an_untitled_name() {
+    let mut editor = editor("text\n");
+
+    run(&mut editor, "w");
+    assert_eq!(editor.mode, Mode::SaveDialog);
+    assert!(
+        editor.command_input.starts_with("untitled") && editor.command_input.ends_with(".txt"),
+        "{}",
+        editor.command_input
+    );
+    assert!(!Path::new(&editor.command_input).exists());
+}
+
+#[test]
+fn a_relative_name_saves_under_the_working_directory() {
+    // Integration tests run in the package directory.
+    let dir = tempfile::Builder::new()
+        .prefix(".save-dialog-test")
+        .tempdir_in(".")
+        .expect("temp dir");
+    let name = Path::new(dir.path().file_name().expect("dir name")).join("new.txt");
+    let mut editor = editor("text\n");
+    run(&mut editor, "w");
+
+    editor.command_input = name.display().to_string();
+    editor.handle_action(Action::SaveBufferAs(None));
+    assert_eq!(editor.mode, Mode::Normal);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("new.txt")).expect("saved file"),
+        "text\n"
+    );
+    assert!(!editor.buffer_manager.current_buffer().dirty);
+}
+
+#[test]
+fn tab_completes_the_typed_path() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::create_dir(dir.path().join("notes")).expect("dir created");
+    let mut editor = editor("text\n");
+    run(&mut editor, "w");
+
+    editor.command_input = dir.path().join("no").display().to_string();
+    editor.handle_action(Action::CompletePath);
+    assert_eq!(
+        editor.command_input,
+        format!("{}/", dir.path().join("notes").display())
+    );
+}
+
+#[test]
+fn an_empty_name_saves_nothing() {
+    let mut editor = editor("text\n");
+    run(&mut editor, "w");
+
+    editor.command_input.clear();
+    editor.handle_action(Action::SaveBufferAs(None));
+    assert_eq!(editor.mode, Mode::Normal);
+    assert_eq!(editor.get_current_path(), None);
+}
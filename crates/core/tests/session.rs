@@ -0,0 +1,111 @@
+mod common;
+
+use common::{editor, error, run};
+use fluxion_core::Cursor;
+use std::path::Path;
+
+fn write(dir: &Path, name: &str, contents: &str) -> std::path::PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, contents).expect("file written");
+    path
+}
+
+#[test]
+fn a_session_reopens_buffers_at_their_cursors() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let first = write(dir.path(), "a.txt", "a\nb\nc\n");
+    let second = write(dir.path(), "b.txt", "one two\n");
+    let session = dir.path().join("session.json");
+    let mut editor = editor("");
+    assert!(editor.open_location(&format!("{}:3", first.display())));
+    assert!(editor.open_location(&format!("{}:1:5", second.display())));
+    let first_id = editor
+        .buffer_manager
+        .find_by_path(&first)
+        .expect("first file open");
+    run(&mut editor, &format!("b {}", first_id));
+    editor.save_session(&session).expect("session saved");
+
+    let mut restored = common::editor("");
+    restored.load_session(&session).expect("session loaded");
+    assert_eq!(restored.get_current_path(), Some(&first));
+    assert_eq!(restored.cursor, Cursor::new(2, 0));
+    run(&mut restored, "bn");
+    assert_eq!(restored.get_current_path(), Some(&second));
+    assert_eq!(restored.cursor, Cursor::new(0, 4));
+}
+
+#[test]
+fn files_that_are_gone_are_skipped() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let kept = write(dir.path(), "kept.txt", "kept\n");
+    let gone = write(dir.path(), "gone.txt", "gone\n");
+    let session = dir.path().join("session.json");
+    let mut editor = editor("");
+    assert!(editor.open_path(kept.clone()));
+    assert!(editor.open_path(gone.clone()));
+    editor.save_session(&session).expect("session saved");
+    std::fs::remove_file(&gone).expect("file removed");
+
+    let mut restored = common::editor("");
+    restored.load_session(&session).expect("session loaded");
+    let paths: Vec<_> = restored
+        .get_buffers()
+        .iter()
+        .filter_map(|b| b.path.clone())
+        .collect();
+    assert_eq!(paths, [kept]);
+}
+
+#[test]
+fn files_that_cannot_be_read_are_skipped() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let first = write(dir.path(), "first.txt", "first\n");
+    let unreadable = write(dir.path(), "unreadable.txt", "unreadable\n");
+    let last = write(dir.path(), "last.txt", "last\n");
+    let session = dir.path().join("session.json");
+    let mut editor = editor("");
+    assert!(editor.open_path(first.clone()));
+    assert!(editor.open_path(unreadable.clone()));
+    assert!(editor.open_path(last.clone()));
+    editor.save_session(&session).expect("session saved");
+    std::fs::remove_file(&unreadable).expect("file removed");
+    std::fs::create_dir(&unreadable).expect("directory created");
+
+    let mut restored = common::editor("");
+    restored.load_session(&session).expect("session loaded");
+    let paths: Vec<_> = restored
+        .get_buffers()
+        .iter()
+        .filter_map(|b| b.path.clone())
+        .collect();
+    assert_eq!(paths, [first, last]);
+}
+
+#[test]
+fn mksession_and_source_commands() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let file = write(dir.path(), "a.txt", "a\n");
+    let session = dir.path().join("my.json");
+    let mut editor = editor("");
+    assert!(editor.open_path(file.clone()));
+
+    run(&mut editor, &format!("mksession {}", session.display()));
+    assert!(session.exists());
+
+    let mut restored = common::editor("");
+    run(&mut restored, &format!("source {}", session.display()));
+    assert_eq!(restored.get_current_path(), Some(&file));
+}
+
+#[test]
+fn sourcing_a_missing_session_is_an_error() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut editor = editor("");
+
+    run(
+        &mut editor,
+        &format!("source {}", dir.path().join("none.json").display()),
+    );
+    assert!(error(&editor).is_some());
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::{editor, error, run, text};
+
+#[test]
+fn sort_orders_every_line() {
+    let mut editor = editor("pear\napple\nfig\n");
+
+    run(&mut editor, "sort");
+    assert_eq!(text(&editor), "apple\nfig\npear\n");
+}
+
+#[test]
+fn sort_keeps_a_missing_final_newline_missing() {
+    let mut editor = editor("b\na");
+
+    run(&mut editor, "sort");
+    assert_eq!(text(&editor), "a\nb");
+}
+
+#[test]
+fn sort_bang_reverses() {
+    let mut editor = editor("pear\napple\nfig\n");
+
+    run(&mut editor, "sort!");
+    assert_eq!(text(&editor), "pear\nfig\napple\n");
+}
+
+#[test]
+fn sort_n_orders_by_the_first_number() {
+    let mut editor = editor("item 10\nitem 9\nnone\nitem -3\n");
+
+    run(&mut editor, "sort n");
+    assert_eq!(text(&editor), "none\nitem -3\nitem 9\nitem 10\n");
+}
+
+#[test]
+fn sort_with_a_range_leaves_other_lines_alone() {
+    let mut editor = editor("z\nc\nb\na\ny\n");
+
+    run(&mut editor, "2,4sort");
+    assert_eq!(text(&editor), "z\na\nb\nc\ny\n");
+}
+
+#[test]
+fn sort_rejects_an_unknown_flag() {
+    let mut editor = editor("b\na\n");
+
+    run(&mut editor, "sort x");
+    assert_eq!(error(&editor), Some("E474: Invalid argument: x"));
+    assert_eq!(text(&editor), "b\na\n");
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::{editor, error, info, run, text};
+
+#[test]
+fn substitute_replaces_the_first_match_on_the_cursor_line() {
+    let mut editor = editor("a a\na a\n");
+
+    run(&mut editor, "s/a/b/");
+    assert_eq!(text(&editor), "b a\na a\n");
+    assert_eq!(info(&editor), Some("1 substitution on 1 line"));
+}
+
+#[test]
+fn the_g_flag_replaces_every_match_on_the_line() {
+    let mut editor = editor("a a\na a\n");
+
+    run(&mut editor, "s/a/b/g");
+    assert_eq!(text(&editor), "b b\na a\n");
+    assert_eq!(info(&editor), Some("2 substitutions on 1 line"));
+}
+
+#[test]
+fn percent_substitutes_across_the_buffer() {
+    let mut editor = editor("a a\nx\na a\n");
+
+    run(&mut editor, "%s/a/b/g");
+    assert_eq!(text(&editor), "b b\nx\nb b\n");
+    assert_eq!(info(&editor), Some("4 substitutions on 2 lines"));
+    assert_eq!(editor.cursor.row, 2);
+}
+
+#[test]
+fn a_pattern_with_an_escaped_slash() {
+    let mut editor = editor("a/b\n");
+
+    run(&mut editor, r"s/a\/b/c/");
+    assert_eq!(text(&editor), "c\n");
+}
+
+#[test]
+fn no_match_leaves_the_buffer_alone() {
+    let mut editor = editor("abc\n");
+
+    run(&mut editor, "%s/z/y/");
+    assert_eq!(text(&editor), "abc\n");
+    assert_eq!(error(&editor), Some("E486: Pattern not found: z"));
+    assert!(!editor.buffer_manager.current_buffer().dirty);
+}
+
+#[test]
+fn substitution_follows_ignorecase_and_smartcase() {
+    let mut editor = editor("Foo foo\n");
+    run(&mut editor, "set ignorecase smartcase");
+
+    run(&mut editor, "s/foo/x/g");
+    assert_eq!(text(&editor), "x x\n");
+    editor
+        .buffer_manager
+        .current_buffer_mut()
+        .set_text("Foo foo\n".into());
+    run(&mut editor, "s/Foo/x/g");
+    assert_eq!(text(&editor), "x foo\n");
+}
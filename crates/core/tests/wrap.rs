@@ -0,0 +1,75 @@
+mod common;
+
+use common::{editor, run};
+use fluxion_core::{Cursor, Editor};
+
+/// An editor showing `text` in a view `width` columns wide and `height`
+/// rows tall, with `wrap` on.
+fn wrapped(text: &str, width: usize, height: usize) -> Editor {
+    let mut editor = editor(text);
+    editor.options.wrap = true;
+    editor.viewport_width = width;
+    editor.viewport_height = height;
+    editor
+}
+
+#[test]
+fn a_long_line_starts_a_screen_row_every_width_columns() {
+    let editor = wrapped("0123456789abcdefghijklmnopqrstuvwxyz\nshort\n\n", 10, 10);
+
+    assert_eq!(editor.wrap_starts(0), [0, 10, 20, 30]);
+    assert_eq!(editor.screen_rows(0), 4);
+    assert_eq!(editor.wrap_starts(1), [0]);
+    assert_eq!(editor.screen_rows(2), 1);
+}
+
+#[test]
+fn a_wide_char_that_does_not_fit_moves_to_the_next_row() {
+    let editor = wrapped("abc日本x\n", 4, 10);
+
+    assert_eq!(editor.wrap_starts(0), [0, 3, 7]);
+}
+
+#[test]
+fn nowrap_keeps_every_line_on_one_row() {
+    let mut editor = wrapped("0123456789abcdefghij\n", 10, 10);
+
+    run(&mut editor, "set nowrap");
+    assert!(!editor.options.wrap);
+    assert_eq!(editor.wrap_starts(0), [0]);
+}
+
+#[test]
+fn lines_below_a_wrapped_one_move_down_a_row_per_wrap() {
+    let editor = wrapped("0123456789abcdefghijklmnopqrstu\nnext\n", 10, 10);
+
+    assert_eq!(editor.screen_position(Cursor::new(0, 0)), Some((0, 0)));
+    assert_eq!(editor.screen_position(Cursor::new(0, 15)), Some((1, 5)));
+    assert_eq!(editor.screen_position(Cursor::new(0, 30)), Some((3, 0)));
+    assert_eq!(editor.screen_position(Cursor::new(1, 2)), Some((4, 2)));
+}
+
+#[test]
+fn past_the_end_of_a_row_filled_exactly_is_the_next_row() {
+    let editor = wrapped("0123456789\n", 10, 10);
+
+    assert_eq!(editor.screen_position(Cursor::new(0, 10)), Some((1, 0)));
+}
+
+#[test]
+fn rows_below_the_view_have_no_screen_position() {
+    let editor = wrapped("0123456789abcdefghij\nnext\n", 10, 2);
+
+    assert_eq!(editor.screen_position(Cursor::new(1, 0)), None);
+}
+
+#[test]
+fn scrolling_counts_the_rows_wrapped_lines_take() {
+    let long = "x".repeat(30);
+    let mut editor = wrapped(&format!("{long}\n{long}\nlast\n"), 10, 4);
+    editor.cursor = Cursor::new(2, 0);
+
+    editor.scroll_to_cursor(4);
+    assert_eq!(editor.scroll_offset, 1);
+    assert_eq!(editor.screen_position(editor.cursor), Some((3, 0)));
+}
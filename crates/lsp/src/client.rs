@@ -0,0 +1,228 @@
+use crate::framing::{read_message, write_message};
+use crate::protocol::{
+    accepts_incremental_changes, initialize_params, parse_diagnostics, path_to_uri,
+};
+use crate::server::Server;
+use fluxion_core::Diagnostic;
+use serde_json::{Value, json};
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+/// Id of the `initialize` request, the only request the client sends.
+const INITIALIZE_ID: u64 = 1;
+
+/// What `LspClient::sync_kind` holds before the server answers `initialize`.
+const NOT_READY: u8 = 0;
+const FULL_SYNC: u8 = 1;
+const INCREMENTAL_SYNC: u8 = 2;
+
+/// Something a language server told the editor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LspEvent {
+    /// The latest diagnostics for the file at `path`, replacing any before.
+    Diagnostics {
+        path: PathBuf,
+        diagnostics: Vec<Diagnostic>,
+    },
+    /// The server started with `command` exited or stopped making sense.
+    Exited { command: &'static str },
+}
+
+/// A running language server, spoken to over its stdin and stdout. Writing
+/// and reading happen on threads of their own, so no call here blocks;
+/// what the server says arrives on the `events` channel given to `start`.
+pub struct LspClient {
+    server: Server,
+    child: Child,
+    outgoing: Sender<Value>,
+    /// `NOT_READY` until the server has answered `initialize`, then how it
+    /// wants edits sent.
+    sync_kind: Arc<AtomicU8>,
+}
+
+impl LspClient {
+    /// Starts `server` for the project at `root` and begins the `initialize`
+    /// handshake. Fails if the server can't be run, e.g. isn't installed.
+    pub fn start(server: Server, root: &Path, events: Sender<LspEvent>) -> io::Result<Self> {
+        let mut child = Command::new(server.command)
+            .args(server.args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            let _ = child.kill();
+            return Err(io::Error::other("language server has no stdio"));
+        };
+
+        let (outgoing, queue) = mpsc::channel();
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let sync_kind = Arc::new(AtomicU8::new(NOT_READY));
+        let initialize = json!({
+            "jsonrpc": "2.0",
+            "id": INITIALIZE_ID,
+            "method": "initialize",
+            "params": initialize_params(root),
+        });
+        thread::spawn(move || write_loop(stdin, initialize, ready_rx, queue));
+        let replies = outgoing.clone();
+        let kind = Arc::clone(&sync_kind);
+        thread::spawn(move || read_loop(server, stdout, ready_tx, replies, kind, events));
+
+        Ok(Self {
+            server,
+            child,
+            outgoing,
+            sync_kind,
+        })
+    }
+
+    pub fn server(&self) -> Server {
+        self.server
+    }
+
+    /// Whether the server has finished starting up and takes documents.
+    pub fn is_ready(&self) -> bool {
+        self.sync_kind.load(Ordering::Acquire) != NOT_READY
+    }
+
+    /// Whether the server takes edits as ranges. Otherwise each change has
+    /// to send the whole text.
+    pub fn incremental(&self) -> bool {
+        self.sync_kind.load(Ordering::Acquire) == INCREMENTAL_SYNC
+    }
+
+    pub fn did_open(&self, path: &Path, language_id: &str, version: i32, text: &str) {
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": {
+                    "uri": path_to_uri(path),
+                    "languageId": language_id,
+                    "version": version,
+                    "text": text,
+                },
+            }),
+        );
+    }
+
+    /// Sends `changes`, each built by `content_change` or `full_change`,
+    /// which bring the document up to `version`.
+    pub fn did_change(&self, path: &Path, version: i32, changes: Vec<Value>) {
+        self.notify(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": path_to_uri(path), "version": version },
+                "contentChanges": changes,
+            }),
+        );
+    }
+
+    pub fn did_close(&self, path: &Path) {
+        self.notify(
+            "textDocument/didClose",
+            json!({ "textDocument": { "uri": path_to_uri(path) } }),
+        );
+    }
+
+    fn notify(&self, method: &str, params: Value) {
+        let _ = self.outgoing.send(json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        }));
+    }
+}
+
+impl Drop for LspClient {
+    /// Stops the server. Nothing it holds needs saving, so there is no
+    /// `shutdown` handshake to wait on.
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Sends `initialize`, holds everything else back until the server has
+/// answered it, then sends the queue in order until the client is dropped.
+fn write_loop(stdin: ChildStdin, initialize: Value, ready: Receiver<()>, queue: Receiver<Value>) {
+    let mut writer = BufWriter::new(stdin);
+    if write_message(&mut writer, &initialize).is_err() || ready.recv().is_err() {
+        return;
+    }
+    let initialized = json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} });
+    if write_message(&mut writer, &initialized).is_err() {
+        return;
+    }
+    for message in queue {
+        if write_message(&mut writer, &message).is_err() {
+            return;
+        }
+    }
+}
+
+/// Reads what the server sends until it closes its stdout, passing on
+/// diagnostics and answering its requests.
+fn read_loop(
+    server: Server,
+    stdout: ChildStdout,
+    ready: Sender<()>,
+    replies: Sender<Value>,
+    sync_kind: Arc<AtomicU8>,
+    events: Sender<LspEvent>,
+) {
+    let mut reader = BufReader::new(stdout);
+    loop {
+        let message = match read_message(&mut reader) {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(e) => {
+                tracing::warn!("Bad message from {}: {}", server.command, e);
+                break;
+            }
+        };
+        let method = message["method"].as_str();
+        match (&message["id"], method) {
+            (id, None) if id.as_u64() == Some(INITIALIZE_ID) => {
+                if let Some(error) = message.get("error") {
+                    tracing::warn!("{} failed to initialize: {}", server.command, error);
+                    break;
+                }
+                let kind = if accepts_incremental_changes(&message["result"]) {
+                    INCREMENTAL_SYNC
+                } else {
+                    FULL_SYNC
+                };
+                sync_kind.store(kind, Ordering::Release);
+                let _ = ready.send(());
+            }
+            (_, Some("textDocument/publishDiagnostics")) => {
+                if let Some((path, diagnostics)) = parse_diagnostics(&message["params"]) {
+                    let _ = events.send(LspEvent::Diagnostics { path, diagnostics });
+                }
+            }
+            // A request from the server. None of them need more than an
+            // empty answer; `workspace/configuration` wants one per item.
+            (id, Some(method)) if !id.is_null() => {
+                let result = match method {
+                    "workspace/configuration" => {
+                        let items = message["params"]["items"].as_array().map_or(0, Vec::len);
+                        Value::Array(vec![Value::Null; items])
+                    }
+                    _ => Value::Null,
+                };
+                let _ = replies.send(json!({ "jsonrpc": "2.0", "id": id, "result": result }));
+            }
+            _ => {}
+        }
+    }
+    let _ = events.send(LspEvent::Exited {
+        command: server.command,
+    });
+}
@@ -0,0 +1,146 @@
+use serde_json::Value;
+use std::io::{self, BufRead, Write};
+
+/// Writes `message` the way LSP frames it on stdio: a `Content-Length`
+/// header, a blank line, then the JSON body.
+pub fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n", body.len())?;
+    writer.write_all(&body)?;
+    writer.flush()
+}
+
+/// Reads the next framed message. Returns `Ok(None)` once the stream ends
+/// cleanly between messages. Headers other than `Content-Length` are skipped.
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    let mut header = String::new();
+    loop {
+        header.clear();
+        if reader.read_line(&mut header)? == 0 {
+            return match length {
+                None => Ok(None),
+                Some(_) => Err(io::ErrorKind::UnexpectedEof.into()),
+            };
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            let value = value.trim().parse().map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "bad Content-Length header")
+            })?;
+            length = Some(value);
+        }
+    }
+
+    let Some(length) = length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "message without a Content-Length header",
+        ));
+    };
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::{BufReader, Read};
+
+    /// A reader that hands out at most `chunk` bytes per read, the way a pipe
+    /// may split a message.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.chunk.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    fn read_all(data: &[u8], chunk: usize) -> io::Result<Vec<Value>> {
+        let mut reader = BufReader::with_capacity(chunk, Trickle { data, chunk });
+        let mut messages = Vec::new();
+        while let Some(message) = read_message(&mut reader)? {
+            messages.push(message);
+        }
+        Ok(messages)
+    }
+
+    #[test]
+    fn writes_a_header_then_the_body() {
+        let mut out = Vec::new();
+        write_message(&mut out, &json!({"id": 1})).expect("written");
+        assert_eq!(out, b"Content-Length: 8\r\n\r\n{\"id\":1}");
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let messages = [json!({"id": 1, "method": "a"}), json!({"text": "é\n"})];
+        let mut out = Vec::new();
+        for message in &messages {
+            write_message(&mut out, message).expect("written");
+        }
+        assert_eq!(read_all(&out, 4096).expect("read"), messages);
+    }
+
+    #[test]
+    fn messages_split_across_reads_are_put_together() {
+        let mut out = Vec::new();
+        write_message(&mut out, &json!({"params": {"uri": "file:///a.rs"}})).expect("written");
+        write_message(&mut out, &json!([1, 2, 3])).expect("written");
+        for chunk in [1, 3, 7] {
+            assert_eq!(
+                read_all(&out, chunk).expect("read"),
+                [json!({"params": {"uri": "file:///a.rs"}}), json!([1, 2, 3])],
+                "{} byte reads",
+                chunk
+            );
+        }
+    }
+
+    #[test]
+    fn other_headers_are_skipped() {
+        let data = b"Content-Type: application/vscode-jsonrpc\r\ncontent-length: 2\r\n\r\n{}";
+        assert_eq!(read_all(data, 4096).expect("read"), [json!({})]);
+    }
+
+    #[test]
+    fn a_missing_length_is_invalid_data() {
+        let error = read_all(b"Content-Type: x\r\n\r\n{}", 4096).expect_err("no length");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let error = read_all(b"Content-Length: two\r\n\r\n{}", 4096).expect_err("bad length");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn an_invalid_body_is_invalid_data() {
+        let error = read_all(b"Content-Length: 5\r\n\r\n{oops", 4096).expect_err("bad json");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn a_stream_cut_off_mid_message_is_unexpected_eof() {
+        let error = read_all(b"Content-Length: 10\r\n\r\n{}", 4096).expect_err("short body");
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        let error = read_all(b"Content-Length: 10\r\n", 4096).expect_err("no body");
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn an_empty_stream_has_no_messages() {
+        assert!(read_all(b"", 4096).expect("read").is_empty());
+    }
+}
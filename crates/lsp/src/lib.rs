@@ -0,0 +1,9 @@
+mod client;
+mod framing;
+mod protocol;
+mod server;
+
+pub use client::{LspClient, LspEvent};
+pub use framing::{read_message, write_message};
+pub use protocol::{content_change, full_change, path_to_uri, uri_to_path};
+pub use server::{Server, server_for};
@@ -0,0 +1,240 @@
+use fluxion_core::{Cursor, Diagnostic, EditEvent, Severity};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+
+/// Parameters of the `initialize` request. Positions are asked for in
+/// chars (`utf-32`), which is how the editor counts columns; a server that
+/// only speaks UTF-16 will be off past chars outside the BMP.
+pub fn initialize_params(root: &Path) -> Value {
+    json!({
+        "processId": std::process::id(),
+        "rootUri": path_to_uri(root),
+        "capabilities": {
+            "general": { "positionEncodings": ["utf-32", "utf-16"] },
+            "textDocument": {
+                "synchronization": { "dynamicRegistration": false },
+                "publishDiagnostics": { "relatedInformation": false },
+            },
+        },
+    })
+}
+
+/// Whether the server in an `initialize` result takes edits as ranges
+/// rather than the whole text each time.
+pub fn accepts_incremental_changes(result: &Value) -> bool {
+    let sync = &result["capabilities"]["textDocumentSync"];
+    let kind = sync.get("change").unwrap_or(sync);
+    kind.as_u64() == Some(2)
+}
+
+fn position(cursor: Cursor) -> Value {
+    json!({ "line": cursor.row, "character": cursor.col })
+}
+
+/// The content change that describes an insert or delete, or `None` for a
+/// reset, which needs the whole text sent instead.
+pub fn content_change(event: &EditEvent) -> Option<Value> {
+    match event {
+        EditEvent::Insert {
+            position: at, text, ..
+        } => Some(json!({
+            "range": { "start": position(*at), "end": position(*at) },
+            "text": text,
+        })),
+        EditEvent::Delete {
+            position: at, text, ..
+        } => {
+            let end = match text.rsplit_once('\n') {
+                Some((before, after)) => Cursor::new(
+                    at.row + before.matches('\n').count() + 1,
+                    after.chars().count(),
+                ),
+                None => Cursor::new(at.row, at.col + text.chars().count()),
+            };
+            Some(json!({
+                "range": { "start": position(*at), "end": position(end) },
+                "text": "",
+            }))
+        }
+        EditEvent::Reset { .. } => None,
+    }
+}
+
+/// A content change that replaces the whole text.
+pub fn full_change(text: &str) -> Value {
+    json!({ "text": text })
+}
+
+/// The file and diagnostics of a `textDocument/publishDiagnostics`
+/// notification.
+pub fn parse_diagnostics(params: &Value) -> Option<(PathBuf, Vec<Diagnostic>)> {
+    let path = uri_to_path(params["uri"].as_str()?)?;
+    let diagnostics = params["diagnostics"]
+        .as_array()?
+        .iter()
+        .filter_map(parse_diagnostic)
+        .collect();
+    Some((path, diagnostics))
+}
+
+fn parse_diagnostic(diagnostic: &Value) -> Option<Diagnostic> {
+    let parse_position = |position: &Value| {
+        Some(Cursor::new(
+            position["line"].as_u64()? as usize,
+            position["character"].as_u64()? as usize,
+        ))
+    };
+    let severity = match diagnostic["severity"].as_u64() {
+        Some(2) => Severity::Warning,
+        Some(3) => Severity::Information,
+        Some(4) => Severity::Hint,
+        _ => Severity::Error,
+    };
+    Some(Diagnostic {
+        start: parse_position(&diagnostic["range"]["start"])?,
+        end: parse_position(&diagnostic["range"]["end"])?,
+        severity,
+        message: diagnostic["message"].as_str()?.to_string(),
+    })
+}
+
+/// The `file://` URI of `path`, which should be absolute.
+pub fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// The path a `file://` URI names, or `None` for other schemes.
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        let escaped = (encoded[i] == b'%')
+            .then(|| std::str::from_utf8(encoded.get(i + 1..i + 3)?).ok())
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                bytes.push(byte);
+                i += 3;
+            }
+            None => {
+                bytes.push(encoded[i]);
+                i += 1;
+            }
+        }
+    }
+    Some(PathBuf::from(String::from_utf8(bytes).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_insert_is_an_empty_range_with_text() {
+        let event = EditEvent::Insert {
+            buffer_id: 0,
+            char_idx: 7,
+            position: Cursor::new(1, 3),
+            text: "ab\nc".to_string(),
+        };
+        assert_eq!(
+            content_change(&event),
+            Some(json!({
+                "range": {
+                    "start": { "line": 1, "character": 3 },
+                    "end": { "line": 1, "character": 3 },
+                },
+                "text": "ab\nc",
+            }))
+        );
+    }
+
+    #[test]
+    fn a_delete_ends_where_its_text_does() {
+        let delete = |position, text: &str| EditEvent::Delete {
+            buffer_id: 0,
+            range: 0..text.chars().count(),
+            position,
+            text: text.to_string(),
+        };
+        let range = |change: Option<Value>| change.map(|change| change["range"].clone());
+        assert_eq!(
+            range(content_change(&delete(Cursor::new(2, 1), "éx"))),
+            Some(json!({
+                "start": { "line": 2, "character": 1 },
+                "end": { "line": 2, "character": 3 },
+            }))
+        );
+        assert_eq!(
+            range(content_change(&delete(Cursor::new(2, 1), "a\nb\ncd"))),
+            Some(json!({
+                "start": { "line": 2, "character": 1 },
+                "end": { "line": 4, "character": 2 },
+            }))
+        );
+    }
+
+    #[test]
+    fn a_reset_needs_the_full_text() {
+        assert_eq!(content_change(&EditEvent::Reset { buffer_id: 0 }), None);
+        assert_eq!(full_change("x\n"), json!({ "text": "x\n" }));
+    }
+
+    #[test]
+    fn incremental_sync_is_read_from_either_form() {
+        let result = |sync: Value| json!({ "capabilities": { "textDocumentSync": sync } });
+        assert!(accepts_incremental_changes(&result(json!(2))));
+        assert!(accepts_incremental_changes(&result(json!({ "change": 2 }))));
+        assert!(!accepts_incremental_changes(&result(json!(1))));
+        assert!(!accepts_incremental_changes(&json!({})));
+    }
+
+    #[test]
+    fn diagnostics_are_parsed_with_their_severity() {
+        let params = json!({
+            "uri": "file:///src/main.rs",
+            "diagnostics": [
+                {
+                    "range": {
+                        "start": { "line": 0, "character": 4 },
+                        "end": { "line": 0, "character": 8 },
+                    },
+                    "severity": 2,
+                    "message": "unused",
+                },
+                { "message": "no range" },
+            ],
+        });
+        let (path, diagnostics) = parse_diagnostics(&params).expect("parsed");
+        assert_eq!(path, PathBuf::from("/src/main.rs"));
+        assert_eq!(
+            diagnostics,
+            [Diagnostic {
+                start: Cursor::new(0, 4),
+                end: Cursor::new(0, 8),
+                severity: Severity::Warning,
+                message: "unused".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn uris_escape_and_unescape_paths() {
+        let path = Path::new("/home/me/my file#1.rs");
+        let uri = path_to_uri(path);
+        assert_eq!(uri, "file:///home/me/my%20file%231.rs");
+        assert_eq!(uri_to_path(&uri).as_deref(), Some(path));
+        assert_eq!(uri_to_path("https://example.com"), None);
+    }
+}
@@ -0,0 +1,21 @@
+/// A language server the editor knows how to start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Server {
+    pub command: &'static str,
+    pub args: &'static [&'static str],
+}
+
+/// The server for buffers of `filetype`, if there is a usual one.
+pub fn server_for(filetype: &str) -> Option<Server> {
+    let (command, args): (_, &[_]) = match filetype {
+        "rust" => ("rust-analyzer", &[]),
+        "python" => ("pylsp", &[]),
+        "c" | "cpp" => ("clangd", &[]),
+        "go" => ("gopls", &[]),
+        "javascript" | "typescript" => ("typescript-language-server", &["--stdio"]),
+        "lua" => ("lua-language-server", &[]),
+        "zig" => ("zls", &[]),
+        _ => return None,
+    };
+    Some(Server { command, args })
+}
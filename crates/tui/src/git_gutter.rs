@@ -0,0 +1,120 @@
+use fluxion_core::{EditEvent, EditObserver, Editor, LineChange};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long typing has to pause before an edited buffer is diffed again.
+const DIFF_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Finished diffs: buffer id, the generation asked for, and the changes.
+type DiffResult = (usize, u64, Option<BTreeMap<usize, LineChange>>);
+
+/// Notes which buffers were edited since `GitGutter::update` last looked.
+struct EditedBuffers(Rc<RefCell<HashSet<usize>>>);
+
+impl EditObserver for EditedBuffers {
+    fn on_edit(&mut self, event: &EditEvent) {
+        let (EditEvent::Insert { buffer_id, .. }
+        | EditEvent::Delete { buffer_id, .. }
+        | EditEvent::Reset { buffer_id }) = event;
+        self.0.borrow_mut().insert(*buffer_id);
+    }
+}
+
+/// Keeps each buffer's `line_changes` up to date by diffing it against git's
+/// `HEAD` on blocking tasks: when it's opened or saved, and once typing
+/// pauses after an edit.
+pub(crate) struct GitGutter {
+    edited: Rc<RefCell<HashSet<usize>>>,
+    /// When each buffer is next to be diffed.
+    due: HashMap<usize, Instant>,
+    /// Modification time of each buffer's file when it was last diffed, to
+    /// notice opens, saves and reloads.
+    diffed: HashMap<usize, Option<SystemTime>>,
+    /// Latest diff asked for per buffer; results of older ones are dropped.
+    generations: HashMap<usize, u64>,
+    tx: Sender<DiffResult>,
+    rx: Receiver<DiffResult>,
+}
+
+impl GitGutter {
+    pub(crate) fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            edited: Rc::new(RefCell::new(HashSet::new())),
+            due: HashMap::new(),
+            diffed: HashMap::new(),
+            generations: HashMap::new(),
+            tx,
+            rx,
+        }
+    }
+
+    /// Has `editor` report its edits here.
+    pub(crate) fn observe(&self, editor: &mut Editor) {
+        editor.add_observer(Box::new(EditedBuffers(Rc::clone(&self.edited))));
+    }
+
+    /// The soonest a scheduled diff is due.
+    pub(crate) fn next_due(&self) -> Option<Instant> {
+        self.due.values().min().copied()
+    }
+
+    /// Schedules and starts diffs, and stores the ones that finished.
+    /// Returns whether any buffer's markers changed.
+    pub(crate) fn update(&mut self, editor: &mut Editor) -> bool {
+        let now = Instant::now();
+        for id in self.edited.borrow_mut().drain() {
+            self.due.insert(id, now + DIFF_DEBOUNCE);
+        }
+        let buffers = editor.buffer_manager.iter();
+        let live: HashSet<usize> = buffers.clone().map(|b| b.id).collect();
+        self.diffed.retain(|id, _| live.contains(id));
+        self.due.retain(|id, _| live.contains(id));
+        for buffer in buffers.filter(|b| b.path.is_some() && !b.loading) {
+            if self.diffed.get(&buffer.id) != Some(&buffer.modified_time) {
+                self.diffed.insert(buffer.id, buffer.modified_time);
+                self.due.insert(buffer.id, now);
+            }
+        }
+
+        let ready: Vec<usize> = self
+            .due
+            .iter()
+            .filter(|(_, due)| **due <= now)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in ready {
+            self.due.remove(&id);
+            let Some(buffer) = editor.buffer_manager.iter().find(|b| b.id == id) else {
+                continue;
+            };
+            let Some(path) = buffer.path.clone() else {
+                continue;
+            };
+            let text = buffer.text.clone();
+            let generation = self.generations.entry(id).or_default();
+            *generation += 1;
+            let generation = *generation;
+            let tx = self.tx.clone();
+            tokio::task::spawn_blocking(move || {
+                let changes = fluxion_core::line_changes(&path, &text.to_string());
+                let _ = tx.send((id, generation, changes));
+            });
+        }
+
+        let mut changed = false;
+        while let Ok((id, generation, changes)) = self.rx.try_recv() {
+            if self.generations.get(&id) != Some(&generation) {
+                continue;
+            }
+            if let Some(buffer) = editor.buffer_manager.get_mut(id) {
+                buffer.line_changes = changes.unwrap_or_default();
+                changed = true;
+            }
+        }
+        changed
+    }
+}
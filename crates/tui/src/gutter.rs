@@ -0,0 +1,64 @@
+/// Shown in the gutter on the extra rows of a wrapped line.
+pub(crate) const WRAP_MARKER: &str = "↪";
+
+/// Blank columns between the line numbers and the text.
+pub(crate) const GUTTER_PADDING: u16 = 2;
+
+/// Columns the line number gutter needs for a buffer of `line_count` lines:
+/// the digits of the largest number, at least four, plus the padding.
+pub(crate) fn gutter_width(line_count: usize) -> u16 {
+    let digits = line_count.max(1).ilog10() as u16 + 1;
+    digits.max(4) + GUTTER_PADDING
+}
+
+/// The screen x of the ruler for the 1-based `column`, given where the text
+/// starts on screen and how far it is scrolled sideways, or `None` when the
+/// column is scrolled off either edge.
+pub(crate) fn color_column_x(
+    column: usize,
+    text_x: u16,
+    left_col: usize,
+    text_width: u16,
+) -> Option<u16> {
+    let offset = column.checked_sub(left_col + 1)?;
+    (offset < text_width as usize).then(|| text_x + offset as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_files_get_at_least_four_digits() {
+        assert_eq!(gutter_width(0), 4 + GUTTER_PADDING);
+        assert_eq!(gutter_width(9), 4 + GUTTER_PADDING);
+        assert_eq!(gutter_width(99), 4 + GUTTER_PADDING);
+        assert_eq!(gutter_width(1000), 4 + GUTTER_PADDING);
+    }
+
+    #[test]
+    fn the_gutter_grows_with_the_largest_line_number() {
+        assert_eq!(gutter_width(9999), 4 + GUTTER_PADDING);
+        assert_eq!(gutter_width(10000), 5 + GUTTER_PADDING);
+        assert_eq!(gutter_width(100000), 6 + GUTTER_PADDING);
+    }
+
+    #[test]
+    fn the_ruler_sits_after_the_gutter() {
+        assert_eq!(color_column_x(1, 6, 0, 80), Some(6));
+        assert_eq!(color_column_x(80, 6, 0, 80), Some(85));
+    }
+
+    #[test]
+    fn the_ruler_moves_with_horizontal_scrolling() {
+        assert_eq!(color_column_x(80, 6, 10, 80), Some(75));
+        assert_eq!(color_column_x(80, 0, 79, 80), Some(0));
+    }
+
+    #[test]
+    fn an_off_screen_ruler_is_not_drawn() {
+        assert_eq!(color_column_x(80, 6, 80, 80), None);
+        assert_eq!(color_column_x(81, 6, 0, 80), None);
+        assert_eq!(color_column_x(0, 6, 0, 80), None);
+    }
+}
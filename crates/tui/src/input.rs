@@ -0,0 +1,276 @@
+use crate::keybindings::{Key, KeyMatch, KeySequence, Keybindings};
+use crate::notation;
+use crossterm::event::KeyCode;
+use fluxion_core::{Action, BufferKind, Editor, Mode};
+use std::time::{Duration, Instant};
+
+/// How long to wait for the rest of a multi-key sequence such as `gg`.
+pub(crate) const KEY_SEQUENCE_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// How deeply macros may replay other macros, which stops `@a` inside `qa`
+/// from recursing forever.
+const MAX_MACRO_DEPTH: usize = 20;
+
+/// Turns key presses into editor actions through the keybindings, holding
+/// the keys of a sequence still being typed. Needs no terminal.
+pub(crate) struct KeyInput {
+    keybindings: Keybindings,
+    /// Keys of a multi-key sequence typed so far, such as the first `g` of `gg`.
+    pending_keys: Vec<Key>,
+    pub(crate) pending_since: Option<Instant>,
+    /// Register of the last macro played, replayed again by `@@`.
+    last_macro: Option<char>,
+}
+
+impl KeyInput {
+    pub fn new(keybindings: Keybindings) -> Self {
+        Self {
+            keybindings,
+            pending_keys: Vec::new(),
+            pending_since: None,
+            last_macro: None,
+        }
+    }
+
+    /// Maps a key press and runs the resulting action, recording the key if a
+    /// macro is being recorded. `depth` counts the macros being replayed;
+    /// keys they feed in are not recorded again. Returns the action the key
+    /// resolved to, `NoOp` when it only started a sequence or has no binding.
+    pub fn handle_key(&mut self, key: Key, editor: &mut Editor, depth: usize) -> Action {
+        let action = self.map_key_to_action(key, editor);
+        if depth == 0
+            && action != Action::StopMacroRecording
+            && let Some(keys) = notation::key_to_notation(key)
+        {
+            editor.record_macro_keys(&keys);
+        }
+        match action {
+            Action::PlayMacro(name) => self.play_macro(name, editor, depth),
+            ref action => editor.handle_action(action.clone()),
+        }
+        action
+    }
+
+    /// Feeds the keys stored in register `name` back through the keybindings,
+    /// once per count. `@` replays the last macro played.
+    fn play_macro(&mut self, name: char, editor: &mut Editor, depth: usize) {
+        let count = editor.pending_count.take().unwrap_or(1);
+        let Some(name) = (if name == '@' {
+            self.last_macro
+        } else {
+            Some(name)
+        }) else {
+            return;
+        };
+        if depth >= MAX_MACRO_DEPTH {
+            return;
+        }
+        let Some(register) = editor.registers.get(&name) else {
+            return;
+        };
+        self.last_macro = Some(name);
+        let keys = notation::parse_notation(&register.text);
+        for _ in 0..count {
+            for &key in &keys {
+                self.handle_key(key, editor, depth + 1);
+                if editor.should_quit {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Resolves a key press through the mode's keybindings, buffering keys
+    /// while they form the start of a longer sequence.
+    fn map_key_to_action(&mut self, key: Key, editor: &mut Editor) -> Action {
+        // While recording, a lone `q` stops instead of waiting for a register.
+        if editor.mode == Mode::Normal
+            && self.pending_keys.is_empty()
+            && editor.recording_macro().is_some()
+            && key.as_plain_char() == Some('q')
+        {
+            return Action::StopMacroRecording;
+        }
+        if editor.mode == Mode::Normal
+            && self.pending_keys.is_empty()
+            && editor.buffer_manager.current_buffer().kind == BufferKind::Output
+            && let Some(action) = self
+                .keybindings
+                .output
+                .get(&KeySequence::from_keys(vec![key]))
+        {
+            return action.clone();
+        }
+        if editor.mode == Mode::Normal
+            && self.pending_keys.is_empty()
+            && let Some(c) = key.as_plain_char()
+            && let Some(digit) = c.to_digit(10)
+            && (digit != 0 || editor.pending_count.is_some())
+        {
+            return Action::CountDigit(digit as usize);
+        }
+
+        self.pending_keys.push(key);
+        match self.keybindings.lookup(editor.mode, &self.pending_keys) {
+            KeyMatch::Complete(action) => {
+                self.pending_keys.clear();
+                self.pending_since = None;
+                action
+            }
+            KeyMatch::Partial(_) => {
+                self.pending_since = Some(Instant::now());
+                Action::NoOp
+            }
+            KeyMatch::None => {
+                let keys = std::mem::take(&mut self.pending_keys);
+                self.pending_since = None;
+                if keys.len() > 1 {
+                    Action::CancelKeySequence
+                } else {
+                    self.map_unbound_key(key, editor)
+                }
+            }
+        }
+    }
+
+    /// Keys with no binding of their own: text entry in the typing modes.
+    /// Unbound Ctrl and Alt chords type nothing.
+    fn map_unbound_key(&self, key: Key, editor: &Editor) -> Action {
+        match (editor.mode, key.as_plain_char()) {
+            (Mode::Insert | Mode::Replace, Some(c)) => Action::Insert(c),
+            (Mode::Command | Mode::Search | Mode::SaveDialog, Some(c)) => {
+                Action::InsertIntoCommand(c)
+            }
+            _ => Action::NoOp,
+        }
+    }
+
+    /// Gives up on a pending key sequence once `KEY_SEQUENCE_TIMEOUT` passes,
+    /// running the keys typed so far if they are a binding on their own.
+    pub fn expire_key_sequence(&mut self, editor: &Editor) -> Option<Action> {
+        let since = self.pending_since?;
+        if since.elapsed() < KEY_SEQUENCE_TIMEOUT {
+            return None;
+        }
+
+        self.pending_since = None;
+        let keys = std::mem::take(&mut self.pending_keys);
+        match self.keybindings.lookup(editor.mode, &keys) {
+            KeyMatch::Complete(action) | KeyMatch::Partial(Some(action)) => Some(action),
+            KeyMatch::Partial(None) | KeyMatch::None => Some(Action::CancelKeySequence),
+        }
+    }
+}
+
+/// Types `keys` into `editor` through the default keybindings, as if they
+/// were pressed one by one. Keys are written the way macros keep them, e.g.
+/// `"dd"` or `"ihi<Esc>"`; a raw `\x1b` is Esc too, and `\n` or `\r` Enter.
+///
+/// It lives here rather than on `Editor` because keys only become actions
+/// through the keybindings, which belong to this crate; core knows nothing
+/// of keys and can't depend on tui without a cycle.
+pub fn apply_keys(editor: &mut Editor, keys: &str) {
+    let mut input = KeyInput::new(Keybindings::default_vim());
+    for mut key in notation::parse_notation(keys) {
+        key.code = match key.code {
+            KeyCode::Char('\x1b') => KeyCode::Esc,
+            KeyCode::Char('\n' | '\r') => KeyCode::Enter,
+            code => code,
+        };
+        input.handle_key(key, editor, 0);
+        if editor.should_quit {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(input: &mut KeyInput, editor: &mut Editor, keys: &str) -> Vec<Action> {
+        KeySequence::chars(keys)
+            .keys
+            .into_iter()
+            .map(|key| input.map_key_to_action(key, editor))
+            .collect()
+    }
+
+    #[test]
+    fn a_key_that_does_nothing_resolves_to_no_op() {
+        let mut input = KeyInput::new(Keybindings::default_vim());
+        let mut editor = Editor::new("");
+        assert_eq!(
+            input.handle_key(Key::plain(KeyCode::Char('g')), &mut editor, 0),
+            Action::NoOp
+        );
+        assert_eq!(
+            input.handle_key(Key::plain(KeyCode::Char('g')), &mut editor, 0),
+            Action::GotoFirstLine
+        );
+        assert_eq!(
+            input.handle_key(Key::ctrl('z'), &mut editor, 0),
+            Action::NoOp
+        );
+    }
+
+    #[test]
+    fn typing_on_the_command_line_is_an_action() {
+        let mut input = KeyInput::new(Keybindings::default_vim());
+        let mut editor = Editor::new("");
+        let actions: Vec<Action> = KeySequence::chars(":w")
+            .keys
+            .into_iter()
+            .map(|key| input.handle_key(key, &mut editor, 0))
+            .collect();
+        assert_eq!(
+            actions,
+            [Action::EnterCommandMode, Action::InsertIntoCommand('w')]
+        );
+        assert_eq!(editor.command_input, "w");
+    }
+
+    #[test]
+    fn keys_of_a_sequence_wait_for_the_rest() {
+        let mut input = KeyInput::new(Keybindings::default_vim());
+        let mut editor = Editor::new("");
+        assert_eq!(
+            press(&mut input, &mut editor, "bnj"),
+            [Action::NoOp, Action::NextBuffer, Action::MoveDown]
+        );
+    }
+
+    #[test]
+    fn an_unknown_sequence_is_cancelled() {
+        let mut input = KeyInput::new(Keybindings::default_vim());
+        let mut editor = Editor::new("");
+        assert_eq!(
+            press(&mut input, &mut editor, "bqj"),
+            [Action::NoOp, Action::CancelKeySequence, Action::MoveDown]
+        );
+    }
+
+    #[test]
+    fn an_expired_sequence_runs_its_own_binding() {
+        let mut bindings = Keybindings::default_vim();
+        bindings
+            .normal
+            .insert(KeySequence::chars("b"), Action::MoveLeft);
+        let mut input = KeyInput::new(bindings);
+        let mut editor = Editor::new("");
+        press(&mut input, &mut editor, "b");
+
+        assert_eq!(input.expire_key_sequence(&editor), None);
+        input.pending_since = Instant::now().checked_sub(KEY_SEQUENCE_TIMEOUT);
+        assert_eq!(input.expire_key_sequence(&editor), Some(Action::MoveLeft));
+        assert_eq!(press(&mut input, &mut editor, "j"), [Action::MoveDown]);
+    }
+
+    #[test]
+    fn unbound_chars_are_typed_in_insert_mode() {
+        let mut input = KeyInput::new(Keybindings::default_vim());
+        let mut editor = Editor::new("");
+        editor.mode = Mode::Insert;
+        assert_eq!(press(&mut input, &mut editor, "q"), [Action::Insert('q')]);
+    }
+}
@@ -0,0 +1,216 @@
+use fluxion_core::{Editor, Job, QuickfixEntry};
+use regex::Regex;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Child, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// What the tasks running a job, or reading its stdout and stderr, send back.
+enum JobOutput {
+    /// A line the job printed, or the unfinished end of its output.
+    Text(usize, String),
+    /// A match a `:grep` found.
+    Match(usize, QuickfixEntry),
+    /// One of the job's streams was closed, or a `:grep` ran out of files.
+    Closed(usize),
+}
+
+struct Running {
+    id: usize,
+    /// The shell command's process. A `:grep` runs on a task of its own
+    /// instead.
+    child: Option<Child>,
+    /// Tells a `:grep` to stop searching.
+    cancelled: Arc<AtomicBool>,
+    /// Streams still being read. The job is only finished once all are
+    /// done, so no output arrives after its exit status.
+    open_streams: usize,
+}
+
+/// Runs the jobs the editor queues: shell commands for `:make` and `:!` as
+/// child processes, with their output read on blocking tasks and fed back to
+/// the output buffer a line at a time, and `:grep` searches on a blocking
+/// task that sends matches back as it finds them.
+pub(crate) struct Jobs {
+    running: Vec<Running>,
+    tx: Sender<JobOutput>,
+    rx: Receiver<JobOutput>,
+}
+
+impl Jobs {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            running: Vec::new(),
+            tx,
+            rx,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.running.is_empty()
+    }
+
+    /// Starts the jobs the editor queued, hands it the output that came in
+    /// and reports the jobs that ended. Superseded jobs are killed. Returns
+    /// whether the editor changed.
+    pub fn update(&mut self, editor: &mut Editor) -> bool {
+        let mut changed = false;
+        for (id, job) in editor.take_pending_jobs() {
+            match job {
+                Job::Shell(command) => self.spawn(editor, id, &command),
+                Job::Grep { regex, .. } => self.grep(id, regex),
+            }
+            changed = true;
+        }
+        while let Ok(output) = self.rx.try_recv() {
+            match output {
+                JobOutput::Text(id, text) => {
+                    editor.append_job_output(id, &text);
+                    changed = true;
+                }
+                JobOutput::Match(id, entry) => {
+                    editor.add_grep_match(id, entry);
+                    changed = true;
+                }
+                JobOutput::Closed(id) => {
+                    if let Some(job) = self.running.iter_mut().find(|job| job.id == id) {
+                        job.open_streams -= 1;
+                    }
+                }
+            }
+        }
+
+        let current = editor.current_job();
+        self.running.retain_mut(|job| {
+            if Some(job.id) != current {
+                job.stop();
+                return false;
+            }
+            if job.open_streams > 0 {
+                return true;
+            }
+            let result = match &mut job.child {
+                None => Ok(()),
+                Some(child) => match child.try_wait() {
+                    Ok(None) => return true,
+                    Ok(Some(status)) => fluxion_core::check_status("", Ok(status)),
+                    Err(e) => Err(e.to_string()),
+                },
+            };
+            editor.finish_job(job.id, result);
+            changed = true;
+            false
+        });
+        changed
+    }
+
+    /// Starts `command` as job `id`, reading its stdout and stderr on a
+    /// blocking task each.
+    fn spawn(&mut self, editor: &mut Editor, id: usize, command: &str) {
+        let name = command;
+        let mut command = fluxion_core::shell_command(command);
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        // A group of its own, so whatever the shell starts can be killed too.
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                editor.finish_job(id, fluxion_core::check_status(name, Err(e)));
+                return;
+            }
+        };
+
+        let streams: [Option<Box<dyn Read + Send>>; 2] = [
+            child
+                .stdout
+                .take()
+                .map(|s| Box::new(s) as Box<dyn Read + Send>),
+            child
+                .stderr
+                .take()
+                .map(|s| Box::new(s) as Box<dyn Read + Send>),
+        ];
+        let mut open_streams = 0;
+        for stream in streams.into_iter().flatten() {
+            let tx = self.tx.clone();
+            open_streams += 1;
+            tokio::task::spawn_blocking(move || {
+                let mut reader = BufReader::new(stream);
+                let mut line = Vec::new();
+                while reader.read_until(b'\n', &mut line).is_ok_and(|n| n > 0) {
+                    let text = String::from_utf8_lossy(&line).into_owned();
+                    if tx.send(JobOutput::Text(id, text)).is_err() {
+                        return;
+                    }
+                    line.clear();
+                }
+                let _ = tx.send(JobOutput::Closed(id));
+            });
+        }
+        self.running.push(Running {
+            id,
+            child: Some(child),
+            cancelled: Arc::default(),
+            open_streams,
+        });
+    }
+
+    /// Starts searching the working directory for `regex` as job `id`.
+    fn grep(&mut self, id: usize, regex: Regex) {
+        let tx = self.tx.clone();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let stop = Arc::clone(&cancelled);
+        tokio::task::spawn_blocking(move || {
+            fluxion_core::grep_files(Path::new("."), &regex, |entry| {
+                !stop.load(Ordering::Relaxed) && tx.send(JobOutput::Match(id, entry)).is_ok()
+            });
+            let _ = tx.send(JobOutput::Closed(id));
+        });
+        self.running.push(Running {
+            id,
+            child: None,
+            cancelled,
+            open_streams: 1,
+        });
+    }
+}
+
+impl Running {
+    /// Kills the job's process, or tells its search to stop.
+    fn stop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        if let Some(child) = &mut self.child {
+            kill(child);
+        }
+    }
+}
+
+impl Drop for Jobs {
+    /// Kills jobs still running when the editor quits, which also ends the
+    /// tasks reading their output.
+    fn drop(&mut self) {
+        for job in &mut self.running {
+            job.stop();
+        }
+    }
+}
+
+/// Kills a job's shell and, on Unix, the processes it started, which share
+/// its process group. Otherwise a `cargo build` run through `sh -c` would
+/// keep going and hold the output pipes open.
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    let _ = std::process::Command::new("kill")
+        .args(["-KILL", "--", &format!("-{}", child.id())])
+        .stderr(Stdio::null())
+        .status();
+    let _ = child.kill();
+    let _ = child.wait();
+}
@@ -1,25 +1,103 @@
-use crossterm::event::KeyCode;
-use fluxion_core::Action;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use fluxion_core::{Action, Mode, Operator};
 use std::collections::HashMap;
 
+/// A single key press as bindings see it: the key and any Ctrl or Alt held
+/// with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    pub fn plain(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+
+    pub fn ctrl(c: char) -> Self {
+        Self::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    /// Whether this is a character typed without Ctrl or Alt.
+    pub fn as_plain_char(&self) -> Option<char> {
+        match self.code {
+            KeyCode::Char(c) if self.modifiers.is_empty() => Some(c),
+            _ => None,
+        }
+    }
+}
+
+impl From<KeyEvent> for Key {
+    /// Keeps only Ctrl and Alt. Shift is already part of the typed char.
+    fn from(event: KeyEvent) -> Self {
+        let modifiers = event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        Self::new(event.code, modifiers)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct KeySequence {
-    pub keys: Vec<KeyCode>,
+    pub keys: Vec<Key>,
 }
 
 impl KeySequence {
+    /// Builds a sequence of unmodified keys.
     pub fn new(keys: Vec<KeyCode>) -> Self {
+        Self::from_keys(keys.into_iter().map(Key::plain).collect())
+    }
+
+    pub fn from_keys(keys: Vec<Key>) -> Self {
         Self { keys }
     }
+
+    /// Builds a sequence of plain character keys, e.g. `"gg"`.
+    pub fn chars(keys: &str) -> Self {
+        Self::new(keys.chars().map(KeyCode::Char).collect())
+    }
+
+    /// A single Ctrl-modified character key, e.g. `Ctrl-r`.
+    pub fn ctrl(c: char) -> Self {
+        Self::from_keys(vec![Key::ctrl(c)])
+    }
+}
+
+/// Builds an action from the character typed after a prefix such as `"`.
+pub type CharArgAction = fn(char) -> Action;
+
+/// Result of looking up the keys typed so far in a mode's bindings.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyMatch {
+    /// The keys form a complete binding.
+    Complete(Action),
+    /// The keys are the start of a longer binding. Carries the action to run
+    /// if the sequence times out while it is also a binding on its own.
+    Partial(Option<Action>),
+    /// No binding starts with these keys.
+    None,
 }
 
 pub struct Keybindings {
     pub normal: HashMap<KeySequence, Action>,
+    /// Single keys that act differently in normal mode in the output buffer,
+    /// where they walk the quickfix list.
+    pub output: HashMap<KeySequence, Action>,
+    /// Normal mode prefixes that take any following character as an argument,
+    /// such as `"a` to select register `a`.
+    pub normal_char_args: HashMap<KeySequence, CharArgAction>,
     pub insert: HashMap<KeySequence, Action>,
+    pub replace: HashMap<KeySequence, Action>,
     pub visual: HashMap<KeySequence, Action>,
+    pub visual_char_args: HashMap<KeySequence, CharArgAction>,
     pub command: HashMap<KeySequence, Action>,
+    pub search: HashMap<KeySequence, Action>,
     pub save_dialog: HashMap<KeySequence, Action>,
     pub file_picker: HashMap<KeySequence, Action>,
+    pub buffer_list: HashMap<KeySequence, Action>,
 }
 
 impl Keybindings {
@@ -27,67 +105,109 @@ impl Keybindings {
         let mut normal = HashMap::new();
 
         // Single key mappings
-        normal.insert(
-            KeySequence::new(vec![KeyCode::Char(':')]),
-            Action::EnterCommandMode,
-        );
-        normal.insert(KeySequence::new(vec![KeyCode::Char('h')]), Action::MoveLeft);
-        normal.insert(KeySequence::new(vec![KeyCode::Char('j')]), Action::MoveDown);
-        normal.insert(KeySequence::new(vec![KeyCode::Char('k')]), Action::MoveUp);
-        normal.insert(
-            KeySequence::new(vec![KeyCode::Char('l')]),
-            Action::MoveRight,
-        );
-        normal.insert(
-            KeySequence::new(vec![KeyCode::Char('i')]),
-            Action::EnterInsertMode,
-        );
-        normal.insert(
-            KeySequence::new(vec![KeyCode::Char('v')]),
-            Action::EnterVisualMode,
-        );
+        normal.insert(KeySequence::chars(":"), Action::EnterCommandMode);
+        normal.insert(KeySequence::chars("/"), Action::EnterSearchMode);
+        normal.insert(KeySequence::chars("n"), Action::SearchNext);
+        normal.insert(KeySequence::chars("N"), Action::SearchPrev);
+        normal.insert(KeySequence::chars("h"), Action::MoveLeft);
+        normal.insert(KeySequence::chars("j"), Action::MoveDown);
+        normal.insert(KeySequence::chars("k"), Action::MoveUp);
+        normal.insert(KeySequence::chars("l"), Action::MoveRight);
+        normal.insert(KeySequence::chars("w"), Action::MoveWordForward);
+        normal.insert(KeySequence::chars("0"), Action::MoveLineStart);
+        normal.insert(KeySequence::chars("$"), Action::MoveLineEnd);
+        normal.insert(KeySequence::chars("^"), Action::MoveFirstNonBlank);
+        normal.insert(KeySequence::chars("G"), Action::GotoLastLine);
+        normal.insert(KeySequence::chars("%"), Action::MatchBracket);
+        normal.insert(KeySequence::chars(";"), Action::RepeatFind);
+        normal.insert(KeySequence::chars(","), Action::RepeatFindReverse);
+        normal.insert(KeySequence::chars("u"), Action::Undo);
+        normal.insert(KeySequence::chars("."), Action::RepeatLastChange);
+        normal.insert(KeySequence::ctrl('r'), Action::Redo);
+        normal.insert(KeySequence::ctrl('n'), Action::AddCursorAtNextMatch);
+        normal.insert(KeySequence::ctrl('o'), Action::JumpBack);
+        // Terminals send Tab for Ctrl-i.
+        normal.insert(KeySequence::new(vec![KeyCode::Tab]), Action::JumpForward);
+        normal.insert(KeySequence::ctrl('i'), Action::JumpForward);
+        normal.insert(KeySequence::ctrl('d'), Action::HalfPageDown);
+        normal.insert(KeySequence::ctrl('u'), Action::HalfPageUp);
+        normal.insert(KeySequence::ctrl('f'), Action::PageDown);
+        normal.insert(KeySequence::ctrl('b'), Action::PageUp);
+        normal.insert(KeySequence::chars("x"), Action::DeleteCharUnderCursor);
+        normal.insert(KeySequence::chars("J"), Action::JoinLines);
+        normal.insert(KeySequence::chars("p"), Action::PasteAfter);
+        normal.insert(KeySequence::chars("P"), Action::PasteBefore);
+        normal.insert(KeySequence::chars("i"), Action::EnterInsertMode);
+        normal.insert(KeySequence::chars("R"), Action::EnterReplaceMode);
+        normal.insert(KeySequence::chars("a"), Action::AppendAfterCursor);
+        normal.insert(KeySequence::chars("A"), Action::AppendLineEnd);
+        normal.insert(KeySequence::chars("I"), Action::InsertLineStart);
+        normal.insert(KeySequence::chars("o"), Action::OpenLineBelow);
+        normal.insert(KeySequence::chars("O"), Action::OpenLineAbove);
+        normal.insert(KeySequence::chars("v"), Action::EnterVisualMode);
+        normal.insert(KeySequence::chars("V"), Action::EnterVisualLineMode);
+        normal.insert(KeySequence::ctrl('v'), Action::EnterVisualBlockMode);
 
-        // Buffer prefix sequences
+        // Operators, applied to the motion typed after them
+        normal.insert(KeySequence::chars("d"), Action::Operator(Operator::Delete));
+        normal.insert(KeySequence::chars("c"), Action::Operator(Operator::Change));
+        normal.insert(KeySequence::chars("y"), Action::Operator(Operator::Yank));
         normal.insert(
-            KeySequence::new(vec![KeyCode::Char('b'), KeyCode::Char('n')]),
-            Action::NextBuffer,
+            KeySequence::chars("gu"),
+            Action::Operator(Operator::Lowercase),
         );
         normal.insert(
-            KeySequence::new(vec![KeyCode::Char('b'), KeyCode::Char('p')]),
-            Action::PrevBuffer,
+            KeySequence::chars("gU"),
+            Action::Operator(Operator::Uppercase),
         );
         normal.insert(
-            KeySequence::new(vec![KeyCode::Char('b'), KeyCode::Char('x')]),
-            Action::CloseBuffer,
+            KeySequence::chars("g~"),
+            Action::Operator(Operator::ToggleCase),
         );
+        normal.insert(KeySequence::chars("!"), Action::Operator(Operator::Filter));
+        // Esc drops an operator or count typed so far, then extra cursors.
         normal.insert(
-            KeySequence::new(vec![KeyCode::Char('b'), KeyCode::Char('a')]),
-            Action::CloseAllBuffersExcept,
+            KeySequence::new(vec![KeyCode::Esc]),
+            Action::EnterNormalMode,
         );
+        normal.insert(KeySequence::chars("~"), Action::ToggleCaseUnderCursor);
+        normal.insert(KeySequence::chars("gcc"), Action::ToggleComment);
 
-        // Single key buffer switches
-        normal.insert(
-            KeySequence::new(vec![KeyCode::Char('[')]),
-            Action::PrevBuffer,
-        );
+        // Multi key sequences
+        normal.insert(KeySequence::chars("gg"), Action::GotoFirstLine);
         normal.insert(
-            KeySequence::new(vec![KeyCode::Char(']')]),
-            Action::NextBuffer,
+            KeySequence::from_keys(vec![Key::plain(KeyCode::Char('g')), Key::ctrl('g')]),
+            Action::ShowStats,
         );
+        normal.insert(KeySequence::chars("C"), Action::ChangeToLineEnd);
+        normal.insert(KeySequence::chars(">>"), Action::IndentLine);
+        normal.insert(KeySequence::chars("<<"), Action::DedentLine);
 
-        for i in 0..=9 {
-            let c = char::from_digit(i, 10).unwrap();
-            normal.insert(
-                KeySequence::new(vec![KeyCode::Char(c)]),
-                Action::SwitchBuffer(i as usize),
-            );
-        }
+        // Buffer prefix sequences
+        normal.insert(KeySequence::chars("bn"), Action::NextBuffer);
+        normal.insert(KeySequence::chars("bp"), Action::PrevBuffer);
+        normal.insert(KeySequence::chars("bx"), Action::CloseBuffer);
+        normal.insert(KeySequence::chars("ba"), Action::CloseAllBuffersExcept);
+
+        // Single key buffer switches
+        normal.insert(KeySequence::chars("["), Action::PrevBuffer);
+        normal.insert(KeySequence::chars("]"), Action::NextBuffer);
 
         // Leader key sequences (Space = leader)
-        normal.insert(
-            KeySequence::new(vec![KeyCode::Char(' '), KeyCode::Char('f')]),
-            Action::EnterFilePicker,
-        );
+        normal.insert(KeySequence::chars(" f"), Action::EnterFilePicker);
+        normal.insert(KeySequence::chars(" b"), Action::EnterBufferList);
+        normal.insert(KeySequence::chars(" n"), Action::NewBuffer);
+
+        let mut normal_char_args: HashMap<KeySequence, CharArgAction> = HashMap::new();
+        normal_char_args.insert(KeySequence::chars("\""), Action::SelectRegister);
+        normal_char_args.insert(KeySequence::chars("m"), Action::SetMark);
+        normal_char_args.insert(KeySequence::chars("`"), Action::JumpToMark);
+        normal_char_args.insert(KeySequence::chars("q"), Action::RecordMacro);
+        normal_char_args.insert(KeySequence::chars("@"), Action::PlayMacro);
+        normal_char_args.insert(KeySequence::chars("f"), Action::FindChar);
+        normal_char_args.insert(KeySequence::chars("F"), Action::FindCharBackward);
+        normal_char_args.insert(KeySequence::chars("t"), Action::TillChar);
+        normal_char_args.insert(KeySequence::chars("T"), Action::TillCharBackward);
 
         let mut insert = HashMap::new();
         insert.insert(
@@ -96,19 +216,68 @@ impl Keybindings {
         );
         insert.insert(KeySequence::new(vec![KeyCode::Enter]), Action::Insert('\n'));
         insert.insert(KeySequence::new(vec![KeyCode::Backspace]), Action::Delete);
+        insert.insert(KeySequence::new(vec![KeyCode::Tab]), Action::InsertTab);
+
+        // Replace mode edits with the same keys; the editor makes them overtype.
+        let replace = insert.clone();
 
         let mut visual = HashMap::new();
         visual.insert(
             KeySequence::new(vec![KeyCode::Esc]),
             Action::EnterNormalMode,
         );
-        visual.insert(KeySequence::new(vec![KeyCode::Char('h')]), Action::MoveLeft);
-        visual.insert(KeySequence::new(vec![KeyCode::Char('j')]), Action::MoveDown);
-        visual.insert(KeySequence::new(vec![KeyCode::Char('k')]), Action::MoveUp);
+        visual.insert(KeySequence::chars("v"), Action::EnterVisualMode);
+        visual.insert(KeySequence::chars("V"), Action::EnterVisualLineMode);
+        visual.insert(KeySequence::ctrl('v'), Action::EnterVisualBlockMode);
+        visual.insert(KeySequence::chars("h"), Action::MoveLeft);
+        visual.insert(KeySequence::chars("j"), Action::MoveDown);
+        visual.insert(KeySequence::chars("k"), Action::MoveUp);
+        visual.insert(KeySequence::chars("l"), Action::MoveRight);
+        visual.insert(KeySequence::chars("w"), Action::MoveWordForward);
+        visual.insert(KeySequence::chars("0"), Action::MoveLineStart);
+        visual.insert(KeySequence::chars("$"), Action::MoveLineEnd);
+        visual.insert(KeySequence::chars("^"), Action::MoveFirstNonBlank);
+        visual.insert(KeySequence::chars("%"), Action::MatchBracket);
+        visual.insert(KeySequence::ctrl('d'), Action::HalfPageDown);
+        visual.insert(KeySequence::ctrl('u'), Action::HalfPageUp);
+        visual.insert(KeySequence::ctrl('f'), Action::PageDown);
+        visual.insert(KeySequence::ctrl('b'), Action::PageUp);
+        visual.insert(
+            KeySequence::from_keys(vec![Key::plain(KeyCode::Char('g')), Key::ctrl('g')]),
+            Action::ShowStats,
+        );
+        visual.insert(KeySequence::chars(";"), Action::RepeatFind);
+        visual.insert(KeySequence::chars(","), Action::RepeatFindReverse);
+        visual.insert(KeySequence::chars("y"), Action::Yank);
+        visual.insert(KeySequence::chars("d"), Action::DeleteSelection);
+        visual.insert(KeySequence::chars("x"), Action::DeleteSelection);
+        // `:` starts a command on the selected lines, as `:'<,'>`.
+        visual.insert(KeySequence::chars(":"), Action::EnterCommandMode);
+        visual.insert(KeySequence::chars(">"), Action::IndentLine);
+        visual.insert(KeySequence::chars("<"), Action::DedentLine);
         visual.insert(
-            KeySequence::new(vec![KeyCode::Char('l')]),
-            Action::MoveRight,
+            KeySequence::chars("u"),
+            Action::Operator(Operator::Lowercase),
         );
+        visual.insert(
+            KeySequence::chars("U"),
+            Action::Operator(Operator::Uppercase),
+        );
+        visual.insert(
+            KeySequence::chars("~"),
+            Action::Operator(Operator::ToggleCase),
+        );
+        visual.insert(KeySequence::chars("!"), Action::Operator(Operator::Filter));
+        visual.insert(KeySequence::chars("gc"), Action::ToggleComment);
+        // Insert on every row of a block selection.
+        visual.insert(KeySequence::chars("I"), Action::InsertLineStart);
+        visual.insert(KeySequence::chars("A"), Action::AppendLineEnd);
+
+        let mut visual_char_args: HashMap<KeySequence, CharArgAction> = HashMap::new();
+        visual_char_args.insert(KeySequence::chars("f"), Action::FindChar);
+        visual_char_args.insert(KeySequence::chars("F"), Action::FindCharBackward);
+        visual_char_args.insert(KeySequence::chars("t"), Action::TillChar);
+        visual_char_args.insert(KeySequence::chars("T"), Action::TillCharBackward);
 
         let mut command = HashMap::new();
         command.insert(
@@ -123,6 +292,33 @@ impl Keybindings {
             KeySequence::new(vec![KeyCode::Backspace]),
             Action::DeleteFromCommand,
         );
+        command.insert(
+            KeySequence::new(vec![KeyCode::Up]),
+            Action::CommandHistoryPrev,
+        );
+        command.insert(
+            KeySequence::new(vec![KeyCode::Down]),
+            Action::CommandHistoryNext,
+        );
+        command.insert(
+            KeySequence::new(vec![KeyCode::Tab]),
+            Action::CompleteCommand,
+        );
+        command.insert(
+            KeySequence::new(vec![KeyCode::BackTab]),
+            Action::CompleteCommandBack,
+        );
+
+        let mut search = HashMap::new();
+        search.insert(KeySequence::new(vec![KeyCode::Esc]), Action::CancelDialog);
+        search.insert(
+            KeySequence::new(vec![KeyCode::Enter]),
+            Action::ExecuteSearch,
+        );
+        search.insert(
+            KeySequence::new(vec![KeyCode::Backspace]),
+            Action::DeleteFromCommand,
+        );
 
         let mut save_dialog = HashMap::new();
         save_dialog.insert(KeySequence::new(vec![KeyCode::Esc]), Action::CancelDialog);
@@ -134,6 +330,7 @@ impl Keybindings {
             KeySequence::new(vec![KeyCode::Backspace]),
             Action::DeleteFromCommand,
         );
+        save_dialog.insert(KeySequence::new(vec![KeyCode::Tab]), Action::CompletePath);
 
         let mut file_picker = HashMap::new();
         file_picker.insert(KeySequence::new(vec![KeyCode::Esc]), Action::FilePickerEsc);
@@ -141,22 +338,241 @@ impl Keybindings {
             KeySequence::new(vec![KeyCode::Enter]),
             Action::FilePickerEnter,
         );
+        file_picker.insert(KeySequence::chars("j"), Action::FilePickerDown);
+        file_picker.insert(KeySequence::chars("k"), Action::FilePickerUp);
+        file_picker.insert(KeySequence::chars("h"), Action::FilePickerParent);
         file_picker.insert(
-            KeySequence::new(vec![KeyCode::Char('j')]),
-            Action::FilePickerDown,
+            KeySequence::new(vec![KeyCode::Backspace]),
+            Action::FilePickerParent,
         );
-        file_picker.insert(
-            KeySequence::new(vec![KeyCode::Char('k')]),
-            Action::FilePickerUp,
+        file_picker.insert(KeySequence::chars("l"), Action::FilePickerEnter);
+        file_picker.insert(KeySequence::chars("."), Action::FilePickerToggleHidden);
+
+        let mut output = HashMap::new();
+        output.insert(
+            KeySequence::new(vec![KeyCode::Enter]),
+            Action::OpenErrorLocation,
+        );
+        output.insert(KeySequence::ctrl('n'), Action::QuickfixNext);
+        output.insert(KeySequence::ctrl('p'), Action::QuickfixPrev);
+
+        let mut buffer_list = HashMap::new();
+        buffer_list.insert(KeySequence::new(vec![KeyCode::Esc]), Action::CancelDialog);
+        buffer_list.insert(
+            KeySequence::new(vec![KeyCode::Enter]),
+            Action::BufferListSelect,
         );
+        buffer_list.insert(KeySequence::chars("j"), Action::BufferListDown);
+        buffer_list.insert(KeySequence::chars("k"), Action::BufferListUp);
 
         Self {
             normal,
+            output,
+            normal_char_args,
             insert,
+            replace,
             visual,
+            visual_char_args,
             command,
+            search,
             save_dialog,
             file_picker,
+            buffer_list,
+        }
+    }
+
+    /// The key map used while the editor is in `mode`.
+    pub fn map_for(&self, mode: Mode) -> &HashMap<KeySequence, Action> {
+        match mode {
+            Mode::Normal => &self.normal,
+            Mode::Insert => &self.insert,
+            Mode::Replace => &self.replace,
+            Mode::Visual | Mode::VisualLine | Mode::VisualBlock => &self.visual,
+            Mode::Command => &self.command,
+            Mode::Search => &self.search,
+            Mode::SaveDialog => &self.save_dialog,
+            Mode::FilePicker => &self.file_picker,
+            Mode::BufferList => &self.buffer_list,
         }
     }
+
+    fn char_args_for(&self, mode: Mode) -> Option<&HashMap<KeySequence, CharArgAction>> {
+        match mode {
+            Mode::Normal => Some(&self.normal_char_args),
+            Mode::Visual | Mode::VisualLine | Mode::VisualBlock => Some(&self.visual_char_args),
+            _ => None,
+        }
+    }
+
+    /// Resolves the keys typed so far in `mode`.
+    pub fn lookup(&self, mode: Mode, keys: &[Key]) -> KeyMatch {
+        let map = self.map_for(mode);
+        let exact = map.get(&KeySequence::from_keys(keys.to_vec())).cloned();
+        let mut is_prefix = map
+            .keys()
+            .any(|seq| seq.keys.len() > keys.len() && seq.keys.starts_with(keys));
+
+        if let Some(char_args) = self.char_args_for(mode) {
+            if let Some((last, prefix)) = keys.split_last()
+                && let Some(c) = last.as_plain_char()
+                && let Some(to_action) = char_args.get(&KeySequence::from_keys(prefix.to_vec()))
+            {
+                return KeyMatch::Complete(to_action(c));
+            }
+            is_prefix |= char_args.contains_key(&KeySequence::from_keys(keys.to_vec()));
+        }
+
+        match (exact, is_prefix) {
+            (exact, true) => KeyMatch::Partial(exact),
+            (Some(action), false) => KeyMatch::Complete(action),
+            (None, false) => KeyMatch::None,
+        }
+    }
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self::default_vim()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(text: &str) -> Vec<Key> {
+        KeySequence::chars(text).keys
+    }
+
+    #[test]
+    fn default_vim_binds_the_buffer_and_file_picker_actions() {
+        let bindings = Keybindings::default_vim();
+        let bound =
+            |mode: Mode, action: Action| bindings.map_for(mode).values().any(|a| *a == action);
+
+        for action in [
+            Action::CloseBuffer,
+            Action::CloseAllBuffersExcept,
+            Action::EnterFilePicker,
+            Action::EnterBufferList,
+        ] {
+            assert!(bound(Mode::Normal, action.clone()), "{:?}", action);
+        }
+        for action in [
+            Action::FilePickerEsc,
+            Action::FilePickerEnter,
+            Action::FilePickerUp,
+            Action::FilePickerDown,
+        ] {
+            assert!(bound(Mode::FilePicker, action.clone()), "{:?}", action);
+        }
+    }
+
+    #[test]
+    fn every_mode_but_normal_can_be_left_with_esc() {
+        let bindings = Keybindings::default_vim();
+        let esc = [Key::plain(KeyCode::Esc)];
+        for mode in [
+            Mode::Insert,
+            Mode::Replace,
+            Mode::Visual,
+            Mode::VisualLine,
+            Mode::VisualBlock,
+            Mode::Command,
+            Mode::Search,
+            Mode::SaveDialog,
+            Mode::FilePicker,
+            Mode::BufferList,
+        ] {
+            assert!(
+                matches!(bindings.lookup(mode, &esc), KeyMatch::Complete(_)),
+                "{:?}",
+                mode
+            );
+        }
+    }
+
+    #[test]
+    fn single_keys_resolve_at_once() {
+        let bindings = Keybindings::default_vim();
+        assert_eq!(
+            bindings.lookup(Mode::Normal, &keys("j")),
+            KeyMatch::Complete(Action::MoveDown)
+        );
+        assert_eq!(
+            bindings.lookup(Mode::Normal, &[Key::ctrl('r')]),
+            KeyMatch::Complete(Action::Redo)
+        );
+    }
+
+    #[test]
+    fn ctrl_keys_are_bound_apart_from_the_bare_key() {
+        let bindings = Keybindings::default_vim();
+        assert_eq!(
+            bindings.lookup(Mode::Normal, &[Key::ctrl('r')]),
+            KeyMatch::Complete(Action::Redo)
+        );
+        assert_eq!(bindings.lookup(Mode::Normal, &keys("r")), KeyMatch::None);
+        assert_eq!(
+            bindings.lookup(Mode::Normal, &[Key::ctrl('d')]),
+            KeyMatch::Complete(Action::HalfPageDown)
+        );
+        assert_ne!(
+            bindings.lookup(Mode::Normal, &keys("d")),
+            KeyMatch::Complete(Action::HalfPageDown)
+        );
+    }
+
+    #[test]
+    fn a_sequence_is_partial_until_it_is_complete() {
+        let bindings = Keybindings::default_vim();
+        assert_eq!(
+            bindings.lookup(Mode::Normal, &keys("b")),
+            KeyMatch::Partial(None)
+        );
+        assert_eq!(
+            bindings.lookup(Mode::Normal, &keys("bn")),
+            KeyMatch::Complete(Action::NextBuffer)
+        );
+        assert_eq!(bindings.lookup(Mode::Normal, &keys("bq")), KeyMatch::None);
+    }
+
+    #[test]
+    fn a_binding_that_starts_a_longer_one_is_partial_with_its_action() {
+        let mut bindings = Keybindings::default_vim();
+        bindings
+            .normal
+            .insert(KeySequence::chars("b"), Action::MoveLeft);
+        assert_eq!(
+            bindings.lookup(Mode::Normal, &keys("b")),
+            KeyMatch::Partial(Some(Action::MoveLeft))
+        );
+    }
+
+    #[test]
+    fn char_arg_prefixes_take_any_following_char() {
+        let bindings = Keybindings::default_vim();
+        assert_eq!(
+            bindings.lookup(Mode::Normal, &keys("\"")),
+            KeyMatch::Partial(None)
+        );
+        assert_eq!(
+            bindings.lookup(Mode::Normal, &keys("\"a")),
+            KeyMatch::Complete(Action::SelectRegister('a'))
+        );
+        assert_eq!(
+            bindings.lookup(Mode::Normal, &keys("fx")),
+            KeyMatch::Complete(Action::FindChar('x'))
+        );
+    }
+
+    #[test]
+    fn each_mode_has_its_own_map() {
+        let bindings = Keybindings::default_vim();
+        assert_eq!(
+            bindings.lookup(Mode::Visual, &keys("d")),
+            KeyMatch::Complete(Action::DeleteSelection)
+        );
+        assert_eq!(bindings.lookup(Mode::Insert, &keys("j")), KeyMatch::None);
+    }
 }
@@ -1,22 +1,112 @@
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    cursor::SetCursorStyle,
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
-use fluxion_core::{Action, Editor, Mode};
+use fluxion_core::{Action, Editor, LineNumberMode, MessageKind, Mode, Search};
+use git_gutter::GitGutter;
+pub use input::apply_keys;
+use input::{KEY_SEQUENCE_TIMEOUT, KeyInput};
+use jobs::Jobs;
+use keybindings::{Key, Keybindings};
 use ratatui::{
     Terminal,
     backend::CrosstermBackend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, Paragraph},
 };
-use std::{error::Error, io};
+use std::{
+    collections::HashSet,
+    error::Error,
+    io,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant},
+};
+pub use theme::Theme;
+
+mod git_gutter;
+mod gutter;
+mod input;
+mod jobs;
+mod keybindings;
+mod line;
+#[cfg(feature = "lsp")]
+mod lsp;
+mod notation;
+mod status;
+mod theme;
+
+/// How long a status message stays up without a keypress.
+const STATUS_MESSAGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often the loop wakes while files are being read in the background.
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Longest the loop waits for input when there is nothing else to do.
+const IDLE_POLL_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Most quickfix entries the pane along the bottom shows at once.
+const QUICKFIX_ROWS: usize = 8;
+
+/// The cursor shape for `mode`: a bar where typing inserts text, an underline
+/// where it overwrites, and a block elsewhere.
+fn cursor_style_for(mode: Mode) -> SetCursorStyle {
+    match mode {
+        Mode::Insert | Mode::Command | Mode::Search | Mode::SaveDialog => SetCursorStyle::SteadyBar,
+        Mode::Replace => SetCursorStyle::SteadyUnderScore,
+        Mode::Normal
+        | Mode::Visual
+        | Mode::VisualLine
+        | Mode::VisualBlock
+        | Mode::FilePicker
+        | Mode::BufferList => SetCursorStyle::SteadyBlock,
+    }
+}
+
+/// Whether the screen must be drawn again after `event`. A key press needs
+/// it only if it resolved to `action`, something other than `NoOp`; keys
+/// that start a sequence or have no binding change nothing on screen. Mouse
+/// and focus events aren't handled, so they never do.
+fn should_redraw(event: &Event, action: Option<&Action>) -> bool {
+    match event {
+        Event::Key(_) => action.is_some_and(|action| *action != Action::NoOp),
+        Event::Resize(..) => true,
+        Event::FocusGained | Event::FocusLost | Event::Mouse(_) | Event::Paste(_) => false,
+    }
+}
 
 /// Handles the Terminal User Interface
 pub struct Tui {
     terminal: Terminal<CrosstermBackend<io::Stdout>>,
+    input: KeyInput,
+    theme: Theme,
+    /// When the status message on screen was first drawn.
+    message_since: Option<Instant>,
+    /// Cursor shape last sent to the terminal.
+    cursor_style: Option<SetCursorStyle>,
+    /// Set when something on screen may have changed since the last draw.
+    needs_redraw: bool,
+    /// Files still being read on blocking tasks.
+    file_loads: usize,
+    /// File contents read on blocking tasks, keyed by placeholder buffer id.
+    loaded_tx: Sender<(usize, io::Result<Vec<u8>>)>,
+    loaded_rx: Receiver<(usize, io::Result<Vec<u8>>)>,
+    /// When autosave last copied modified buffers to their swap files.
+    last_autosave: Instant,
+    /// Swap files written this session, deleted again on a clean exit.
+    swap_files: HashSet<PathBuf>,
+    /// Swap file writes still running on blocking tasks.
+    swap_writes: usize,
+    swap_tx: Sender<(PathBuf, io::Result<()>)>,
+    swap_rx: Receiver<(PathBuf, io::Result<()>)>,
+    git_gutter: GitGutter,
+    jobs: Jobs,
+    #[cfg(feature = "lsp")]
+    lsp: lsp::Lsp,
 }
 
 impl Tui {
@@ -26,128 +116,205 @@ impl Tui {
         execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
         let backend = CrosstermBackend::new(stdout);
         let terminal = Terminal::new(backend)?;
-        Ok(Self { terminal })
+        let (loaded_tx, loaded_rx) = mpsc::channel();
+        let (swap_tx, swap_rx) = mpsc::channel();
+        Ok(Self {
+            terminal,
+            input: KeyInput::new(Keybindings::default_vim()),
+            theme: Theme::load(),
+            message_since: None,
+            cursor_style: None,
+            needs_redraw: true,
+            file_loads: 0,
+            loaded_tx,
+            loaded_rx,
+            last_autosave: Instant::now(),
+            swap_files: HashSet::new(),
+            swap_writes: 0,
+            swap_tx,
+            swap_rx,
+            git_gutter: GitGutter::new(),
+            jobs: Jobs::new(),
+            #[cfg(feature = "lsp")]
+            lsp: lsp::Lsp::new(),
+        })
     }
 
+    /// Runs the event loop. Must be called from within a Tokio runtime, which
+    /// reads opened files and runs shell commands in the background.
     pub fn run(&mut self, editor: &mut Editor) -> Result<(), Box<dyn Error>> {
+        editor.defer_file_loads = true;
+        editor.defer_jobs = true;
+        self.git_gutter.observe(editor);
+        #[cfg(feature = "lsp")]
+        self.lsp.observe(editor);
         while !editor.should_quit {
-            self.terminal.draw(|f| {
-                Self::render_ui(f, editor);
-            })?;
+            self.spawn_file_loads(editor);
+            while let Ok((id, contents)) = self.loaded_rx.try_recv() {
+                editor.finish_load(id, contents);
+                self.file_loads -= 1;
+                self.needs_redraw = true;
+            }
+            if self.git_gutter.update(editor) {
+                self.needs_redraw = true;
+            }
+            if self.jobs.update(editor) {
+                self.needs_redraw = true;
+            }
+            #[cfg(feature = "lsp")]
+            if self.lsp.sync(editor) {
+                self.needs_redraw = true;
+            }
 
-            if event::poll(std::time::Duration::from_millis(16))?
-                && let Event::Key(key) = event::read()?
-            {
-                let action = self.map_key_to_action(key, editor);
+            if self.needs_redraw {
+                let theme = &self.theme;
+                self.terminal.draw(|f| {
+                    Self::render_ui(f, editor, theme);
+                })?;
+                self.needs_redraw = false;
+                let style = cursor_style_for(editor.mode);
+                if self.cursor_style != Some(style) {
+                    execute!(self.terminal.backend_mut(), style)?;
+                    self.cursor_style = Some(style);
+                }
+            }
+
+            if event::poll(self.poll_timeout())? {
+                let event = event::read()?;
+                let action = match event {
+                    Event::Key(key) => {
+                        self.message_since = None;
+                        Some(self.input.handle_key(Key::from(key), editor, 0))
+                    }
+                    _ => None,
+                };
+                self.needs_redraw |= should_redraw(&event, action.as_ref());
+            } else if let Some(action) = self.input.expire_key_sequence(editor) {
                 editor.handle_action(action);
+                self.needs_redraw = true;
             }
+            self.expire_status_message(editor);
+            self.autosave(editor);
         }
+        self.remove_swap_files(editor);
 
         Ok(())
     }
 
-    fn map_key_to_action(&self, key: event::KeyEvent, editor: &mut Editor) -> Action {
-        match editor.mode {
-            Mode::Normal => self.map_normal_mode(key),
-            Mode::Insert => self.map_insert_mode(key),
-            Mode::Visual => self.map_visual_mode(key),
-            Mode::Command => self.map_command_mode(key, editor),
-            Mode::SaveDialog => self.map_save_dialog_mode(key, editor),
-            Mode::FilePicker => self.map_file_picker_mode(key),
+    /// Every `autosave` seconds, copies the modified buffers to their swap
+    /// files on blocking tasks. A round starts only once the last one is done.
+    fn autosave(&mut self, editor: &Editor) {
+        while let Ok((path, result)) = self.swap_rx.try_recv() {
+            self.finish_swap_write(editor, path, result);
         }
-    }
-
-    fn map_normal_mode(&self, key: event::KeyEvent) -> Action {
-        match key.code {
-            KeyCode::Char(':') => Action::EnterCommandMode,
-            KeyCode::Char('h') => Action::MoveLeft,
-            KeyCode::Char('j') => Action::MoveDown,
-            KeyCode::Char('k') => Action::MoveUp,
-            KeyCode::Char('l') => Action::MoveRight,
-            KeyCode::Char('i') => Action::EnterInsertMode,
-            KeyCode::Char('v') => Action::EnterVisualMode,
-            KeyCode::Char('[') => Action::PrevBuffer,
-            KeyCode::Char(']') => Action::NextBuffer,
-            KeyCode::Char('1') => Action::SwitchBuffer(1),
-            KeyCode::Char('2') => Action::SwitchBuffer(2),
-            KeyCode::Char('3') => Action::SwitchBuffer(3),
-            KeyCode::Char('4') => Action::SwitchBuffer(4),
-            KeyCode::Char('5') => Action::SwitchBuffer(5),
-            KeyCode::Char('6') => Action::SwitchBuffer(6),
-            KeyCode::Char('7') => Action::SwitchBuffer(7),
-            KeyCode::Char('8') => Action::SwitchBuffer(8),
-            KeyCode::Char('9') => Action::SwitchBuffer(9),
-            KeyCode::Char('0') => Action::SwitchBuffer(0),
-            KeyCode::Char(' ') => Action::EnterFilePicker,
-            _ => Action::NoOp,
+        let Some(seconds) = editor.options.autosave else {
+            return;
+        };
+        if self.swap_writes > 0
+            || self.last_autosave.elapsed() < Duration::from_secs(seconds as u64)
+        {
+            return;
+        }
+        self.last_autosave = Instant::now();
+        for (path, text) in editor.buffer_manager.swap_snapshots() {
+            let tx = self.swap_tx.clone();
+            self.swap_writes += 1;
+            tokio::task::spawn_blocking(move || {
+                let result = fluxion_core::write_swap_file(&path, &text);
+                let _ = tx.send((path, result));
+            });
         }
     }
 
-    fn map_insert_mode(&self, key: event::KeyEvent) -> Action {
-        match key.code {
-            KeyCode::Esc => Action::EnterNormalMode,
-            KeyCode::Enter => Action::Insert('\n'),
-            KeyCode::Char(c) => Action::Insert(c),
-            KeyCode::Backspace => Action::Delete,
-            _ => Action::NoOp,
+    /// Notes a finished swap file write. A buffer saved while the write was
+    /// running no longer needs the swap file it left.
+    fn finish_swap_write(&mut self, editor: &Editor, path: PathBuf, result: io::Result<()>) {
+        self.swap_writes -= 1;
+        if let Err(e) = result {
+            tracing::warn!("Failed to write swap file for {}: {}", path.display(), e);
+            return;
+        }
+        let still_dirty = editor
+            .buffer_manager
+            .iter()
+            .any(|b| b.dirty && b.path.as_ref() == Some(&path));
+        if still_dirty {
+            self.swap_files.insert(path);
+        } else {
+            fluxion_core::remove_swap_file(&path);
         }
     }
 
-    fn map_visual_mode(&self, key: event::KeyEvent) -> Action {
-        match key.code {
-            KeyCode::Esc => Action::EnterNormalMode,
-            KeyCode::Char('h') => Action::MoveLeft,
-            KeyCode::Char('j') => Action::MoveDown,
-            KeyCode::Char('k') => Action::MoveUp,
-            KeyCode::Char('l') => Action::MoveRight,
-            _ => Action::NoOp,
+    /// Waits for running swap writes, then deletes the swap files this
+    /// session wrote. Only a crash leaves them behind.
+    fn remove_swap_files(&mut self, editor: &Editor) {
+        while self.swap_writes > 0 {
+            match self.swap_rx.recv() {
+                Ok((path, result)) => self.finish_swap_write(editor, path, result),
+                Err(_) => break,
+            }
+        }
+        for path in self.swap_files.drain() {
+            fluxion_core::remove_swap_file(&path);
         }
     }
 
-    fn map_command_mode(&self, key: event::KeyEvent, editor: &mut Editor) -> Action {
-        match key.code {
-            KeyCode::Esc => Action::EnterNormalMode,
-            KeyCode::Enter => Action::ExecuteCommand,
-            KeyCode::Backspace => Action::DeleteFromCommand,
-            KeyCode::Char(c) => {
-                editor.insert_into_command(c);
-                Action::NoOp
-            }
-            _ => Action::NoOp,
+    /// How long to wait for input: a frame while files are being read or
+    /// shell commands run, otherwise until the next pending key sequence or status message
+    /// expires or git diff is due, up to `IDLE_POLL_TIMEOUT`.
+    fn poll_timeout(&self) -> Duration {
+        if self.file_loads > 0 || self.jobs.is_running() {
+            return FRAME_INTERVAL;
         }
+        let diff = self
+            .git_gutter
+            .next_due()
+            .map(|due| due.saturating_duration_since(Instant::now()));
+        [
+            (self.input.pending_since, KEY_SEQUENCE_TIMEOUT),
+            (self.message_since, STATUS_MESSAGE_TIMEOUT),
+        ]
+        .into_iter()
+        .filter_map(|(since, timeout)| Some(timeout.saturating_sub(since?.elapsed())))
+        .chain(diff)
+        .fold(IDLE_POLL_TIMEOUT, Duration::min)
     }
 
-    fn map_save_dialog_mode(&self, key: event::KeyEvent, editor: &mut Editor) -> Action {
-        match key.code {
-            KeyCode::Esc => Action::CancelDialog,
-            KeyCode::Enter => {
-                let filename = editor.command_input.clone();
-                if !filename.is_empty() {
-                    Action::SaveBufferAs(Some(std::path::PathBuf::from(filename)))
-                } else {
-                    Action::CancelDialog
-                }
-            }
-            KeyCode::Backspace => Action::DeleteFromCommand,
-            KeyCode::Char(c) => {
-                editor.insert_into_command(c);
-                Action::NoOp
-            }
-            _ => Action::NoOp,
+    /// Clears the status message once it has been up for
+    /// `STATUS_MESSAGE_TIMEOUT`. Any keypress restarts the clock.
+    fn expire_status_message(&mut self, editor: &mut Editor) {
+        if editor.status_message.is_none() {
+            self.message_since = None;
+            return;
+        }
+        let since = *self.message_since.get_or_insert_with(Instant::now);
+        if since.elapsed() >= STATUS_MESSAGE_TIMEOUT {
+            editor.status_message = None;
+            self.message_since = None;
+            self.needs_redraw = true;
         }
     }
 
-    fn map_file_picker_mode(&self, key: event::KeyEvent) -> Action {
-        match key.code {
-            KeyCode::Esc => Action::FilePickerEsc,
-            KeyCode::Enter => Action::FilePickerEnter,
-            KeyCode::Char('j') => Action::FilePickerDown,
-            KeyCode::Char('k') => Action::FilePickerUp,
-            _ => Action::NoOp,
+    /// Starts reading the files the editor queued on blocking tasks.
+    fn spawn_file_loads(&mut self, editor: &mut Editor) {
+        for (id, path) in editor.take_pending_loads() {
+            let tx = self.loaded_tx.clone();
+            self.file_loads += 1;
+            tokio::task::spawn_blocking(move || {
+                let _ = tx.send((id, std::fs::read(&path)));
+            });
         }
     }
 
-    fn render_ui(f: &mut ratatui::Frame, editor: &Editor) {
+    fn render_ui(f: &mut ratatui::Frame, editor: &mut Editor, theme: &Theme) {
+        // The status area grows to fit multi-line messages such as `:ls`, up
+        // to half the screen.
+        let message_lines = match &editor.status_message {
+            Some((message, _)) if !Self::is_prompt(editor.mode) => message.lines().count(),
+            _ => 1,
+        };
+        let status_height = (message_lines as u16 + 2).clamp(3, (f.area().height / 2).max(3));
+
         let vertical_chunks = Layout::default()
             .direction(Direction::Vertical)
             .margin(1)
@@ -155,7 +322,7 @@ impl Tui {
                 [
                     Constraint::Length(1),
                     Constraint::Length(2),
-                    Constraint::Length(3),
+                    Constraint::Length(status_height),
                     Constraint::Min(0),
                 ]
                 .as_ref(),
@@ -165,30 +332,51 @@ impl Tui {
         let bufferline_area = vertical_chunks[0];
         let header_area = vertical_chunks[1];
         let status_area = vertical_chunks[2];
-        let main_editor_area = vertical_chunks[3];
+        let mut main_editor_area = vertical_chunks[3];
+
+        let quickfix_len = editor.quickfix.entries().len();
+        if editor.show_quickfix && quickfix_len > 0 {
+            let height =
+                (quickfix_len.min(QUICKFIX_ROWS) as u16 + 2).min(main_editor_area.height / 2);
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(height)])
+                .split(main_editor_area);
+            main_editor_area = chunks[0];
+            Self::render_quickfix(f, editor, theme, chunks[1]);
+        }
 
-        Self::render_bufferline(f, editor, bufferline_area);
-        Self::render_header(f, editor, header_area);
-        Self::render_status(f, editor, status_area);
-        Self::render_main_editor(f, editor, main_editor_area, status_area);
+        Self::render_bufferline(f, editor, theme, bufferline_area);
+        Self::render_header(f, editor, theme, header_area);
+        Self::render_status(f, editor, theme, status_area);
+        Self::render_main_editor(f, editor, theme, main_editor_area, status_area);
 
         if editor.mode == Mode::SaveDialog {
-            Self::render_save_dialog(f, editor, f.area());
+            Self::render_save_dialog(f, editor, theme, f.area());
         }
 
         if editor.mode == Mode::FilePicker {
-            Self::render_file_picker(f, editor, f.area());
+            Self::render_file_picker(f, editor, theme, f.area());
+        }
+
+        if editor.mode == Mode::BufferList {
+            Self::render_buffer_list(f, editor, theme, f.area());
         }
     }
 
-    fn render_header(f: &mut ratatui::Frame, editor: &Editor, area: Rect) {
+    fn render_header(f: &mut ratatui::Frame, editor: &Editor, theme: &Theme, area: Rect) {
         let mode_text = match editor.mode {
             Mode::Normal => "NORMAL",
             Mode::Insert => "INSERT",
+            Mode::Replace => "REPLACE",
             Mode::Visual => "VISUAL",
+            Mode::VisualLine => "VISUAL LINE",
+            Mode::VisualBlock => "VISUAL BLOCK",
             Mode::Command => "COMMAND",
+            Mode::Search => "SEARCH",
             Mode::SaveDialog => "SAVE AS",
             Mode::FilePicker => "FILE PICKER",
+            Mode::BufferList => "BUFFERS",
         };
 
         let title = if editor.is_current_dirty() {
@@ -197,59 +385,101 @@ impl Tui {
             format!("{} - Fluxion", editor.get_current_title())
         };
 
-        let header = Paragraph::new(Line::from(vec![
-            Span::styled(
-                format!(" MODE: {} ", mode_text),
-                Style::default()
-                    .fg(Color::Green)
-                    .add_modifier(Modifier::BOLD),
-            ),
+        let mut spans = vec![
+            Span::styled(format!(" MODE: {} ", mode_text), theme.mode),
             Span::raw(" "),
-            Span::styled(title, Style::default().fg(Color::White)),
-        ]))
-        .block(
+            Span::styled(title, theme.emphasis),
+        ];
+        if let Some(name) = editor.recording_macro() {
+            spans.push(Span::styled(format!("  recording @{}", name), theme.accent));
+        }
+        let header = Paragraph::new(Line::from(spans)).block(
             Block::default()
                 .borders(Borders::ALL)
                 .title("Fluxion Editor")
-                .border_style(Style::default().fg(Color::White)),
+                .border_style(theme.emphasis),
         );
         f.render_widget(header, area);
     }
 
-    fn render_status(f: &mut ratatui::Frame, editor: &Editor, area: Rect) {
+    /// Modes that read a line of input in the status area.
+    fn is_prompt(mode: Mode) -> bool {
+        matches!(mode, Mode::Command | Mode::Search | Mode::SaveDialog)
+    }
+
+    fn render_status(f: &mut ratatui::Frame, editor: &Editor, theme: &Theme, area: Rect) {
         let mode_help = match editor.mode {
-            Mode::Normal => ":cmd i=ins v=vis ]/[/=prev/next Space+f=file",
-            Mode::Insert => "Esc=normal",
-            Mode::Visual => "Esc=normal",
+            Mode::Normal => {
+                ":cmd i=ins v=vis ]/[/=prev/next Space+f=file Space+b=buffers Space+n=new"
+            }
+            Mode::Insert | Mode::Replace => "Esc=normal",
+            Mode::Visual | Mode::VisualLine => "Esc=normal",
+            Mode::VisualBlock => "I/A=insert on each line Esc=normal",
             Mode::Command => "Enter=exec Esc=cancel",
-            Mode::SaveDialog => "Enter=save Esc=cancel",
-            Mode::FilePicker => "Enter=open j/k=navigate Esc=cancel",
+            Mode::Search => "Enter=search Esc=cancel",
+            Mode::SaveDialog => "Enter=save Tab=complete Esc=cancel",
+            Mode::FilePicker => "Enter=open j/k=navigate h=parent .=hidden Esc=cancel",
+            Mode::BufferList => "Enter=switch j/k=navigate Esc=cancel",
         };
 
         let status_text = if editor.mode == Mode::Command {
             format!(":{}", editor.command_input)
+        } else if editor.mode == Mode::Search {
+            format!("/{}", editor.command_input)
         } else if editor.mode == Mode::SaveDialog {
             format!("Save as: {}", editor.command_input)
+        } else if let Some((message, _)) = &editor.status_message {
+            message.clone()
         } else {
             mode_help.to_string()
         };
 
-        let status_area = Paragraph::new(status_text)
-            .style(
-                if editor.mode == Mode::Command || editor.mode == Mode::SaveDialog {
-                    Style::default().fg(Color::Cyan)
-                } else {
-                    Style::default().fg(Color::Yellow)
-                },
+        let is_prompt = Self::is_prompt(editor.mode);
+        let block = Block::default().borders(Borders::ALL);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let info = if is_prompt {
+            String::new()
+        } else {
+            let buffer = editor.buffer_manager.current_buffer();
+            status::file_info(
+                editor.cursor,
+                editor.line_count(),
+                buffer.line_ending,
+                buffer.encoding,
+                buffer.filetype.as_deref(),
             )
-            .alignment(Alignment::Left)
-            .block(Block::default().borders(Borders::ALL));
-        f.render_widget(status_area, area);
+        };
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(0), Constraint::Length(info.len() as u16)])
+            .split(inner);
+
+        let status_area = Paragraph::new(status_text)
+            .style(match &editor.status_message {
+                _ if is_prompt => theme.prompt,
+                Some((_, MessageKind::Error)) => theme.status_error,
+                Some((_, MessageKind::Info)) => theme.status_info,
+                None => theme.status,
+            })
+            .alignment(Alignment::Left);
+        f.render_widget(status_area, chunks[0]);
+        f.render_widget(
+            Paragraph::new(info)
+                .style(theme.muted)
+                .alignment(Alignment::Right),
+            chunks[1],
+        );
     }
 
-    fn render_bufferline(f: &mut ratatui::Frame, editor: &Editor, area: Rect) {
-        let buffers = editor.get_buffers();
-        let current_id = editor.buffer_manager.current_buffer_id();
+    fn render_bufferline(f: &mut ratatui::Frame, editor: &Editor, theme: &Theme, area: Rect) {
+        let mut buffers = editor.get_buffers();
+        let current = editor.buffer_manager.current_buffer();
+        let current_id = current.id;
+        if current.is_transient {
+            buffers.push(current);
+        }
 
         let mut buffer_spans: Vec<Span> = Vec::new();
         let mut current_width = 0;
@@ -257,22 +487,27 @@ impl Tui {
         for buffer in buffers {
             let is_current = buffer.id == current_id;
             let dirty_mark = if buffer.dirty { " [+]" } else { "" };
-            let buffer_text = format!(" {}:{}{}", buffer.id, buffer.title, dirty_mark);
+            let readonly_mark = if buffer.readonly { " [RO]" } else { "" };
+            let buffer_text = format!(
+                " {}:{}{}{}",
+                buffer.id, buffer.title, readonly_mark, dirty_mark
+            );
             current_width += 2 + buffer_text.chars().count();
 
-            let style = if is_current {
-                Style::default()
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD)
+            let mut style = if is_current {
+                theme.bufferline_current
             } else {
-                Style::default().fg(Color::Gray)
+                theme.bufferline
             };
+            if buffer.is_transient {
+                style = style.add_modifier(Modifier::ITALIC);
+            }
 
-            buffer_spans.push(Span::styled(" | ", Style::default().fg(Color::DarkGray)));
+            buffer_spans.push(Span::styled(" | ", theme.border));
             buffer_spans.push(Span::styled(buffer_text, style));
         }
 
-        buffer_spans.push(Span::styled(" |", Style::default().fg(Color::DarkGray)));
+        buffer_spans.push(Span::styled(" |", theme.border));
 
         let bufferline = Line::from(buffer_spans);
 
@@ -292,79 +527,215 @@ impl Tui {
             ));
         }
 
-        let bufferline_widget =
-            Paragraph::new(display_line).style(Style::default().fg(Color::Cyan));
+        let bufferline_widget = Paragraph::new(display_line).style(theme.text);
         f.render_widget(bufferline_widget, area);
     }
 
-    fn render_main_editor(f: &mut ratatui::Frame, editor: &Editor, area: Rect, status_area: Rect) {
+    fn render_main_editor(
+        f: &mut ratatui::Frame,
+        editor: &mut Editor,
+        theme: &Theme,
+        area: Rect,
+        status_area: Rect,
+    ) {
+        let line_numbers = editor.options.line_numbers;
+        let gutter_width = if line_numbers == LineNumberMode::None {
+            0
+        } else {
+            gutter::gutter_width(editor.line_count())
+        };
+        let number_width = gutter_width.saturating_sub(gutter::GUTTER_PADDING) as usize;
         let horizontal_chunks = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Length(6), Constraint::Min(0)].as_ref())
+            .constraints([Constraint::Length(gutter_width), Constraint::Min(0)].as_ref())
             .split(area);
 
         let line_numbers_area = horizontal_chunks[0];
         let text_area = horizontal_chunks[1];
 
-        let text = editor.get_current_text();
-        let max_lines = area.height.saturating_sub(2) as usize;
+        let max_lines = text_area.height as usize;
+        editor.set_viewport_size(text_area.width as usize, max_lines);
+
         let start_line = editor.scroll_offset;
-        let end_line = (start_line + max_lines).min(text.len_lines());
+        let end_line = (start_line + max_lines).min(editor.get_current_text().len_lines());
+        let wraps: Vec<Vec<usize>> = (start_line..end_line)
+            .map(|i| editor.wrap_starts(i))
+            .collect();
+        // The screen rows each visible line takes, cut off at the bottom edge.
+        let mut screen_rows = Vec::with_capacity(wraps.len());
+        let mut top = 0;
+        for starts in &wraps {
+            screen_rows.push(top.min(max_lines)..(top + starts.len()).min(max_lines));
+            top += starts.len();
+        }
+        let rows_of = |row: usize| {
+            row.checked_sub(start_line)
+                .and_then(|i| screen_rows.get(i).cloned())
+                .unwrap_or_default()
+        };
+        let fill_rows = |f: &mut ratatui::Frame, rows: std::ops::Range<usize>, x, width, style| {
+            for y in rows {
+                let row = Rect::new(x, text_area.y + y as u16, width, 1);
+                f.buffer_mut().set_style(row, style);
+            }
+        };
+
+        // Drawn first so the gutter, syntax and selection styles layer on top.
+        if editor.options.cursor_line && !editor.buffer_manager.current_buffer().loading {
+            let rows = rows_of(editor.cursor.row);
+            fill_rows(f, rows, area.x, area.width, theme.current_line);
+        }
+        // A linewise selection covers the full width, not just the text.
+        if editor.mode == Mode::VisualLine
+            && let Some((start, end)) = editor.selection_range()
+        {
+            for row in start.row..=end.row {
+                let rows = rows_of(row);
+                fill_rows(f, rows, text_area.x, text_area.width, theme.selection);
+            }
+        }
+        if let Some(x) = editor.options.color_column.and_then(|column| {
+            gutter::color_column_x(column, text_area.x, editor.left_col, text_area.width)
+        }) {
+            let ruler = Rect::new(x, text_area.y, 1, text_area.height);
+            f.buffer_mut().set_style(ruler, theme.color_column);
+        }
 
-        let line_number_style = Style::default().fg(Color::Gray);
         let mut line_number_lines: Vec<Line> = Vec::new();
+        let line_changes = &editor.buffer_manager.current_buffer().line_changes;
 
-        for i in start_line..end_line {
-            let line_num = (i as i32 - editor.cursor.row as i32).abs();
+        for (i, starts) in (start_line..end_line).zip(&wraps) {
+            let Some(line_num) = line_numbers.number_for(i, editor.cursor.row) else {
+                break;
+            };
             let style = if i == editor.cursor.row {
-                line_number_style
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD)
+                theme.current_line_number
             } else {
-                line_number_style
+                theme.line_number
             };
-            line_number_lines.push(Line::from(vec![Span::styled(
-                format!("{:>4}", line_num),
-                style,
-            )]));
+            // Git markers go in the padding after the number.
+            let change = line_changes
+                .get(&i)
+                .map(|&change| Span::styled(change.marker().to_string(), theme.git_change(change)));
+            line_number_lines.push(Line::from_iter(
+                [Span::styled(
+                    format!("{:>width$}", line_num, width = number_width),
+                    style,
+                )]
+                .into_iter()
+                .chain(change),
+            ));
+            for _ in 1..starts.len() {
+                line_number_lines.push(Line::styled(
+                    format!("{:>width$}", gutter::WRAP_MARKER, width = number_width),
+                    theme.line_number,
+                ));
+            }
         }
 
         let line_numbers = Paragraph::new(line_number_lines);
         f.render_widget(line_numbers, line_numbers_area);
 
+        let selection = editor.selection_range();
+        let block = editor.block_selection();
+        let search_matcher = editor.search.matcher(&editor.options).ok().flatten();
+        // The highlighter caches as it goes, so tokens are gathered before the
+        // lines are borrowed.
+        let buffer = editor.buffer_manager.current_buffer_mut();
+        let syntax: Vec<Vec<_>> = (start_line..end_line)
+            .map(|i| {
+                buffer
+                    .highlighter
+                    .line(&buffer.text, i)
+                    .iter()
+                    .map(|(range, kind)| (range.clone(), line::token_style(*kind)))
+                    .collect()
+            })
+            .collect();
         let mut text_lines: Vec<Line> = Vec::new();
-        for i in start_line..end_line {
-            let line = text.line(i);
-            text_lines.push(Line::from(line.to_string()));
+        let buffer = editor.buffer_manager.current_buffer();
+        // Collected so the drawn lines can borrow from them.
+        let lines: Vec<_> = buffer.visible_lines(start_line..end_line).collect();
+        for (((i, line), mut highlights), starts) in
+            (start_line..).zip(&lines).zip(syntax).zip(&wraps)
+        {
+            highlights.extend(buffer.diagnostics.iter().filter_map(|diagnostic| {
+                let cols = diagnostic.cols_on(i)?;
+                Some((cols, theme.diagnostic(diagnostic.severity)))
+            }));
+            let search_matches = search_matcher
+                .as_ref()
+                .map(|matcher| Search::find_in_line(matcher, line))
+                .unwrap_or_default();
+            highlights.extend(search_matches.into_iter().map(|range| {
+                let current = i == editor.cursor.row && range.start == editor.cursor.col;
+                let style = if current {
+                    theme.search_current
+                } else {
+                    theme.search_match
+                };
+                (range, style)
+            }));
+            if let Some((top_left, bottom_right)) = block {
+                if (top_left.row..=bottom_right.row).contains(&i) {
+                    highlights.push((top_left.col..bottom_right.col + 1, theme.selection));
+                }
+            } else if let Some((start, end)) = selection
+                && (start.row..=end.row).contains(&i)
+            {
+                let from = if i == start.row { start.col } else { 0 };
+                let to = if i == end.row {
+                    end.col + 1
+                } else {
+                    usize::MAX
+                };
+                highlights.push((from..to, theme.selection));
+            }
+            let styled = line::styled_line(
+                line,
+                Style::default(),
+                &highlights,
+                editor.options.tab_width.max(1),
+                editor.options.list.then_some(theme.whitespace),
+            );
+            text_lines.extend(line::wrap_line(styled, starts));
         }
 
+        if editor.buffer_manager.current_buffer().loading {
+            text_lines = vec![Line::styled("Loading…", theme.muted)];
+        }
+
+        // Long lines are already split into screen rows, so only `nowrap`
+        // needs the paragraph to scroll sideways.
         let paragraph = Paragraph::new(text_lines)
-            .wrap(Wrap { trim: false })
-            .style(Style::default().fg(Color::Cyan))
+            .scroll((0, editor.left_col as u16))
+            .style(theme.text)
             .alignment(Alignment::Left);
         f.render_widget(paragraph, text_area);
 
-        let cursor_row = editor.cursor.row.saturating_sub(editor.scroll_offset);
-        let cursor_col = editor.cursor.col;
-
-        let area_x = text_area.x + 1;
-        let area_y = text_area.y + 1;
+        // The terminal shows only the primary cursor; draw the others as blocks.
+        for cursor in &editor.secondary_cursors {
+            if let Some((row, col)) = editor.screen_position(*cursor) {
+                let cell = Rect::new(text_area.x + col as u16, text_area.y + row as u16, 1, 1);
+                f.buffer_mut().set_style(cell, theme.secondary_cursor);
+            }
+        }
 
-        if editor.mode == Mode::Command {
+        if editor.mode == Mode::Command || editor.mode == Mode::Search {
             let cursor_pos = editor.command_input.len() as u16 + 2;
             if cursor_pos + 2 < status_area.width {
                 f.set_cursor_position((status_area.x + cursor_pos, status_area.y + 1));
             }
         } else if (editor.mode == Mode::Normal
-            || editor.mode == Mode::Insert
-            || editor.mode == Mode::Visual)
-            && cursor_row < (text_area.height - 2) as usize
+            || editor.mode.is_typing()
+            || editor.mode.is_visual())
+            && let Some((row, col)) = editor.screen_position(editor.cursor)
         {
-            f.set_cursor_position((area_x + cursor_col as u16, area_y + cursor_row as u16));
+            f.set_cursor_position((text_area.x + col as u16, text_area.y + row as u16));
         }
     }
 
-    fn render_save_dialog(f: &mut ratatui::Frame, editor: &Editor, area: Rect) {
+    fn render_save_dialog(f: &mut ratatui::Frame, editor: &Editor, theme: &Theme, area: Rect) {
         let dialog_width = 50.min(area.width.saturating_sub(4));
         let dialog_height = 6;
         let x = (area.width - dialog_width) / 2;
@@ -376,22 +747,19 @@ impl Tui {
             Line::from("Save As"),
             Line::from(""),
             Line::from(vec![
-                Span::styled("> ", Style::default().fg(Color::Green)),
-                Span::styled(
-                    editor.command_input.clone(),
-                    Style::default().fg(Color::White),
-                ),
+                Span::styled("> ", theme.accent),
+                Span::styled(editor.command_input.clone(), theme.emphasis),
             ]),
             Line::from(""),
             Line::from("Enter to save, Esc to cancel"),
         ];
 
         let dialog = Paragraph::new(dialog_content)
-            .style(Style::default().fg(Color::Cyan))
+            .style(theme.text)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title_style(Style::default().fg(Color::Yellow)),
+                    .title_style(theme.dialog_title),
             )
             .alignment(Alignment::Center);
 
@@ -404,7 +772,91 @@ impl Tui {
         }
     }
 
-    fn render_file_picker(f: &mut ratatui::Frame, editor: &Editor, area: Rect) {
+    /// Draws the quickfix list in a pane along the bottom, scrolled to keep
+    /// the current entry in view.
+    fn render_quickfix(f: &mut ratatui::Frame, editor: &Editor, theme: &Theme, area: Rect) {
+        let entries = editor.quickfix.entries();
+        let current = editor.quickfix.current();
+        let rows = area.height.saturating_sub(2) as usize;
+        let first = current.map_or(0, |idx| (idx + 1).saturating_sub(rows));
+
+        let lines: Vec<Line> = entries
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(rows)
+            .map(|(idx, entry)| {
+                let style = if Some(idx) == current {
+                    theme.list_selected
+                } else {
+                    theme.text
+                };
+                let col = entry.col.map(|col| format!(":{}", col)).unwrap_or_default();
+                Line::from(Span::styled(
+                    format!(
+                        "{}:{}{} {}",
+                        entry.path.display(),
+                        entry.line,
+                        col,
+                        entry.message
+                    ),
+                    style,
+                ))
+            })
+            .collect();
+
+        let title = match current {
+            Some(idx) => format!("Quickfix ({} of {})", idx + 1, entries.len()),
+            None => format!("Quickfix ({})", entries.len()),
+        };
+        let pane = Paragraph::new(lines)
+            .style(theme.text)
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(pane, area);
+    }
+
+    fn render_buffer_list(f: &mut ratatui::Frame, editor: &Editor, theme: &Theme, area: Rect) {
+        let mut buffers = editor.get_buffers();
+        let current = editor.buffer_manager.current_buffer();
+        let current_id = current.id;
+        if current.is_transient {
+            buffers.push(current);
+        }
+
+        let dialog_width = 60.min(area.width.saturating_sub(4));
+        let dialog_height = (buffers.len() as u16 + 2).min(area.height.saturating_sub(4));
+        let x = (area.width - dialog_width) / 2;
+        let y = (area.height - dialog_height) / 2;
+
+        let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
+
+        let lines: Vec<Line> = buffers
+            .iter()
+            .enumerate()
+            .map(|(idx, buffer)| {
+                let style = if idx == editor.buffer_list_idx {
+                    theme.list_selected
+                } else {
+                    theme.text
+                };
+                let marker = if buffer.id == current_id { "%" } else { " " };
+                let dirty = if buffer.dirty { "+" } else { " " };
+                Line::from(Span::styled(
+                    format!("{:>3} {}{} {}", buffer.id, marker, dirty, buffer.title),
+                    style,
+                ))
+            })
+            .collect();
+
+        let dialog = Paragraph::new(lines)
+            .style(theme.text)
+            .block(Block::default().borders(Borders::ALL).title("Buffers"));
+
+        f.render_widget(Clear, dialog_area);
+        f.render_widget(dialog, dialog_area);
+    }
+
+    fn render_file_picker(f: &mut ratatui::Frame, editor: &Editor, theme: &Theme, area: Rect) {
         let picker = &editor.file_picker;
 
         let dialog_width = 60.min(area.width.saturating_sub(4));
@@ -414,19 +866,21 @@ impl Tui {
 
         let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
 
+        let (heading, title) = if picker.listing_recent {
+            ("Recent Files", "Open Recent File")
+        } else {
+            ("File Picker", "Open File")
+        };
         let mut lines: Vec<Line> = Vec::new();
-        lines.push(Line::from("File Picker"));
+        lines.push(Line::from(heading));
         lines.push(Line::from(""));
 
         for (idx, file) in picker.files.iter().enumerate() {
             let icon = if file.is_dir { "📁 " } else { "📄 " };
             let style = if idx == picker.selected_idx {
-                Style::default()
-                    .fg(Color::Black)
-                    .bg(Color::White)
-                    .add_modifier(Modifier::BOLD)
+                theme.list_selected
             } else {
-                Style::default().fg(Color::Cyan)
+                theme.text
             };
 
             lines.push(Line::from(vec![
@@ -437,21 +891,18 @@ impl Tui {
 
         lines.push(Line::from(""));
         lines.push(Line::from(vec![
-            Span::styled("Path: ", Style::default().fg(Color::Gray)),
-            Span::styled(
-                picker.current_dir.display().to_string(),
-                Style::default().fg(Color::White),
-            ),
+            Span::styled("Path: ", theme.muted),
+            Span::styled(picker.current_dir.display().to_string(), theme.emphasis),
         ]));
 
         lines.push(Line::from(""));
         lines.push(Line::from(
-            "Enter: select/open | Esc: cancel | j/k: navigate",
+            "Enter: select/open | h: parent | .: hidden | Esc: cancel | j/k: navigate",
         ));
 
         let dialog = Paragraph::new(lines)
-            .style(Style::default().fg(Color::Cyan))
-            .block(Block::default().borders(Borders::ALL).title("Open File"));
+            .style(theme.text)
+            .block(Block::default().borders(Borders::ALL).title(title));
 
         f.render_widget(Clear, dialog_area);
         f.render_widget(dialog, dialog_area);
@@ -470,9 +921,140 @@ impl Drop for Tui {
         let _ = disable_raw_mode();
         let _ = execute!(
             self.terminal.backend_mut(),
+            SetCursorStyle::DefaultUserShape,
             LeaveAlternateScreen,
             DisableMouseCapture
         );
         let _ = self.terminal.show_cursor();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{backend::TestBackend, style::Color};
+
+    fn key_event(c: char) -> Event {
+        Event::Key(crossterm::event::KeyEvent::from(
+            crossterm::event::KeyCode::Char(c),
+        ))
+    }
+
+    #[test]
+    fn the_cursor_is_a_bar_for_typing_and_an_underline_for_replacing() {
+        for mode in [Mode::Insert, Mode::Command, Mode::Search, Mode::SaveDialog] {
+            assert_eq!(
+                cursor_style_for(mode),
+                SetCursorStyle::SteadyBar,
+                "{:?}",
+                mode
+            );
+        }
+        assert_eq!(
+            cursor_style_for(Mode::Replace),
+            SetCursorStyle::SteadyUnderScore
+        );
+        for mode in [
+            Mode::Normal,
+            Mode::Visual,
+            Mode::VisualLine,
+            Mode::VisualBlock,
+        ] {
+            assert_eq!(
+                cursor_style_for(mode),
+                SetCursorStyle::SteadyBlock,
+                "{:?}",
+                mode
+            );
+        }
+    }
+
+    #[test]
+    fn a_key_redraws_only_when_it_did_something() {
+        assert!(should_redraw(&key_event('j'), Some(&Action::MoveDown)));
+        assert!(should_redraw(
+            &key_event('x'),
+            Some(&Action::InsertIntoCommand('x'))
+        ));
+        assert!(!should_redraw(&key_event('g'), Some(&Action::NoOp)));
+        assert!(!should_redraw(&key_event('g'), None));
+    }
+
+    #[test]
+    fn a_resize_redraws_but_mouse_and_focus_events_do_not() {
+        assert!(should_redraw(&Event::Resize(80, 24), None));
+        assert!(!should_redraw(&Event::FocusGained, None));
+        assert!(!should_redraw(&Event::FocusLost, None));
+        let click = crossterm::event::MouseEvent {
+            kind: crossterm::event::MouseEventKind::Moved,
+            column: 0,
+            row: 0,
+            modifiers: crossterm::event::KeyModifiers::NONE,
+        };
+        assert!(!should_redraw(&Event::Mouse(click), None));
+    }
+
+    #[test]
+    fn the_header_draws_the_mode_in_the_themed_style() {
+        let theme = Theme::from_toml("[mode]\nfg = \"#ff8800\"\n").expect("a valid theme");
+        let editor = Editor::new("");
+        let mut terminal = Terminal::new(TestBackend::new(40, 3)).expect("a test terminal");
+
+        terminal
+            .draw(|f| Tui::render_header(f, &editor, &theme, f.area()))
+            .expect("drawn");
+        let cell = &terminal.backend().buffer()[(2, 1)];
+        assert_eq!(cell.symbol(), "M");
+        assert_eq!(cell.fg, Color::Rgb(0xff, 0x88, 0x00));
+    }
+
+    #[test]
+    fn cursorline_highlights_the_cursor_rows_screen_line() {
+        let mut editor = Editor::new("");
+        editor
+            .buffer_manager
+            .current_buffer_mut()
+            .set_text("one\ntwo\nthree\nfour\n".into());
+        editor.options.cursor_line = true;
+        editor.handle_action(fluxion_core::Action::MoveDown);
+        editor.handle_action(fluxion_core::Action::MoveDown);
+        let theme = Theme::default();
+        let mut terminal = Terminal::new(TestBackend::new(20, 4)).expect("a test terminal");
+
+        terminal
+            .draw(|f| Tui::render_main_editor(f, &mut editor, &theme, f.area(), Rect::default()))
+            .expect("drawn");
+        let buffer = terminal.backend().buffer();
+        let background = theme.current_line.bg;
+        for y in 0..4 {
+            assert_eq!(
+                Some(buffer[(10, y)].bg) == background,
+                y == 2,
+                "screen row {}",
+                y
+            );
+        }
+    }
+
+    #[test]
+    fn a_wrapped_line_marks_its_extra_rows_in_the_gutter() {
+        let mut editor = Editor::new("");
+        editor
+            .buffer_manager
+            .current_buffer_mut()
+            .set_text("abcdefghijklmno\nx\n".into());
+        editor.options.wrap = true;
+        editor.options.line_numbers = fluxion_core::LineNumberMode::Absolute;
+        let theme = Theme::default();
+        let mut terminal = Terminal::new(TestBackend::new(16, 4)).expect("a test terminal");
+
+        terminal
+            .draw(|f| Tui::render_main_editor(f, &mut editor, &theme, f.area(), Rect::default()))
+            .expect("drawn");
+        let buffer = terminal.backend().buffer();
+        let row = |y: u16| (0..16).map(|x| buffer[(x, y)].symbol()).collect::<String>();
+        assert_eq!(row(0), "   1  abcdefghij");
+        assert_eq!(row(1), "   ↪  klmno     ");
+        assert_eq!(row(2), "   2  x         ");
+    }
+}
@@ -0,0 +1,315 @@
+use fluxion_core::TokenKind;
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use std::borrow::Cow;
+use std::ops::Range;
+use unicode_width::UnicodeWidthChar;
+
+/// Builds a styled `Line` from raw line text, patching `base` with each
+/// highlight whose char range covers a given char. Later highlights win.
+/// Tabs are expanded to the next multiple of `tab_width`, counting wide chars
+/// as two cells the way the editor does. With `whitespace`, as for
+/// `:set list`, tabs start with `→` and trailing spaces show as `·`, drawn in
+/// that style. Spans borrow from `text` unless a tab or glyph had
+/// to be drawn in them.
+pub(crate) fn styled_line<'a>(
+    text: &'a str,
+    base: Style,
+    highlights: &[(Range<usize>, Style)],
+    tab_width: usize,
+    whitespace: Option<Style>,
+) -> Line<'a> {
+    let text = text.trim_end_matches(['\n', '\r']);
+    let trailing = text.trim_end_matches([' ', '\t']).chars().count();
+    let mut spans: Vec<Span> = Vec::new();
+    let mut run = Run::new(0);
+    let mut run_style = base;
+    let mut width = 0;
+
+    for (idx, (byte, c)) in text.char_indices().enumerate() {
+        let mut style = highlights
+            .iter()
+            .filter(|(range, _)| range.contains(&idx))
+            .fold(base, |style, (_, patch)| style.patch(*patch));
+        let glyph = match whitespace {
+            Some(patch) if c == '\t' || (c == ' ' && idx >= trailing) => {
+                style = style.patch(patch);
+                true
+            }
+            _ => false,
+        };
+
+        if style != run_style && !run.is_empty(byte) {
+            spans.push(Span::styled(run.take(text, byte), run_style));
+        }
+        run_style = style;
+        if c == '\t' {
+            let spaces = tab_width - width % tab_width;
+            let owned = run.make_owned(text, byte);
+            if glyph {
+                owned.push('→');
+                owned.extend(std::iter::repeat_n(' ', spaces - 1));
+            } else {
+                owned.extend(std::iter::repeat_n(' ', spaces));
+            }
+            width += spaces;
+        } else if glyph {
+            run.make_owned(text, byte).push('·');
+            width += 1;
+        } else {
+            run.push(c);
+            width += c.width().unwrap_or(0);
+        }
+    }
+
+    if !run.is_empty(text.len()) {
+        spans.push(Span::styled(run.take(text, text.len()), run_style));
+    }
+
+    Line::from(spans)
+}
+
+/// The text of the span being built: a slice of the line from `start` while
+/// it is copied verbatim, or its own string once a tab or glyph is drawn.
+struct Run {
+    start: usize,
+    owned: Option<String>,
+}
+
+impl Run {
+    fn new(start: usize) -> Self {
+        Self { start, owned: None }
+    }
+
+    fn is_empty(&self, end: usize) -> bool {
+        match &self.owned {
+            Some(owned) => owned.is_empty(),
+            None => self.start == end,
+        }
+    }
+
+    /// Appends a char copied from the line.
+    fn push(&mut self, c: char) {
+        if let Some(owned) = &mut self.owned {
+            owned.push(c);
+        }
+    }
+
+    /// The run as a string it may change, copying the slice so far.
+    fn make_owned(&mut self, text: &str, end: usize) -> &mut String {
+        self.owned
+            .get_or_insert_with(|| text[self.start..end].to_string())
+    }
+
+    /// Ends the run at byte `end` and starts the next one there.
+    fn take<'a>(&mut self, text: &'a str, end: usize) -> Cow<'a, str> {
+        let run = std::mem::replace(self, Self::new(end));
+        match run.owned {
+            Some(owned) => Cow::Owned(owned),
+            None => Cow::Borrowed(&text[run.start..end]),
+        }
+    }
+}
+
+/// Splits a line drawn by [`styled_line`] into one line per screen row,
+/// breaking at the screen columns in `starts` (as given by
+/// `Editor::wrap_starts`). Zero-width chars stay with the char before them.
+/// Borrowed spans stay borrowed.
+pub(crate) fn wrap_line<'a>(line: Line<'a>, starts: &[usize]) -> Vec<Line<'a>> {
+    if starts.len() < 2 {
+        return vec![line];
+    }
+    let mut rows = Vec::with_capacity(starts.len());
+    let mut row = Line::default();
+    let mut breaks = starts[1..].iter().peekable();
+    let mut width = 0;
+    for span in line.spans {
+        let mut run_start = 0;
+        for (byte, c) in span.content.char_indices() {
+            let c_width = c.width().unwrap_or(0);
+            if c_width > 0 && breaks.next_if(|&&start| width >= start).is_some() {
+                if byte > run_start {
+                    let part = slice(&span.content, run_start..byte);
+                    row.push_span(Span::styled(part, span.style));
+                }
+                rows.push(std::mem::take(&mut row));
+                run_start = byte;
+            }
+            width += c_width;
+        }
+        if run_start == 0 {
+            row.push_span(span);
+        } else if run_start < span.content.len() {
+            let part = slice(&span.content, run_start..span.content.len());
+            row.push_span(Span::styled(part, span.style));
+        }
+    }
+    rows.push(row);
+    rows
+}
+
+/// Part of a span's text, borrowing from the line when the span does.
+fn slice<'a>(content: &Cow<'a, str>, range: Range<usize>) -> Cow<'a, str> {
+    match content {
+        Cow::Borrowed(text) => Cow::Borrowed(&text[range]),
+        Cow::Owned(text) => Cow::Owned(text[range].to_string()),
+    }
+}
+
+/// How each kind of syntax token is drawn.
+pub(crate) fn token_style(kind: TokenKind) -> Style {
+    let style = Style::default();
+    match kind {
+        TokenKind::Keyword => style.fg(Color::Magenta).add_modifier(Modifier::BOLD),
+        TokenKind::Type => style.fg(Color::Yellow),
+        TokenKind::Constant | TokenKind::Number => style.fg(Color::LightRed),
+        TokenKind::String => style.fg(Color::Green),
+        TokenKind::Comment => style.fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        TokenKind::Macro | TokenKind::Attribute => style.fg(Color::Blue),
+        TokenKind::Property => style.fg(Color::LightBlue),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(line: &Line) -> Vec<(String, Style)> {
+        line.spans
+            .iter()
+            .map(|span| (span.content.to_string(), span.style))
+            .collect()
+    }
+
+    #[test]
+    fn highlights_split_the_line_into_styled_spans() {
+        let selected = Style::default().bg(Color::Blue);
+        let line = styled_line("abcdef\n", Style::default(), &[(1..3, selected)], 4, None);
+        assert_eq!(
+            spans(&line),
+            [
+                ("a".to_string(), Style::default()),
+                ("bc".to_string(), selected),
+                ("def".to_string(), Style::default()),
+            ]
+        );
+    }
+
+    #[test]
+    fn later_highlights_patch_earlier_ones() {
+        let keyword = Style::default().fg(Color::Magenta);
+        let selected = Style::default().bg(Color::Blue);
+        let line = styled_line(
+            "fn x",
+            Style::default(),
+            &[(0..2, keyword), (0..usize::MAX, selected)],
+            4,
+            None,
+        );
+        assert_eq!(
+            spans(&line),
+            [
+                ("fn".to_string(), keyword.patch(selected)),
+                (" x".to_string(), selected),
+            ]
+        );
+    }
+
+    #[test]
+    fn tabs_expand_to_the_next_tab_stop() {
+        let line = styled_line("\tx\ta\tb", Style::default(), &[], 4, None);
+        assert_eq!(line.to_string(), "    x   a   b");
+        let line = styled_line("ab\tc", Style::default(), &[], 8, None);
+        assert_eq!(line.to_string(), "ab      c");
+    }
+
+    #[test]
+    fn list_draws_glyphs_for_tabs_and_trailing_spaces() {
+        let dim = Style::default().fg(Color::DarkGray);
+        let line = styled_line("a b\tc  \n", Style::default(), &[], 4, Some(dim));
+        assert_eq!(
+            spans(&line),
+            [
+                ("a b".to_string(), Style::default()),
+                ("→".to_string(), dim),
+                ("c".to_string(), Style::default()),
+                ("··".to_string(), dim),
+            ]
+        );
+    }
+
+    #[test]
+    fn list_glyphs_keep_the_tab_width() {
+        let dim = Style::default().fg(Color::DarkGray);
+        let line = styled_line("\tx", Style::default(), &[], 4, Some(dim));
+        assert_eq!(line.to_string(), "→   x");
+        let line = styled_line("a\t ", Style::default(), &[], 4, Some(dim));
+        assert_eq!(line.to_string(), "a→  ·");
+    }
+
+    #[test]
+    fn wide_chars_count_two_cells_toward_a_tab_stop() {
+        let line = styled_line("中\tx", Style::default(), &[], 4, None);
+        assert_eq!(line.to_string(), "中  x");
+        let line = styled_line("e\u{301}\tx", Style::default(), &[], 4, None);
+        assert_eq!(line.to_string(), "e\u{301}   x");
+    }
+
+    fn borrowed(line: &Line) -> Vec<bool> {
+        line.spans
+            .iter()
+            .map(|span| matches!(span.content, Cow::Borrowed(_)))
+            .collect()
+    }
+
+    #[test]
+    fn plain_text_is_borrowed_not_copied() {
+        let selected = Style::default().bg(Color::Blue);
+        let text = String::from("abcdef\n");
+        let line = styled_line(&text, Style::default(), &[(1..3, selected)], 4, None);
+        assert_eq!(borrowed(&line), [true, true, true]);
+        // The spans point into `text` itself.
+        assert_eq!(line.spans[1].content.as_ptr(), text[1..].as_ptr());
+    }
+
+    #[test]
+    fn only_spans_with_tabs_or_glyphs_are_copied() {
+        let dim = Style::default().fg(Color::DarkGray);
+        let line = styled_line("ab\tc d  ", Style::default(), &[(4..5, dim)], 4, None);
+        assert_eq!(spans(&line)[0].0, "ab  c");
+        assert_eq!(borrowed(&line), [false, true, true]);
+
+        let line = styled_line("ab  ", Style::default(), &[], 4, Some(dim));
+        assert_eq!(line.to_string(), "ab··");
+        assert_eq!(borrowed(&line), [true, false]);
+    }
+
+    #[test]
+    fn wrapping_keeps_borrowed_spans_borrowed() {
+        let line = styled_line("abcdefgh", Style::default(), &[], 4, None);
+        let rows = wrap_line(line, &[0, 3, 6]);
+        let texts: Vec<String> = rows.iter().map(|row| row.to_string()).collect();
+        assert_eq!(texts, ["abc", "def", "gh"]);
+        assert!(rows.iter().all(|row| borrowed(row).iter().all(|&b| b)));
+    }
+
+    #[test]
+    fn wrapping_splits_at_span_boundaries_too() {
+        let selected = Style::default().bg(Color::Blue);
+        let line = styled_line("ab\tcd", Style::default(), &[(1..2, selected)], 4, None);
+        let rows = wrap_line(line, &[0, 3]);
+        assert_eq!(
+            rows.iter().map(spans).collect::<Vec<_>>(),
+            [
+                vec![
+                    ("a".to_string(), Style::default()),
+                    ("b".to_string(), selected),
+                    (" ".to_string(), Style::default()),
+                ],
+                vec![(" cd".to_string(), Style::default())],
+            ]
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use fluxion_core::{EditEvent, EditObserver, Editor};
+use fluxion_lsp::{LspClient, LspEvent, content_change, full_change, server_for};
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Collects the editor's edits until `Lsp::sync` passes them on.
+struct EditQueue(Rc<RefCell<Vec<EditEvent>>>);
+
+impl EditObserver for EditQueue {
+    fn on_edit(&mut self, event: &EditEvent) {
+        self.0.borrow_mut().push(event.clone());
+    }
+}
+
+/// How a document's path is given to its server, which wants it absolute.
+fn document_path(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// A buffer a language server has been sent with `didOpen`.
+struct Document {
+    path: PathBuf,
+    /// Command of the server that has it open.
+    server: &'static str,
+    version: i32,
+}
+
+/// Keeps language servers told about the open buffers with a filetype one
+/// handles, starting each server the first time it is needed, and hands
+/// their diagnostics to the buffers.
+pub(crate) struct Lsp {
+    clients: HashMap<&'static str, LspClient>,
+    /// Servers that couldn't be started or have exited, not tried again.
+    failed: HashSet<&'static str>,
+    /// Open documents, keyed by buffer id.
+    documents: HashMap<usize, Document>,
+    edits: Rc<RefCell<Vec<EditEvent>>>,
+    events_tx: Sender<LspEvent>,
+    events_rx: Receiver<LspEvent>,
+}
+
+impl Lsp {
+    pub(crate) fn new() -> Self {
+        let (events_tx, events_rx) = mpsc::channel();
+        Self {
+            clients: HashMap::new(),
+            failed: HashSet::new(),
+            documents: HashMap::new(),
+            edits: Rc::new(RefCell::new(Vec::new())),
+            events_tx,
+            events_rx,
+        }
+    }
+
+    /// Has `editor` report its edits here.
+    pub(crate) fn observe(&self, editor: &mut Editor) {
+        editor.add_observer(Box::new(EditQueue(Rc::clone(&self.edits))));
+    }
+
+    /// Sends the edits made since the last call, opens and closes documents
+    /// to match the buffers, and stores any diagnostics that came in.
+    /// Returns whether diagnostics changed and need drawing.
+    pub(crate) fn sync(&mut self, editor: &mut Editor) -> bool {
+        self.send_edits(editor);
+        self.close_documents(editor);
+        self.open_documents(editor);
+        self.receive(editor)
+    }
+
+    fn send_edits(&mut self, editor: &Editor) {
+        let edits = std::mem::take(&mut *self.edits.borrow_mut());
+        let mut changes: HashMap<usize, Vec<_>> = HashMap::new();
+        let mut resets = HashSet::new();
+        for event in &edits {
+            let buffer_id = match event {
+                EditEvent::Insert { buffer_id, .. }
+                | EditEvent::Delete { buffer_id, .. }
+                | EditEvent::Reset { buffer_id } => *buffer_id,
+            };
+            let Some(document) = self.documents.get(&buffer_id) else {
+                continue;
+            };
+            let incremental = self
+                .clients
+                .get(document.server)
+                .is_some_and(LspClient::incremental);
+            match content_change(event) {
+                Some(change) if incremental => changes.entry(buffer_id).or_default().push(change),
+                _ => {
+                    resets.insert(buffer_id);
+                }
+            }
+        }
+
+        // A document sent whole needs none of its edits.
+        for &buffer_id in &resets {
+            if let Some(buffer) = editor.buffer_manager.iter().find(|b| b.id == buffer_id) {
+                let text = full_change(&buffer.text.to_string());
+                changes.insert(buffer_id, vec![text]);
+            }
+        }
+        for (buffer_id, changes) in changes {
+            let Some(document) = self.documents.get_mut(&buffer_id) else {
+                continue;
+            };
+            if let Some(client) = self.clients.get(document.server) {
+                document.version += 1;
+                client.did_change(&document.path, document.version, changes);
+            }
+        }
+    }
+
+    /// Closes the documents of buffers that are gone or were saved elsewhere.
+    fn close_documents(&mut self, editor: &Editor) {
+        let clients = &self.clients;
+        self.documents.retain(|&buffer_id, document| {
+            let buffer = editor.buffer_manager.iter().find(|b| b.id == buffer_id);
+            let path = buffer.and_then(|b| b.path.as_deref()).map(document_path);
+            let open = path.as_ref() == Some(&document.path);
+            if !open && let Some(client) = clients.get(document.server) {
+                client.did_close(&document.path);
+            }
+            open
+        });
+    }
+
+    fn open_documents(&mut self, editor: &Editor) {
+        let Ok(root) = std::env::current_dir() else {
+            return;
+        };
+        for buffer in editor.buffer_manager.iter() {
+            if buffer.loading || self.documents.contains_key(&buffer.id) {
+                continue;
+            }
+            let (Some(path), Some(filetype)) = (&buffer.path, &buffer.filetype) else {
+                continue;
+            };
+            let Some(server) = server_for(filetype) else {
+                continue;
+            };
+            if self.failed.contains(server.command) {
+                continue;
+            }
+            let client = match self.clients.entry(server.command) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    match LspClient::start(server, &root, self.events_tx.clone()) {
+                        Ok(client) => entry.insert(client),
+                        Err(e) => {
+                            tracing::warn!("Failed to start {}: {}", server.command, e);
+                            self.failed.insert(server.command);
+                            continue;
+                        }
+                    }
+                }
+            };
+            // Documents wait until the server is up, then go with the text
+            // as it is by then.
+            if !client.is_ready() {
+                continue;
+            }
+            let path = document_path(path);
+            client.did_open(&path, filetype, 0, &buffer.text.to_string());
+            self.documents.insert(
+                buffer.id,
+                Document {
+                    path,
+                    server: server.command,
+                    version: 0,
+                },
+            );
+        }
+    }
+
+    fn receive(&mut self, editor: &mut Editor) -> bool {
+        let mut changed = false;
+        while let Ok(event) = self.events_rx.try_recv() {
+            match event {
+                LspEvent::Diagnostics { path, diagnostics } => {
+                    if let Some(id) = editor.buffer_manager.find_by_path(&path)
+                        && let Some(buffer) = editor.buffer_manager.get_mut(id)
+                    {
+                        buffer.diagnostics = diagnostics;
+                        changed = true;
+                    }
+                }
+                LspEvent::Exited { command } => {
+                    tracing::warn!("Language server {} exited", command);
+                    self.clients.remove(command);
+                    self.failed.insert(command);
+                    self.documents
+                        .retain(|_, document| document.server != command);
+                }
+            }
+        }
+        changed
+    }
+}
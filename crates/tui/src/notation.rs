@@ -0,0 +1,92 @@
+use crate::keybindings::Key;
+use crossterm::event::{KeyCode, KeyModifiers};
+
+/// Names of special keys inside `<...>`.
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("Esc", KeyCode::Esc),
+    ("CR", KeyCode::Enter),
+    ("BS", KeyCode::Backspace),
+    ("Tab", KeyCode::Tab),
+    ("Del", KeyCode::Delete),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Space", KeyCode::Char(' ')),
+    ("lt", KeyCode::Char('<')),
+];
+
+/// Writes `key` in vim-style notation, e.g. `d`, `<Esc>` or `<C-r>`, which is
+/// how recorded macros are kept in a register. `None` for keys it has no name for.
+pub(crate) fn key_to_notation(key: Key) -> Option<String> {
+    let mut prefix = String::new();
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        prefix.push_str("C-");
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        prefix.push_str("A-");
+    }
+    let name = match key.code {
+        KeyCode::Char(c) if prefix.is_empty() && c != '<' => return Some(c.to_string()),
+        KeyCode::Char(' ') if prefix.is_empty() => "Space".to_string(),
+        KeyCode::Char(c) if c != '<' => c.to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        code => NAMED_KEYS
+            .iter()
+            .find(|(_, named)| *named == code)
+            .map(|(name, _)| name.to_string())?,
+    };
+    Some(format!("<{}{}>", prefix, name))
+}
+
+/// Reads keys back from notation. A `<` that doesn't start a known key name
+/// is taken literally.
+pub(crate) fn parse_notation(text: &str) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '<'
+            && let Some(end) = rest.find('>')
+            && let Some(key) = parse_named(&rest[1..end])
+        {
+            keys.push(key);
+            rest = &rest[end + 1..];
+            continue;
+        }
+        keys.push(Key::plain(KeyCode::Char(c)));
+        rest = &rest[c.len_utf8()..];
+    }
+    keys
+}
+
+/// Parses the inside of `<...>`, such as `C-r` or `Esc`.
+fn parse_named(mut name: &str) -> Option<Key> {
+    let mut modifiers = KeyModifiers::NONE;
+    loop {
+        if let Some(rest) = name.strip_prefix("C-") {
+            modifiers |= KeyModifiers::CONTROL;
+            name = rest;
+        } else if let Some(rest) = name.strip_prefix("A-") {
+            modifiers |= KeyModifiers::ALT;
+            name = rest;
+        } else {
+            break;
+        }
+    }
+    let mut chars = name.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) if !modifiers.is_empty() => KeyCode::Char(c),
+        _ => match name.strip_prefix('F').and_then(|n| n.parse().ok()) {
+            Some(n) => KeyCode::F(n),
+            None => NAMED_KEYS
+                .iter()
+                .find(|(named, _)| *named == name)
+                .map(|(_, code)| *code)?,
+        },
+    };
+    Some(Key::new(code, modifiers))
+}
@@ -0,0 +1,61 @@
+use fluxion_core::{Cursor, Encoding, LineEnding};
+
+/// Right-hand side of the status line, e.g. `rust utf-8 unix 12:4 25% of 48`,
+/// starting with the filetype when there is one. Rows and columns are shown
+/// 1-based.
+pub(crate) fn file_info(
+    cursor: Cursor,
+    line_count: usize,
+    line_ending: LineEnding,
+    encoding: Encoding,
+    filetype: Option<&str>,
+) -> String {
+    let line_count = line_count.max(1);
+    let percent = (cursor.row + 1) * 100 / line_count;
+    let filetype = filetype.map(|filetype| format!("{} ", filetype));
+    format!(
+        "{}{} {} {}:{} {}% of {}",
+        filetype.unwrap_or_default(),
+        encoding.name(),
+        line_ending.name(),
+        cursor.row + 1,
+        cursor.col + 1,
+        percent.min(100),
+        line_count
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_the_one_based_position_and_percentage() {
+        let info = file_info(
+            Cursor::new(11, 3),
+            48,
+            LineEnding::Lf,
+            Encoding::Utf8,
+            Some("rust"),
+        );
+        assert_eq!(info, "rust utf-8 unix 12:4 25% of 48");
+    }
+
+    #[test]
+    fn leaves_out_a_missing_filetype() {
+        let info = file_info(
+            Cursor::new(0, 0),
+            1,
+            LineEnding::Crlf,
+            Encoding::Latin1,
+            None,
+        );
+        assert_eq!(info, "latin1 dos 1:1 100% of 1");
+    }
+
+    #[test]
+    fn an_empty_buffer_counts_as_one_line() {
+        let info = file_info(Cursor::new(0, 0), 0, LineEnding::Lf, Encoding::Utf8, None);
+        assert_eq!(info, "utf-8 unix 1:1 100% of 1");
+    }
+}
@@ -0,0 +1,275 @@
+use fluxion_core::{LineChange, Severity};
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Named styles the renderer draws with. Defaults match the built-in look;
+/// a `theme.toml` in the config directory overrides any of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// Buffer text and dialog contents.
+    pub text: Style,
+    /// Titles and typed input.
+    pub emphasis: Style,
+    /// Secondary text such as labels, placeholders and the file info.
+    pub muted: Style,
+    /// Markers that draw the eye, such as the input arrow in dialogs.
+    pub accent: Style,
+    pub line_number: Style,
+    pub current_line_number: Style,
+    /// Background of the cursor line when `cursorline` is on.
+    pub current_line: Style,
+    /// Ruler drawn at `colorcolumn`.
+    pub color_column: Style,
+    /// Tab and trailing space glyphs drawn with `:set list`.
+    pub whitespace: Style,
+    pub selection: Style,
+    /// Cells under the cursors added with `Ctrl-n`.
+    pub secondary_cursor: Style,
+    pub search_match: Style,
+    /// Underlines for the problems language servers report, by severity.
+    pub diagnostic_error: Style,
+    pub diagnostic_warning: Style,
+    /// Information and hints.
+    pub diagnostic_info: Style,
+    /// Gutter markers for lines that differ from git's `HEAD`.
+    pub git_added: Style,
+    pub git_modified: Style,
+    pub git_deleted: Style,
+    /// The match under the cursor, e.g. the one a search being typed previews.
+    pub search_current: Style,
+    /// Key hints shown in the status area when there is no message.
+    pub status: Style,
+    pub status_info: Style,
+    pub status_error: Style,
+    /// The `:`, `/` and save prompts.
+    pub prompt: Style,
+    /// Mode name in the header.
+    pub mode: Style,
+    pub bufferline: Style,
+    pub bufferline_current: Style,
+    /// Separators, borders and other chrome.
+    pub border: Style,
+    /// Highlighted row in pickers and lists.
+    pub list_selected: Style,
+    pub dialog_title: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let style = Style::default();
+        Self {
+            text: style.fg(Color::Cyan),
+            emphasis: style.fg(Color::White),
+            muted: style.fg(Color::Gray),
+            accent: style.fg(Color::Green),
+            line_number: style.fg(Color::Gray),
+            current_line_number: style.fg(Color::White).add_modifier(Modifier::BOLD),
+            current_line: style.bg(Color::Indexed(236)),
+            color_column: style.bg(Color::Indexed(235)),
+            whitespace: style.fg(Color::DarkGray),
+            selection: style.bg(Color::DarkGray),
+            secondary_cursor: style.add_modifier(Modifier::REVERSED),
+            search_match: style.fg(Color::Black).bg(Color::Yellow),
+            diagnostic_error: style
+                .underline_color(Color::Red)
+                .add_modifier(Modifier::UNDERLINED),
+            diagnostic_warning: style
+                .underline_color(Color::Yellow)
+                .add_modifier(Modifier::UNDERLINED),
+            diagnostic_info: style
+                .underline_color(Color::Blue)
+                .add_modifier(Modifier::UNDERLINED),
+            git_added: style.fg(Color::Green),
+            git_modified: style.fg(Color::Yellow),
+            git_deleted: style.fg(Color::Red),
+            search_current: style.fg(Color::Black).bg(Color::LightRed),
+            status: style.fg(Color::Yellow),
+            status_info: style.fg(Color::White),
+            status_error: style.fg(Color::White).bg(Color::Red),
+            prompt: style.fg(Color::Cyan),
+            mode: style.fg(Color::Green).add_modifier(Modifier::BOLD),
+            bufferline: style.fg(Color::Gray),
+            bufferline_current: style.fg(Color::White).add_modifier(Modifier::BOLD),
+            border: style.fg(Color::DarkGray),
+            list_selected: style
+                .fg(Color::Black)
+                .bg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            dialog_title: style.fg(Color::Yellow),
+        }
+    }
+}
+
+/// One style as written in `theme.toml`. Colors are terminal color names
+/// such as `"cyan"` or `"light-red"`, or hex RGB such as `"#1e1e2e"`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StyleSpec {
+    fg: Option<String>,
+    bg: Option<String>,
+    bold: Option<bool>,
+    italic: Option<bool>,
+    underline: Option<bool>,
+}
+
+impl StyleSpec {
+    /// Applies the fields that are set on top of `base`.
+    fn apply(&self, base: Style) -> Result<Style, Box<dyn Error>> {
+        let mut style = base;
+        if let Some(fg) = &self.fg {
+            style = style.fg(parse_color(fg)?);
+        }
+        if let Some(bg) = &self.bg {
+            style = style.bg(parse_color(bg)?);
+        }
+        for (set, modifier) in [
+            (self.bold, Modifier::BOLD),
+            (self.italic, Modifier::ITALIC),
+            (self.underline, Modifier::UNDERLINED),
+        ] {
+            style = match set {
+                Some(true) => style.add_modifier(modifier),
+                Some(false) => style.remove_modifier(modifier),
+                None => style,
+            };
+        }
+        Ok(style)
+    }
+}
+
+fn parse_color(name: &str) -> Result<Color, Box<dyn Error>> {
+    Color::from_str(name).map_err(|_| format!("unknown color {:?}", name).into())
+}
+
+impl Theme {
+    /// The gutter marker style for a line with `change`.
+    pub fn git_change(&self, change: LineChange) -> Style {
+        match change {
+            LineChange::Added => self.git_added,
+            LineChange::Modified => self.git_modified,
+            LineChange::Deleted => self.git_deleted,
+        }
+    }
+
+    /// The underline for a diagnostic of `severity`.
+    pub fn diagnostic(&self, severity: Severity) -> Style {
+        match severity {
+            Severity::Error => self.diagnostic_error,
+            Severity::Warning => self.diagnostic_warning,
+            Severity::Information | Severity::Hint => self.diagnostic_info,
+        }
+    }
+
+    /// The style a theme file refers to as `name`.
+    fn style_mut(&mut self, name: &str) -> Option<&mut Style> {
+        Some(match name {
+            "text" => &mut self.text,
+            "emphasis" => &mut self.emphasis,
+            "muted" => &mut self.muted,
+            "accent" => &mut self.accent,
+            "line_number" => &mut self.line_number,
+            "current_line_number" => &mut self.current_line_number,
+            "current_line" => &mut self.current_line,
+            "color_column" => &mut self.color_column,
+            "whitespace" => &mut self.whitespace,
+            "selection" => &mut self.selection,
+            "secondary_cursor" => &mut self.secondary_cursor,
+            "search_match" => &mut self.search_match,
+            "diagnostic_error" => &mut self.diagnostic_error,
+            "diagnostic_warning" => &mut self.diagnostic_warning,
+            "diagnostic_info" => &mut self.diagnostic_info,
+            "git_added" => &mut self.git_added,
+            "git_modified" => &mut self.git_modified,
+            "git_deleted" => &mut self.git_deleted,
+            "search_current" => &mut self.search_current,
+            "status" => &mut self.status,
+            "status_info" => &mut self.status_info,
+            "status_error" => &mut self.status_error,
+            "prompt" => &mut self.prompt,
+            "mode" => &mut self.mode,
+            "bufferline" => &mut self.bufferline,
+            "bufferline_current" => &mut self.bufferline_current,
+            "border" => &mut self.border,
+            "list_selected" => &mut self.list_selected,
+            "dialog_title" => &mut self.dialog_title,
+            _ => return None,
+        })
+    }
+
+    /// Parses a theme file's contents over the default theme. Each table
+    /// names a style, e.g. `[selection]` with `bg = "#44475a"`.
+    pub fn from_toml(source: &str) -> Result<Self, Box<dyn Error>> {
+        let specs: BTreeMap<String, StyleSpec> = toml::from_str(source)?;
+        let mut theme = Self::default();
+        for (name, spec) in &specs {
+            let style = theme
+                .style_mut(name)
+                .ok_or_else(|| format!("unknown style {:?}", name))?;
+            *style = spec.apply(*style).map_err(|e| format!("{}: {}", name, e))?;
+        }
+        Ok(theme)
+    }
+
+    /// Reads the theme at `path`.
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Loads `theme.toml` from the config directory, falling back to the
+    /// default theme when there is none or it can't be used.
+    pub fn load() -> Self {
+        let Some(path) = config_path() else {
+            return Self::default();
+        };
+        if !path.exists() {
+            return Self::default();
+        }
+        Self::from_file(&path).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring theme {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+}
+
+/// `theme.toml` in the config dir.
+fn config_path() -> Option<PathBuf> {
+    Some(fluxion_core::config_dir()?.join("theme.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_theme_file_overrides_only_the_styles_it_names() {
+        let theme = Theme::from_toml(
+            "[selection]\nbg = \"#44475a\"\n\n[mode]\nfg = \"light-red\"\nbold = false\n",
+        )
+        .expect("a valid theme");
+
+        assert_eq!(
+            theme.selection,
+            Style::default().bg(Color::Rgb(0x44, 0x47, 0x5a))
+        );
+        assert_eq!(
+            theme.mode,
+            Style::default()
+                .fg(Color::LightRed)
+                .remove_modifier(Modifier::BOLD)
+        );
+        assert_eq!(theme.text, Theme::default().text);
+    }
+
+    #[test]
+    fn unknown_styles_and_colors_are_errors() {
+        let error = Theme::from_toml("[nope]\nfg = \"red\"\n").expect_err("an unknown style");
+        assert_eq!(error.to_string(), "unknown style \"nope\"");
+
+        let error = Theme::from_toml("[text]\nfg = \"reddish\"\n").expect_err("an unknown color");
+        assert_eq!(error.to_string(), "text: unknown color \"reddish\"");
+    }
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::editor;
+use fluxion_core::{Editor, Mode};
+use fluxion_tui::apply_keys;
+
+/// An editor whose command history holds `commands`, oldest first.
+fn with_history(commands: &[&str]) -> Editor {
+    let mut editor = editor("");
+    editor.command_history = commands.iter().map(|c| c.to_string()).collect();
+    editor
+}
+
+#[test]
+fn executed_commands_are_remembered_newest_last() {
+    let mut editor = editor("");
+
+    apply_keys(
+        &mut editor,
+        ":set list<CR>:set nolist<CR>:set list<CR>:<CR>",
+    );
+    assert_eq!(editor.command_history, ["set nolist", "set list"]);
+}
+
+#[test]
+fn up_recalls_older_commands_and_stops_at_the_oldest() {
+    let mut editor = with_history(&["first", "second", "third"]);
+
+    apply_keys(&mut editor, ":<Up>");
+    assert_eq!(editor.command_input, "third");
+    apply_keys(&mut editor, "<Up><Up><Up>");
+    assert_eq!(editor.command_input, "first");
+    assert_eq!(editor.mode, Mode::Command);
+}
+
+#[test]
+fn down_past_the_newest_restores_the_typed_input() {
+    let mut editor = with_history(&["first", "second"]);
+
+    apply_keys(&mut editor, ":<Up><Up><Down>");
+    assert_eq!(editor.command_input, "second");
+    apply_keys(&mut editor, "<Esc>:fo<Up><Down>");
+    assert_eq!(editor.command_input, "fo");
+    apply_keys(&mut editor, "<Esc>:typed<Down>");
+    assert_eq!(editor.command_input, "typed");
+}
+
+#[test]
+fn recall_only_offers_commands_starting_with_the_input() {
+    let mut editor = with_history(&["set list", "w", "set nolist", "q"]);
+
+    apply_keys(&mut editor, ":se<Up>");
+    assert_eq!(editor.command_input, "set nolist");
+    apply_keys(&mut editor, "<Esc>:se<Up><Up>");
+    assert_eq!(editor.command_input, "set list");
+    apply_keys(&mut editor, "<Esc>:se<Up><Up><Down>");
+    assert_eq!(editor.command_input, "set nolist");
+}
+
+#[test]
+fn an_empty_history_leaves_the_input_alone() {
+    let mut editor = editor("");
+
+    apply_keys(&mut editor, ":abc<Up><Down>");
+    assert_eq!(editor.command_input, "abc");
+}
+
+#[test]
+fn tab_cycles_through_command_names() {
+    let mut editor = editor("");
+
+    apply_keys(&mut editor, ":wq<Tab>");
+    assert_eq!(editor.command_input, "wq");
+    apply_keys(&mut editor, "<Tab>");
+    assert_eq!(editor.command_input, "wqall");
+    apply_keys(&mut editor, "<Tab>");
+    assert_eq!(editor.command_input, "wq");
+}
+
+#[test]
+fn a_lone_candidate_is_taken() {
+    let mut editor = editor("");
+
+    apply_keys(&mut editor, ":bn<Tab>");
+    assert_eq!(editor.command_input, "bnext");
+}
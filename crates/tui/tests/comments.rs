@@ -0,0 +1,43 @@
+mod common;
+
+use common::{cursor, editor, text};
+use fluxion_core::Editor;
+use fluxion_tui::apply_keys;
+
+fn editor_for(filetype: &str, text: &str) -> Editor {
+    let mut editor = editor(text);
+    editor
+        .buffer_manager
+        .current_buffer_mut()
+        .set_filetype(Some(filetype.to_string()));
+    editor
+}
+
+#[test]
+fn gcc_comments_a_rust_line_after_its_indent() {
+    let mut editor = editor_for("rust", "fn main() {\n    run();\n}\n");
+
+    apply_keys(&mut editor, "jgcc");
+    assert_eq!(text(&editor), "fn main() {\n    // run();\n}\n");
+    apply_keys(&mut editor, "gcc");
+    assert_eq!(text(&editor), "fn main() {\n    run();\n}\n");
+    assert_eq!(cursor(&editor).0, 1);
+}
+
+#[test]
+fn gc_comments_a_python_selection_at_its_smallest_indent() {
+    let mut editor = editor_for("python", "if x:\n    y()\n\nz()\n");
+
+    apply_keys(&mut editor, "Vjjjgc");
+    assert_eq!(text(&editor), "# if x:\n#     y()\n\n# z()\n");
+    apply_keys(&mut editor, "ggVjjjgc");
+    assert_eq!(text(&editor), "if x:\n    y()\n\nz()\n");
+}
+
+#[test]
+fn a_partly_commented_selection_is_commented_again() {
+    let mut editor = editor_for("python", "# a\nb\n");
+
+    apply_keys(&mut editor, "Vjgc");
+    assert_eq!(text(&editor), "# # a\n# b\n");
+}
@@ -0,0 +1,29 @@
+//! Helpers shared by the tests that drive an editor through its keybindings.
+
+#![allow(dead_code)]
+
+use fluxion_core::Editor;
+
+/// An editor whose current buffer holds `text`, with the cursor at the start.
+pub fn editor(text: &str) -> Editor {
+    let mut editor = Editor::new("");
+    editor
+        .buffer_manager
+        .current_buffer_mut()
+        .set_text(text.into());
+    editor
+}
+
+pub fn text(editor: &Editor) -> String {
+    editor.get_current_text().to_string()
+}
+
+/// The cursor as `(row, col)`.
+pub fn cursor(editor: &Editor) -> (usize, usize) {
+    (editor.cursor.row, editor.cursor.col)
+}
+
+/// `count` numbered lines, `line 1` to `line {count}`.
+pub fn numbered_lines(count: usize) -> String {
+    (1..=count).map(|n| format!("line {}\n", n)).collect()
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use common::{cursor, editor, text};
+use fluxion_core::Cursor;
+use fluxion_tui::apply_keys;
+
+#[test]
+fn ctrl_n_adds_a_cursor_at_the_next_whole_word_match() {
+    let mut editor = editor("foo food\nfoo\n");
+    apply_keys(&mut editor, "<C-n>");
+
+    assert_eq!(editor.secondary_cursors.len(), 1);
+    assert_eq!(
+        (
+            editor.secondary_cursors[0].row,
+            editor.secondary_cursors[0].col
+        ),
+        (1, 0)
+    );
+}
+
+#[test]
+fn typing_edits_at_every_cursor() {
+    let mut editor = editor("foo bar\nfoo baz\n");
+    apply_keys(&mut editor, "<C-n>ix<Esc>");
+
+    assert_eq!(text(&editor), "xfoo bar\nxfoo baz\n");
+    assert_eq!(cursor(&editor), (0, 1));
+}
+
+#[test]
+fn backspace_deletes_at_every_cursor() {
+    let mut editor = editor("ab ab\n");
+    apply_keys(&mut editor, "l<C-n>i<BS><Esc>");
+
+    assert_eq!(text(&editor), "b b\n");
+}
+
+#[test]
+fn esc_in_normal_mode_drops_the_extra_cursors() {
+    let mut editor = editor("foo foo\n");
+    apply_keys(&mut editor, "<C-n>");
+    assert_eq!(editor.secondary_cursors.len(), 1);
+
+    apply_keys(&mut editor, "<Esc>");
+    assert!(editor.secondary_cursors.is_empty());
+}
+
+#[test]
+fn cursors_that_meet_go_on_as_one() {
+    let mut editor = editor("ab\n");
+    apply_keys(&mut editor, "a");
+    editor.secondary_cursors.push(Cursor::new(0, 2));
+
+    apply_keys(&mut editor, "<BS>");
+    assert_eq!(text(&editor), "\n");
+    assert_eq!(cursor(&editor), (0, 0));
+    assert!(editor.secondary_cursors.is_empty());
+    apply_keys(&mut editor, "x");
+    assert_eq!(text(&editor), "x\n");
+    assert_eq!(cursor(&editor), (0, 1));
+}
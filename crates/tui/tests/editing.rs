@@ -0,0 +1,475 @@
+mod common;
+
+use common::{cursor, editor, text};
+use fluxion_core::{DEFAULT_REGISTER, Mode, Register};
+use fluxion_tui::apply_keys;
+
+#[test]
+fn undo_removes_a_whole_insert_session() {
+    let mut editor = editor("end\n");
+
+    apply_keys(&mut editor, "ihello <Esc>");
+    assert_eq!(text(&editor), "hello end\n");
+    apply_keys(&mut editor, "u");
+    assert_eq!(text(&editor), "end\n");
+    assert_eq!(cursor(&editor), (0, 0));
+}
+
+#[test]
+fn undo_after_a_newline_joins_the_lines_again() {
+    let mut editor = editor("ab\n");
+
+    apply_keys(&mut editor, "li<CR><Esc>");
+    assert_eq!(text(&editor), "a\nb\n");
+    apply_keys(&mut editor, "u");
+    assert_eq!(text(&editor), "ab\n");
+}
+
+#[test]
+fn redo_reapplies_an_undone_edit() {
+    let mut editor = editor("one\ntwo\n");
+
+    apply_keys(&mut editor, "ddu<C-r>");
+    assert_eq!(text(&editor), "two\n");
+}
+
+#[test]
+fn a_new_edit_invalidates_redo() {
+    let mut editor = editor("one\ntwo\n");
+
+    apply_keys(&mut editor, "ddux<C-r>");
+    assert_eq!(text(&editor), "ne\ntwo\n");
+}
+
+#[test]
+fn dd_on_the_first_line_lands_on_the_next_first_non_blank() {
+    let mut editor = editor("one\n  two\nthree\n");
+
+    apply_keys(&mut editor, "dd");
+    assert_eq!(text(&editor), "  two\nthree\n");
+    assert_eq!(cursor(&editor), (0, 2));
+    assert_eq!(
+        editor.registers.get(&DEFAULT_REGISTER),
+        Some(&Register::linewise("one\n".into()))
+    );
+}
+
+#[test]
+fn dd_on_a_middle_line() {
+    let mut editor = editor("one\ntwo\nthree\n");
+
+    apply_keys(&mut editor, "jdd");
+    assert_eq!(text(&editor), "one\nthree\n");
+    assert_eq!(cursor(&editor), (1, 0));
+}
+
+#[test]
+fn dd_on_the_last_line_moves_up() {
+    let mut editor = editor("one\n  two\nthree");
+
+    apply_keys(&mut editor, "Gdd");
+    assert_eq!(text(&editor), "one\n  two");
+    assert_eq!(cursor(&editor), (1, 2));
+}
+
+#[test]
+fn dd_on_the_only_line_leaves_an_empty_buffer() {
+    let mut editor = editor("only\n");
+
+    apply_keys(&mut editor, "dd");
+    assert_eq!(text(&editor), "");
+    assert_eq!(cursor(&editor), (0, 0));
+}
+
+#[test]
+fn x_deletes_the_char_under_the_cursor() {
+    let mut editor = editor("abc\n");
+
+    apply_keys(&mut editor, "lx");
+    assert_eq!(text(&editor), "ac\n");
+    assert_eq!(cursor(&editor), (0, 1));
+}
+
+#[test]
+fn x_at_the_end_of_a_line_moves_left() {
+    let mut editor = editor("abc\nnext\n");
+
+    apply_keys(&mut editor, "$x");
+    assert_eq!(text(&editor), "ab\nnext\n");
+    assert_eq!(cursor(&editor), (0, 1));
+}
+
+#[test]
+fn x_on_an_empty_line_does_nothing() {
+    let mut editor = editor("\nnext\n");
+
+    apply_keys(&mut editor, "x");
+    assert_eq!(text(&editor), "\nnext\n");
+    assert_eq!(cursor(&editor), (0, 0));
+    assert!(!editor.buffer_manager.current_buffer().dirty);
+}
+
+#[test]
+fn insert_entries_place_the_cursor() {
+    let cases = [
+        ("a", "abc\n", (0, 1)),
+        ("A", "abc\n", (0, 3)),
+        ("I", "  abc\n", (0, 2)),
+        ("a", "\n", (0, 0)),
+        ("A", "\n", (0, 0)),
+        ("I", "\n", (0, 0)),
+    ];
+    for (keys, start, expected) in cases {
+        let mut editor = editor(start);
+        apply_keys(&mut editor, keys);
+        assert_eq!(editor.mode, Mode::Insert, "{} on {:?}", keys, start);
+        assert_eq!(cursor(&editor), expected, "{} on {:?}", keys, start);
+    }
+}
+
+#[test]
+fn o_and_capital_o_open_a_line_below_or_above() {
+    let mut editor = editor("one\ntwo\n");
+
+    apply_keys(&mut editor, "onew<Esc>");
+    assert_eq!(text(&editor), "one\nnew\ntwo\n");
+
+    apply_keys(&mut editor, "ggOtop<Esc>");
+    assert_eq!(text(&editor), "top\none\nnew\ntwo\n");
+}
+
+#[test]
+fn o_on_an_empty_buffer_opens_the_second_line() {
+    let mut editor = editor("");
+
+    apply_keys(&mut editor, "o");
+    assert_eq!(text(&editor), "\n");
+    assert_eq!(cursor(&editor), (1, 0));
+}
+
+#[test]
+fn capital_o_on_an_empty_buffer_stays_on_the_first_line() {
+    let mut editor = editor("");
+
+    apply_keys(&mut editor, "O");
+    assert_eq!(text(&editor), "\n");
+    assert_eq!(cursor(&editor), (0, 0));
+}
+
+#[test]
+fn a_count_repeats_dd_and_x() {
+    let mut editor = editor("one\ntwo\nthree\n");
+
+    apply_keys(&mut editor, "2dd");
+    assert_eq!(text(&editor), "three\n");
+    assert_eq!(
+        editor.registers.get(&DEFAULT_REGISTER),
+        Some(&Register::linewise("one\ntwo\n".into()))
+    );
+
+    apply_keys(&mut editor, "3x");
+    assert_eq!(text(&editor), "ee\n");
+}
+
+#[test]
+fn tab_inserts_a_literal_tab() {
+    let mut editor = editor("x\n");
+
+    apply_keys(&mut editor, "i<Tab><Esc>");
+    assert_eq!(text(&editor), "\tx\n");
+}
+
+#[test]
+fn expandtab_inserts_spaces_to_the_next_tab_stop() {
+    let mut editor = editor("\n");
+    editor.options.expand_tabs = true;
+
+    apply_keys(&mut editor, "iab<Tab>c<Tab><Esc>");
+    assert_eq!(text(&editor), "ab  c   \n");
+}
+
+#[test]
+fn enter_copies_the_indent_of_the_line() {
+    let mut editor = editor("    code\n");
+
+    apply_keys(&mut editor, "A<CR>");
+    assert_eq!(text(&editor), "    code\n    \n");
+    assert_eq!(cursor(&editor), (1, 4));
+}
+
+#[test]
+fn enter_after_an_unindented_line_starts_at_column_zero() {
+    let mut editor = editor("code\n");
+
+    apply_keys(&mut editor, "A<CR>x<Esc>");
+    assert_eq!(text(&editor), "code\nx\n");
+}
+
+#[test]
+fn o_and_capital_o_copy_the_indent_too() {
+    let mut editor = editor("\tcode\n");
+
+    apply_keys(&mut editor, "ox<Esc>");
+    assert_eq!(text(&editor), "\tcode\n\tx\n");
+    apply_keys(&mut editor, "Oy<Esc>");
+    assert_eq!(text(&editor), "\tcode\n\ty\n\tx\n");
+}
+
+#[test]
+fn without_autoindent_new_lines_start_at_column_zero() {
+    let mut editor = editor("    code\n");
+    editor.options.auto_indent = false;
+
+    apply_keys(&mut editor, "A<CR>x<Esc>");
+    assert_eq!(text(&editor), "    code\nx\n");
+}
+
+#[test]
+fn double_angle_brackets_shift_a_line() {
+    let mut editor = editor("code\n");
+
+    apply_keys(&mut editor, ">>");
+    assert_eq!(text(&editor), "\tcode\n");
+    apply_keys(&mut editor, "<<");
+    assert_eq!(text(&editor), "code\n");
+}
+
+#[test]
+fn shifting_respects_expandtab_and_shiftwidth() {
+    let mut editor = editor("code\n");
+    editor.options.expand_tabs = true;
+    editor.options.shift_width = 2;
+
+    apply_keys(&mut editor, ">>>>");
+    assert_eq!(text(&editor), "    code\n");
+    assert_eq!(cursor(&editor), (0, 4));
+}
+
+#[test]
+fn visual_shift_moves_every_selected_line() {
+    let mut editor = editor("a\nb\nc\nd\n");
+    editor.options.expand_tabs = true;
+
+    apply_keys(&mut editor, "Vjj>");
+    assert_eq!(text(&editor), "    a\n    b\n    c\nd\n");
+    assert_eq!(editor.mode, Mode::Normal);
+}
+
+#[test]
+fn dedenting_an_unindented_line_does_nothing() {
+    let mut editor = editor("code\n");
+
+    apply_keys(&mut editor, "<<");
+    assert_eq!(text(&editor), "code\n");
+}
+
+#[test]
+fn dedenting_mixed_tabs_and_spaces_removes_one_shiftwidth() {
+    // Two spaces, a tab to column 4, then two more spaces: 6 columns.
+    let mut editor = editor("  \t  code\n");
+    editor.options.expand_tabs = true;
+
+    apply_keys(&mut editor, "<<");
+    assert_eq!(text(&editor), "  code\n");
+}
+
+#[test]
+fn j_replaces_the_next_lines_indent_with_a_single_space() {
+    let mut editor = editor("one\n    two\n");
+
+    apply_keys(&mut editor, "J");
+    assert_eq!(text(&editor), "one two\n");
+    assert_eq!(cursor(&editor), (0, 3));
+}
+
+#[test]
+fn j_after_trailing_whitespace_adds_no_space() {
+    let mut editor = editor("one  \n\ttwo\n");
+
+    apply_keys(&mut editor, "J");
+    assert_eq!(text(&editor), "one  two\n");
+}
+
+#[test]
+fn j_onto_a_closing_paren_adds_no_space() {
+    let mut editor = editor("call(\n  )\n");
+
+    apply_keys(&mut editor, "J");
+    assert_eq!(text(&editor), "call()\n");
+}
+
+#[test]
+fn j_with_a_count_joins_that_many_lines() {
+    let mut editor = editor("a\nb\nc\nd\n");
+
+    apply_keys(&mut editor, "3J");
+    assert_eq!(text(&editor), "a b c\nd\n");
+}
+
+#[test]
+fn j_on_the_last_line_does_nothing() {
+    let mut editor = editor("one\ntwo");
+
+    apply_keys(&mut editor, "jJ");
+    assert_eq!(text(&editor), "one\ntwo");
+    assert!(!editor.buffer_manager.current_buffer().dirty);
+}
+
+#[test]
+fn dot_repeats_an_insert_session() {
+    let mut editor = editor("\n");
+    apply_keys(&mut editor, "ihello<Esc>");
+    assert_eq!(text(&editor), "hello\n");
+
+    apply_keys(&mut editor, ".");
+    assert_eq!(text(&editor), "hellohello\n");
+}
+
+#[test]
+fn dot_repeats_dd_and_x() {
+    let mut editor = editor("one\ntwo\nthree\nfour\n");
+    apply_keys(&mut editor, "dd.");
+    assert_eq!(text(&editor), "three\nfour\n");
+
+    apply_keys(&mut editor, "x..");
+    assert_eq!(text(&editor), "ee\nfour\n");
+}
+
+#[test]
+fn replace_mode_overtypes_within_the_line() {
+    let mut editor = editor("abcd\n");
+    apply_keys(&mut editor, "lRxy");
+
+    assert_eq!(editor.mode, Mode::Replace);
+    assert_eq!(text(&editor), "axyd\n");
+    assert_eq!(cursor(&editor), (0, 3));
+}
+
+#[test]
+fn replace_mode_appends_past_the_end_of_the_line() {
+    let mut editor = editor("ab\nnext\n");
+    apply_keys(&mut editor, "lRxyz<Esc>");
+
+    assert_eq!(text(&editor), "axyz\nnext\n");
+    assert_eq!(editor.mode, Mode::Normal);
+}
+
+#[test]
+fn backspace_in_replace_mode_restores_the_original() {
+    let mut editor = editor("abcd\n");
+    apply_keys(&mut editor, "Rxyz<BS><BS>");
+    assert_eq!(text(&editor), "xbcd\n");
+}
+
+#[test]
+fn backspace_in_replace_mode_removes_appended_chars() {
+    let mut editor = editor("ab\n");
+    apply_keys(&mut editor, "lRxyz<BS><BS>");
+    assert_eq!(text(&editor), "ax\n");
+}
+
+#[test]
+fn inserting_after_a_multi_byte_char() {
+    let mut editor = editor("é\n");
+
+    apply_keys(&mut editor, "ax<Esc>");
+    assert_eq!(text(&editor), "éx\n");
+    apply_keys(&mut editor, "0x");
+    assert_eq!(text(&editor), "x\n");
+}
+
+#[test]
+fn inserting_between_wide_chars() {
+    let mut editor = editor("日本\n");
+
+    apply_keys(&mut editor, "ax<Esc>");
+    assert_eq!(text(&editor), "日x本\n");
+}
+
+#[test]
+fn tilde_toggles_case_and_advances() {
+    let mut editor = editor("aB1c\n");
+
+    apply_keys(&mut editor, "~~~");
+    assert_eq!(text(&editor), "Ab1c\n");
+    assert_eq!(cursor(&editor), (0, 3));
+}
+
+#[test]
+fn tilde_at_the_end_of_a_line_stays_put() {
+    let mut editor = editor("ab\nc\n");
+
+    apply_keys(&mut editor, "$~");
+    assert_eq!(text(&editor), "aB\nc\n");
+    assert_eq!(cursor(&editor), (0, 1));
+    apply_keys(&mut editor, "~");
+    assert_eq!(text(&editor), "ab\nc\n");
+}
+
+#[test]
+fn gu_operators_change_the_case_of_a_motion() {
+    let mut editor = editor("hello world\n");
+
+    apply_keys(&mut editor, "gUw");
+    assert_eq!(text(&editor), "HELLO world\n");
+    assert_eq!(cursor(&editor), (0, 0));
+    apply_keys(&mut editor, "wg~w");
+    assert_eq!(text(&editor), "HELLO WORLD\n");
+    apply_keys(&mut editor, "0gu$");
+    assert_eq!(text(&editor), "hello world\n");
+}
+
+#[test]
+fn visual_capital_u_uppercases_the_selection() {
+    let mut editor = editor("straße\n");
+
+    apply_keys(&mut editor, "vlU");
+    assert_eq!(text(&editor), "STraße\n");
+}
+
+#[test]
+fn autopairs_closes_brackets_and_quotes() {
+    let mut editor = editor("\n");
+    editor.options.autopairs = true;
+
+    apply_keys(&mut editor, "if(x");
+    assert_eq!(text(&editor), "f(x)\n");
+    assert_eq!(cursor(&editor), (0, 3));
+    apply_keys(&mut editor, "<Esc>A [\"");
+    assert_eq!(text(&editor), "f(x) [\"\"]\n");
+}
+
+#[test]
+fn autopairs_leaves_an_apostrophe_alone() {
+    let mut editor = editor("\n");
+    editor.options.autopairs = true;
+
+    apply_keys(&mut editor, "idon't<Esc>");
+    assert_eq!(text(&editor), "don't\n");
+}
+
+#[test]
+fn typing_the_closer_steps_over_it() {
+    let mut editor = editor("\n");
+    editor.options.autopairs = true;
+
+    apply_keys(&mut editor, "i{a}b<Esc>");
+    assert_eq!(text(&editor), "{a}b\n");
+}
+
+#[test]
+fn backspace_in_an_empty_pair_removes_both() {
+    let mut editor = editor("\n");
+    editor.options.autopairs = true;
+
+    apply_keys(&mut editor, "ix(<BS>y<Esc>");
+    assert_eq!(text(&editor), "xy\n");
+}
+
+#[test]
+fn without_autopairs_brackets_are_typed_as_is() {
+    let mut editor = editor("\n");
+
+    apply_keys(&mut editor, "i(<BS>)<Esc>");
+    assert_eq!(text(&editor), ")\n");
+}
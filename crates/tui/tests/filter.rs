@@ -0,0 +1,39 @@
+//! Filters run through `sh`, so these only run on Unix.
+#![cfg(unix)]
+
+mod common;
+
+use common::{cursor, editor, text};
+use fluxion_core::Mode;
+use fluxion_tui::apply_keys;
+
+#[test]
+fn bang_motion_starts_a_filter_over_the_lines_it_covers() {
+    let mut editor = editor("b\na\nc\nz\n");
+
+    apply_keys(&mut editor, "!2j");
+    assert_eq!(editor.mode, Mode::Command);
+    assert_eq!(editor.command_input, ".,.+2!");
+    apply_keys(&mut editor, "sort -r<CR>");
+    assert_eq!(text(&editor), "c\nb\na\nz\n");
+    assert_eq!(cursor(&editor), (0, 0));
+}
+
+#[test]
+fn bang_bang_filters_the_cursor_line() {
+    let mut editor = editor("one\ntwo\n");
+
+    apply_keys(&mut editor, "j!!tr a-z A-Z<CR>");
+    assert_eq!(text(&editor), "one\nTWO\n");
+}
+
+#[test]
+fn bang_on_a_selection_filters_its_lines() {
+    let mut editor = editor("one\ntwo\nthree\n");
+
+    apply_keys(&mut editor, "jlvj!");
+    assert_eq!(editor.command_input, "'<,'>!");
+    apply_keys(&mut editor, "tr a-z A-Z<CR>");
+    assert_eq!(text(&editor), "one\nTWO\nTHREE\n");
+    assert_eq!(editor.mode, Mode::Normal);
+}
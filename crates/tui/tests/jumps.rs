@@ -0,0 +1,39 @@
+mod common;
+
+use common::{cursor, editor, numbered_lines};
+use fluxion_tui::apply_keys;
+
+#[test]
+fn ctrl_o_and_ctrl_i_walk_the_jump_list() {
+    let mut editor = editor(&numbered_lines(50));
+    editor.set_viewport_size(80, 10);
+    apply_keys(&mut editor, "jjG:20<CR>/line 3<CR>");
+    assert_eq!(cursor(&editor), (29, 0));
+
+    apply_keys(&mut editor, "<C-o>");
+    assert_eq!(cursor(&editor), (19, 0));
+    apply_keys(&mut editor, "<C-o>");
+    assert_eq!(cursor(&editor), (49, 0));
+    apply_keys(&mut editor, "<C-o>");
+    assert_eq!(cursor(&editor), (2, 0));
+    apply_keys(&mut editor, "<C-o>");
+    assert_eq!(cursor(&editor), (2, 0));
+
+    apply_keys(&mut editor, "<Tab><C-i>");
+    assert_eq!(cursor(&editor), (19, 0));
+    apply_keys(&mut editor, "<C-i><C-i><C-i>");
+    assert_eq!(cursor(&editor), (29, 0));
+}
+
+#[test]
+fn a_new_jump_drops_the_newer_entries() {
+    let mut editor = editor(&numbered_lines(50));
+    apply_keys(&mut editor, "G:10<CR><C-o><C-o>");
+    assert_eq!(cursor(&editor), (0, 0));
+    apply_keys(&mut editor, "jj:5<CR>");
+
+    apply_keys(&mut editor, "<C-o><C-o>");
+    assert_eq!(cursor(&editor), (2, 0));
+    apply_keys(&mut editor, "<C-i><C-i>");
+    assert_eq!(cursor(&editor), (4, 0));
+}
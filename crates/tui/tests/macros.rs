@@ -0,0 +1,39 @@
+mod common;
+
+use common::{cursor, editor, text};
+use fluxion_tui::apply_keys;
+
+#[test]
+fn a_recorded_insert_and_move_replays() {
+    let mut editor = editor("a\nb\nc\n");
+    apply_keys(&mut editor, "qaI-<Esc>jq");
+    assert_eq!(text(&editor), "-a\nb\nc\n");
+    assert_eq!(cursor(&editor).0, 1);
+
+    apply_keys(&mut editor, "@a");
+    assert_eq!(text(&editor), "-a\n-b\nc\n");
+    assert_eq!(cursor(&editor).0, 2);
+}
+
+#[test]
+fn a_count_replays_the_macro_that_many_times() {
+    let mut editor = editor("a\nb\nc\nd\ne\n");
+    apply_keys(&mut editor, "qqI-<Esc>jq2@q");
+    assert_eq!(text(&editor), "-a\n-b\n-c\nd\ne\n");
+}
+
+#[test]
+fn double_at_replays_the_last_macro() {
+    let mut editor = editor("a\nb\nc\nd\ne\n");
+    apply_keys(&mut editor, "qqI-<Esc>jq2@q@@");
+    assert_eq!(text(&editor), "-a\n-b\n-c\n-d\ne\n");
+}
+
+#[test]
+fn the_recording_is_kept_in_its_register() {
+    let mut editor = editor("a\n");
+    apply_keys(&mut editor, "qaA!<Esc>q");
+
+    let register = editor.registers.get(&'a').map(|r| r.text.as_str());
+    assert_eq!(register, Some("A!<Esc>"));
+}
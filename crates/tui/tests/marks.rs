@@ -0,0 +1,43 @@
+mod common;
+
+use common::{cursor, editor};
+use fluxion_core::MessageKind;
+use fluxion_tui::apply_keys;
+
+#[test]
+fn backtick_jumps_to_a_set_mark() {
+    let mut editor = editor("one\ntwo three\nfour\n");
+    apply_keys(&mut editor, "jwmaG");
+    assert_eq!(cursor(&editor).0, 2);
+
+    apply_keys(&mut editor, "`a");
+    assert_eq!(cursor(&editor), (1, 4));
+}
+
+#[test]
+fn a_mark_keeps_its_position_after_editing_above_it() {
+    let mut editor = editor("one\ntwo\nthree\n");
+    apply_keys(&mut editor, "jjmaggOnew<Esc>");
+
+    apply_keys(&mut editor, "`a");
+    assert_eq!(cursor(&editor), (2, 0));
+}
+
+#[test]
+fn a_mark_past_the_end_is_clamped() {
+    let mut editor = editor("one\ntwo\nthree\n");
+    apply_keys(&mut editor, "Gmaggdjdd");
+
+    apply_keys(&mut editor, "`a");
+    assert_eq!(cursor(&editor), (0, 0));
+}
+
+#[test]
+fn jumping_to_an_unset_mark_is_an_error() {
+    let mut editor = editor("one\n");
+    apply_keys(&mut editor, "`b");
+    assert_eq!(
+        editor.status_message,
+        Some(("E20: Mark not set".to_string(), MessageKind::Error))
+    );
+}
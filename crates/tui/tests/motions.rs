@@ -0,0 +1,319 @@
+mod common;
+
+use common::{cursor, editor, numbered_lines, text};
+use fluxion_tui::apply_keys;
+
+#[test]
+fn scrolling_down_keeps_a_margin_below_the_cursor() {
+    let mut editor = editor(&numbered_lines(50));
+    editor.set_viewport_size(80, 10);
+
+    apply_keys(&mut editor, "jjjjjj");
+    assert_eq!(editor.scroll_offset, 0);
+    apply_keys(&mut editor, "jjj");
+    assert_eq!(cursor(&editor), (9, 0));
+    assert_eq!(editor.scroll_offset, 3);
+}
+
+#[test]
+fn scrolling_up_keeps_a_margin_above_the_cursor() {
+    let mut editor = editor(&numbered_lines(50));
+    editor.set_viewport_size(80, 10);
+
+    apply_keys(&mut editor, "G");
+    assert_eq!(editor.scroll_offset, 41);
+    apply_keys(&mut editor, "kkkkkkkk");
+    assert_eq!(editor.cursor.row, 41);
+    assert_eq!(editor.scroll_offset, 38);
+}
+
+#[test]
+fn a_buffer_shorter_than_the_view_never_scrolls() {
+    let mut editor = editor(&numbered_lines(5));
+    editor.set_viewport_size(80, 10);
+
+    apply_keys(&mut editor, "G");
+    assert_eq!(editor.scroll_offset, 0);
+}
+
+#[test]
+fn dollar_moves_to_the_last_char_and_zero_back_to_the_first_column() {
+    let mut editor = editor("    indented line\nnext\n");
+
+    apply_keys(&mut editor, "$");
+    assert_eq!(cursor(&editor), (0, 16));
+    apply_keys(&mut editor, "0");
+    assert_eq!(cursor(&editor), (0, 0));
+}
+
+#[test]
+fn caret_moves_to_the_first_non_blank_char() {
+    let mut editor = editor("  \tword\n");
+
+    apply_keys(&mut editor, "$^");
+    assert_eq!(cursor(&editor), (0, 3));
+}
+
+#[test]
+fn line_motions_stay_put_on_an_empty_line() {
+    let mut editor = editor("\nnext\n");
+
+    for keys in ["$", "0", "^"] {
+        apply_keys(&mut editor, keys);
+        assert_eq!(cursor(&editor), (0, 0), "after {}", keys);
+    }
+}
+
+#[test]
+fn gg_on_an_empty_buffer_stays_on_the_first_line() {
+    let mut editor = editor("");
+
+    apply_keys(&mut editor, "gg");
+    assert_eq!(cursor(&editor), (0, 0));
+    assert_eq!(editor.scroll_offset, 0);
+}
+
+#[test]
+fn capital_g_lands_on_the_last_non_empty_line() {
+    let mut editor = editor("first\nsecond\nlast\n");
+
+    apply_keys(&mut editor, "G");
+    assert_eq!(cursor(&editor).0, 2);
+    apply_keys(&mut editor, "$gg");
+    assert_eq!(cursor(&editor), (0, 3));
+}
+
+#[test]
+fn a_single_g_does_not_move_the_cursor() {
+    let mut editor = editor(&numbered_lines(5));
+
+    apply_keys(&mut editor, "Gg");
+    assert_eq!(cursor(&editor).0, 4);
+    apply_keys(&mut editor, "Ggg");
+    assert_eq!(cursor(&editor).0, 0);
+}
+
+#[test]
+fn a_count_repeats_a_motion() {
+    let mut editor = editor("1\n2\n3\n4\n5");
+
+    apply_keys(&mut editor, "3j");
+    assert_eq!(cursor(&editor), (3, 0));
+    apply_keys(&mut editor, "10j");
+    assert_eq!(cursor(&editor), (4, 0));
+}
+
+#[test]
+fn a_count_past_the_end_of_the_line_clamps() {
+    let mut editor = editor("abcde\n");
+
+    apply_keys(&mut editor, "10l");
+    assert_eq!(cursor(&editor), (0, 5));
+}
+
+#[test]
+fn zero_after_a_count_digit_is_part_of_the_count() {
+    let mut editor = editor(&numbered_lines(12));
+
+    apply_keys(&mut editor, "l10j");
+    assert_eq!(cursor(&editor), (10, 1));
+}
+
+#[test]
+fn a_huge_count_stops_growing_and_moves_to_the_last_line() {
+    let mut editor = editor("1\n2\n3");
+
+    apply_keys(&mut editor, "99999999999999999999");
+    assert_eq!(editor.pending_count, Some(99_999));
+    apply_keys(&mut editor, "j");
+    assert_eq!(cursor(&editor), (2, 0));
+}
+
+#[test]
+fn display_columns_expand_tabs_to_the_next_stop() {
+    let mut editor = editor("\tx\na\tb\n");
+    editor.options.tab_width = 4;
+
+    assert_eq!(editor.display_col(0, 1), 4);
+    assert_eq!(editor.display_col(1, 1), 1);
+    assert_eq!(editor.display_col(1, 2), 4);
+    editor.options.tab_width = 8;
+    assert_eq!(editor.display_col(1, 2), 8);
+}
+
+#[test]
+fn vertical_motion_keeps_the_screen_column_across_tabs() {
+    let mut editor = editor("\tx\nabcdefgh\n");
+    editor.options.tab_width = 4;
+
+    apply_keys(&mut editor, "$j");
+    assert_eq!(cursor(&editor), (1, 4));
+    apply_keys(&mut editor, "k");
+    assert_eq!(cursor(&editor), (0, 1));
+}
+
+#[test]
+fn f_and_capital_f_land_on_the_char() {
+    let mut editor = editor("a.b.c.d\n");
+
+    apply_keys(&mut editor, "f.");
+    assert_eq!(cursor(&editor), (0, 1));
+    apply_keys(&mut editor, "$F.");
+    assert_eq!(cursor(&editor), (0, 5));
+}
+
+#[test]
+fn t_and_capital_t_stop_one_short() {
+    let mut editor = editor("a.b.c.d\n");
+
+    apply_keys(&mut editor, "t.");
+    assert_eq!(cursor(&editor), (0, 0));
+    apply_keys(&mut editor, "lt.");
+    assert_eq!(cursor(&editor), (0, 2));
+    apply_keys(&mut editor, "$T.");
+    assert_eq!(cursor(&editor), (0, 6));
+}
+
+#[test]
+fn a_missing_char_leaves_the_cursor_alone() {
+    let mut editor = editor("abc\nxyz\n");
+
+    apply_keys(&mut editor, "lfx");
+    assert_eq!(cursor(&editor), (0, 1));
+    apply_keys(&mut editor, "Fz");
+    assert_eq!(cursor(&editor), (0, 1));
+}
+
+#[test]
+fn semicolon_and_comma_repeat_the_last_find() {
+    let mut editor = editor("a.b.c.d\n");
+
+    apply_keys(&mut editor, "f.;;");
+    assert_eq!(cursor(&editor), (0, 5));
+    apply_keys(&mut editor, "f.;;,");
+    assert_eq!(cursor(&editor), (0, 3));
+}
+
+#[test]
+fn percent_jumps_between_nested_brackets() {
+    let mut editor = editor("f(a, (b), [c])\n");
+
+    apply_keys(&mut editor, "l%");
+    assert_eq!(cursor(&editor), (0, 13));
+    apply_keys(&mut editor, "%");
+    assert_eq!(cursor(&editor), (0, 1));
+    apply_keys(&mut editor, "fb%");
+    assert_eq!(cursor(&editor), (0, 5));
+}
+
+#[test]
+fn percent_finds_the_first_bracket_after_the_cursor() {
+    let mut editor = editor("if x { y }\n");
+
+    apply_keys(&mut editor, "%");
+    assert_eq!(cursor(&editor), (0, 9));
+}
+
+#[test]
+fn percent_matches_brackets_across_lines() {
+    let mut editor = editor("fn f() {\n    {\n    }\n}\n");
+
+    apply_keys(&mut editor, "$%");
+    assert_eq!(cursor(&editor), (3, 0));
+    apply_keys(&mut editor, "k^%");
+    assert_eq!(cursor(&editor), (1, 4));
+}
+
+#[test]
+fn percent_on_an_unmatched_bracket_stays_put() {
+    let mut editor = editor("(a]\nb)\n");
+
+    apply_keys(&mut editor, "ll%");
+    assert_eq!(cursor(&editor), (0, 2));
+}
+
+#[test]
+fn percent_without_a_closing_bracket_stays_put() {
+    let mut editor = editor("x (a\n");
+
+    apply_keys(&mut editor, "%");
+    assert_eq!(cursor(&editor), (0, 0));
+}
+
+#[test]
+fn half_page_scrolling_moves_the_cursor_and_the_view_together() {
+    let mut editor = editor(&numbered_lines(50));
+    editor.set_viewport_size(80, 10);
+
+    // The cursor keeps its margin from the top of the scrolled view.
+    apply_keys(&mut editor, "<C-d>");
+    assert_eq!((editor.cursor.row, editor.scroll_offset), (8, 5));
+    apply_keys(&mut editor, "<C-u>");
+    assert_eq!((editor.cursor.row, editor.scroll_offset), (3, 0));
+}
+
+#[test]
+fn scrolling_up_at_the_top_stays_on_the_first_line() {
+    let mut editor = editor(&numbered_lines(50));
+    editor.set_viewport_size(80, 10);
+
+    apply_keys(&mut editor, "jj<C-u>");
+    assert_eq!((editor.cursor.row, editor.scroll_offset), (0, 0));
+    apply_keys(&mut editor, "<C-b>");
+    assert_eq!((editor.cursor.row, editor.scroll_offset), (0, 0));
+}
+
+#[test]
+fn scrolling_down_at_the_bottom_stops_at_the_last_line() {
+    let mut editor = editor(&numbered_lines(50));
+    editor.set_viewport_size(80, 10);
+
+    apply_keys(&mut editor, "<C-f><C-f><C-f><C-f><C-f><C-f><C-f>");
+    assert_eq!(editor.scroll_offset, 41);
+    let bottom = cursor(&editor);
+    apply_keys(&mut editor, "<C-d>");
+    assert_eq!(editor.scroll_offset, 41);
+    assert_eq!(cursor(&editor), bottom);
+}
+
+#[test]
+fn l_and_h_step_over_a_whole_emoji_sequence() {
+    // A family emoji: four people joined by zero-width joiners.
+    let mut editor = editor("a👨‍👩‍👧‍👦b\n");
+
+    apply_keys(&mut editor, "l");
+    assert_eq!(cursor(&editor), (0, 1));
+    apply_keys(&mut editor, "l");
+    assert_eq!(cursor(&editor), (0, 8));
+    apply_keys(&mut editor, "h");
+    assert_eq!(cursor(&editor), (0, 1));
+}
+
+#[test]
+fn a_combining_accent_moves_with_its_base_char() {
+    let mut editor = editor("e\u{301}x\n");
+
+    apply_keys(&mut editor, "l");
+    assert_eq!(cursor(&editor), (0, 2));
+    apply_keys(&mut editor, "x");
+    assert_eq!(text(&editor), "e\u{301}\n");
+}
+
+#[test]
+fn wide_chars_take_two_screen_columns() {
+    let mut editor = editor("日本x\n");
+    editor.set_viewport_size(80, 10);
+
+    apply_keys(&mut editor, "$");
+    assert_eq!(cursor(&editor), (0, 2));
+    assert_eq!(editor.display_col(0, 2), 4);
+    assert_eq!(editor.screen_position(editor.cursor), Some((0, 4)));
+}
+
+#[test]
+fn x_deletes_a_whole_emoji_sequence() {
+    let mut editor = editor("👨‍👩‍👧‍👦b\n");
+
+    apply_keys(&mut editor, "x");
+    assert_eq!(text(&editor), "b\n");
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::editor;
+use fluxion_core::{Cursor, EditEvent, EditObserver, Editor};
+use fluxion_tui::apply_keys;
+use std::{cell::RefCell, rc::Rc};
+
+/// Keeps every event it is told about where the test can read them.
+struct Recorder(Rc<RefCell<Vec<EditEvent>>>);
+
+impl EditObserver for Recorder {
+    fn on_edit(&mut self, event: &EditEvent) {
+        self.0.borrow_mut().push(event.clone());
+    }
+}
+
+fn recorded(text: &str) -> (Editor, Rc<RefCell<Vec<EditEvent>>>) {
+    let mut editor = editor(text);
+    let events = Rc::new(RefCell::new(Vec::new()));
+    editor.add_observer(Box::new(Recorder(events.clone())));
+    (editor, events)
+}
+
+#[test]
+fn each_typed_char_is_an_insert() {
+    let (mut editor, events) = recorded("x\n");
+    let id = editor.buffer_manager.current_buffer_id();
+
+    apply_keys(&mut editor, "Aab<Esc>");
+    assert_eq!(
+        *events.borrow(),
+        [
+            EditEvent::Insert {
+                buffer_id: id,
+                char_idx: 1,
+                position: Cursor::new(0, 1),
+                text: "a".to_string(),
+            },
+            EditEvent::Insert {
+                buffer_id: id,
+                char_idx: 2,
+                position: Cursor::new(0, 2),
+                text: "b".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn deletes_carry_the_removed_text() {
+    let (mut editor, events) = recorded("one\ntwo\n");
+    let id = editor.buffer_manager.current_buffer_id();
+
+    apply_keys(&mut editor, "jlxdd");
+    assert_eq!(
+        *events.borrow(),
+        [
+            EditEvent::Delete {
+                buffer_id: id,
+                range: 5..6,
+                position: Cursor::new(1, 1),
+                text: "w".to_string(),
+            },
+            EditEvent::Delete {
+                buffer_id: id,
+                range: 4..7,
+                position: Cursor::new(1, 0),
+                text: "to\n".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn undo_resets_the_buffer() {
+    let (mut editor, events) = recorded("a\n");
+    let id = editor.buffer_manager.current_buffer_id();
+
+    apply_keys(&mut editor, "xu");
+    assert_eq!(events.borrow().len(), 2);
+    assert_eq!(events.borrow()[1], EditEvent::Reset { buffer_id: id });
+}
+
+#[test]
+fn motions_send_nothing() {
+    let (mut editor, events) = recorded("one two\nthree\n");
+
+    apply_keys(&mut editor, "wj0$gg");
+    assert!(events.borrow().is_empty());
+}
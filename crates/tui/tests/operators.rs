@@ -0,0 +1,116 @@
+mod common;
+
+use common::{cursor, editor, text};
+use fluxion_core::{DEFAULT_REGISTER, Mode, RegisterKind};
+use fluxion_tui::apply_keys;
+
+fn unnamed(editor: &fluxion_core::Editor) -> Option<&str> {
+    editor
+        .registers
+        .get(&DEFAULT_REGISTER)
+        .map(|r| r.text.as_str())
+}
+
+#[test]
+fn cw_changes_to_the_end_of_the_word() {
+    let mut editor = editor("one two three\n");
+    apply_keys(&mut editor, "wcw");
+
+    assert_eq!(text(&editor), "one  three\n");
+    assert_eq!(editor.mode, Mode::Insert);
+    assert_eq!(cursor(&editor), (0, 4));
+    assert_eq!(unnamed(&editor), Some("two"));
+}
+
+#[test]
+fn cc_changes_the_line_and_keeps_its_indent() {
+    let mut editor = editor("first\n    indented line\nlast\n");
+    apply_keys(&mut editor, "jcc");
+
+    assert_eq!(text(&editor), "first\n    \nlast\n");
+    assert_eq!(editor.mode, Mode::Insert);
+    assert_eq!(cursor(&editor), (1, 4));
+}
+
+#[test]
+fn cc_without_autoindent_clears_the_indent_too() {
+    let mut editor = editor("    indented\n");
+    editor.options.auto_indent = false;
+    apply_keys(&mut editor, "cc");
+
+    assert_eq!(text(&editor), "\n");
+    assert_eq!(cursor(&editor), (0, 0));
+}
+
+#[test]
+fn capital_c_changes_to_the_end_of_the_line() {
+    let mut editor = editor("keep this\n");
+    apply_keys(&mut editor, "wC");
+
+    assert_eq!(text(&editor), "keep \n");
+    assert_eq!(editor.mode, Mode::Insert);
+    assert_eq!(cursor(&editor), (0, 5));
+    assert_eq!(unnamed(&editor), Some("this"));
+}
+
+#[test]
+fn d_dollar_deletes_to_the_end_of_the_line() {
+    let mut editor = editor("keep this\nnext\n");
+    apply_keys(&mut editor, "wd$");
+
+    assert_eq!(text(&editor), "keep \nnext\n");
+    assert_eq!(editor.mode, Mode::Normal);
+}
+
+#[test]
+fn dw_deletes_the_word_and_its_trailing_space() {
+    let mut editor = editor("one two three\n");
+    apply_keys(&mut editor, "wdw");
+
+    assert_eq!(text(&editor), "one three\n");
+    assert_eq!(cursor(&editor), (0, 4));
+    assert_eq!(unnamed(&editor), Some("two "));
+}
+
+#[test]
+fn dj_deletes_both_lines_linewise() {
+    let mut editor = editor("one\ntwo\nthree\nfour\n");
+    apply_keys(&mut editor, "jdj");
+
+    assert_eq!(text(&editor), "one\nfour\n");
+    assert_eq!(unnamed(&editor), Some("two\nthree\n"));
+    assert_eq!(
+        editor.registers[&DEFAULT_REGISTER].kind,
+        RegisterKind::Linewise
+    );
+}
+
+#[test]
+fn yw_yanks_without_changing_the_text() {
+    let mut editor = editor("one two\n");
+    apply_keys(&mut editor, "yw");
+
+    assert_eq!(text(&editor), "one two\n");
+    assert_eq!(cursor(&editor), (0, 0));
+    assert_eq!(unnamed(&editor), Some("one "));
+}
+
+#[test]
+fn esc_cancels_a_pending_operator() {
+    let mut editor = editor("one two\n");
+    apply_keys(&mut editor, "d<Esc>x");
+
+    assert_eq!(text(&editor), "ne two\n");
+}
+
+#[test]
+fn dd_with_a_huge_count_deletes_to_the_end_only() {
+    let mut editor = editor("one\ntwo\nthree\n");
+    apply_keys(&mut editor, "j99999999999999999999dd");
+
+    assert_eq!(text(&editor), "one\n");
+    assert_eq!(cursor(&editor), (0, 0));
+
+    apply_keys(&mut editor, "2d99999999999999999999d");
+    assert_eq!(text(&editor), "");
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use common::{cursor, editor, text};
+use fluxion_core::{ClipboardProvider, DEFAULT_REGISTER, Register};
+use fluxion_tui::apply_keys;
+use std::cell::RefCell;
+use std::error::Error;
+use std::rc::Rc;
+
+/// A clipboard that keeps its text in memory, shared with the test.
+struct FakeClipboard(Rc<RefCell<String>>);
+
+impl ClipboardProvider for FakeClipboard {
+    fn get_text(&mut self) -> Result<String, Box<dyn Error>> {
+        Ok(self.0.borrow().clone())
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), Box<dyn Error>> {
+        *self.0.borrow_mut() = text.to_string();
+        Ok(())
+    }
+}
+
+/// A clipboard that is never reachable, as on a headless machine.
+struct BrokenClipboard;
+
+impl ClipboardProvider for BrokenClipboard {
+    fn get_text(&mut self) -> Result<String, Box<dyn Error>> {
+        Err("no clipboard".into())
+    }
+
+    fn set_text(&mut self, _text: &str) -> Result<(), Box<dyn Error>> {
+        Err("no clipboard".into())
+    }
+}
+
+#[test]
+fn yy_yanks_the_line_linewise() {
+    let mut editor = editor("one\ntwo\n");
+
+    apply_keys(&mut editor, "yy");
+    assert_eq!(
+        editor.registers.get(&DEFAULT_REGISTER),
+        Some(&Register::linewise("one\n".into()))
+    );
+}
+
+#[test]
+fn linewise_paste_goes_below_or_above_the_line() {
+    let mut editor = editor("one\ntwo\n");
+
+    apply_keys(&mut editor, "yyjp");
+    assert_eq!(text(&editor), "one\ntwo\none\n");
+    assert_eq!(cursor(&editor), (2, 0));
+
+    apply_keys(&mut editor, "ggjP");
+    assert_eq!(text(&editor), "one\none\ntwo\none\n");
+    assert_eq!(cursor(&editor), (1, 0));
+}
+
+#[test]
+fn charwise_paste_goes_after_or_before_the_cursor() {
+    let mut editor = editor("abc\n");
+
+    apply_keys(&mut editor, "vly");
+    assert_eq!(
+        editor.registers.get(&DEFAULT_REGISTER),
+        Some(&Register::charwise("ab".into()))
+    );
+
+    apply_keys(&mut editor, "$p");
+    assert_eq!(text(&editor), "abcab\n");
+
+    apply_keys(&mut editor, "0P");
+    assert_eq!(text(&editor), "ababcab\n");
+}
+
+#[test]
+fn the_plus_register_yanks_to_the_clipboard() {
+    let clipboard = Rc::new(RefCell::new(String::new()));
+    let mut editor = editor("one\ntwo\n");
+    editor.set_clipboard_provider(Some(Box::new(FakeClipboard(clipboard.clone()))));
+
+    apply_keys(&mut editor, "\"+yy");
+    assert_eq!(*clipboard.borrow(), "one\n");
+    assert_eq!(editor.registers.get(&'+'), None);
+}
+
+#[test]
+fn the_plus_register_pastes_from_the_clipboard() {
+    let clipboard = Rc::new(RefCell::new("two\n".to_string()));
+    let mut editor = editor("one\n");
+    editor.set_clipboard_provider(Some(Box::new(FakeClipboard(clipboard))));
+
+    apply_keys(&mut editor, "\"+p");
+    assert_eq!(text(&editor), "one\ntwo\n");
+}
+
+#[test]
+fn an_unavailable_clipboard_falls_back_to_the_register() {
+    let mut editor = editor("one\n");
+    editor.set_clipboard_provider(Some(Box::new(BrokenClipboard)));
+
+    apply_keys(&mut editor, "\"+yy\"+p");
+    assert_eq!(
+        editor.registers.get(&'+'),
+        Some(&Register::linewise("one\n".into()))
+    );
+    assert_eq!(text(&editor), "one\none\n");
+}
+
+#[test]
+fn without_a_clipboard_the_plus_register_is_internal() {
+    let mut editor = editor("one\n");
+    editor.set_clipboard_provider(None);
+
+    apply_keys(&mut editor, "\"+yy\"+P");
+    assert_eq!(text(&editor), "one\none\n");
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::{cursor, editor};
+use fluxion_core::MessageKind;
+use fluxion_tui::apply_keys;
+
+#[test]
+fn search_jumps_to_the_next_match() {
+    let mut editor = editor("one\ntwo\none two\n");
+
+    apply_keys(&mut editor, "/two<CR>");
+    assert_eq!(cursor(&editor), (1, 0));
+    assert_eq!(editor.search.query.as_deref(), Some("two"));
+}
+
+#[test]
+fn n_and_capital_n_wrap_around_the_buffer() {
+    let mut editor = editor("one\ntwo\none two\n");
+
+    apply_keys(&mut editor, "/two<CR>n");
+    assert_eq!(cursor(&editor), (2, 4));
+    apply_keys(&mut editor, "/two<CR>nn");
+    assert_eq!(cursor(&editor), (1, 0));
+    apply_keys(&mut editor, "N");
+    assert_eq!(cursor(&editor), (2, 4));
+}
+
+#[test]
+fn a_search_without_matches_reports_an_error() {
+    let mut editor = editor("one\ntwo\n");
+
+    apply_keys(&mut editor, "j/three<CR>");
+    assert_eq!(cursor(&editor), (1, 0));
+    assert_eq!(
+        editor.status_message,
+        Some((
+            "E486: Pattern not found: three".to_string(),
+            MessageKind::Error
+        ))
+    );
+}
+
+#[test]
+fn matches_update_as_the_query_is_typed() {
+    let mut editor = editor("one\ntwo\nten two\n");
+
+    apply_keys(&mut editor, "/t");
+    assert_eq!(editor.search.matches, [4..5, 8..9, 12..13]);
+    assert_eq!(cursor(&editor), (1, 0));
+    apply_keys(&mut editor, "w");
+    assert_eq!(editor.search.matches, [4..6, 12..14]);
+    apply_keys(&mut editor, "<BS>e");
+    assert_eq!(editor.search.matches, vec![8..10]);
+    assert_eq!(cursor(&editor), (2, 0));
+}
+
+#[test]
+fn escape_returns_the_cursor_to_where_the_search_started() {
+    let mut editor = editor("one\ntwo\nten two\n");
+
+    apply_keys(&mut editor, "/ten");
+    assert_eq!(cursor(&editor), (2, 0));
+    apply_keys(&mut editor, "<Esc>");
+    assert_eq!(cursor(&editor), (0, 0));
+}
+
+#[test]
+fn an_invalid_pattern_reports_an_error_and_stays_put() {
+    let mut editor = editor("one\ntwo\n");
+
+    apply_keys(&mut editor, "j/t(<CR>");
+    assert_eq!(cursor(&editor), (1, 0));
+    assert_eq!(
+        editor.status_message,
+        Some((
+            "E383: Invalid search string: t(".to_string(),
+            MessageKind::Error
+        ))
+    );
+}
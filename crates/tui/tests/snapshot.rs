@@ -0,0 +1,75 @@
+mod common;
+
+use common::editor;
+use fluxion_core::{Cursor, EditorSnapshot, Mode, PREVIEW_CHARS};
+use fluxion_tui::apply_keys;
+
+#[test]
+fn typing_shows_in_the_preview() {
+    let mut editor = editor("");
+
+    apply_keys(&mut editor, "ihi\x1b");
+    assert_eq!(editor.snapshot().text_preview, "hi");
+    assert_eq!(editor.snapshot().mode, Mode::Normal);
+}
+
+#[test]
+fn a_snapshot_captures_the_whole_state() {
+    let mut editor = editor("one\ntwo\nthree\n");
+
+    apply_keys(&mut editor, "jw");
+    assert_eq!(
+        editor.snapshot(),
+        EditorSnapshot {
+            cursor: Cursor::new(2, 0),
+            mode: Mode::Normal,
+            buffer_id: editor.buffer_manager.current_buffer_id(),
+            scroll_offset: 0,
+            line_count: 3,
+            text_preview: "one\ntwo\nthree\n".to_string(),
+        }
+    );
+}
+
+#[test]
+fn motions_operators_and_undo_in_one_sequence() {
+    let mut editor = editor("alpha beta\ngamma\n");
+    let before = editor.snapshot();
+
+    apply_keys(&mut editor, "wdw");
+    assert_eq!(editor.snapshot().text_preview, "alpha \ngamma\n");
+    apply_keys(&mut editor, "jdd");
+    assert_eq!(editor.snapshot().line_count, 1);
+    apply_keys(&mut editor, "uu");
+    assert_eq!(editor.snapshot().text_preview, before.text_preview);
+    apply_keys(&mut editor, "<C-r>");
+    assert_eq!(editor.snapshot().text_preview, "alpha \ngamma\n");
+}
+
+#[test]
+fn enter_and_escape_can_be_raw_chars() {
+    let mut editor = editor("");
+
+    apply_keys(&mut editor, "ia\rb\x1b");
+    let snapshot = editor.snapshot();
+    assert_eq!(snapshot.text_preview, "a\nb");
+    assert_eq!(snapshot.mode, Mode::Normal);
+}
+
+#[test]
+fn the_preview_stops_at_its_limit() {
+    let mut editor = editor(&"x".repeat(PREVIEW_CHARS + 50));
+
+    assert_eq!(editor.snapshot().text_preview.len(), PREVIEW_CHARS);
+    apply_keys(&mut editor, "ggdd");
+    assert_eq!(editor.snapshot().text_preview, "");
+}
+
+#[test]
+fn keys_after_quitting_are_not_applied() {
+    let mut editor = editor("a\n");
+
+    apply_keys(&mut editor, ":q!<CR>x");
+    assert!(editor.should_quit);
+    assert_eq!(editor.snapshot().text_preview, "a\n");
+}
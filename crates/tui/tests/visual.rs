@@ -0,0 +1,153 @@
+mod common;
+
+use common::{cursor, editor, text};
+use fluxion_core::{Cursor, DEFAULT_REGISTER, Mode, Register};
+use fluxion_tui::apply_keys;
+
+#[test]
+fn selection_spans_multiple_lines() {
+    let mut editor = editor("one\ntwo\nthree\n");
+
+    apply_keys(&mut editor, "lvjl");
+    assert_eq!(
+        editor.selection_range(),
+        Some((Cursor::new(0, 1), Cursor::new(1, 2)))
+    );
+}
+
+#[test]
+fn a_reversed_selection_is_normalized() {
+    let mut editor = editor("one\ntwo\nthree\n");
+
+    apply_keys(&mut editor, "jllvkh");
+    assert_eq!(
+        editor.selection_range(),
+        Some((Cursor::new(0, 1), Cursor::new(1, 2)))
+    );
+}
+
+#[test]
+fn leaving_visual_mode_clears_the_selection() {
+    let mut editor = editor("one\n");
+
+    apply_keys(&mut editor, "vl");
+    assert!(editor.selection_range().is_some());
+    apply_keys(&mut editor, "vl<Esc>");
+    assert_eq!(editor.selection_anchor, None);
+    assert_eq!(editor.selection_range(), None);
+}
+
+#[test]
+fn deleting_a_single_char_selection() {
+    let mut editor = editor("abc\n");
+
+    apply_keys(&mut editor, "lvd");
+    assert_eq!(text(&editor), "ac\n");
+    assert_eq!(cursor(&editor), (0, 1));
+    assert_eq!(editor.mode, Mode::Normal);
+    assert_eq!(
+        editor.registers.get(&DEFAULT_REGISTER),
+        Some(&Register::charwise("b".into()))
+    );
+}
+
+#[test]
+fn deleting_a_selection_across_a_full_line() {
+    let mut editor = editor("one\ntwo\nthree\n");
+
+    apply_keys(&mut editor, "jv$x");
+    assert_eq!(text(&editor), "one\n\nthree\n");
+    assert_eq!(cursor(&editor), (1, 0));
+}
+
+#[test]
+fn deleting_a_selection_that_ends_the_buffer() {
+    let mut editor = editor("one\ntwo");
+
+    apply_keys(&mut editor, "lvj$d");
+    assert_eq!(text(&editor), "o");
+    assert_eq!(
+        editor.registers.get(&DEFAULT_REGISTER),
+        Some(&Register::charwise("ne\ntwo".into()))
+    );
+}
+
+#[test]
+fn visual_line_selects_whole_lines_whatever_the_column() {
+    let mut editor = editor("one\ntwo\nthree\nfour\n");
+
+    apply_keys(&mut editor, "lVjj");
+    assert_eq!(editor.mode, Mode::VisualLine);
+    assert_eq!(
+        editor.selection_range(),
+        Some((Cursor::new(0, 0), Cursor::new(2, 5)))
+    );
+}
+
+#[test]
+fn deleting_three_lines_with_visual_line() {
+    let mut editor = editor("one\ntwo\nthree\nfour\n");
+
+    apply_keys(&mut editor, "lVjjd");
+    assert_eq!(text(&editor), "four\n");
+    assert_eq!(cursor(&editor), (0, 0));
+    assert_eq!(editor.mode, Mode::Normal);
+    assert_eq!(
+        editor.registers.get(&DEFAULT_REGISTER),
+        Some(&Register::linewise("one\ntwo\nthree\n".into()))
+    );
+}
+
+#[test]
+fn yanking_with_visual_line_is_linewise() {
+    let mut editor = editor("one\ntwo\n");
+
+    apply_keys(&mut editor, "Vyjp");
+    assert_eq!(text(&editor), "one\ntwo\none\n");
+}
+
+#[test]
+fn block_insert_adds_a_prefix_to_every_row() {
+    let mut editor = editor("one\ntwo\nsix\nten\n");
+
+    apply_keys(&mut editor, "<C-v>jjI// <Esc>");
+    assert_eq!(text(&editor), "// one\n// two\n// six\nten\n");
+    assert_eq!(editor.mode, Mode::Normal);
+}
+
+#[test]
+fn block_append_goes_after_the_right_edge() {
+    let mut editor = editor("abc\nabc\n");
+
+    apply_keys(&mut editor, "<C-v>jlA-<Esc>");
+    assert_eq!(text(&editor), "ab-c\nab-c\n");
+}
+
+#[test]
+fn block_delete_removes_a_rectangle() {
+    let mut editor = editor("abcd\nefgh\nijkl\n");
+
+    apply_keys(&mut editor, "l<C-v>jld");
+    assert_eq!(text(&editor), "ad\neh\nijkl\n");
+    assert_eq!(cursor(&editor), (0, 1));
+    assert_eq!(
+        editor.registers.get(&DEFAULT_REGISTER),
+        Some(&Register::charwise("bc\nfg".into()))
+    );
+}
+
+#[test]
+fn block_delete_stops_at_short_lines() {
+    let mut editor = editor("abcd\nab\nabcd\n");
+
+    apply_keys(&mut editor, "ll<C-v>jjd");
+    assert_eq!(text(&editor), "abd\nab\nabd\n");
+}
+
+#[test]
+fn sort_over_a_visual_selection_sorts_only_those_lines() {
+    let mut editor = editor("z\nc\nb\na\ny\n");
+
+    apply_keys(&mut editor, "jVjj:sort<CR>");
+    assert_eq!(text(&editor), "z\na\nb\nc\ny\n");
+}